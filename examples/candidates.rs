@@ -5,7 +5,7 @@
 use std::env;
 use std::path::PathBuf;
 
-use icloud_dedupe::scanner::{find_candidates, normalize_path};
+use icloud_dedupe::scanner::{candidates_iter, normalize_path};
 use icloud_dedupe::types::ScanConfig;
 
 fn main() {
@@ -33,28 +33,27 @@ fn main() {
         ..Default::default()
     };
 
-    match find_candidates(&config) {
-        Ok(candidates) => {
-            if candidates.is_empty() {
-                println!("No conflict patterns found.");
-                return;
-            }
-
-            println!("Found {} candidates:\n", candidates.len());
-
-            for candidate in &candidates {
+    // Stream candidates: each one prints as soon as the walk reaches it
+    let mut found = 0;
+    for result in candidates_iter(&config) {
+        match result {
+            Ok(candidate) => {
+                found += 1;
                 println!("  Conflict: {}", candidate.path.display());
                 println!("  Pattern:  {:?}", candidate.pattern);
                 println!("  Original: {}", candidate.presumed_original.display());
                 println!();
             }
-
-            println!("---");
-            println!("To verify these with hash comparison, use `cargo run --example scan`");
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            Err(e) => eprintln!("Warning: {}", e),
         }
     }
+
+    if found == 0 {
+        println!("No conflict patterns found.");
+        return;
+    }
+
+    println!("---");
+    println!("Found {} candidates.", found);
+    println!("To verify these with hash comparison, use `cargo run --example scan`");
 }
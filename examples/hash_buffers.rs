@@ -0,0 +1,72 @@
+//! Hashing throughput by read buffer size.
+//!
+//! Run with: cargo run --release --example hash_buffers [file] [size-mb]
+//!
+//! Without a file, writes a temporary one (default 256 MB). Each buffer size
+//! hashes the file several times; the best run is reported, so the numbers
+//! reflect the page cache rather than the disk after the first pass. Point
+//! it at a cold file on the target disk to measure the drive itself.
+
+use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use icloud_dedupe::hash::{hash_file, hash_file_with_buffer};
+
+const RUNS: usize = 3;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let size_mb: usize = args
+        .get(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(256);
+
+    // Keep the temp file alive for the whole benchmark
+    let temp;
+    let path = match args.first() {
+        Some(p) => PathBuf::from(p),
+        None => {
+            temp = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+            let chunk: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+            let mut file = temp.as_file();
+            for _ in 0..size_mb {
+                file.write_all(&chunk).expect("Failed to write temp file");
+            }
+            temp.path().to_path_buf()
+        }
+    };
+
+    let len = std::fs::metadata(&path).expect("Failed to stat file").len();
+    println!("File: {} ({} MB)\n", path.display(), len / (1024 * 1024));
+
+    let sizes = [8, 16, 32, 64, 128, 256, 512, 1024];
+    for kb in sizes {
+        let best = best_of(|| {
+            hash_file_with_buffer(&path, Some(kb * 1024)).expect("Failed to hash");
+        });
+        println!("{:>6} KB  {:>8.1} MB/s", kb, throughput(len, best));
+    }
+
+    let best = best_of(|| {
+        hash_file(&path).expect("Failed to hash");
+    });
+    println!("adaptive  {:>8.1} MB/s", throughput(len, best));
+}
+
+fn best_of(mut f: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn throughput(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64()
+}
@@ -11,7 +11,7 @@ use rayon::prelude::*;
 
 use icloud_dedupe::report::format_report;
 use icloud_dedupe::scanner::{find_candidates, normalize_path, verify_candidate};
-use icloud_dedupe::types::{OutputFormat, ScanConfig, ScanReport, VerificationResult};
+use icloud_dedupe::types::{DivergedPair, OutputFormat, ScanConfig, ScanReport, VerificationResult};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -116,6 +116,7 @@ fn main() {
                             original: keep,
                             hash,
                             duplicates: vec![remove],
+                            size_bytes: size,
                         },
                     );
                 }
@@ -126,9 +127,31 @@ fn main() {
             Ok(VerificationResult::ContentDiverged {
                 conflict_path,
                 original_path,
-                ..
+                divergence,
             }) => {
-                report.content_diverged.push((conflict_path, original_path));
+                report
+                    .content_diverged
+                    .push(DivergedPair::new(conflict_path, original_path, &divergence));
+            }
+            Ok(VerificationResult::NotLocal { path, .. }) => {
+                report.not_local.push(path);
+            }
+            Ok(VerificationResult::Hardlinked { path, original }) => {
+                report.hardlinked.push((path, original));
+            }
+            Ok(VerificationResult::ProbableDuplicate { path, original }) => {
+                report.probable_duplicates.push((path, original));
+            }
+            Ok(VerificationResult::MetadataDiffers {
+                path,
+                original,
+                fields,
+            }) => {
+                report.metadata_differs.push(icloud_dedupe::types::MetadataMismatch {
+                    conflict: path,
+                    original,
+                    fields,
+                });
             }
             Err(e) => {
                 // Track files we couldn't read
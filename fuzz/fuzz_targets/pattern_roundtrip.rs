@@ -0,0 +1,29 @@
+//! Fuzz the conflict-name round trip.
+//!
+//! Run with: cargo +nightly fuzz run pattern_roundtrip
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use icloud_dedupe::testing::{check_roundtrip, is_plausible_original, ConflictTransform};
+
+fuzz_target!(|input: (String, u8, u16)| {
+    let (original, kind, index) = input;
+
+    if !is_plausible_original(&original) {
+        return;
+    }
+
+    // Indices below 2 aren't conflicts by definition
+    let index = u32::from(index).max(2);
+    let transform = match kind % 3 {
+        0 => ConflictTransform::Copy,
+        1 => ConflictTransform::CopyN(index),
+        _ => ConflictTransform::Numbered(index),
+    };
+
+    if let Err(e) = check_roundtrip(&original, transform) {
+        panic!("{}", e);
+    }
+});
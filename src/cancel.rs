@@ -0,0 +1,285 @@
+//! Cooperative cancellation.
+//!
+//! Long operations check a [`CancelToken`] between files instead of being
+//! killed mid-move, so Ctrl-C never leaves a file moved without a manifest
+//! entry. The CLI ties a token to SIGINT with [`cancel_on_interrupt`].
+//!
+//! A [`ScanBudget`] is the gentler sibling: when a scan runs out of files
+//! or time it stops the same way, but keeps what it found and says why.
+//! It also keeps the scan's clock and tallies for the report's stats.
+//!
+//! Structure:
+//! - Types: the token, the scan budget
+//! - Effect functions: signal handler installation
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Shared flag asking work in progress to stop at the next safe point.
+///
+/// Clones share the flag. A fresh token is never cancelled until
+/// [`CancelToken::cancel`] is called.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask work holding this token to stop.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// True once cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+/// Shared clock, tallies, and verdict of a scan's budget.
+///
+/// Clones share state, so discovery and verification of one scan draw on
+/// the same clock. [`ScanBudget::begin`] restarts it for a new scan.
+#[derive(Debug, Clone, Default)]
+pub struct ScanBudget {
+    state: Arc<Mutex<BudgetState>>,
+    tally: Arc<Tally>,
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    started: Option<Instant>,
+    started_at: Option<SystemTime>,
+    exhausted: Option<String>,
+}
+
+/// Counters bumped from worker threads, kept out of the mutex.
+#[derive(Debug, Default)]
+struct Tally {
+    files: AtomicUsize,
+    files_hashed: AtomicUsize,
+    bytes_hashed: AtomicU64,
+    hash_nanos: AtomicU64,
+    walk_nanos: AtomicU64,
+    verify_nanos: AtomicU64,
+}
+
+impl ScanBudget {
+    /// Start the clock for a new scan, forgetting any earlier verdict.
+    pub fn begin(&self) {
+        let mut state = self.lock();
+        state.started = Some(Instant::now());
+        state.started_at = Some(SystemTime::now());
+        state.exhausted = None;
+        self.tally.files.store(0, Ordering::Relaxed);
+        self.tally.files_hashed.store(0, Ordering::Relaxed);
+        for counter in [
+            &self.tally.bytes_hashed,
+            &self.tally.hash_nanos,
+            &self.tally.walk_nanos,
+            &self.tally.verify_nanos,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Wall-clock time [`ScanBudget::begin`] was called.
+    pub fn started_at(&self) -> Option<SystemTime> {
+        self.lock().started_at
+    }
+
+    /// Time since [`ScanBudget::begin`] (zero if never begun).
+    pub fn elapsed(&self) -> Duration {
+        self.lock().started.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// Record that the budget ran out. The first reason given is kept.
+    pub fn exhaust(&self, reason: impl Into<String>) {
+        self.lock().exhausted.get_or_insert_with(|| reason.into());
+    }
+
+    /// Why the budget ran out, if it did.
+    pub fn exhausted(&self) -> Option<String> {
+        self.lock().exhausted.clone()
+    }
+
+    /// Count a file walked.
+    pub fn count_file(&self) {
+        self.tally.files.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a file read for hashing: its size and how long it took.
+    pub fn count_hashed(&self, bytes: u64, took: Duration) {
+        self.tally.files_hashed.fetch_add(1, Ordering::Relaxed);
+        self.tally.bytes_hashed.fetch_add(bytes, Ordering::Relaxed);
+        self.tally.hash_nanos.fetch_add(nanos(took), Ordering::Relaxed);
+    }
+
+    /// Count time spent walking directories.
+    pub fn count_walk(&self, took: Duration) {
+        self.tally.walk_nanos.fetch_add(nanos(took), Ordering::Relaxed);
+    }
+
+    /// Count time spent verifying candidates (wall clock, not per thread).
+    pub fn count_verify(&self, took: Duration) {
+        self.tally.verify_nanos.fetch_add(nanos(took), Ordering::Relaxed);
+    }
+
+    /// Files walked since [`ScanBudget::begin`].
+    pub fn files(&self) -> usize {
+        self.tally.files.load(Ordering::Relaxed)
+    }
+
+    /// Files hashed since [`ScanBudget::begin`].
+    pub fn files_hashed(&self) -> usize {
+        self.tally.files_hashed.load(Ordering::Relaxed)
+    }
+
+    /// Bytes hashed since [`ScanBudget::begin`].
+    pub fn bytes_hashed(&self) -> u64 {
+        self.tally.bytes_hashed.load(Ordering::Relaxed)
+    }
+
+    /// Time spent hashing, summed over all threads.
+    pub fn hash_time(&self) -> Duration {
+        Duration::from_nanos(self.tally.hash_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Time spent walking directories.
+    pub fn walk_time(&self) -> Duration {
+        Duration::from_nanos(self.tally.walk_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Time spent verifying candidates.
+    pub fn verify_time(&self) -> Duration {
+        Duration::from_nanos(self.tally.verify_nanos.load(Ordering::Relaxed))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BudgetState> {
+        // The state is two plain fields: a panic can't leave it half-written
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A duration as nanoseconds for an atomic counter (saturating: no scan
+/// runs for 584 years).
+fn nanos(took: Duration) -> u64 {
+    took.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// Error returned by work that stopped because it was cancelled.
+pub fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "Cancelled")
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Token the SIGINT handler cancels (set once, read from the handler).
+static INTERRUPT_TOKEN: OnceLock<CancelToken> = OnceLock::new();
+
+/// Return a token cancelled by Ctrl-C (SIGINT).
+///
+/// The first Ctrl-C only cancels the token, letting the current file
+/// finish and the manifest be written. A second one exits at once, for
+/// when cleanup itself hangs. Repeated calls return the same token.
+pub fn cancel_on_interrupt() -> CancelToken {
+    let mut installed = false;
+    let token = INTERRUPT_TOKEN
+        .get_or_init(|| {
+            installed = true;
+            CancelToken::new()
+        })
+        .clone();
+
+    if installed {
+        // SAFETY: the handler only touches atomics and calls _exit, both
+        // async-signal-safe.
+        unsafe {
+            libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
+        }
+    }
+
+    token
+}
+
+extern "C" fn on_sigint(_signal: libc::c_int) {
+    let Some(token) = INTERRUPT_TOKEN.get() else {
+        return;
+    };
+    if token.flag.swap(true, Ordering::SeqCst) {
+        // Second Ctrl-C: the user really wants out (128 + SIGINT)
+        unsafe { libc::_exit(130) };
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_token_is_not_cancelled() {
+        assert!(!CancelToken::new().is_cancelled());
+    }
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn budget_keeps_first_reason_until_restarted() {
+        let budget = ScanBudget::default();
+        budget.begin();
+        let clone = budget.clone();
+        clone.exhaust("out of files");
+        clone.exhaust("out of time");
+        assert_eq!(budget.exhausted().as_deref(), Some("out of files"));
+
+        budget.begin();
+        assert!(clone.exhausted().is_none());
+    }
+
+    #[test]
+    fn budget_tallies_are_shared_and_reset_by_begin() {
+        let budget = ScanBudget::default();
+        budget.begin();
+        let clone = budget.clone();
+        clone.count_file();
+        clone.count_file();
+        clone.count_hashed(100, Duration::from_millis(5));
+        clone.count_hashed(50, Duration::from_millis(5));
+        clone.count_walk(Duration::from_millis(3));
+        assert_eq!((budget.files(), budget.files_hashed(), budget.bytes_hashed()), (2, 2, 150));
+        assert_eq!(budget.hash_time(), Duration::from_millis(10));
+        assert_eq!(budget.walk_time(), Duration::from_millis(3));
+        assert!(budget.started_at().is_some());
+        budget.begin();
+        assert_eq!((clone.files(), clone.files_hashed(), clone.bytes_hashed()), (0, 0, 0));
+        assert_eq!(clone.walk_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn cancelled_error_is_interrupted() {
+        assert_eq!(cancelled_error().kind(), io::ErrorKind::Interrupted);
+    }
+}
@@ -0,0 +1,468 @@
+//! User configuration file for icloud-dedupe.
+//!
+//! An optional TOML file at `<config dir>/icloud-dedupe/config.toml`.
+//! Every section is optional; a missing file means all defaults.
+//!
+//! Structure:
+//! - Types: the config and its sections
+//! - Pure functions: default path, parsing (config files, durations)
+//! - Effect functions: loading from disk
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::quarantine::quarantine_dir_in;
+use crate::types::{HashAlgorithm, RetryPolicy};
+
+/// Config filename within the app config directory.
+const CONFIG_FILENAME: &str = "config.toml";
+
+/// Environment variable that relocates all tool state.
+pub const DATA_DIR_ENV: &str = "ICLOUD_DEDUPE_DATA_DIR";
+
+/// Environment variable that relocates just the quarantine.
+pub const QUARANTINE_DIR_ENV: &str = "ICLOUD_DEDUPE_QUARANTINE";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Top-level configuration.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Directory for all tool state: quarantine, index, hash cache
+    /// (None = the platform data directory).
+    pub data_dir: Option<PathBuf>,
+    /// Metrics exporter settings (None = no metrics).
+    pub metrics: Option<MetricsConfig>,
+    /// Hashing performance tuning.
+    pub hashing: HashingConfig,
+    /// What scans walk.
+    pub scan: ScanSettings,
+    /// Quarantine housekeeping.
+    pub quarantine: QuarantineSettings,
+}
+
+/// What scans walk.
+///
+/// ```toml
+/// [scan]
+/// ignore = [".git", "node_modules", "*.photoslibrary"]   # replaces the defaults
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanSettings {
+    /// Directory names to skip (None = the built-in list).
+    pub ignore: Option<Vec<String>>,
+}
+
+/// Quarantine housekeeping.
+///
+/// ```toml
+/// [quarantine]
+/// dir = "/Volumes/Backup/quarantine"   # default: <data dir>/quarantine
+/// retention_days = 30   # purge quarantined files this old on the next run
+/// protect = ["~/Documents/Taxes/**", "*.key"]   # never quarantined
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuarantineSettings {
+    /// Where quarantined files go (None = inside the state directory).
+    pub dir: Option<PathBuf>,
+    /// Days a file stays in quarantine before it's purged (None = forever).
+    pub retention_days: Option<u64>,
+    /// Path globs never quarantined, whatever a scan finds. A pattern
+    /// without `/` matches the file name; a leading `~/` is the home directory.
+    pub protect: Vec<String>,
+}
+
+impl QuarantineSettings {
+    /// The protected patterns with `~/` expanded against `home`.
+    pub fn protected(&self, home: Option<&Path>) -> Vec<String> {
+        self.protect
+            .iter()
+            .map(|pattern| match (pattern.strip_prefix("~/"), home) {
+                (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+                _ => pattern.clone(),
+            })
+            .collect()
+    }
+
+    /// The retention period, if one is set.
+    pub fn retention(&self) -> Option<Duration> {
+        self.retention_days.map(|days| Duration::from_secs(days * 86400))
+    }
+}
+
+/// Hashing performance tuning.
+///
+/// ```toml
+/// [hashing]
+/// algorithm = "blake3"    # or "sha256", "xxh3-128"
+/// buffer_size = 1048576   # bytes; omit for adaptive sizing
+/// parallel_min_size = 67108864  # bytes; hash files this big on several cores
+/// readahead = true        # ask the OS to read ahead while hashing
+/// retries = 3             # extra attempts after a transient read error
+/// retry_backoff_ms = 100  # first wait, doubled per retry
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HashingConfig {
+    /// Content hash algorithm (None = BLAKE3).
+    pub algorithm: Option<HashAlgorithm>,
+    /// Fixed read buffer size in bytes (None = adaptive per file).
+    pub buffer_size: Option<usize>,
+    /// Smallest file hashed on several cores (None = the default).
+    pub parallel_min_size: Option<u64>,
+    /// Whether to hint the OS to read ahead (None = yes).
+    pub readahead: Option<bool>,
+    /// Retries after a transient read error (None = the default).
+    pub retries: Option<u32>,
+    /// Milliseconds before the first retry (None = the default).
+    pub retry_backoff_ms: Option<u64>,
+}
+
+impl HashingConfig {
+    /// The retry policy these settings ask for, defaults filling the gaps.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            retries: self.retries.unwrap_or(default.retries),
+            backoff: self
+                .retry_backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.backoff),
+        }
+    }
+}
+
+/// Where and how to send end-of-run metrics.
+///
+/// ```toml
+/// [metrics]
+/// statsd = "127.0.0.1:8125"
+/// prefix = "icloud_dedupe"
+/// tags = { fleet = "design-team" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// statsd endpoint as "host:port" (UDP).
+    pub statsd: String,
+    /// Prefix for every metric name.
+    #[serde(default = "default_metrics_prefix")]
+    pub prefix: String,
+    /// Extra tags attached to every metric (DogStatsD `key:value` style).
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+fn default_metrics_prefix() -> String {
+    "icloud_dedupe".to_string()
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Returns the default config file location.
+///
+/// On macOS: ~/Library/Application Support/icloud-dedupe/config.toml
+pub fn default_config_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("icloud-dedupe")
+        .join(CONFIG_FILENAME)
+}
+
+/// Returns the default directory for tool state.
+///
+/// On macOS: ~/Library/Application Support/icloud-dedupe/
+pub fn default_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("icloud-dedupe")
+}
+
+/// Pick the state directory: `--data-dir` flag, then the
+/// [`DATA_DIR_ENV`] variable, then `data_dir` in the config file, then
+/// [`default_data_dir`].
+pub fn resolve_data_dir(flag: Option<PathBuf>, env: Option<PathBuf>, config: &Config) -> PathBuf {
+    flag.or(env)
+        .or_else(|| config.data_dir.clone())
+        .unwrap_or_else(default_data_dir)
+}
+
+/// Pick the quarantine directory: `--quarantine-dir` flag, then the
+/// [`QUARANTINE_DIR_ENV`] variable, then `[quarantine] dir` in the config
+/// file, then `quarantine` inside the resolved state directory.
+pub fn resolve_quarantine_dir(
+    flag: Option<PathBuf>,
+    env: Option<PathBuf>,
+    config: &Config,
+    data_dir: &Path,
+) -> PathBuf {
+    flag.or(env)
+        .or_else(|| config.quarantine.dir.clone())
+        .unwrap_or_else(|| quarantine_dir_in(data_dir))
+}
+
+/// Parse config file contents.
+pub fn parse_config(contents: &str) -> Result<Config, String> {
+    toml::from_str(contents).map_err(|e| e.to_string())
+}
+
+/// Parse a duration such as `90s`, `30m`, `2h`, `1h30m`, or `30d`. A bare
+/// number is seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}' (try 90s, 30m, 1h30m, or 30d)", input);
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = digits.parse().map_err(|_| invalid())?;
+        total += value * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Parse a size such as `500K`, `100M`, `1.5G`, or `2T` in binary units
+/// (`100MiB` and `100MB` mean the same). A bare number is bytes.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid size '{}' (try 500K, 100M, or 1.5G)", input);
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let value: f64 = number.parse().map_err(|_| invalid())?;
+
+    let unit = unit.trim_start();
+    let unit = unit.strip_suffix("iB").or_else(|| unit.strip_suffix('B')).unwrap_or(unit);
+    let scale: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+    Ok((value * scale as f64).round() as u64)
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Load the config file. A missing file yields the default config.
+pub fn load_config(path: &Path) -> io::Result<Config> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e),
+    };
+
+    parse_config(&contents).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid config file {}: {}", path.display(), e),
+        )
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("30d").unwrap(), Duration::from_secs(30 * 86400));
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("500K").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("100MB").unwrap(), 100 << 20);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 3 << 29);
+        assert_eq!(parse_size("2t").unwrap(), 2 << 40);
+        for bad in ["", "M", "10X", "1.2.3G", "big"] {
+            assert!(parse_size(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_garbage() {
+        for bad in ["", "m", "10x", "1h30", "soon"] {
+            assert!(parse_duration(bad).is_err(), "{:?} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_hashing_retry_policy_fills_defaults() {
+        let config = parse_config("[hashing]\nretries = 5\n").unwrap();
+        let policy = config.hashing.retry_policy();
+        assert_eq!(policy.retries, 5);
+        assert_eq!(policy.backoff, RetryPolicy::default().backoff);
+        assert_eq!(Config::default().hashing.retry_policy(), RetryPolicy::default());
+    }
+
+    #[test]
+    fn test_parse_scan_ignore_list() {
+        let config = parse_config("[scan]\nignore = [\"build\"]\n").unwrap();
+        assert_eq!(config.scan.ignore, Some(vec!["build".to_string()]));
+        assert_eq!(parse_config("").unwrap().scan.ignore, None);
+    }
+
+    #[test]
+    fn test_default_config_path_is_reasonable() {
+        let path = default_config_path();
+        assert!(path.to_string_lossy().contains("icloud-dedupe"));
+        assert!(path.ends_with(CONFIG_FILENAME));
+    }
+
+    #[test]
+    fn test_parse_empty_config_is_default() {
+        assert_eq!(parse_config("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_metrics_section() {
+        let config = parse_config(
+            r#"
+            [metrics]
+            statsd = "10.0.0.5:8125"
+            tags = { fleet = "design" }
+            "#,
+        )
+        .unwrap();
+
+        let metrics = config.metrics.unwrap();
+        assert_eq!(metrics.statsd, "10.0.0.5:8125");
+        assert_eq!(metrics.prefix, "icloud_dedupe");
+        assert_eq!(metrics.tags.get("fleet").map(String::as_str), Some("design"));
+    }
+
+    #[test]
+    fn test_parse_hashing_section() {
+        let config = parse_config("[hashing]\nbuffer_size = 65536\n").unwrap();
+        assert_eq!(config.hashing.buffer_size, Some(65536));
+        assert_eq!(parse_config("").unwrap().hashing.buffer_size, None);
+        assert_eq!(parse_config("[hashing]\nreadahead = false\n").unwrap().hashing.readahead, Some(false));
+    }
+
+    #[test]
+    fn test_parse_data_dir() {
+        let config = parse_config("data_dir = \"/tmp/dedupe\"\n").unwrap();
+        assert_eq!(config.data_dir, Some(PathBuf::from("/tmp/dedupe")));
+    }
+
+    #[test]
+    fn test_resolve_data_dir_precedence() {
+        let config = Config {
+            data_dir: Some(PathBuf::from("/from/config")),
+            ..Default::default()
+        };
+        let flag = || Some(PathBuf::from("/from/flag"));
+        let env = || Some(PathBuf::from("/from/env"));
+
+        assert_eq!(resolve_data_dir(flag(), env(), &config), PathBuf::from("/from/flag"));
+        assert_eq!(resolve_data_dir(None, env(), &config), PathBuf::from("/from/env"));
+        assert_eq!(resolve_data_dir(None, None, &config), PathBuf::from("/from/config"));
+        assert_eq!(
+            resolve_data_dir(None, None, &Config::default()),
+            default_data_dir()
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(parse_config("[metrics]\nstatsd = \"x:1\"\nstatd = 1\n").is_err());
+        assert!(parse_config("[metricz]\n").is_err());
+    }
+
+    #[test]
+    fn test_load_config_missing_file_is_default() {
+        let dir = TempDir::new().unwrap();
+        let config = load_config(&dir.path().join("nope.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_config_invalid_file_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, "metrics = [").unwrap();
+
+        let err = load_config(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_quarantine_retention() {
+        let config = parse_config("[quarantine]\nretention_days = 30\n").unwrap();
+        assert_eq!(config.quarantine.retention(), Some(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_config("").unwrap().quarantine.retention(), None);
+    }
+
+    #[test]
+    fn test_quarantine_protect_expands_home() {
+        let config = parse_config("[quarantine]\nprotect = [\"~/Taxes/**\", \"*.key\"]\n").unwrap();
+        assert_eq!(
+            config.quarantine.protected(Some(Path::new("/Users/me"))),
+            vec!["/Users/me/Taxes/**".to_string(), "*.key".to_string()]
+        );
+        assert_eq!(config.quarantine.protected(None)[0], "~/Taxes/**");
+    }
+
+    #[test]
+    fn test_resolve_quarantine_dir_precedence() {
+        let config = parse_config("[quarantine]\ndir = \"/from/config\"\n").unwrap();
+        let data_dir = Path::new("/state");
+        let flag = || Some(PathBuf::from("/from/flag"));
+        let env = || Some(PathBuf::from("/from/env"));
+
+        assert_eq!(resolve_quarantine_dir(flag(), env(), &config, data_dir), PathBuf::from("/from/flag"));
+        assert_eq!(resolve_quarantine_dir(None, env(), &config, data_dir), PathBuf::from("/from/env"));
+        assert_eq!(resolve_quarantine_dir(None, None, &config, data_dir), PathBuf::from("/from/config"));
+        assert_eq!(
+            resolve_quarantine_dir(None, None, &Config::default(), data_dir),
+            PathBuf::from("/state/quarantine")
+        );
+    }
+}
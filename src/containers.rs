@@ -0,0 +1,193 @@
+//! iCloud app containers: what lives under `Mobile Documents`.
+//!
+//! Every app that syncs documents through iCloud gets its own folder,
+//! named after its bundle ID with `~` for `.` (`com~apple~Pages`,
+//! `iCloud~md~obsidian`). Listing them with sizes and a rough count of
+//! conflict copies shows where a full scan is worth running.
+//!
+//! The count is pattern-only: names are matched, nothing is hashed, so a
+//! candidate may turn out to differ from its original.
+//!
+//! Structure:
+//! - Types: one container's survey
+//! - Pure functions: friendly app names, picking a container
+//! - Effect functions: survey, list
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::pattern::is_conflict_file;
+use crate::platform::{app_containers, placeholder_target, ICLOUD_DRIVE_BUNDLE};
+
+/// Bundle IDs with a better name than their last component.
+const KNOWN_APPS: &[(&str, &str)] = &[
+    (ICLOUD_DRIVE_BUNDLE, "iCloud Drive"),
+    (app_containers::PAGES, "Pages"),
+    (app_containers::NUMBERS, "Numbers"),
+    (app_containers::KEYNOTE, "Keynote"),
+    (app_containers::PREVIEW, "Preview"),
+    ("com~apple~TextEdit", "TextEdit"),
+    ("com~apple~ScriptEditor2", "Script Editor"),
+    ("com~apple~shoebox", "Shortcuts"),
+    ("com~apple~mail", "Mail"),
+    ("com~apple~QuickTimePlayerX", "QuickTime Player"),
+    ("iCloud~com~apple~iBooks", "Books"),
+    ("iCloud~com~apple~Playgrounds", "Swift Playgrounds"),
+    ("iCloud~md~obsidian", "Obsidian"),
+];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// One app container and what it holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppContainer {
+    /// Folder name, e.g. `com~apple~Pages`.
+    pub bundle_id: String,
+    /// Name to show, e.g. "Pages".
+    pub name: String,
+    pub path: PathBuf,
+    pub files: usize,
+    /// Bytes of the files stored locally; evicted files count as zero.
+    pub size_bytes: u64,
+    /// Files whose names look like conflict copies.
+    pub conflict_candidates: usize,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Friendly name for a container folder.
+///
+/// Known apps get their product name; others their bundle ID's last
+/// component (`iCloud~com~readdle~CommonDocuments` → "CommonDocuments").
+pub fn app_name(bundle_id: &str) -> String {
+    if let Some((_, name)) = KNOWN_APPS.iter().find(|(id, _)| *id == bundle_id) {
+        return name.to_string();
+    }
+    bundle_id.rsplit('~').next().unwrap_or(bundle_id).to_string()
+}
+
+/// Find a container by list number (1-based), bundle ID, or app name.
+///
+/// IDs and names match case-insensitively.
+pub fn pick_container<'a>(containers: &'a [AppContainer], choice: &str) -> Option<&'a AppContainer> {
+    let choice = choice.trim();
+    if let Ok(n) = choice.parse::<usize>() {
+        return n.checked_sub(1).and_then(|i| containers.get(i));
+    }
+    containers
+        .iter()
+        .find(|c| c.bundle_id.eq_ignore_ascii_case(choice))
+        .or_else(|| containers.iter().find(|c| c.name.eq_ignore_ascii_case(choice)))
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Count and size the files in one container, and how many look like
+/// conflict copies. Unreadable entries are skipped.
+pub fn survey_container(path: &Path) -> AppContainer {
+    let bundle_id = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut container = AppContainer {
+        name: app_name(&bundle_id),
+        bundle_id,
+        path: path.to_path_buf(),
+        files: 0,
+        size_bytes: 0,
+        conflict_candidates: 0,
+    };
+
+    for entry in WalkDir::new(path).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        let logical = placeholder_target(&name);
+        container.files += 1;
+        if logical.is_none() {
+            container.size_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+        if is_conflict_file(logical.unwrap_or(&name)) {
+            container.conflict_candidates += 1;
+        }
+    }
+    container
+}
+
+/// Survey every container under `Mobile Documents`, largest first.
+pub fn list_containers(mobile_documents: &Path) -> io::Result<Vec<AppContainer>> {
+    let mut containers: Vec<AppContainer> = fs::read_dir(mobile_documents)?
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| survey_container(&e.path()))
+        .collect();
+    containers.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.bundle_id.cmp(&b.bundle_id)));
+    Ok(containers)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn app_name_prefers_known_names() {
+        assert_eq!(app_name("com~apple~CloudDocs"), "iCloud Drive");
+        assert_eq!(app_name("com~apple~Pages"), "Pages");
+        assert_eq!(app_name("iCloud~com~readdle~CommonDocuments"), "CommonDocuments");
+        assert_eq!(app_name("plain"), "plain");
+    }
+
+    #[test]
+    fn list_counts_sizes_and_candidates() {
+        let dir = TempDir::new().unwrap();
+        let pages = dir.path().join("com~apple~Pages");
+        let notes = dir.path().join("iCloud~md~obsidian").join("Vault");
+        fs::create_dir_all(&pages).unwrap();
+        fs::create_dir_all(&notes).unwrap();
+        fs::write(pages.join("Report.pdf"), "12345").unwrap();
+        fs::write(notes.join("Note.md"), "1234567890").unwrap();
+        fs::write(notes.join("Note 2.md"), "1234567890").unwrap();
+        fs::write(notes.join(".Todo 2.md.icloud"), "stub").unwrap();
+        fs::write(dir.path().join(".DS_Store"), "").unwrap();
+
+        let containers = list_containers(dir.path()).unwrap();
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].name, "Obsidian");
+        assert_eq!(containers[0].files, 3);
+        assert_eq!(containers[0].size_bytes, 20);
+        assert_eq!(containers[0].conflict_candidates, 2);
+        assert_eq!(containers[1].bundle_id, "com~apple~Pages");
+        assert_eq!(containers[1].conflict_candidates, 0);
+    }
+
+    #[test]
+    fn pick_by_number_id_or_name() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("com~apple~Pages")).unwrap();
+        fs::create_dir(dir.path().join("com~apple~Numbers")).unwrap();
+        let containers = list_containers(dir.path()).unwrap();
+
+        assert_eq!(pick_container(&containers, "1").unwrap().name, "Numbers");
+        assert_eq!(pick_container(&containers, "com~apple~pages").unwrap().name, "Pages");
+        assert_eq!(pick_container(&containers, " pages ").unwrap().name, "Pages");
+        assert!(pick_container(&containers, "0").is_none());
+        assert!(pick_container(&containers, "3").is_none());
+        assert!(pick_container(&containers, "Keynote").is_none());
+    }
+}
@@ -0,0 +1,534 @@
+//! Content-based duplicate detection.
+//!
+//! The default scan trusts iCloud's naming patterns to find candidates.
+//! This mode ignores names: every file is grouped by size, same-size files
+//! are hashed, and files sharing a hash form a duplicate group. That also
+//! finds copies that were renamed or moved to another folder.
+//!
+//! The result is an ordinary [`ScanReport`], so reporting and quarantine
+//! work unchanged.
+//!
+//! Structure:
+//! - Pure functions: size grouping, keep selection
+//! - Effect functions: walking, hashing, report assembly
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime};
+
+use rayon::prelude::*;
+
+use crate::cancel::cancelled_error;
+use crate::hash::{hash_file_with_retry, is_not_local_error};
+use crate::hash_cache::{configured_store, hash_through};
+use crate::pattern::detect_pattern;
+use crate::platform::{file_id, is_dataless, placeholder_target};
+use crate::scanner::{
+    finish_report, modified_within, out_of_time, physical_recoverable, too_recent_error, walk_roots,
+    Walked,
+};
+use crate::types::{ContentHash, DuplicateGroup, ScanConfig, ScanProgress, ScanReport};
+
+/// A file considered for content grouping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentFile {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+    /// Last modification time, if known.
+    pub modified: Option<SystemTime>,
+    /// (device, inode): hard links to one file share it.
+    pub file_id: (u64, u64),
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Group files by size, keeping only sizes shared by two or more files.
+///
+/// Empty files are dropped: they are all "identical" but removing them
+/// recovers nothing. Groups come out largest size first, paths sorted.
+pub fn group_by_size(files: Vec<ContentFile>) -> Vec<Vec<ContentFile>> {
+    let mut by_size: HashMap<u64, Vec<ContentFile>> = HashMap::new();
+    for file in files.into_iter().filter(|f| f.size > 0) {
+        by_size.entry(file.size).or_default().push(file);
+    }
+
+    let mut groups: Vec<Vec<ContentFile>> = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    for group in &mut groups {
+        group.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    groups.sort_by_key(|group| (Reverse(group[0].size), group[0].path.clone()));
+    groups
+}
+
+/// Index of the file to keep among identical copies.
+///
+/// Preference, in order:
+/// 1. A name without a conflict pattern ("report.pdf" over "report 2.pdf")
+/// 2. The oldest modification time (the first copy is usually the original)
+/// 3. The shallowest path (copies tend to land in nested folders)
+/// 4. The lexicographically first path, so the choice is deterministic
+///
+/// # Panics
+/// Panics if `files` is empty.
+pub fn choose_keep(files: &[ContentFile]) -> usize {
+    let rank = |f: &ContentFile| {
+        let conflict_named = f
+            .path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .is_some_and(|name| detect_pattern(name).is_some());
+        (
+            conflict_named,
+            f.modified.unwrap_or(SystemTime::UNIX_EPOCH),
+            f.path.components().count(),
+            f.path.clone(),
+        )
+    };
+
+    (0..files.len())
+        .min_by_key(|&i| rank(&files[i]))
+        .expect("choose_keep needs at least one file")
+}
+
+/// Split same-size files into duplicate groups by hash.
+///
+/// Each group keeps the file picked by [`choose_keep`] and lists the rest
+/// as duplicates. Hard links are another name for one file, so removing
+/// them recovers nothing: only one link per file joins the group, and the
+/// others come back as (link, chosen link) pairs. Files with a unique hash
+/// are dropped.
+pub fn group_by_hash(
+    hashed: Vec<(ContentFile, ContentHash)>,
+) -> (Vec<DuplicateGroup>, Vec<(PathBuf, PathBuf)>) {
+    let mut by_hash: HashMap<ContentHash, Vec<ContentFile>> = HashMap::new();
+    let mut order: Vec<ContentHash> = Vec::new();
+    for (file, hash) in hashed {
+        let members = by_hash.entry(hash.clone()).or_default();
+        if members.is_empty() {
+            order.push(hash);
+        }
+        members.push(file);
+    }
+
+    let mut groups = Vec::new();
+    let mut hardlinked = Vec::new();
+    for hash in order {
+        let Some(members) = by_hash.remove(&hash) else {
+            continue;
+        };
+        let mut distinct = split_hardlinks(members, &mut hardlinked);
+        if distinct.len() < 2 {
+            continue;
+        }
+        let keep = distinct.remove(choose_keep(&distinct));
+        groups.push(DuplicateGroup {
+            original: keep.path,
+            hash,
+            duplicates: distinct.into_iter().map(|f| f.path).collect(),
+            size_bytes: keep.size,
+        });
+    }
+
+    (groups, hardlinked)
+}
+
+/// One file per (device, inode), chosen with [`choose_keep`]; the other
+/// links are pushed to `hardlinked` paired with the chosen one.
+fn split_hardlinks(
+    files: Vec<ContentFile>,
+    hardlinked: &mut Vec<(PathBuf, PathBuf)>,
+) -> Vec<ContentFile> {
+    let mut by_id: HashMap<(u64, u64), Vec<ContentFile>> = HashMap::new();
+    let mut order: Vec<(u64, u64)> = Vec::new();
+    for file in files {
+        let links = by_id.entry(file.file_id).or_default();
+        if links.is_empty() {
+            order.push(file.file_id);
+        }
+        links.push(file);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|id| by_id.remove(&id))
+        .map(|mut links| {
+            let chosen = links.remove(choose_keep(&links));
+            for link in links {
+                hardlinked.push((link.path, chosen.path.clone()));
+            }
+            chosen
+        })
+        .collect()
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Scan for files with identical content, regardless of their names.
+///
+/// Walks the configured roots (honoring depth, hidden-file, and symlink
+/// settings), groups files by size, and hashes only sizes shared by more
+/// than one file. Placeholders are listed in [`ScanReport::not_local`] and
+/// never read. With a hash store configured (`config.hash_store` or
+/// `config.hash_cache_path`), unchanged files reuse stored hashes.
+///
+/// `on_progress` is called after each file walked and each file hashed.
+///
+/// # Errors
+/// Returns an error if a root directory cannot be read.
+pub fn scan_by_content<F>(config: &ScanConfig, on_progress: F) -> io::Result<ScanReport>
+where
+    F: Fn(&ScanProgress) + Sync,
+{
+    let mut report = ScanReport::default();
+    let files = collect_files(config, &mut report, &on_progress);
+    let groups = group_by_size(files);
+
+    let to_hash: Vec<ContentFile> = groups.into_iter().flatten().collect();
+    let hashed = hash_files(&to_hash, config, &on_progress);
+
+    let mut same_size: Vec<(ContentFile, ContentHash)> = Vec::with_capacity(hashed.len());
+    for (file, result) in to_hash.into_iter().zip(hashed) {
+        match result {
+            Some(Ok(hash)) => same_size.push((file, hash)),
+            // Evicted since the walk: listed like any other placeholder
+            Some(Err(e)) if is_not_local_error(&e) => report.not_local.push(file.path),
+            Some(Err(e)) => report.skipped.push((file.path, e.to_string())),
+            None => {}
+        }
+    }
+
+    let sizes: HashMap<PathBuf, u64> = same_size
+        .iter()
+        .map(|(f, _)| (f.path.clone(), f.size))
+        .collect();
+    let (groups, hardlinked) = group_by_hash(same_size);
+    report.confirmed_duplicates = groups;
+    report.hardlinked = hardlinked;
+    report.bytes_recoverable = report
+        .confirmed_duplicates
+        .iter()
+        .flat_map(|g| &g.duplicates)
+        .map(|p| sizes.get(p).copied().unwrap_or(0))
+        .sum();
+    report.bytes_recoverable_physical = physical_recoverable(&report.confirmed_duplicates);
+    finish_report(&mut report, config);
+
+    Ok(report)
+}
+
+/// Walk every root, returning the regular files worth comparing.
+fn collect_files<F>(config: &ScanConfig, report: &mut ScanReport, on_progress: &F) -> Vec<ContentFile>
+where
+    F: Fn(&ScanProgress),
+{
+    let started = Instant::now();
+    let mut progress = ScanProgress::default();
+    let mut files = Vec::new();
+    let mut links_by_id: HashMap<(u64, u64), Vec<PathBuf>> = HashMap::new();
+
+    for walked in walk_roots(config) {
+        let entry = match walked {
+            Walked::Entry(entry) => entry,
+            Walked::SymlinkSkip(path, reason) => {
+                report.symlink_skips.push((path, reason));
+                continue;
+            }
+            Walked::Error(_) => continue,
+        };
+        let path = entry.path();
+        let Ok(meta) = fs::metadata(path) else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let Some(filename) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        progress.files_scanned += 1;
+        progress.elapsed = started.elapsed();
+        on_progress(&progress);
+
+        // Never read placeholders: that would download them
+        if let Some(target) = placeholder_target(filename) {
+            if config.include_hidden || !target.starts_with('.') {
+                report.not_local.push(path.with_file_name(target));
+            }
+            continue;
+        }
+        if !config.include_hidden && filename.starts_with('.') {
+            continue;
+        }
+        if is_dataless(&meta) {
+            report.not_local.push(path.to_path_buf());
+            continue;
+        }
+        if let Some(window) = config.ignore_newer_than
+            && let Some(age) = modified_within(&meta, window)
+        {
+            report.skipped.push((path.to_path_buf(), too_recent_error(path, age).to_string()));
+            continue;
+        }
+
+        // A second link to a known file may be the same path reached
+        // through overlapping roots; only then is resolving worth it.
+        // Without device and inode, every file counts as its own.
+        let file_id = file_id(&meta).map_or((u64::MAX, files.len() as u64), |id| (id.dev, id.ino));
+        let links = links_by_id.entry(file_id).or_default();
+        if !links.is_empty() {
+            let real = fs::canonicalize(path).ok();
+            if links.iter().any(|l| fs::canonicalize(l).ok() == real) {
+                continue;
+            }
+        }
+        links.push(path.to_path_buf());
+
+        files.push(ContentFile {
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            file_id,
+        });
+    }
+
+    files
+}
+
+/// Hash files in parallel, through the hash cache when configured.
+///
+/// Results are returned in input order; None for files left unhashed
+/// because the time budget ran out.
+fn hash_files<F>(
+    files: &[ContentFile],
+    config: &ScanConfig,
+    on_progress: &F,
+) -> Vec<Option<io::Result<ContentHash>>>
+where
+    F: Fn(&ScanProgress) + Sync,
+{
+    let started = Instant::now();
+    let bytes_to_verify: u64 = files.iter().map(|f| f.size).sum();
+    let verified = AtomicUsize::new(0);
+    let bytes_verified = AtomicU64::new(0);
+    let bytes_hashed = AtomicU64::new(0);
+
+    let store = configured_store(config);
+    let read = |p: &Path| {
+        let reading = Instant::now();
+        let hash = hash_file_with_retry(p, config.hash_algorithm, config.read_tuning(), &config.retry)?;
+        let size = fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+        bytes_hashed.fetch_add(size, Ordering::Relaxed);
+        config.budget.count_hashed(size, reading.elapsed());
+        Ok(hash)
+    };
+
+    let results = files
+        .par_iter()
+        .map(|file| {
+            if config.cancel.is_cancelled() {
+                return Some(Err(cancelled_error()));
+            }
+            if out_of_time(config) {
+                return None;
+            }
+            let result = match &store {
+                Some(store) => hash_through(store.as_ref(), &file.path, config.hash_algorithm, read),
+                None => read(&file.path),
+            };
+            let candidates_verified = verified.fetch_add(1, Ordering::Relaxed) + 1;
+            let done = bytes_verified.fetch_add(file.size, Ordering::Relaxed) + file.size;
+            on_progress(&ScanProgress {
+                candidates_found: files.len(),
+                candidates_verified,
+                bytes_verified: done,
+                bytes_to_verify,
+                bytes_hashed: bytes_hashed.load(Ordering::Relaxed),
+                elapsed: started.elapsed(),
+                ..Default::default()
+            });
+            Some(result)
+        })
+        .collect();
+    config.budget.count_verify(started.elapsed());
+
+    if let Some(store) = &store {
+        let _ = store.flush();
+    }
+
+    results
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn file(path: &str, size: u64, age_secs: u64) -> ContentFile {
+        ContentFile {
+            path: PathBuf::from(path),
+            size,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 - age_secs)),
+            file_id: (1, path.len() as u64 * 1000 + path.bytes().map(u64::from).sum::<u64>()),
+        }
+    }
+
+    fn hash(byte: u8) -> ContentHash {
+        ContentHash([byte; 32])
+    }
+
+    #[test]
+    fn size_groups_drop_unique_and_empty_files() {
+        let groups = group_by_size(vec![
+            file("/a", 10, 0),
+            file("/b", 10, 0),
+            file("/c", 20, 0),
+            file("/d", 0, 0),
+            file("/e", 0, 0),
+        ]);
+        assert_eq!(groups.len(), 1);
+        let paths: Vec<_> = groups[0].iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("/a"), PathBuf::from("/b")]);
+    }
+
+    #[test]
+    fn keep_prefers_clean_name_over_conflict_name() {
+        let files = [file("/x/report 2.pdf", 5, 100), file("/y/z/report.pdf", 5, 0)];
+        assert_eq!(choose_keep(&files), 1);
+    }
+
+    #[test]
+    fn keep_prefers_oldest_then_shallowest() {
+        let files = [file("/a/b/photo.jpg", 5, 100), file("/a/pic.jpg", 5, 50)];
+        assert_eq!(choose_keep(&files), 0);
+
+        let files = [file("/a/b/photo.jpg", 5, 0), file("/a/pic.jpg", 5, 0)];
+        assert_eq!(choose_keep(&files), 1);
+    }
+
+    #[test]
+    fn hash_groups_keep_one_and_drop_singletons() {
+        let (groups, hardlinked) = group_by_hash(vec![
+            (file("/moved/notes.txt", 5, 0), hash(1)),
+            (file("/notes.txt", 5, 0), hash(1)),
+            (file("/other.txt", 5, 0), hash(2)),
+        ]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].original, PathBuf::from("/notes.txt"));
+        assert_eq!(groups[0].duplicates, vec![PathBuf::from("/moved/notes.txt")]);
+        assert!(hardlinked.is_empty());
+    }
+
+    #[test]
+    fn hash_groups_set_hardlinks_aside() {
+        let mut link = file("/b/notes.txt", 5, 0);
+        link.file_id = file("/notes.txt", 5, 0).file_id;
+
+        let (groups, hardlinked) = group_by_hash(vec![
+            (file("/notes.txt", 5, 0), hash(1)),
+            (link, hash(1)),
+        ]);
+        assert!(groups.is_empty());
+        assert_eq!(
+            hardlinked,
+            vec![(PathBuf::from("/b/notes.txt"), PathBuf::from("/notes.txt"))]
+        );
+    }
+
+    #[test]
+    fn scan_finds_renamed_and_moved_copies() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("archive")).unwrap();
+        fs::write(dir.path().join("thesis.pdf"), "chapter one").unwrap();
+        fs::write(dir.path().join("archive/final-final.pdf"), "chapter one").unwrap();
+        fs::write(dir.path().join("unrelated.pdf"), "chapter two").unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let report = scan_by_content(&config, |_| {}).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        let group = &report.confirmed_duplicates[0];
+        assert_eq!(group.original, dir.path().join("thesis.pdf"));
+        assert_eq!(
+            group.duplicates,
+            vec![dir.path().join("archive/final-final.pdf")]
+        );
+        assert_eq!(report.bytes_recoverable, "chapter one".len() as u64);
+    }
+
+    #[test]
+    fn scan_excludes_hardlinks_from_recoverable_bytes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "shared").unwrap();
+        fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+        fs::write(dir.path().join("c.txt"), "shared").unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let report = scan_by_content(&config, |_| {}).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 1);
+        assert_eq!(report.hardlinked.len(), 1);
+        assert_eq!(report.bytes_recoverable, "shared".len() as u64);
+    }
+
+    #[test]
+    fn scan_lists_placeholders_as_not_local() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".big.mov.icloud"), "stub").unwrap();
+        fs::write(dir.path().join("a.txt"), "stub").unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let report = scan_by_content(&config, |_| {}).unwrap();
+
+        assert!(report.confirmed_duplicates.is_empty());
+        assert_eq!(report.not_local, vec![dir.path().join("big.mov")]);
+    }
+
+    #[test]
+    fn test_overlapping_roots_count_files_once() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("one.txt"), "same").unwrap();
+        fs::write(sub.join("two.txt"), "same").unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf(), sub],
+            ..Default::default()
+        };
+        let report = scan_by_content(&config, |_| {}).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 1);
+        assert!(report.hardlinked.is_empty());
+        assert_eq!(report.bytes_recoverable, 4);
+    }
+}
@@ -0,0 +1,450 @@
+//! Persistent content-hash cache.
+//!
+//! Maps a file's identity and state — (device, inode, size, mtime) — and a
+//! hash algorithm to the file's hash under it, so originals that haven't
+//! changed aren't re-read on every run. Any metadata change produces a
+//! different key, which is all the invalidation needed; hashes of
+//! different algorithms never stand in for each other.
+//!
+//! Like the scan index, this is a cache: a missing or corrupt file just
+//! means hashing from scratch.
+//!
+//! Verification reads and writes hashes through the [`HashStore`] trait:
+//! [`HashCache`] keeps them in memory, [`FileHashStore`] persists them to
+//! the file above, and embedders can plug in their own (an app's existing
+//! database, say) with [`ScanConfig::hash_store`].
+//!
+//! Structure:
+//! - Types: file keys, the store trait, the cache, the file-backed store
+//! - Pure functions: default path
+//! - Effect functions: lookup-or-hash, load, save
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::default_data_dir;
+use crate::hash::hash_file;
+use crate::platform::file_id;
+use crate::types::{ContentHash, FileId, HashAlgorithm, ScanConfig};
+
+/// Current cache format version (2: keys name their hash algorithm).
+const CACHE_VERSION: u32 = 2;
+
+/// Cache filename within the app data directory.
+const CACHE_FILENAME: &str = "hashes.json";
+
+/// Beyond this many entries, entries not used by the latest run are dropped.
+const MAX_ENTRIES: usize = 200_000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Identity and state of a file, as far as its content is concerned, and
+/// the algorithm its hash is wanted under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileKey {
+    pub dev: u64,
+    pub ino: u64,
+    pub size: u64,
+    pub mtime_secs: i64,
+    pub mtime_nanos: i64,
+    pub algorithm: HashAlgorithm,
+}
+
+/// Where verification keeps hashes between lookups.
+///
+/// Entries are keyed by path and [`FileKey`]: a store must only return a
+/// hash put under the same key, so any change to the file is a miss. Like
+/// any cache, a store may drop entries, and a failed flush never fails a
+/// scan. Shared across verification threads.
+pub trait HashStore: Send + Sync + std::fmt::Debug {
+    /// The hash stored for `path` in the state `key` describes.
+    fn get(&self, path: &Path, key: &FileKey) -> Option<ContentHash>;
+
+    /// Store the hash of `path` in the state `key` describes.
+    fn put(&self, path: &Path, key: FileKey, hash: ContentHash);
+
+    /// Persist what was put, once verification is done.
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// In-memory hash store.
+///
+/// Keyed by [`FileKey`] alone: the same file under another path (a hard
+/// link) hits the same entry.
+#[derive(Debug, Default)]
+pub struct HashCache {
+    inner: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<FileKey, ContentHash>,
+    /// Keys looked up or inserted during this run.
+    used: HashSet<FileKey>,
+}
+
+/// On-disk representation.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: Vec<CacheRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheRecord {
+    #[serde(flatten)]
+    key: FileKey,
+    hash: ContentHash,
+}
+
+/// A [`HashCache`] loaded from a file and saved back to it on flush.
+#[derive(Debug)]
+pub struct FileHashStore {
+    cache: HashCache,
+    path: PathBuf,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Returns the default cache location.
+///
+/// On macOS: ~/Library/Application Support/icloud-dedupe/hashes.json
+pub fn default_hash_cache_path() -> PathBuf {
+    hash_cache_path_in(&default_data_dir())
+}
+
+/// Location of the file within a given state directory.
+pub fn hash_cache_path_in(data_dir: &Path) -> PathBuf {
+    data_dir.join(CACHE_FILENAME)
+}
+
+impl FileKey {
+    /// Build a key from file metadata, for a hash made with `algorithm`.
+    pub fn from_metadata(meta: &fs::Metadata, algorithm: HashAlgorithm) -> Self {
+        let id = file_id(meta).unwrap_or(FileId { dev: 0, ino: 0 });
+        let (mtime_secs, mtime_nanos) = modified_time(meta);
+        FileKey {
+            dev: id.dev,
+            ino: id.ino,
+            size: meta.len(),
+            mtime_secs,
+            mtime_nanos,
+            algorithm,
+        }
+    }
+}
+
+/// Modification time as seconds and nanoseconds since the Unix epoch.
+#[cfg(unix)]
+fn modified_time(meta: &fs::Metadata) -> (i64, i64) {
+    use std::os::unix::fs::MetadataExt;
+    (meta.mtime(), meta.mtime_nsec())
+}
+
+#[cfg(not(unix))]
+fn modified_time(meta: &fs::Metadata) -> (i64, i64) {
+    let since_epoch = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    (since_epoch.as_secs() as i64, since_epoch.subsec_nanos() as i64)
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+impl HashCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached hashes.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// True if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the cached BLAKE3 hash for `path`, hashing (and caching) on
+    /// a miss.
+    pub fn hash(&self, path: &Path) -> io::Result<ContentHash> {
+        self.hash_with(path, HashAlgorithm::Blake3, hash_file)
+    }
+
+    /// Like [`HashCache::hash`], computing misses with `hasher`, which
+    /// must hash with `algorithm`.
+    pub fn hash_with<H>(&self, path: &Path, algorithm: HashAlgorithm, hasher: H) -> io::Result<ContentHash>
+    where
+        H: FnOnce(&Path) -> io::Result<ContentHash>,
+    {
+        hash_through(self, path, algorithm, hasher)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // A panic mid-insert can't leave the map inconsistent; keep going
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HashStore for HashCache {
+    fn get(&self, _path: &Path, key: &FileKey) -> Option<ContentHash> {
+        let mut state = self.lock();
+        let hash = state.entries.get(key).cloned()?;
+        state.used.insert(*key);
+        Some(hash)
+    }
+
+    fn put(&self, _path: &Path, key: FileKey, hash: ContentHash) {
+        let mut state = self.lock();
+        state.entries.insert(key, hash);
+        state.used.insert(key);
+    }
+}
+
+impl FileHashStore {
+    /// Load the store at `path` (empty if missing or unreadable).
+    pub fn open(path: &Path) -> Self {
+        Self {
+            cache: load_hash_cache(path),
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// The hashes loaded and put so far.
+    pub fn cache(&self) -> &HashCache {
+        &self.cache
+    }
+}
+
+impl HashStore for FileHashStore {
+    fn get(&self, path: &Path, key: &FileKey) -> Option<ContentHash> {
+        self.cache.get(path, key)
+    }
+
+    fn put(&self, path: &Path, key: FileKey, hash: ContentHash) {
+        self.cache.put(path, key, hash);
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        save_hash_cache(&self.cache, &self.path)
+    }
+}
+
+/// The hash store a scan uses: `config.hash_store` if set, else the file
+/// at `config.hash_cache_path`, else none.
+pub fn configured_store(config: &ScanConfig) -> Option<Arc<dyn HashStore>> {
+    if let Some(store) = &config.hash_store {
+        return Some(Arc::clone(store));
+    }
+    let path = config.hash_cache_path.as_deref()?;
+    Some(Arc::new(FileHashStore::open(path)))
+}
+
+/// Return the stored `algorithm` hash for `path`, hashing (and storing)
+/// on a miss. `hasher` must hash with `algorithm`.
+///
+/// The hash is only stored if the file didn't change while being read.
+pub fn hash_through<H>(
+    store: &dyn HashStore,
+    path: &Path,
+    algorithm: HashAlgorithm,
+    hasher: H,
+) -> io::Result<ContentHash>
+where
+    H: FnOnce(&Path) -> io::Result<ContentHash>,
+{
+    let key = FileKey::from_metadata(&fs::metadata(path)?, algorithm);
+    if let Some(hash) = store.get(path, &key) {
+        return Ok(hash);
+    }
+
+    let hash = hasher(path)?;
+
+    let after = FileKey::from_metadata(&fs::metadata(path)?, algorithm);
+    if after == key {
+        store.put(path, key, hash.clone());
+    }
+
+    Ok(hash)
+}
+
+/// Load the cache. Missing, unreadable, or outdated files yield an empty cache.
+pub fn load_hash_cache(path: &Path) -> HashCache {
+    let entries = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<CacheFile>(&contents).ok())
+        .filter(|file| file.version == CACHE_VERSION)
+        .map(|file| file.entries.into_iter().map(|r| (r.key, r.hash)).collect())
+        .unwrap_or_default();
+
+    HashCache {
+        inner: Mutex::new(CacheState {
+            entries,
+            used: HashSet::new(),
+        }),
+    }
+}
+
+/// Save the cache, creating the parent directory if needed.
+pub fn save_hash_cache(cache: &HashCache, path: &Path) -> io::Result<()> {
+    let state = cache.lock();
+
+    let over_limit = state.entries.len() > MAX_ENTRIES;
+    let entries = state
+        .entries
+        .iter()
+        .filter(|(key, _)| !over_limit || state.used.contains(key))
+        .map(|(key, hash)| CacheRecord {
+            key: *key,
+            hash: hash.clone(),
+        })
+        .collect();
+
+    let file = CacheFile {
+        version: CACHE_VERSION,
+        entries,
+    };
+    let contents = serde_json::to_string(&file).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize hash cache: {}", e))
+    })?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_default_hash_cache_path_is_reasonable() {
+        let path = default_hash_cache_path();
+        assert!(path.to_string_lossy().contains("icloud-dedupe"));
+        assert!(path.ends_with(CACHE_FILENAME));
+    }
+
+    #[test]
+    fn test_hash_matches_hash_file_and_is_cached() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let cache = HashCache::new();
+        let hash = cache.hash(&file).unwrap();
+
+        assert_eq!(hash, hash_file(&file).unwrap());
+        assert_eq!(cache.len(), 1);
+
+        // Second lookup hits the same entry
+        assert_eq!(cache.hash(&file).unwrap(), hash);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_file_store_persists_on_flush() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+        let path = hash_cache_path_in(dir.path());
+
+        let store = FileHashStore::open(&path);
+        let hash = hash_through(&store, &file, HashAlgorithm::Blake3, hash_file).unwrap();
+        assert!(!path.exists());
+        store.flush().unwrap();
+
+        let reopened = FileHashStore::open(&path);
+        let key = FileKey::from_metadata(&fs::metadata(&file).unwrap(), HashAlgorithm::Blake3);
+        assert_eq!(reopened.get(&file, &key), Some(hash));
+        assert_eq!(reopened.cache().len(), 1);
+    }
+
+    #[test]
+    fn test_algorithms_get_separate_entries() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+        let sha256 = |p: &Path| crate::hash::hash_path_with(p, HashAlgorithm::Sha256);
+
+        let cache = HashCache::new();
+        let blake3 = cache.hash(&file).unwrap();
+        let hash = cache.hash_with(&file, HashAlgorithm::Sha256, sha256).unwrap();
+
+        assert_ne!(hash, blake3);
+        assert_eq!(hash, sha256(&file).unwrap());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.hash(&file).unwrap(), blake3);
+    }
+
+    #[test]
+    fn test_modified_file_gets_new_entry() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let cache = HashCache::new();
+        let before = cache.hash(&file).unwrap();
+
+        fs::write(&file, "hello, world").unwrap();
+        let after = cache.hash(&file).unwrap();
+
+        assert_ne!(before, after);
+        assert_eq!(after, hash_file(&file).unwrap());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+        let cache_path = dir.path().join("state").join(CACHE_FILENAME);
+
+        let cache = HashCache::new();
+        let hash = cache.hash(&file).unwrap();
+        save_hash_cache(&cache, &cache_path).unwrap();
+
+        let loaded = load_hash_cache(&cache_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.hash(&file).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_load_corrupt_cache_is_empty() {
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join(CACHE_FILENAME);
+        fs::write(&cache_path, "{ not json").unwrap();
+
+        assert!(load_hash_cache(&cache_path).is_empty());
+        assert!(load_hash_cache(&dir.path().join("missing.json")).is_empty());
+    }
+
+    #[test]
+    fn test_missing_file_errors() {
+        let dir = TempDir::new().unwrap();
+        let cache = HashCache::new();
+        assert!(cache.hash(&dir.path().join("nope")).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Scan history: what the last scan of each set of roots found.
+//!
+//! After every full scan a small summary is stored per root set, so the
+//! next scan of the same roots can say what changed ("+3 new duplicate
+//! groups, 1.2 GiB more recoverable since 2026-06-02").
+//!
+//! Like the scan index, this is a cache: a missing or corrupt file just
+//! means there is nothing to compare against.
+//!
+//! Structure:
+//! - Types: scan summaries, deltas, the history file
+//! - Pure functions: summarize, diff, lookup, record
+//! - Effect functions: load, save
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::ScanReport;
+
+/// Current history format version.
+const HISTORY_VERSION: u32 = 1;
+
+/// History filename within the app data directory.
+const HISTORY_FILENAME: &str = "scans.json";
+
+/// Root sets remembered; the least recently scanned are dropped first.
+const MAX_SCANS: usize = 64;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Headline numbers of one scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanSummary {
+    /// Roots that were scanned, in the order given.
+    pub roots: Vec<PathBuf>,
+    /// When the scan finished, seconds since the Unix epoch.
+    pub scanned_at: u64,
+    /// Confirmed duplicate groups.
+    pub duplicate_groups: usize,
+    /// Duplicate files across all groups.
+    pub duplicate_files: usize,
+    /// Conflicts whose original is missing.
+    pub orphaned: usize,
+    /// Conflicts whose content differs from the original.
+    pub diverged: usize,
+    /// Bytes recoverable by removing duplicates.
+    pub bytes_recoverable: u64,
+}
+
+/// Change between a previous scan and the current report.
+///
+/// Positive values mean the current scan found more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanDelta {
+    /// When the previous scan finished, seconds since the Unix epoch.
+    pub since: u64,
+    pub duplicate_groups: i64,
+    pub duplicate_files: i64,
+    pub orphaned: i64,
+    pub diverged: i64,
+    pub bytes_recoverable: i64,
+}
+
+/// Last scan summary per root set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanHistory {
+    pub scans: Vec<ScanSummary>,
+}
+
+/// On-disk representation.
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    #[serde(flatten)]
+    history: ScanHistory,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Location of the file within a given state directory.
+pub fn history_path_in(data_dir: &Path) -> PathBuf {
+    data_dir.join(HISTORY_FILENAME)
+}
+
+/// Summarize a report for the history.
+pub fn summarize(report: &ScanReport, roots: &[PathBuf], scanned_at: u64) -> ScanSummary {
+    ScanSummary {
+        roots: roots.to_vec(),
+        scanned_at,
+        duplicate_groups: report.confirmed_duplicates.len(),
+        duplicate_files: report
+            .confirmed_duplicates
+            .iter()
+            .map(|g| g.duplicates.len())
+            .sum(),
+        orphaned: report.orphaned_conflicts.len(),
+        diverged: report.content_diverged.len(),
+        bytes_recoverable: report.bytes_recoverable,
+    }
+}
+
+/// Compare a report against the summary of a previous scan.
+pub fn diff(previous: &ScanSummary, report: &ScanReport) -> ScanDelta {
+    let current = summarize(report, &previous.roots, previous.scanned_at);
+    let change = |now: usize, then: usize| now as i64 - then as i64;
+
+    ScanDelta {
+        since: previous.scanned_at,
+        duplicate_groups: change(current.duplicate_groups, previous.duplicate_groups),
+        duplicate_files: change(current.duplicate_files, previous.duplicate_files),
+        orphaned: change(current.orphaned, previous.orphaned),
+        diverged: change(current.diverged, previous.diverged),
+        bytes_recoverable: current.bytes_recoverable as i64 - previous.bytes_recoverable as i64,
+    }
+}
+
+impl ScanDelta {
+    /// True when nothing changed since the previous scan.
+    pub fn is_empty(&self) -> bool {
+        self.duplicate_groups == 0
+            && self.duplicate_files == 0
+            && self.orphaned == 0
+            && self.diverged == 0
+            && self.bytes_recoverable == 0
+    }
+}
+
+impl ScanHistory {
+    /// The last scan of exactly these roots, if any.
+    pub fn previous(&self, roots: &[PathBuf]) -> Option<&ScanSummary> {
+        self.scans.iter().find(|s| s.roots == roots)
+    }
+
+    /// Record a scan, replacing any earlier one of the same roots.
+    pub fn record(&mut self, summary: ScanSummary) {
+        self.scans.retain(|s| s.roots != summary.roots);
+        self.scans.insert(0, summary);
+        self.scans.truncate(MAX_SCANS);
+    }
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Seconds since the Unix epoch, for [`ScanSummary::scanned_at`].
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Load the history. Missing, unreadable, or outdated files yield an empty one.
+pub fn load_history(path: &Path) -> ScanHistory {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<HistoryFile>(&contents).ok())
+        .filter(|file| file.version == HISTORY_VERSION)
+        .map(|file| file.history)
+        .unwrap_or_default()
+}
+
+/// Save the history, creating the parent directory if needed.
+pub fn save_history(history: &ScanHistory, path: &Path) -> io::Result<()> {
+    let file = HistoryFile {
+        version: HISTORY_VERSION,
+        history: history.clone(),
+    };
+    let contents = serde_json::to_string_pretty(&file).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize scan history: {}", e))
+    })?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+/// Record a finished scan in the history at `path`, returning the previous
+/// scan of the same roots.
+pub fn record_scan(path: &Path, report: &ScanReport, roots: &[PathBuf]) -> io::Result<Option<ScanSummary>> {
+    let mut history = load_history(path);
+    let previous = history.previous(roots).cloned();
+    history.record(summarize(report, roots, now_secs()));
+    save_history(&history, path)?;
+    Ok(previous)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ContentHash, DuplicateGroup};
+    use tempfile::TempDir;
+
+    fn report(groups: usize, bytes: u64) -> ScanReport {
+        ScanReport {
+            confirmed_duplicates: (0..groups)
+                .map(|i| DuplicateGroup {
+                    original: PathBuf::from(format!("/f{}.txt", i)),
+                    hash: ContentHash([0; 32]),
+                    duplicates: vec![PathBuf::from(format!("/f{} 2.txt", i))],
+                    size_bytes: 0,
+                })
+                .collect(),
+            bytes_recoverable: bytes,
+            ..Default::default()
+        }
+    }
+
+    fn roots(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn diff_reports_growth_and_shrinkage() {
+        let previous = summarize(&report(2, 1000), &roots(&["/a"]), 100);
+        let delta = diff(&previous, &report(5, 400));
+
+        assert_eq!(delta.since, 100);
+        assert_eq!(delta.duplicate_groups, 3);
+        assert_eq!(delta.duplicate_files, 3);
+        assert_eq!(delta.bytes_recoverable, -600);
+        assert!(!delta.is_empty());
+    }
+
+    #[test]
+    fn diff_of_same_report_is_empty() {
+        let previous = summarize(&report(2, 1000), &roots(&["/a"]), 100);
+        assert!(diff(&previous, &report(2, 1000)).is_empty());
+    }
+
+    #[test]
+    fn record_replaces_same_roots() {
+        let mut history = ScanHistory::default();
+        history.record(summarize(&report(1, 10), &roots(&["/a"]), 1));
+        history.record(summarize(&report(2, 20), &roots(&["/b"]), 2));
+        history.record(summarize(&report(3, 30), &roots(&["/a"]), 3));
+
+        assert_eq!(history.scans.len(), 2);
+        assert_eq!(history.previous(&roots(&["/a"])).unwrap().scanned_at, 3);
+        assert!(history.previous(&roots(&["/a", "/b"])).is_none());
+    }
+
+    #[test]
+    fn record_scan_returns_previous_and_persists() {
+        let dir = TempDir::new().unwrap();
+        let path = history_path_in(dir.path());
+        let scanned = roots(&["/a"]);
+
+        assert!(record_scan(&path, &report(1, 10), &scanned).unwrap().is_none());
+        let previous = record_scan(&path, &report(4, 40), &scanned).unwrap().unwrap();
+        assert_eq!(previous.duplicate_groups, 1);
+        assert_eq!(load_history(&path).previous(&scanned).unwrap().duplicate_groups, 4);
+    }
+
+    #[test]
+    fn corrupt_history_loads_empty() {
+        let dir = TempDir::new().unwrap();
+        let path = history_path_in(dir.path());
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(load_history(&path), ScanHistory::default());
+    }
+}
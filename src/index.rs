@@ -0,0 +1,514 @@
+//! Persistent scan index for incremental verification.
+//!
+//! Remembers, per conflict candidate, the size and mtime of both the
+//! conflict file and its presumed original along with the last verdict.
+//! When neither file changed since the previous scan, the stored verdict
+//! is reused and no hashing happens. Verdicts only stand for scans with
+//! the hash algorithm they were reached with.
+//!
+//! The index is a cache: a missing, unreadable, or corrupt index file
+//! simply means every candidate is verified again.
+//!
+//! Structure:
+//! - Types: stamps, stored verdicts, the index itself
+//! - Pure functions: lookup, record, prune
+//! - Effect functions: stamp capture, load, save
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::default_data_dir;
+use crate::types::{ConflictCandidate, ContentHash, Divergence, HashAlgorithm, VerificationResult};
+
+/// Current index format version (2: entries name their hash algorithm).
+const INDEX_VERSION: u32 = 2;
+
+/// Index filename within the app data directory.
+const INDEX_FILENAME: &str = "index.json";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Cheap change detector for a file: size plus modification time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// File size in bytes.
+    pub size: u64,
+    /// Modification time, whole seconds since the Unix epoch.
+    pub mtime_secs: u64,
+    /// Sub-second part of the modification time.
+    pub mtime_nanos: u32,
+}
+
+/// Stamps of a candidate's two files, captured before verification.
+///
+/// `None` means the path is missing or not a regular file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandidateStamps {
+    pub conflict: Option<FileStamp>,
+    pub original: Option<FileStamp>,
+}
+
+/// The verdict stored for a candidate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StoredVerdict {
+    Duplicate {
+        hash: ContentHash,
+    },
+    Orphaned,
+    Diverged {
+        conflict_hash: ContentHash,
+        original_hash: ContentHash,
+    },
+    SizeMismatch {
+        conflict_size: u64,
+        original_size: u64,
+    },
+    Hardlinked,
+    EndsDiffer {
+        conflict_hash: ContentHash,
+        original_hash: ContentHash,
+    },
+}
+
+/// One index record, keyed by conflict path in [`ScanIndex`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub conflict: FileStamp,
+    pub original: Option<FileStamp>,
+    /// Algorithm of the hashes behind the verdict.
+    pub algorithm: HashAlgorithm,
+    pub verdict: StoredVerdict,
+}
+
+/// The on-disk index: conflict path → last known state and verdict.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanIndex {
+    /// Index format version.
+    pub version: u32,
+    /// Entries keyed by conflict file path.
+    pub entries: BTreeMap<PathBuf, IndexEntry>,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Returns the default index location.
+///
+/// On macOS: ~/Library/Application Support/icloud-dedupe/index.json
+pub fn default_index_path() -> PathBuf {
+    index_path_in(&default_data_dir())
+}
+
+/// Location of the file within a given state directory.
+pub fn index_path_in(data_dir: &Path) -> PathBuf {
+    data_dir.join(INDEX_FILENAME)
+}
+
+impl Default for ScanIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanIndex {
+    /// Create an empty index at the current format version.
+    pub fn new() -> Self {
+        ScanIndex {
+            version: INDEX_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Reuse the stored verdict if neither file changed since it was
+    /// recorded, and it was reached with `algorithm`.
+    pub fn lookup(
+        &self,
+        candidate: &ConflictCandidate,
+        stamps: &CandidateStamps,
+        algorithm: HashAlgorithm,
+    ) -> Option<VerificationResult> {
+        let entry = self.entries.get(&candidate.path)?;
+
+        if stamps.conflict != Some(entry.conflict) || stamps.original != entry.original || entry.algorithm != algorithm
+        {
+            return None;
+        }
+
+        let conflict = candidate.path.clone();
+        let original = candidate.presumed_original.clone();
+
+        Some(match &entry.verdict {
+            StoredVerdict::Duplicate { hash } => VerificationResult::ConfirmedDuplicate {
+                keep: original,
+                remove: conflict,
+                hash: hash.clone(),
+            },
+            StoredVerdict::Orphaned => VerificationResult::OrphanedConflict {
+                path: conflict,
+                presumed_original: original,
+            },
+            StoredVerdict::Hardlinked => VerificationResult::Hardlinked {
+                path: conflict,
+                original,
+            },
+            StoredVerdict::Diverged {
+                conflict_hash,
+                original_hash,
+            } => VerificationResult::ContentDiverged {
+                conflict_path: conflict,
+                original_path: original,
+                divergence: Divergence::Content {
+                    conflict_hash: conflict_hash.clone(),
+                    original_hash: original_hash.clone(),
+                },
+            },
+            StoredVerdict::EndsDiffer {
+                conflict_hash,
+                original_hash,
+            } => VerificationResult::ContentDiverged {
+                conflict_path: conflict,
+                original_path: original,
+                divergence: Divergence::Ends {
+                    conflict_hash: conflict_hash.clone(),
+                    original_hash: original_hash.clone(),
+                },
+            },
+            StoredVerdict::SizeMismatch {
+                conflict_size,
+                original_size,
+            } => VerificationResult::ContentDiverged {
+                conflict_path: conflict,
+                original_path: original,
+                divergence: Divergence::Size {
+                    conflict_size: *conflict_size,
+                    original_size: *original_size,
+                },
+            },
+        })
+    }
+
+    /// Store a fresh verdict, reached with `algorithm`, with the stamps
+    /// captured before it was computed.
+    ///
+    /// Candidates whose conflict file vanished, and placeholders that were
+    /// never checked, are not recorded.
+    pub fn record(
+        &mut self,
+        candidate: &ConflictCandidate,
+        stamps: &CandidateStamps,
+        algorithm: HashAlgorithm,
+        result: &VerificationResult,
+    ) {
+        let Some(conflict) = stamps.conflict else {
+            self.entries.remove(&candidate.path);
+            return;
+        };
+
+        let verdict = match result {
+            VerificationResult::ConfirmedDuplicate { hash, .. } => {
+                StoredVerdict::Duplicate { hash: hash.clone() }
+            }
+            VerificationResult::OrphanedConflict { .. } => StoredVerdict::Orphaned,
+            VerificationResult::Hardlinked { .. } => StoredVerdict::Hardlinked,
+            VerificationResult::ContentDiverged { divergence, .. } => match divergence {
+                Divergence::Content {
+                    conflict_hash,
+                    original_hash,
+                } => StoredVerdict::Diverged {
+                    conflict_hash: conflict_hash.clone(),
+                    original_hash: original_hash.clone(),
+                },
+                Divergence::Ends {
+                    conflict_hash,
+                    original_hash,
+                } => StoredVerdict::EndsDiffer {
+                    conflict_hash: conflict_hash.clone(),
+                    original_hash: original_hash.clone(),
+                },
+                Divergence::Size {
+                    conflict_size,
+                    original_size,
+                } => StoredVerdict::SizeMismatch {
+                    conflict_size: *conflict_size,
+                    original_size: *original_size,
+                },
+            },
+            // Unhashed verdicts must never stand in for a strict one
+            VerificationResult::NotLocal { .. } | VerificationResult::ProbableDuplicate { .. } => {
+                self.entries.remove(&candidate.path);
+                return;
+            }
+            // Metadata isn't stamped: it's compared afresh on every scan
+            VerificationResult::MetadataDiffers { .. } => {
+                self.entries.remove(&candidate.path);
+                return;
+            }
+        };
+
+        self.entries.insert(
+            candidate.path.clone(),
+            IndexEntry {
+                conflict,
+                original: stamps.original,
+                algorithm,
+                verdict,
+            },
+        );
+    }
+
+    /// Drop entries under `roots` that were not seen in the latest scan.
+    ///
+    /// Entries outside the scanned roots are left alone so scanning one
+    /// folder doesn't forget another.
+    pub fn prune(&mut self, roots: &[PathBuf], seen: &HashSet<&Path>) {
+        self.entries.retain(|path, _| {
+            seen.contains(path.as_path()) || !roots.iter().any(|r| path.starts_with(r))
+        });
+    }
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+impl FileStamp {
+    /// Stamp a regular file. Returns None if missing or not a regular file.
+    pub fn of_file(path: &Path) -> Option<FileStamp> {
+        let meta = fs::metadata(path).ok()?;
+        if !meta.is_file() {
+            return None;
+        }
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(FileStamp {
+            size: meta.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+impl CandidateStamps {
+    /// Stamp both files of a candidate.
+    pub fn capture(candidate: &ConflictCandidate) -> Self {
+        CandidateStamps {
+            conflict: FileStamp::of_file(&candidate.path),
+            original: FileStamp::of_file(&candidate.presumed_original),
+        }
+    }
+}
+
+/// Load an index, treating a missing, unreadable, or outdated file as empty.
+pub fn load_index(path: &Path) -> ScanIndex {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<ScanIndex>(&contents).ok())
+        .filter(|index| index.version == INDEX_VERSION)
+        .unwrap_or_default()
+}
+
+/// Save an index, creating its parent directory if needed.
+pub fn save_index(index: &ScanIndex, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string(index).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize index: {}", e))
+    })?;
+    fs::write(path, contents)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ConflictPattern, FileKind, ItemState};
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn stamp(size: u64) -> FileStamp {
+        FileStamp {
+            size,
+            mtime_secs: 1_700_000_000,
+            mtime_nanos: 0,
+        }
+    }
+
+    fn candidate(conflict: &str, original: &str) -> ConflictCandidate {
+        ConflictCandidate {
+            path: PathBuf::from(conflict),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: PathBuf::from(original),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        }
+    }
+
+    fn duplicate_result(c: &ConflictCandidate) -> VerificationResult {
+        VerificationResult::ConfirmedDuplicate {
+            keep: c.presumed_original.clone(),
+            remove: c.path.clone(),
+            hash: ContentHash([7; 32]),
+        }
+    }
+
+    #[test]
+    fn lookup_hits_when_stamps_unchanged() {
+        let c = candidate("/d/a Copy.txt", "/d/a.txt");
+        let stamps = CandidateStamps {
+            conflict: Some(stamp(5)),
+            original: Some(stamp(5)),
+        };
+        let mut index = ScanIndex::new();
+        index.record(&c, &stamps, HashAlgorithm::Blake3, &duplicate_result(&c));
+
+        let hit = index.lookup(&c, &stamps, HashAlgorithm::Blake3);
+        assert!(matches!(hit, Some(VerificationResult::ConfirmedDuplicate { .. })));
+    }
+
+    #[test]
+    fn lookup_misses_when_either_file_changed() {
+        let c = candidate("/d/a Copy.txt", "/d/a.txt");
+        let stamps = CandidateStamps {
+            conflict: Some(stamp(5)),
+            original: Some(stamp(5)),
+        };
+        let mut index = ScanIndex::new();
+        index.record(&c, &stamps, HashAlgorithm::Blake3, &duplicate_result(&c));
+
+        let conflict_changed = CandidateStamps {
+            conflict: Some(stamp(6)),
+            ..stamps
+        };
+        let original_gone = CandidateStamps {
+            original: None,
+            ..stamps
+        };
+
+        assert!(index.lookup(&c, &conflict_changed, HashAlgorithm::Blake3).is_none());
+        assert!(index.lookup(&c, &original_gone, HashAlgorithm::Blake3).is_none());
+        assert!(index.lookup(&c, &stamps, HashAlgorithm::Sha256).is_none());
+    }
+
+    #[test]
+    fn size_mismatch_verdict_round_trips() {
+        let c = candidate("/d/a Copy.txt", "/d/a.txt");
+        let stamps = CandidateStamps {
+            conflict: Some(stamp(9)),
+            original: Some(stamp(5)),
+        };
+        let result = VerificationResult::ContentDiverged {
+            conflict_path: c.path.clone(),
+            original_path: c.presumed_original.clone(),
+            divergence: Divergence::Size {
+                conflict_size: 9,
+                original_size: 5,
+            },
+        };
+        let mut index = ScanIndex::new();
+        index.record(&c, &stamps, HashAlgorithm::Blake3, &result);
+
+        let hit = index.lookup(&c, &stamps, HashAlgorithm::Blake3);
+        assert!(matches!(
+            hit,
+            Some(VerificationResult::ContentDiverged {
+                divergence: Divergence::Size { conflict_size: 9, original_size: 5 },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn record_skips_vanished_conflict() {
+        let c = candidate("/d/a Copy.txt", "/d/a.txt");
+        let stamps = CandidateStamps {
+            conflict: None,
+            original: Some(stamp(5)),
+        };
+        let mut index = ScanIndex::new();
+        index.record(&c, &stamps, HashAlgorithm::Blake3, &duplicate_result(&c));
+
+        assert!(index.entries.is_empty());
+    }
+
+    #[test]
+    fn prune_only_touches_scanned_roots() {
+        let stamps = CandidateStamps {
+            conflict: Some(stamp(1)),
+            original: None,
+        };
+        let orphan = |c: &ConflictCandidate| VerificationResult::OrphanedConflict {
+            path: c.path.clone(),
+            presumed_original: c.presumed_original.clone(),
+        };
+
+        let kept = candidate("/a/x Copy.txt", "/a/x.txt");
+        let gone = candidate("/a/y Copy.txt", "/a/y.txt");
+        let elsewhere = candidate("/b/z Copy.txt", "/b/z.txt");
+
+        let mut index = ScanIndex::new();
+        for c in [&kept, &gone, &elsewhere] {
+            index.record(c, &stamps, HashAlgorithm::Blake3, &orphan(c));
+        }
+
+        let seen: HashSet<&Path> = [kept.path.as_path()].into_iter().collect();
+        index.prune(&[PathBuf::from("/a")], &seen);
+
+        assert!(index.entries.contains_key(&kept.path));
+        assert!(!index.entries.contains_key(&gone.path));
+        assert!(index.entries.contains_key(&elsewhere.path));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("nested").join("index.json");
+
+        let c = candidate("/d/a Copy.txt", "/d/a.txt");
+        let stamps = CandidateStamps {
+            conflict: Some(stamp(5)),
+            original: Some(stamp(5)),
+        };
+        let mut index = ScanIndex::new();
+        index.record(&c, &stamps, HashAlgorithm::Blake3, &duplicate_result(&c));
+
+        save_index(&index, &path).unwrap();
+        let loaded = load_index(&path);
+
+        assert_eq!(loaded.entries, index.entries);
+    }
+
+    #[test]
+    fn load_treats_corrupt_file_as_empty() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("index.json");
+        fs::write(&path, "not json").unwrap();
+
+        let loaded = load_index(&path);
+        assert!(loaded.entries.is_empty());
+        assert_eq!(loaded.version, INDEX_VERSION);
+    }
+
+    #[test]
+    fn file_stamp_reflects_size() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("f.txt");
+        let mut f = fs::File::create(&path).unwrap();
+        f.write_all(b"12345").unwrap();
+
+        assert_eq!(FileStamp::of_file(&path).unwrap().size, 5);
+        assert!(FileStamp::of_file(temp.path()).is_none());
+        assert!(FileStamp::of_file(&temp.path().join("missing")).is_none());
+    }
+}
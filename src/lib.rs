@@ -1,10 +1,23 @@
 //! icloud-dedupe: Detect and remove iCloud sync conflict duplicates.
 
+pub mod cancel;
+pub mod config;
+pub mod containers;
+pub mod content;
 pub mod hash;
+pub mod hash_cache;
+pub mod history;
+pub mod index;
+pub mod messages;
+pub mod metrics;
 pub mod pattern;
 pub mod platform;
 pub mod quarantine;
 pub mod report;
 pub mod scanner;
+pub mod snapshot;
+pub mod stats;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod tui;
 pub mod types;
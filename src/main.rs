@@ -2,24 +2,57 @@
 //!
 //! Detect and remove iCloud sync conflict duplicates on macOS.
 
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use humansize::{format_size, BINARY};
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
-use rayon::prelude::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use walkdir::WalkDir;
 
-use icloud_dedupe::platform::{detect_icloud, ICloudState};
+use icloud_dedupe::cancel::{cancel_on_interrupt, CancelToken};
+use icloud_dedupe::config::{
+    default_config_path, load_config, parse_duration, parse_size, resolve_data_dir, resolve_quarantine_dir, Config,
+    DATA_DIR_ENV, QUARANTINE_DIR_ENV,
+};
+use icloud_dedupe::containers::{list_containers, pick_container};
+use icloud_dedupe::content::scan_by_content;
+use icloud_dedupe::hash::DEFAULT_PARALLEL_HASH_MIN_SIZE;
+use icloud_dedupe::hash_cache::hash_cache_path_in;
+use icloud_dedupe::history::{history_path_in, now_secs, record_scan};
+use icloud_dedupe::index::index_path_in;
+use icloud_dedupe::metrics::{format_statsd, RunMetrics};
+use icloud_dedupe::platform::{
+    detect_icloud, detect_onedrive, evict, icloud_container_path, is_dataless, placeholder_target, scan_root,
+    sync_state, ICloudState, OneDriveState,
+};
 use icloud_dedupe::quarantine::{
-    default_quarantine_dir, init_quarantine, load_manifest, purge_quarantine,
-    quarantine_duplicates, restore_file,
+    completed_groups, export_quarantine, import_quarantine, init_quarantine, load_export,
+    check_kept_file, journal_path, load_manifest, lock_quarantine, manifest_path, partition_selected, purge_expired,
+    purge_quarantine_cancellable, purge_selected,
+    quarantine_duplicates_cancellable, receipt_age, recover_journal, remap_originals, plan_restore, restore_receipts,
+    quarantine_status, run_settings, select_duplicates, session_receipts, sessions, verify_manifest,
+};
+use icloud_dedupe::messages::{count, current_locale, Locale, Noun};
+use icloud_dedupe::report::{
+    format_date, format_eta, format_jsonl_records, format_jsonl_summary, format_report_colored, parse_report,
+    retain_categories,
+};
+use icloud_dedupe::scanner::{
+    assemble_report, dedupe_resolved_roots, discover, finish_report,
+    normalize_path, physical_recoverable, resolve_roots, root_warnings, scan_streaming, scan_streaming_with,
+    settled_groups, syncing_files, wait_for_sync, DEFAULT_BATCH_SIZE,
+    verify_candidates,
 };
-use icloud_dedupe::report::format_report;
-use icloud_dedupe::scanner::{assemble_report, find_candidates, find_candidates_with_progress, normalize_path, verify_candidate};
+use icloud_dedupe::snapshot::{prepare_snapshot, rematch_live};
+use icloud_dedupe::stats::{history_log_path, load_history_log, summarize, Tally};
 use icloud_dedupe::types::{
-    ConflictCandidate, OutputFormat, QuarantineConfig, ScanConfig, ScanReport,
+    ConflictCandidate, Destination, DuplicateGroup, HashAlgorithm, KeepPolicy, Manifest, MetadataField, KeptFileCheck, OutputFormat, Provenance, PurgeSelection, QuarantineConfig, ReceiptCheck, ReportCategory, RestoreConflict, QuarantineReceipt, ScanConfig,
+    ScanProgress, ScanReport, VerificationLevel, DEFAULT_IGNORES,
 };
 
 #[derive(Parser)]
@@ -30,9 +63,23 @@ struct Cli {
     /// Directory to scan (default: auto-detect iCloud location)
     path: Option<PathBuf>,
 
-    /// Maximum directory depth
-    #[arg(long)]
-    max_depth: Option<usize>,
+    /// Directory for all tool state: quarantine, index, hash cache
+    /// (default: $ICLOUD_DEDUPE_DATA_DIR, then `data_dir` in the config file)
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Where quarantined files go (default: $ICLOUD_DEDUPE_QUARANTINE, then
+    /// `[quarantine] dir` in the config file, then `quarantine` in the data dir)
+    #[arg(long, global = true, value_name = "DIR")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Color human reports: auto (when printing to a terminal and NO_COLOR
+    /// is unset), always, or never
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    color: ColorArg,
+
+    #[command(flatten)]
+    scan: ScanArgs,
 
     #[command(subcommand)]
     command: Option<Commands>,
@@ -42,30 +89,83 @@ struct Cli {
 enum Commands {
     /// Scan for conflict files and report findings (no modifications)
     Scan {
-        /// Directory to scan (default: iCloud location)
-        path: Option<PathBuf>,
+        /// Directories to scan (default: iCloud location)
+        paths: Vec<PathBuf>,
 
         /// Output format
         #[arg(long, value_enum, default_value = "human")]
-        format: OutputFormatArg,
+        format: ReportFormatArg,
+
+        /// Print only the summary counts, no per-file listings (for quick
+        /// checks and cron mail)
+        #[arg(long, conflicts_with = "format")]
+        summary: bool,
 
-        /// Maximum directory depth
+        /// Exit with status 2 when confirmed duplicates are found
         #[arg(long)]
-        max_depth: Option<usize>,
+        fail_if_found: bool,
+
+        /// Show only these findings; repeatable
+        #[arg(long, value_enum, value_name = "CATEGORY", value_delimiter = ',')]
+        only: Vec<CategoryArg>,
+
+        /// Write the report to this file instead of stdout, replacing it
+        /// only once the report is complete
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Scan the Desktop folder synced through iCloud Drive
+        #[arg(long, conflicts_with = "provider")]
+        desktop: bool,
+
+        /// Scan the Documents folder synced through iCloud Drive
+        #[arg(long, conflicts_with = "provider")]
+        documents: bool,
+
+        #[command(flatten)]
+        scan: ScanArgs,
     },
 
     /// Move confirmed duplicates to quarantine
+    #[command(args_conflicts_with_subcommands = true)]
     Quarantine {
-        /// Directory to scan (default: iCloud location)
-        path: Option<PathBuf>,
+        #[command(subcommand)]
+        action: Option<QuarantineCommands>,
+
+        /// Directories to scan (default: iCloud location)
+        paths: Vec<PathBuf>,
 
         /// Preview only, don't actually move files
         #[arg(long)]
         dry_run: bool,
 
-        /// Maximum directory depth
+        /// Move duplicates to the Trash instead, so Finder's "Put Back" works (macOS)
         #[arg(long)]
-        max_depth: Option<usize>,
+        to_trash: bool,
+
+        /// Clone duplicates into quarantine and remove the originals (instant
+        /// and space-free on APFS; moves them elsewhere)
+        #[arg(long = "clone", conflicts_with = "to_trash")]
+        clone_files: bool,
+
+        /// Only quarantine this duplicate, leaving the rest of its group (repeatable)
+        #[arg(long = "file", value_name = "PATH")]
+        files: Vec<PathBuf>,
+
+        /// Wait up to TIMEOUT (default 5m) for iCloud to finish syncing the
+        /// files involved; groups still syncing are left in place
+        #[arg(
+            long,
+            value_name = "TIMEOUT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "5m",
+            value_parser = parse_duration
+        )]
+        wait_for_sync: Option<Duration>,
+
+        #[command(flatten)]
+        scan: ScanArgs,
     },
 
     /// Restore files from quarantine
@@ -74,8 +174,25 @@ enum Commands {
         #[arg(long)]
         all: bool,
 
+        /// Restore every file moved by one quarantine run (see `status --sessions`)
+        #[arg(long, value_name = "ID", conflicts_with = "all")]
+        session: Option<String>,
+
         /// Specific receipt ID to restore
+        #[arg(conflicts_with_all = ["all", "session"])]
         id: Option<String>,
+
+        /// What to do when something already exists at the original path
+        #[arg(long, value_enum, default_value = "fail")]
+        on_conflict: RestoreConflictArg,
+
+        /// Show what would be restored, and what would block it, without moving anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Files to restore at once (default: one per CPU)
+        #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+        jobs: usize,
     },
 
     /// Permanently delete all quarantined files
@@ -83,11 +200,306 @@ enum Commands {
         /// Skip confirmation prompt
         #[arg(long)]
         force: bool,
+
+        /// Show what would be deleted without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only these receipt IDs
+        #[arg(long = "id", value_name = "ID", num_args = 1..)]
+        ids: Vec<String>,
+
+        /// Only files quarantined at least this long ago (e.g. 30d, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        older_than: Option<Duration>,
+
+        /// Only files whose original path matches this glob (a pattern
+        /// without `/` matches the file name)
+        #[arg(long = "match", value_name = "GLOB")]
+        pattern: Option<String>,
+    },
+
+    /// Re-hash quarantined files and report any missing or corrupted
+    Verify {
+        /// Output format
+        #[arg(long, value_enum, default_value = "human")]
+        format: OutputFormatArg,
     },
 
     /// Show quarantine status and contents
-    Status,
+    Status {
+        /// List quarantine runs instead of individual files
+        #[arg(long)]
+        sessions: bool,
+
+        /// Output format (json includes every receipt, session, and age)
+        #[arg(long, value_enum, default_value = "human")]
+        format: OutputFormatArg,
+    },
+
+    /// Show space recovered over time and what became of each quarantine run
+    Stats {
+        /// Output format
+        #[arg(long, value_enum, default_value = "human")]
+        format: OutputFormatArg,
+    },
+
+    /// List iCloud app containers with their size and likely conflict
+    /// copies, and pick one to scan
+    Containers {
+        /// Scan this container: its number in the list, bundle ID, or app
+        /// name (asked for when omitted in a terminal)
+        #[arg(long = "scan", value_name = "CONTAINER")]
+        pick: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "human")]
+        format: OutputFormatArg,
+
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+
+    /// Remove local copies of iCloud files, keeping them in the cloud, to
+    /// free disk space once duplicates are gone (macOS)
+    Evict {
+        /// Files or directories to evict (directories are walked)
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Only files at least this big (e.g. 500K, 100M, 1G)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        min_size: Option<u64>,
+
+        /// Show what would be evicted without evicting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Work with saved scan reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuarantineCommands {
+    /// Save the quarantine manifest (and optionally its files) for
+    /// archiving or another machine
+    Export {
+        /// JSON file to write, or a new directory with --include-files
+        dest: PathBuf,
+
+        /// Copy the quarantined files along with the manifest
+        #[arg(long)]
+        include_files: bool,
+    },
+
+    /// Add an exported quarantine to this one
+    Import {
+        /// Export file or directory
+        src: PathBuf,
+
+        /// Re-map original paths from one home directory to another
+        /// (asked interactively when the homes differ)
+        #[arg(long, value_name = "OLD=NEW")]
+        remap: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Render a report saved with `scan --format json`
+    View {
+        /// Saved JSON report
+        file: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "human")]
+        format: ReportFormatArg,
+
+        /// Show only these findings; repeatable
+        #[arg(long, value_enum, value_name = "CATEGORY", value_delimiter = ',')]
+        only: Vec<CategoryArg>,
+    },
+}
+
+/// Scan tuning flags shared by every command that scans.
+#[derive(Args, Clone)]
+struct ScanArgs {
+    /// Sync service whose folder to scan when no path is given
+    #[arg(long, value_enum, default_value = "icloud")]
+    provider: ProviderArg,
+
+    /// Maximum directory depth
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Follow symbolic links (each directory is still walked only once)
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Find identical files by content, whatever their names (hashes every
+    /// file that shares its size with another)
+    #[arg(long)]
+    by_content: bool,
+
+    /// Re-verify every candidate instead of reusing verdicts for unchanged files
+    #[arg(long)]
+    no_index: bool,
+
+    /// Re-hash every file instead of reusing cached hashes
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Verify against a fresh APFS local snapshot instead of live files
+    #[arg(long)]
+    snapshot: bool,
+
+    /// Verify against an existing APFS local snapshot (implies --snapshot)
+    #[arg(long, value_name = "NAME")]
+    use_snapshot: Option<String>,
+
+    /// Download evicted candidates and their originals from iCloud and
+    /// verify them, waiting up to TIMEOUT (default 5m); files still not
+    /// downloaded are skipped
+    #[arg(
+        long,
+        value_name = "TIMEOUT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5m",
+        value_parser = parse_duration,
+        conflicts_with_all = ["snapshot", "use_snapshot"]
+    )]
+    materialize: Option<Duration>,
+
+    /// Which file of each duplicate group to keep
+    #[arg(long, value_enum, default_value = "original-name")]
+    keep: KeepPolicyArg,
+
+    /// When a conflict's original is missing, look in nearby folders for an
+    /// identical file to treat as the original
+    #[arg(long)]
+    find_originals: bool,
+
+    /// Stop after walking this many files and report what was found
+    #[arg(long, value_name = "N")]
+    max_files: Option<usize>,
+
+    /// Stop after scanning this long (e.g. 90s, 30m, 1h30m) and report what
+    /// was found
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    max_duration: Option<Duration>,
+
+    /// Leave out files modified within this long (e.g. 10m, 2h), which a
+    /// sync or an open document may still be writing
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    ignore_newer_than: Option<Duration>,
 
+    /// Also skip directories with this name (`*.ext` matches an extension);
+    /// repeatable
+    #[arg(long, value_name = "NAME")]
+    ignore: Vec<String>,
+
+    /// Walk directories skipped by default (.git, node_modules, ...) and
+    /// the config file's ignore list
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Report same-size, same-date pairs as probable duplicates without
+    /// hashing them (never quarantined)
+    #[arg(long, conflicts_with = "strict")]
+    fast: bool,
+
+    /// Hash every same-size pair (the default)
+    #[arg(long)]
+    strict: bool,
+
+    /// Content hash to confirm duplicates with (default: the config
+    /// file's, else blake3)
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    hash_algorithm: Option<HashAlgorithmArg>,
+
+    /// Also compare this metadata: byte-identical copies that differ in it
+    /// are reported apart and never quarantined
+    #[arg(long, value_enum, value_name = "FIELDS", value_delimiter = ',')]
+    compare_metadata: Vec<MetadataFieldArg>,
+
+    /// Verify candidates in batches of N while walking, keeping memory
+    /// bounded on very large trees (no overall ETA)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    batch_size: Option<u64>,
+}
+
+impl ScanArgs {
+    fn snapshot_mode(&self) -> bool {
+        self.snapshot || self.use_snapshot.is_some()
+    }
+
+    /// Reject flag combinations that can't work together.
+    fn check(&self) -> Result<(), String> {
+        if self.by_content && self.snapshot_mode() {
+            return Err("--by-content can't be combined with snapshot mode".to_string());
+        }
+        if self.batch_size.is_some() && (self.by_content || self.snapshot_mode()) {
+            return Err("--batch-size can't be combined with --by-content or snapshot mode".to_string());
+        }
+        if !self.compare_metadata.is_empty() && self.by_content {
+            return Err("--compare-metadata can't be combined with --by-content".to_string());
+        }
+        Ok(())
+    }
+
+    fn config(&self, roots: Vec<PathBuf>, settings: &Config, data_dir: &Path) -> ScanConfig {
+        let mut ignore = match (&settings.scan.ignore, self.no_ignore) {
+            (_, true) => Vec::new(),
+            (Some(list), false) => list.clone(),
+            (None, false) => DEFAULT_IGNORES.iter().map(|s| s.to_string()).collect(),
+        };
+        ignore.extend(self.ignore.iter().cloned());
+        let algorithm = self.hash_algorithm(settings);
+
+        ScanConfig {
+            roots,
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+            ignore,
+            hash_algorithm: algorithm,
+            hash_buffer_size: settings.hashing.buffer_size,
+            parallel_hash_min_size: Some(
+                settings
+                    .hashing
+                    .parallel_min_size
+                    .unwrap_or(DEFAULT_PARALLEL_HASH_MIN_SIZE),
+            ),
+            readahead: settings.hashing.readahead.unwrap_or(true),
+            retry: settings.hashing.retry_policy(),
+            verification_level: if self.fast {
+                VerificationLevel::Fast
+            } else {
+                VerificationLevel::Strict
+            },
+            compare_metadata: self.compare_metadata.iter().map(|&f| f.into()).collect(),
+            index_path: (!self.no_index).then(|| index_path_in(data_dir)),
+            hash_cache_path: (!self.no_cache).then(|| hash_cache_path_in(data_dir)),
+            keep_policy: self.keep.into(),
+            search_orphans: self.find_originals,
+            max_files: self.max_files,
+            max_duration: self.max_duration,
+            ignore_newer_than: self.ignore_newer_than,
+            materialize: self.materialize,
+            ..Default::default()
+        }
+    }
+
+    fn hash_algorithm(&self, settings: &Config) -> HashAlgorithm {
+        self.hash_algorithm
+            .map(Into::into)
+            .or(settings.hashing.algorithm)
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Clone, Copy, clap::ValueEnum)]
@@ -105,24 +517,398 @@ impl From<OutputFormatArg> for OutputFormat {
     }
 }
 
+/// Formats a scan report can be written in.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ReportFormatArg {
+    Human,
+    Json,
+    /// One row per duplicate, orphan, or diverged file
+    Csv,
+    /// Tables per category, for notes and issues
+    Markdown,
+    /// A self-contained page with collapsible groups and sortable tables
+    Html,
+    /// One JSON record per finding, written as the scan verifies them
+    Jsonl,
+}
+
+impl From<ReportFormatArg> for OutputFormat {
+    fn from(arg: ReportFormatArg) -> Self {
+        match arg {
+            ReportFormatArg::Human => OutputFormat::Human,
+            ReportFormatArg::Json => OutputFormat::Json,
+            ReportFormatArg::Csv => OutputFormat::Csv,
+            ReportFormatArg::Markdown => OutputFormat::Markdown,
+            ReportFormatArg::Html => OutputFormat::Html,
+            ReportFormatArg::Jsonl => OutputFormat::Jsonl,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ProviderArg {
+    Icloud,
+    Onedrive,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorArg {
+    fn enabled(self) -> bool {
+        match self {
+            ColorArg::Always => true,
+            ColorArg::Never => false,
+            ColorArg::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+/// Where a report goes: stdout, or a temporary file renamed over the
+/// destination once the report is complete, so an interrupted scan never
+/// leaves a truncated report behind.
+enum ReportSink {
+    Stdout,
+    File {
+        dest: PathBuf,
+        temp: PathBuf,
+        /// None once finished.
+        file: Option<std::io::BufWriter<std::fs::File>>,
+    },
+}
+
+impl ReportSink {
+    fn open(dest: Option<&Path>) -> Result<Self, String> {
+        let Some(dest) = dest else {
+            return Ok(ReportSink::Stdout);
+        };
+        let name = dest.file_name().unwrap_or_default().to_string_lossy();
+        let temp = dest.with_file_name(format!(".{}.tmp", name));
+        let file = std::fs::File::create(&temp)
+            .map_err(|e| format!("Cannot write {}: {}", dest.display(), e))?;
+        Ok(ReportSink::File {
+            dest: dest.to_path_buf(),
+            temp,
+            file: Some(std::io::BufWriter::new(file)),
+        })
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        match self {
+            ReportSink::Stdout => {
+                print!("{}", text);
+                Ok(())
+            }
+            ReportSink::File { dest, file, .. } => file
+                .as_mut()
+                .expect("sink not finished")
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Cannot write {}: {}", dest.display(), e)),
+        }
+    }
+
+    /// Flush, sync, and move the report into place.
+    fn finish(mut self) -> Result<(), String> {
+        let ReportSink::File { dest, temp, file } = &mut self else {
+            return Ok(());
+        };
+        let file = file.take().expect("sink finished once");
+        let failed = |e: std::io::Error| format!("Cannot write {}: {}", dest.display(), e);
+        let file = file.into_inner().map_err(|e| failed(e.into_error()))?;
+        file.sync_all().map_err(failed)?;
+        if let Err(e) = std::fs::rename(&*temp, &*dest) {
+            let _ = std::fs::remove_file(&*temp);
+            return Err(failed(e));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ReportSink {
+    fn drop(&mut self) {
+        // Unfinished: the scan failed or was interrupted
+        if let ReportSink::File { temp, file: Some(_), .. } = self {
+            let _ = std::fs::remove_file(temp);
+        }
+    }
+}
+
+/// Findings `--only` can limit a report to.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum CategoryArg {
+    /// Confirmed duplicates, hard links, probable duplicates, and metadata
+    /// mismatches
+    Duplicates,
+    /// Conflicts whose originals are missing
+    Orphans,
+    /// Conflicts that differ from their originals
+    Diverged,
+    /// Files skipped for errors or symlinks, and ones not downloaded
+    Skipped,
+}
+
+impl From<CategoryArg> for ReportCategory {
+    fn from(arg: CategoryArg) -> Self {
+        match arg {
+            CategoryArg::Duplicates => ReportCategory::Duplicates,
+            CategoryArg::Orphans => ReportCategory::Orphans,
+            CategoryArg::Diverged => ReportCategory::Diverged,
+            CategoryArg::Skipped => ReportCategory::Skipped,
+        }
+    }
+}
+
+/// How a scan report is printed.
+struct ReportOutput {
+    format: OutputFormat,
+    color: bool,
+    /// Categories to show (empty = all).
+    only: Vec<ReportCategory>,
+    /// File to write instead of stdout.
+    file: Option<PathBuf>,
+}
+
+impl ReportOutput {
+    fn new(format: OutputFormat, color: ColorArg, only: &[CategoryArg], file: Option<PathBuf>) -> Self {
+        let only = only.iter().map(|&c| c.into()).collect();
+        // Files get color only when asked for
+        let color = match (color, &file) {
+            (ColorArg::Auto, Some(_)) => false,
+            (color, _) => color.enabled(),
+        };
+        ReportOutput { format, color, only, file }
+    }
+
+    fn sink(&self) -> Result<ReportSink, String> {
+        ReportSink::open(self.file.as_deref())
+    }
+
+    /// Write the whole report to stdout or the output file.
+    fn emit(&self, report: &ScanReport) -> Result<(), String> {
+        let mut sink = self.sink()?;
+        sink.write(&self.render(report))?;
+        sink.finish()
+    }
+
+    /// `report` as limited by `--only`.
+    fn filter<'r>(&self, report: &'r ScanReport) -> Cow<'r, ScanReport> {
+        if self.only.is_empty() {
+            return Cow::Borrowed(report);
+        }
+        let mut filtered = report.clone();
+        retain_categories(&mut filtered, &self.only);
+        Cow::Owned(filtered)
+    }
+
+    fn render(&self, report: &ScanReport) -> String {
+        format_report_colored(&self.filter(report), self.format, current_locale(), self.color)
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum KeepPolicyArg {
+    /// The file without a conflict suffix
+    OriginalName,
+    /// The most recently modified copy
+    Newest,
+    /// The largest copy
+    Largest,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum MetadataFieldArg {
+    /// Extended attributes (system bookkeeping ones aside)
+    Xattrs,
+    /// Finder tags
+    Tags,
+    /// Creation date
+    Created,
+}
+
+impl From<MetadataFieldArg> for MetadataField {
+    fn from(arg: MetadataFieldArg) -> Self {
+        match arg {
+            MetadataFieldArg::Xattrs => MetadataField::Xattrs,
+            MetadataFieldArg::Tags => MetadataField::Tags,
+            MetadataFieldArg::Created => MetadataField::Created,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum HashAlgorithmArg {
+    /// BLAKE3: fast and cryptographic
+    Blake3,
+    /// SHA-256: slower, expected by most audit tooling
+    Sha256,
+    /// XXH3-128: fastest, not cryptographic
+    #[value(name = "xxh3-128")]
+    Xxh3_128,
+}
+
+impl From<HashAlgorithmArg> for HashAlgorithm {
+    fn from(arg: HashAlgorithmArg) -> Self {
+        match arg {
+            HashAlgorithmArg::Blake3 => HashAlgorithm::Blake3,
+            HashAlgorithmArg::Sha256 => HashAlgorithm::Sha256,
+            HashAlgorithmArg::Xxh3_128 => HashAlgorithm::Xxh3_128,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum RestoreConflictArg {
+    /// Leave it and report the file as failed
+    Fail,
+    /// Restore alongside as "name (restored).ext"
+    Rename,
+    /// Replace it if its content is identical
+    OverwriteIfIdentical,
+}
+
+impl From<RestoreConflictArg> for RestoreConflict {
+    fn from(arg: RestoreConflictArg) -> Self {
+        match arg {
+            RestoreConflictArg::Fail => RestoreConflict::Fail,
+            RestoreConflictArg::Rename => RestoreConflict::Rename,
+            RestoreConflictArg::OverwriteIfIdentical => RestoreConflict::OverwriteIfIdentical,
+        }
+    }
+}
+
+impl From<KeepPolicyArg> for KeepPolicy {
+    fn from(arg: KeepPolicyArg) -> Self {
+        match arg {
+            KeepPolicyArg::OriginalName => KeepPolicy::OriginalName,
+            KeepPolicyArg::Newest => KeepPolicy::NewestMtime,
+            KeepPolicyArg::Largest => KeepPolicy::Largest,
+        }
+    }
+}
+
 fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    let settings = match load_config(&default_config_path()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let data_dir = normalize_path(&resolve_data_dir(
+        cli.data_dir,
+        std::env::var_os(DATA_DIR_ENV).map(PathBuf::from),
+        &settings,
+    ))
+    .path;
+    let quarantine_dir = normalize_path(&resolve_quarantine_dir(
+        cli.quarantine_dir,
+        std::env::var_os(QUARANTINE_DIR_ENV).map(PathBuf::from),
+        &settings,
+        &data_dir,
+    ))
+    .path;
+
+    // Dry runs and read-only commands leave the quarantine exactly as it is
+    if changes_quarantine(&cli.command) {
+        recover_quarantine(&quarantine_dir);
+        if let Some(retention) = settings.quarantine.retention() {
+            expire_quarantine(retention, &quarantine_dir);
+        }
+    }
+
+    let mut found = false;
     let result = match cli.command {
-        None => cmd_interactive(cli.path, cli.max_depth),
-        Some(Commands::Scan { path, format, max_depth }) => cmd_scan(path, format.into(), max_depth),
-        Some(Commands::Quarantine { path, dry_run, max_depth }) => cmd_quarantine(path, dry_run, max_depth),
-        Some(Commands::Restore { all, id }) => cmd_restore(all, id),
-        Some(Commands::Purge { force }) => cmd_purge(force),
-        Some(Commands::Status) => cmd_status(),
+        None => cmd_interactive(cli.path, cli.scan, &settings, &data_dir),
+        Some(Commands::Scan { paths, format, summary, fail_if_found, only, output, desktop, documents, scan }) => {
+            with_run_metrics(&settings, "scan", |run| {
+                let paths = with_synced_folders(paths, desktop, documents)?;
+                let format = if summary { OutputFormat::Summary } else { format.into() };
+                let output = ReportOutput::new(format, cli.color, &only, output);
+                found = cmd_scan(paths, output, scan, &settings, &data_dir, run)? && fail_if_found;
+                Ok(())
+            })
+        }
+        Some(Commands::Quarantine { action: Some(QuarantineCommands::Export { dest, include_files }), .. }) => {
+            cmd_export(&dest, include_files, &quarantine_dir)
+        }
+        Some(Commands::Quarantine { action: Some(QuarantineCommands::Import { src, remap }), .. }) => {
+            cmd_import(&src, remap.as_deref(), &quarantine_dir)
+        }
+        Some(Commands::Quarantine { action: None, paths, dry_run, to_trash, clone_files, files, wait_for_sync, scan }) => {
+            let target = QuarantineConfig {
+                quarantine_dir: quarantine_dir.clone(),
+                dry_run,
+                destination: if to_trash { Destination::Trash } else { Destination::Quarantine },
+                clone_files,
+                protected: settings.quarantine.protected(dirs::home_dir().as_deref()),
+                sync_wait: wait_for_sync,
+                ..Default::default()
+            };
+            with_run_metrics(&settings, "quarantine", |run| {
+                cmd_quarantine(paths, &files, target, scan, &settings, &data_dir, run)
+            })
+        }
+        Some(Commands::Restore { all, session, id, on_conflict, dry_run, jobs }) => {
+            let target = QuarantineConfig {
+                quarantine_dir: quarantine_dir.clone(),
+                dry_run,
+                ..Default::default()
+            };
+            cmd_restore(all, session, id, on_conflict.into(), target, jobs)
+        }
+        Some(Commands::Purge { force, dry_run, ids, older_than, pattern }) => {
+            let selection = PurgeSelection { ids, older_than, pattern };
+            cmd_purge(force, dry_run, &selection, &quarantine_dir)
+        }
+        Some(Commands::Verify { format }) => cmd_verify(format.into(), &quarantine_dir),
+        Some(Commands::Status { sessions, format }) => {
+            cmd_status(sessions, format.into(), settings.quarantine.retention(), &quarantine_dir)
+        }
+        Some(Commands::Stats { format }) => cmd_stats(format.into(), &quarantine_dir),
+        Some(Commands::Containers { pick, format, scan }) => {
+            let output = ReportOutput::new(OutputFormat::Human, cli.color, &[], None);
+            cmd_containers(pick, format.into(), output, scan, &settings, &data_dir)
+        }
+        Some(Commands::Evict { paths, min_size, dry_run }) => cmd_evict(&paths, min_size.unwrap_or(0), dry_run),
+        Some(Commands::Report { command: ReportCommands::View { file, format, only } }) => {
+            cmd_report_view(&file, ReportOutput::new(format.into(), cli.color, &only, None))
+        }
     };
 
+    if let Err(e) = &result {
+        eprintln!("Error: {}", e);
+    }
+    ExitCode::from(exit_status(&result, found))
+}
+
+// ============================================================================
+// EXIT STATUS
+// ============================================================================
+
+/// The command did what was asked (for `scan --fail-if-found`: and found
+/// no duplicates).
+const EXIT_OK: u8 = 0;
+/// The command failed, or was interrupted.
+const EXIT_ERROR: u8 = 1;
+/// `scan --fail-if-found` found confirmed duplicates.
+const EXIT_FOUND: u8 = 2;
+
+/// Exit status for a command's result. `found` is whether `scan
+/// --fail-if-found` found duplicates; an error wins over findings.
+fn exit_status(result: &Result<(), String>, found: bool) -> u8 {
     match result {
-        Ok(()) => ExitCode::SUCCESS,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            ExitCode::FAILURE
-        }
+        Err(_) => EXIT_ERROR,
+        Ok(()) if found => EXIT_FOUND,
+        Ok(()) => EXIT_OK,
     }
 }
 
@@ -130,23 +916,21 @@ fn main() -> ExitCode {
 // PATH RESOLUTION
 // ============================================================================
 
-/// Resolve scan path: use provided path or detect iCloud location.
-fn resolve_scan_path(path: Option<PathBuf>) -> Result<PathBuf, String> {
-    match path {
-        Some(p) => Ok(p),
-        None => {
-            // Auto-detect iCloud location
+/// Detect the sync folders to scan when no path is given.
+fn default_scan_roots(provider: ProviderArg) -> Result<Vec<PathBuf>, String> {
+    match provider {
+        ProviderArg::Icloud => {
             let state = detect_icloud().map_err(|e| e.to_string())?;
 
             match &state {
                 ICloudState::DriveEnabled { container, .. } => {
                     eprintln!("Detected: {}", state);
-                    Ok(container.clone())
+                    Ok(vec![container.clone()])
                 }
                 ICloudState::DriveDisabled { container } => {
                     eprintln!("Detected: {}", state);
                     eprintln!("Note: iCloud Drive is disabled, scanning app containers only.");
-                    Ok(container.clone())
+                    Ok(vec![container.clone()])
                 }
                 ICloudState::NotConfigured { expected } => {
                     Err(format!(
@@ -159,22 +943,147 @@ fn resolve_scan_path(path: Option<PathBuf>) -> Result<PathBuf, String> {
                         expected.display()
                     ))
                 }
+                ICloudState::Unsupported => Err("iCloud Drive is only detected on macOS.\n\
+                     Specify the synced folder to scan: icloud-dedupe scan <path>"
+                    .to_string()),
+            }
+        }
+        ProviderArg::Onedrive => {
+            let state = detect_onedrive().map_err(|e| e.to_string())?;
+
+            match &state {
+                OneDriveState::Found { roots } => {
+                    eprintln!("Detected: {}", state);
+                    Ok(roots.clone())
+                }
+                OneDriveState::NotFound { searched } => {
+                    let searched: Vec<String> = searched.iter().map(|p| format!("- {}", p.display())).collect();
+                    Err(format!(
+                        "No OneDrive folder found.\n\
+                         Searched:\n\
+                         {}\n\
+                         \n\
+                         Either:\n\
+                         - Sign in to the OneDrive app\n\
+                         - Specify a path explicitly: icloud-dedupe scan <path>",
+                        searched.join("\n")
+                    ))
+                }
             }
         }
     }
 }
 
-// ============================================================================
-// PROGRESS HELPERS
-// ============================================================================
+/// Add the iCloud-synced Desktop and/or Documents folders to the scan paths.
+fn with_synced_folders(mut paths: Vec<PathBuf>, desktop: bool, documents: bool) -> Result<Vec<PathBuf>, String> {
+    if !desktop && !documents {
+        return Ok(paths);
+    }
 
-fn spinner(msg: &str) -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg}")
-            .unwrap(),
-    );
+    let state = detect_icloud().map_err(|e| e.to_string())?;
+    let ICloudState::DriveEnabled { desktop_documents: Some(folders), .. } = state else {
+        return Err(format!(
+            "Desktop & Documents aren't synced through iCloud Drive.\n\
+             Detected: {}\n\
+             \n\
+             Either:\n\
+             - Turn on \"Desktop & Documents Folders\" in iCloud Drive settings\n\
+             - Specify a path explicitly: icloud-dedupe scan ~/Desktop",
+            state
+        ));
+    };
+
+    if desktop {
+        paths.push(folders.desktop);
+    }
+    if documents {
+        paths.push(folders.documents);
+    }
+    Ok(paths)
+}
+
+/// Resolve, normalize, and de-duplicate the scan roots given on the command line.
+///
+/// With no paths, falls back to the detected folders of `provider`.
+/// Normalization warnings go to stderr so they never mix with report output.
+fn resolve_scan_roots(paths: Vec<PathBuf>, provider: ProviderArg) -> Result<Vec<PathBuf>, String> {
+    let paths = if paths.is_empty() {
+        default_scan_roots(provider)?
+    } else {
+        paths
+    };
+
+    let normalized: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| {
+            let n = normalize_path(p);
+            for warning in &n.warnings {
+                eprintln!("Note: {}", warning);
+            }
+            n.path
+        })
+        .collect();
+
+    let roots = dedupe_resolved_roots(&resolve_roots(&normalized));
+    if roots.len() < normalized.len() {
+        eprintln!(
+            "Note: Ignoring {} path(s) already covered by another scan root",
+            normalized.len() - roots.len()
+        );
+    }
+
+    Ok(roots)
+}
+
+/// Resolve scan roots; in snapshot mode, also make them canonical so they
+/// can be mapped onto the snapshot mount.
+fn prepare_scan_roots(paths: Vec<PathBuf>, args: &ScanArgs) -> Result<Vec<PathBuf>, String> {
+    let roots = resolve_scan_roots(paths, args.provider)?;
+    if !args.snapshot_mode() {
+        return Ok(roots);
+    }
+
+    roots
+        .iter()
+        .map(|r| {
+            r.canonicalize()
+                .map_err(|e| format!("Cannot resolve {}: {}", r.display(), e))
+        })
+        .collect()
+}
+
+/// Print the list of roots being scanned, with their real location when
+/// a symlink leads elsewhere.
+fn print_scan_roots(roots: &[PathBuf]) {
+    for root in resolve_roots(roots) {
+        if root.is_redirected() {
+            eprintln!("Scanning: {} → {}", root.given.display(), root.real.display());
+        } else {
+            eprintln!("Scanning: {}", root.given.display());
+        }
+    }
+    eprintln!();
+}
+
+/// Warnings about roots that resolve into or out of iCloud, or overlap.
+fn scan_root_warnings(roots: &[PathBuf]) -> Vec<String> {
+    let icloud = dirs::home_dir()
+        .map(|home| icloud_container_path(&home))
+        .map(|c| c.canonicalize().unwrap_or(c));
+    root_warnings(&resolve_roots(roots), icloud.as_deref())
+}
+
+// ============================================================================
+// PROGRESS HELPERS
+// ============================================================================
+
+fn spinner(msg: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap(),
+    );
     pb.set_message(msg.to_string());
     pb.enable_steady_tick(Duration::from_millis(100));
     pb
@@ -192,48 +1101,94 @@ fn progress_bar(total: u64, msg: &str) -> ProgressBar {
     pb
 }
 
+/// Progress bar measured in bytes, for verification.
+fn byte_progress_bar(msg: &str) -> ProgressBar {
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {binary_bytes}/{binary_total_bytes} {msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    pb.set_message(msg.to_string());
+    pb
+}
+
+/// Spinner text for the discovery phase.
+fn discovery_message(p: &ScanProgress) -> String {
+    format!(
+        "Scanned {} files, found {} candidates ({})...",
+        p.files_scanned,
+        p.candidates_found,
+        format_size(p.bytes_discovered, BINARY)
+    )
+}
+
 // ============================================================================
 // COMMAND HANDLERS
 // ============================================================================
 
-fn cmd_scan(path: Option<PathBuf>, format: OutputFormat, max_depth: Option<usize>) -> Result<(), String> {
-    let resolved = resolve_scan_path(path)?;
-    let normalized = normalize_path(&resolved);
-
-    // Print warnings to stderr so they don't interfere with JSON output
-    for warning in &normalized.warnings {
-        eprintln!("Note: {}", warning);
-    }
+/// Scan and print the report. Ok(true) when confirmed duplicates were found.
+fn cmd_scan(
+    paths: Vec<PathBuf>,
+    output: ReportOutput,
+    args: ScanArgs,
+    settings: &Config,
+    data_dir: &Path,
+    run: &mut RunMetrics,
+) -> Result<bool, String> {
+    args.check()?;
+    let format = output.format;
+    let roots = prepare_scan_roots(paths, &args)?;
 
     let show_progress = format == OutputFormat::Human;
 
     if show_progress {
-        eprintln!("Scanning: {}", normalized.path.display());
-        eprintln!();
+        print_scan_roots(&roots);
     }
 
-    let config = ScanConfig {
-        roots: vec![normalized.path],
-        max_depth,
-        ..Default::default()
-    };
+    let warnings = scan_root_warnings(&roots);
+    let mut config = args.config(roots, settings, data_dir);
+    config.cancel = cancel_on_interrupt();
+
+    if args.by_content {
+        let mut report = content_report(&config, show_progress)?;
+        stop_if_interrupted(&config.cancel, "scan incomplete, no report written")?;
+        report.warnings = warnings;
+        run.record_report(&report);
+        output.emit(&report)?;
+        return Ok(!report.confirmed_duplicates.is_empty());
+    }
+
+    if format == OutputFormat::Jsonl && !args.snapshot_mode() {
+        let batch_size = args.batch_size.map_or(DEFAULT_BATCH_SIZE, |n| n as usize);
+        let report = stream_jsonl(&config, batch_size, warnings, &output, run)?;
+        remember_scan(data_dir, &config, &report);
+        return Ok(!report.confirmed_duplicates.is_empty());
+    }
+
+    if let Some(batch_size) = args.batch_size {
+        let result = streamed_report(&config, batch_size as usize, show_progress, run);
+        stop_if_interrupted(&config.cancel, "scan incomplete, no report written")?;
+        let mut report = result?;
+        report.warnings = warnings;
+        run.record_report(&report);
+        remember_scan(data_dir, &config, &report);
+        output.emit(&report)?;
+        return Ok(!report.confirmed_duplicates.is_empty());
+    }
 
     // Phase 1: Discovery
-    let candidates = if show_progress {
+    let discovery = if show_progress {
         let sp = spinner("Discovering conflict patterns...");
-        let result = find_candidates_with_progress(&config, |scanned, found| {
-            sp.set_message(format!(
-                "Scanned {} files, found {} candidates...",
-                scanned, found
-            ));
-        });
+        let result = discover(&config, |p| sp.set_message(discovery_message(p)));
         match result {
-            Ok(c) => {
+            Ok(d) => {
                 sp.finish_with_message(format!(
                     "Scanned files, found {} candidates",
-                    c.len()
+                    d.candidates.len()
                 ));
-                c
+                d
             }
             Err(e) => {
                 sp.finish_and_clear();
@@ -241,87 +1196,172 @@ fn cmd_scan(path: Option<PathBuf>, format: OutputFormat, max_depth: Option<usize
             }
         }
     } else {
-        find_candidates(&config).map_err(|e| e.to_string())?
+        discover(&config, |_| {}).map_err(|e| e.to_string())?
     };
+    stop_if_interrupted(&config.cancel, "scan incomplete, no report written")?;
+    let candidates = discovery.candidates;
+    run.candidates = candidates.len();
 
     if candidates.is_empty() {
-        if show_progress {
+        let mut report = ScanReport {
+            symlink_skips: discovery.symlink_skips,
+            warnings,
+            ..Default::default()
+        };
+        finish_report(&mut report, &config);
+        remember_scan(data_dir, &config, &report);
+        if show_progress && output.file.is_none() && report.symlink_skips.is_empty() && report.warnings.is_empty() {
             println!("No conflict patterns found.");
         } else {
-            println!("{}", format_report(&ScanReport::default(), format));
+            output.emit(&report)?;
         }
-        return Ok(());
+        return Ok(false);
     }
 
     // Phase 2: Verification (parallel)
-    let report = if show_progress {
-        build_report_with_progress(&candidates)
-    } else {
-        build_report(&candidates)
-    };
+    let mut report = verify_report(&candidates, &config, &args, show_progress)?;
+    stop_if_interrupted(&config.cancel, "scan incomplete, no report written")?;
+    report.symlink_skips = discovery.symlink_skips;
+    report.warnings = warnings;
+    run.record_report(&report);
+    remember_scan(data_dir, &config, &report);
 
-    print!("{}", format_report(&report, format));
+    output.emit(&report)?;
 
-    Ok(())
+    Ok(!report.confirmed_duplicates.is_empty())
 }
 
-fn cmd_quarantine(path: Option<PathBuf>, dry_run: bool, max_depth: Option<usize>) -> Result<(), String> {
-    let resolved = resolve_scan_path(path)?;
-    let normalized = normalize_path(&resolved);
-
-    for warning in &normalized.warnings {
-        eprintln!("Note: {}", warning);
+fn cmd_quarantine(
+    paths: Vec<PathBuf>,
+    files: &[PathBuf],
+    target: QuarantineConfig,
+    args: ScanArgs,
+    settings: &Config,
+    data_dir: &Path,
+    run: &mut RunMetrics,
+) -> Result<(), String> {
+    args.check()?;
+    let QuarantineConfig { dry_run, destination, sync_wait, .. } = target;
+    if destination == Destination::Trash && !cfg!(target_os = "macos") {
+        return Err("--to-trash is only supported on macOS".to_string());
     }
-
-    eprintln!("Scanning: {}", normalized.path.display());
-    eprintln!();
-
-    let config = ScanConfig {
-        roots: vec![normalized.path],
-        max_depth,
-        ..Default::default()
+    // Fail before scanning if another run holds the quarantine
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(lock_quarantine(&target).map_err(|e| e.to_string())?)
     };
+    let roots = prepare_scan_roots(paths, &args)?;
+    print_scan_roots(&roots);
+    for warning in scan_root_warnings(&roots) {
+        eprintln!("Warning: {}", warning);
+    }
 
-    // Phase 1: Discovery
-    let sp = spinner("Discovering conflict patterns...");
+    let mut config = args.config(roots, settings, data_dir);
+    config.cancel = cancel_on_interrupt();
 
-    let candidates = match find_candidates_with_progress(&config, |scanned, found| {
-        sp.set_message(format!(
-            "Scanned {} files, found {} candidates...",
-            scanned, found
-        ));
-    }) {
-        Ok(c) => {
-            sp.finish_with_message(format!("Scanned files, found {} candidates", c.len()));
-            c
+    let mut report = if args.by_content {
+        let report = content_report(&config, true)?;
+        for (path, reason) in &report.symlink_skips {
+            eprintln!("Note: Skipped {}: {}", path.display(), reason);
         }
-        Err(e) => {
-            sp.finish_and_clear();
-            return Err(e.to_string());
+        run.record_report(&report);
+        report
+    } else {
+        match conflict_report(&config, &args, run)? {
+            Some(report) => report,
+            None => {
+                println!("No conflict patterns found.");
+                return Ok(());
+            }
         }
     };
+    stop_if_interrupted(&config.cancel, "scan incomplete, nothing was moved")?;
+    if let Some(reason) = &report.partial {
+        eprintln!("Note: Partial scan, {}; only what was verified will be moved", reason);
+    }
+    if !report.probable_duplicates.is_empty() {
+        eprintln!(
+            "Note: {} not hashed and left in place; run without --fast to verify them",
+            count(Locale::English, report.probable_duplicates.len(), Noun::ProbableDuplicate)
+        );
+    }
+    if !report.metadata_differs.is_empty() {
+        eprintln!(
+            "Note: {} left in place: same content, different metadata",
+            count(Locale::English, report.metadata_differs.len(), Noun::MetadataMismatch)
+        );
+    }
 
-    sp.finish_with_message(format!("Found {} candidates", candidates.len()));
-
-    if candidates.is_empty() {
-        println!("No conflict patterns found.");
-        return Ok(());
+    // Snapshot hashes only count if the live files haven't moved on since
+    if args.snapshot_mode() {
+        let (groups, changed) = rematch_live(&report.confirmed_duplicates);
+        for path in &changed {
+            eprintln!("Note: Changed since snapshot, skipping: {}", path.display());
+        }
+        replace_groups(&mut report, groups);
     }
 
-    // Phase 2: Verification (parallel)
-    let report = build_report_with_progress(&candidates);
+    // Hand-picked duplicates only; the rest of their groups stay
+    if !files.is_empty() {
+        let files: Vec<PathBuf> = files
+            .iter()
+            .map(|f| {
+                let f = normalize_path(f).path;
+                std::path::absolute(&f).unwrap_or(f)
+            })
+            .collect();
+        let groups = select_duplicates(&report.confirmed_duplicates, &files)
+            .map_err(|path| format!("Not a confirmed duplicate: {}", path.display()))?;
+        replace_groups(&mut report, groups);
+    }
 
     if report.confirmed_duplicates.is_empty() {
         println!("No confirmed duplicates found.");
         return Ok(());
     }
 
+    // Moving files iCloud is still syncing is how conflicts start
+    let syncing = syncing_files(&report.confirmed_duplicates, sync_state);
+    if let Some(timeout) = sync_wait
+        && !syncing.is_empty()
+    {
+        let sp = spinner(&syncing_message(syncing.len()));
+        let syncing = wait_for_sync(&report.confirmed_duplicates, timeout, &config.cancel, sync_state, |n| {
+            sp.set_message(syncing_message(n))
+        });
+        sp.finish_and_clear();
+        stop_if_interrupted(&config.cancel, "waiting for iCloud, nothing was moved")?;
+        if !syncing.is_empty() {
+            eprintln!(
+                "Note: {} still syncing after {}s; leaving their groups in place",
+                count(Locale::English, syncing.len(), Noun::File),
+                timeout.as_secs()
+            );
+            let groups = settled_groups(&report.confirmed_duplicates, &syncing);
+            replace_groups(&mut report, groups);
+            if report.confirmed_duplicates.is_empty() {
+                println!("No confirmed duplicates left to move.");
+                return Ok(());
+            }
+        }
+    } else if !syncing.is_empty() {
+        eprintln!(
+            "Warning: iCloud is still syncing {}; moving them mid-sync can create new conflicts:",
+            count(Locale::English, syncing.len(), Noun::File)
+        );
+        for (path, state) in &syncing {
+            eprintln!("  {} ({})", path.display(), state.name());
+        }
+        eprintln!("Wait for iCloud to finish, or rerun with --wait-for-sync");
+    }
+
     let total_files: usize = report.confirmed_duplicates.iter().map(|g| g.duplicates.len()).sum();
 
     if dry_run {
         println!();
-        println!("DRY RUN - would quarantine {} files ({}):",
-            total_files,
+        println!("DRY RUN - would quarantine {} ({}):",
+            count(Locale::English, total_files, Noun::File),
             format_size(report.bytes_recoverable, BINARY)
         );
         for group in &report.confirmed_duplicates {
@@ -333,98 +1373,286 @@ fn cmd_quarantine(path: Option<PathBuf>, dry_run: bool, max_depth: Option<usize>
     }
 
     println!();
-    println!("Quarantining {} files ({})...",
-        total_files,
+    println!("Quarantining {} ({})...",
+        count(Locale::English, total_files, Noun::File),
         format_size(report.bytes_recoverable, BINARY)
     );
 
     let quarantine_config = QuarantineConfig {
-        quarantine_dir: default_quarantine_dir(),
-        dry_run: false,
-        preserve_structure: true,
+        hash_algorithm: config.hash_algorithm,
+        ..target
+    };
+    let place = match destination {
+        Destination::Quarantine => "quarantine",
+        Destination::Trash => "the Trash",
     };
 
-    let manifest = quarantine_duplicates(&report.confirmed_duplicates, &quarantine_config)
-        .map_err(|e| e.to_string())?;
+    let settings = run_settings(&config, &quarantine_config);
+    let manifest = match quarantine_duplicates_cancellable(
+        &report.confirmed_duplicates,
+        &quarantine_config,
+        &settings,
+        Some(&report.stats.id).filter(|id| !id.is_empty()).map(String::as_str),
+        &config.cancel,
+    ) {
+        Ok(manifest) => manifest,
+        Err(e) if e.kind() == std::io::ErrorKind::StorageFull => {
+            return Err(volume_full(&e, &report.confirmed_duplicates, &quarantine_config));
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    run.files_quarantined = manifest.quarantined.len();
+    run.bytes_quarantined = manifest.quarantined.iter().map(|r| r.size_bytes).sum();
 
-    println!(
-        "Done. {} files moved to quarantine.",
-        manifest.quarantined.len()
-    );
-    println!("Quarantine location: {}", quarantine_config.quarantine_dir.display());
+    if config.cancel.is_cancelled() {
+        println!(
+            "Interrupted. {} of {} moved to {}; the manifest lists them.",
+            manifest.quarantined.len(),
+            count(Locale::English, total_files, Noun::File),
+            place
+        );
+    } else {
+        println!(
+            "Done. {} moved to {}.",
+            count(Locale::English, manifest.quarantined.len(), Noun::File),
+            place
+        );
+    }
+    match destination {
+        Destination::Quarantine => {
+            println!("Quarantine location: {}", quarantine_config.quarantine_dir.display())
+        }
+        Destination::Trash => println!(
+            "Manifest: {} (Finder's Put Back works too)",
+            manifest_path(&quarantine_config).display()
+        ),
+    }
     println!();
+    if let Some(session) = manifest.quarantined.first().and_then(|r| r.session.as_deref()) {
+        println!("To restore this run: icloud-dedupe restore --session {}", session);
+    }
     println!("To restore: icloud-dedupe restore --all");
     println!("To purge:   icloud-dedupe purge");
 
-    Ok(())
+    stop_if_interrupted(&config.cancel, "quarantine incomplete")
 }
 
-fn cmd_restore(all: bool, id: Option<String>) -> Result<(), String> {
-    let config = QuarantineConfig {
-        quarantine_dir: default_quarantine_dir(),
-        ..Default::default()
-    };
+/// Explain a quarantine run stopped by a full volume: which groups were
+/// finished, and how to make room.
+fn volume_full(
+    error: &std::io::Error,
+    groups: &[DuplicateGroup],
+    quarantine_config: &QuarantineConfig,
+) -> String {
+    // Nothing was moved if the up-front check failed; otherwise this run's
+    // receipts were just added to the manifest
+    let completed = load_manifest(quarantine_config)
+        .map(|manifest| completed_groups(groups, &manifest))
+        .unwrap_or_default();
+    if !completed.is_empty() {
+        println!("Completed {} of {} groups:", completed.len(), groups.len());
+        for original in &completed {
+            println!("  {}", original.display());
+        }
+    }
 
-    let config = init_quarantine(&config).map_err(|e| e.to_string())?;
-    let manifest = load_manifest(&config).map_err(|e| format!("No quarantine found: {}", e))?;
+    eprintln!("Note: `icloud-dedupe purge` deletes what's already quarantined to free space.");
+    eprintln!("Note: `--data-dir DIR` moves the quarantine to a volume with more room.");
+    error.to_string()
+}
+
+fn cmd_restore(
+    all: bool,
+    session: Option<String>,
+    id: Option<String>,
+    on_conflict: RestoreConflict,
+    target: QuarantineConfig,
+    jobs: usize,
+) -> Result<(), String> {
+    let dry_run = target.dry_run;
+    let config = init_quarantine(&target).map_err(|e| e.to_string())?;
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(lock_quarantine(&config).map_err(|e| e.to_string())?)
+    };
+    let manifest = load_manifest(&config).map_err(manifest_unavailable)?;
 
     if manifest.quarantined.is_empty() {
         println!("Quarantine is empty.");
         return Ok(());
     }
 
-    if all {
-        let pb = progress_bar(manifest.quarantined.len() as u64, "Restoring...");
-
-        let mut restored = 0;
-        let mut failed = 0;
-
-        for receipt in &manifest.quarantined {
-            match restore_file(receipt) {
-                Ok(()) => {
-                    restored += 1;
-                }
-                Err(e) => {
-                    pb.println(format!("  Failed: {} - {}", receipt.original_path.display(), e));
-                    failed += 1;
-                }
-            }
-            pb.inc(1);
+    let single = id.is_some();
+    let receipts = if all {
+        manifest.quarantined.iter().collect()
+    } else if let Some(session) = session {
+        let receipts = session_receipts(&manifest, &session);
+        if receipts.is_empty() {
+            return Err(format!("Session not found: {}", session));
         }
-
-        pb.finish_with_message("Done");
-        println!();
-        println!("Restored: {}, Failed: {}", restored, failed);
+        receipts
     } else if let Some(id) = id {
         let receipt = manifest
             .quarantined
             .iter()
             .find(|r| r.id == id)
             .ok_or_else(|| format!("Receipt not found: {}", id))?;
+        vec![receipt]
+    } else {
+        return Err("Specify --all, --session ID, or a receipt ID".to_string());
+    };
 
-        restore_file(receipt).map_err(|e| e.to_string())?;
-        println!("Restored: {}", receipt.original_path.display());
+    for receipt in &receipts {
+        if let Some(check) = check_kept_file(receipt).filter(|c| *c != KeptFileCheck::Unchanged) {
+            eprintln!(
+                "Warning: {} was quarantined as a duplicate of a file that {}",
+                receipt.original_path.display(),
+                describe_kept_change(check)
+            );
+        }
+    }
+
+    if dry_run {
+        preview_restore(&receipts, on_conflict);
+    } else if single {
+        let cancel = CancelToken::new();
+        let summary = restore_receipts(&receipts, on_conflict, &config, 1, &cancel, |_, _| {})
+            .map_err(|e| e.to_string())?;
+        if let Some((_, e)) = summary.failed.first() {
+            return Err(e.clone());
+        }
+        println!("Restored: {}", summary.restored[0].1.display());
     } else {
-        return Err("Specify --all or a receipt ID".to_string());
+        restore_with_progress(&receipts, on_conflict, &config, jobs)?;
     }
 
     Ok(())
 }
 
-fn cmd_purge(force: bool) -> Result<(), String> {
+/// Why the manifest couldn't be used, for commands that need one.
+fn manifest_unavailable(error: std::io::Error) -> String {
+    if error.kind() == std::io::ErrorKind::Unsupported {
+        error.to_string()
+    } else {
+        format!("No quarantine found: {}", error)
+    }
+}
+
+/// How a kept file changed since quarantine, as a verb phrase.
+fn describe_kept_change(check: KeptFileCheck) -> &'static str {
+    match check {
+        KeptFileCheck::Unchanged => "is unchanged",
+        KeptFileCheck::Gone => "has since been moved or deleted",
+        KeptFileCheck::Replaced => "has since been replaced",
+    }
+}
+
+/// Print where each receipt would be restored, or what would stop it.
+fn preview_restore(receipts: &[&QuarantineReceipt], on_conflict: RestoreConflict) {
+    println!("DRY RUN - would restore {}:", count(Locale::English, receipts.len(), Noun::File));
+
+    let mut blocked = 0;
+    for receipt in receipts {
+        match plan_restore(receipt, on_conflict) {
+            Ok(target) if target == receipt.original_path => {
+                println!("  [{}] {}", receipt.id, target.display());
+            }
+            Ok(target) => {
+                println!(
+                    "  [{}] {} (as {})",
+                    receipt.id,
+                    receipt.original_path.display(),
+                    target.display()
+                );
+            }
+            Err(e) => {
+                println!("  [{}] {} - blocked: {}", receipt.id, receipt.original_path.display(), e);
+                blocked += 1;
+            }
+        }
+    }
+
+    println!();
+    println!("Would restore: {}, Blocked: {}", receipts.len() - blocked, blocked);
+}
+
+/// Swap in a narrowed set of duplicate groups, recounting what they free.
+fn replace_groups(report: &mut ScanReport, groups: Vec<DuplicateGroup>) {
+    report.bytes_recoverable = groups
+        .iter()
+        .flat_map(|g| &g.duplicates)
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+    report.bytes_recoverable_physical = physical_recoverable(&groups);
+    report.confirmed_duplicates = groups;
+}
+
+/// Restore several receipts in parallel with a progress bar, stopping on Ctrl-C.
+fn restore_with_progress(
+    receipts: &[&QuarantineReceipt],
+    on_conflict: RestoreConflict,
+    config: &QuarantineConfig,
+    jobs: usize,
+) -> Result<(), String> {
+    let cancel = cancel_on_interrupt();
+    let pb = progress_bar(receipts.len() as u64, "Restoring...");
+
+    let summary = restore_receipts(receipts, on_conflict, config, jobs, &cancel, |receipt, result| {
+        match result {
+            Ok(path) if *path != receipt.original_path => {
+                pb.println(format!("  Restored {} as {}", receipt.original_path.display(), path.display()));
+            }
+            Ok(_) => {}
+            Err(e) => pb.println(format!("  Failed: {} - {}", receipt.original_path.display(), e)),
+        }
+        pb.inc(1);
+    })
+    .map_err(|e| e.to_string())?;
+
+    pb.finish_with_message("Done");
+    println!();
+    println!("Restored: {}, Failed: {}", summary.restored.len(), summary.failed.len());
+    stop_if_interrupted(
+        &cancel,
+        &format!("{} files not attempted, still in quarantine", summary.not_attempted),
+    )
+}
+
+fn cmd_purge(
+    force: bool,
+    dry_run: bool,
+    selection: &PurgeSelection,
+    quarantine_dir: &Path,
+) -> Result<(), String> {
     let config = QuarantineConfig {
-        quarantine_dir: default_quarantine_dir(),
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        dry_run,
         ..Default::default()
     };
 
     let config = init_quarantine(&config).map_err(|e| e.to_string())?;
-    let manifest = load_manifest(&config).map_err(|e| format!("No quarantine found: {}", e))?;
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(lock_quarantine(&config).map_err(|e| e.to_string())?)
+    };
+    let manifest = load_manifest(&config).map_err(manifest_unavailable)?;
 
     if manifest.quarantined.is_empty() {
         println!("Quarantine is empty.");
         return Ok(());
     }
 
+    if dry_run {
+        return preview_purge(&manifest, selection);
+    }
+
+    if !selection.is_empty() {
+        return purge_some(&manifest, selection, &config, force);
+    }
+
     let total_bytes: u64 = manifest.quarantined.iter().map(|r| r.size_bytes).sum();
 
     println!(
@@ -433,37 +1661,304 @@ fn cmd_purge(force: bool) -> Result<(), String> {
         format_size(total_bytes, BINARY)
     );
 
-    if !force {
-        eprint!("Continue? [y/N] ");
+    if !force && !prompt_yes_no("Continue? [y/N] ", false)? {
+        println!("Aborted.");
+        return Ok(());
+    }
 
-        let mut input = String::new();
-        std::io::stdin()
-            .read_line(&mut input)
-            .map_err(|e| e.to_string())?;
+    let cancel = cancel_on_interrupt();
+    let purged =
+        purge_quarantine_cancellable(&manifest, &config, &cancel).map_err(|e| e.to_string())?;
+    println!("Purged {}.", count(Locale::English, purged, Noun::File));
+    stop_if_interrupted(
+        &cancel,
+        &format!(
+            "{} files still in quarantine",
+            manifest.quarantined.len() - purged
+        ),
+    )?;
 
-        if !input.trim().eq_ignore_ascii_case("y") {
-            println!("Aborted.");
-            return Ok(());
+    Ok(())
+}
+
+fn cmd_export(dest: &Path, include_files: bool, quarantine_dir: &Path) -> Result<(), String> {
+    let config = QuarantineConfig {
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let _lock = lock_quarantine(&config).map_err(|e| e.to_string())?;
+    let exported = export_quarantine(&config, dest, include_files)
+        .map_err(|e| format!("Cannot export to {}: {}", dest.display(), e))?;
+    println!("Exported {} to {}", count(Locale::English, exported, Noun::File), dest.display());
+    Ok(())
+}
+
+fn cmd_import(src: &Path, remap: Option<&str>, quarantine_dir: &Path) -> Result<(), String> {
+    let config = QuarantineConfig {
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let _lock = lock_quarantine(&config).map_err(|e| e.to_string())?;
+    let mut export = load_export(src).map_err(|e| format!("Cannot read {}: {}", src.display(), e))?;
+
+    let mapping = match remap {
+        Some(spec) => {
+            let (from, to) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("--remap expects OLD=NEW, got '{}'", spec))?;
+            Some((PathBuf::from(from), PathBuf::from(to)))
         }
+        None => match (&export.home, dirs::home_dir()) {
+            (Some(theirs), Some(ours)) if *theirs != ours => {
+                let question = format!(
+                    "This quarantine came from home {}. Re-map its paths to {}? [Y/n] ",
+                    theirs.display(),
+                    ours.display()
+                );
+                prompt_yes_no(&question, true)?.then(|| (theirs.clone(), ours))
+            }
+            _ => None,
+        },
+    };
+    if let Some((from, to)) = &mapping {
+        remap_originals(&mut export.manifest, from, to);
+    }
+
+    let imported = import_quarantine(src, &export, &config).map_err(|e| e.to_string())?;
+    let skipped = export.manifest.quarantined.len() - imported.len();
+    println!("Imported {}.", count(Locale::English, imported.len(), Noun::File));
+    if skipped > 0 {
+        println!("Skipped {} already in the quarantine or not matching their receipts.", skipped);
+    }
+    if !export.files_included {
+        eprintln!("Note: The export has no files; `icloud-dedupe verify` shows which are present here.");
+    }
+    Ok(())
+}
+
+fn cmd_verify(format: OutputFormat, quarantine_dir: &Path) -> Result<(), String> {
+    let config = QuarantineConfig {
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let manifest = load_manifest(&config).map_err(manifest_unavailable)?;
+    let verification = verify_manifest(&manifest);
+
+    if format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&verification).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else {
+        for problem in &verification.problems {
+            let what = match &problem.check {
+                ReceiptCheck::Ok => continue,
+                ReceiptCheck::Missing => "missing".to_string(),
+                ReceiptCheck::Corrupted { .. } => "corrupted (hash differs from receipt)".to_string(),
+                ReceiptCheck::Unreadable { error } => format!("unreadable: {}", error),
+            };
+            println!("  [{}] {} - {}", problem.id, problem.original_path.display(), what);
+        }
+        for change in &verification.kept_changed {
+            println!(
+                "  [{}] note: kept file {} {}",
+                change.id,
+                change.kept.display(),
+                describe_kept_change(change.check)
+            );
+        }
+        println!(
+            "Checked {}: {} intact, {} with problems.",
+            count(Locale::English, verification.checked, Noun::File),
+            verification.checked - verification.problems.len(),
+            verification.problems.len()
+        );
+    }
+
+    if verification.is_ok() {
+        Ok(())
+    } else {
+        Err(format!("{} quarantined files failed verification", verification.problems.len()))
+    }
+}
+
+/// Reject purge selections naming receipts the manifest doesn't have.
+fn check_purge_ids(manifest: &Manifest, selection: &PurgeSelection) -> Result<(), String> {
+    match selection.ids.iter().find(|id| !manifest.quarantined.iter().any(|r| &r.id == *id)) {
+        Some(id) => Err(format!("Receipt not found: {}", id)),
+        None => Ok(()),
+    }
+}
+
+/// Print which receipts a purge would delete.
+fn preview_purge(manifest: &Manifest, selection: &PurgeSelection) -> Result<(), String> {
+    check_purge_ids(manifest, selection)?;
+
+    let (selected, _) = partition_selected(manifest, selection, now_secs());
+    if selected.is_empty() {
+        println!("No quarantined files match.");
+        return Ok(());
+    }
+
+    println!(
+        "DRY RUN - would permanently delete {} of {} files ({}):",
+        selected.len(),
+        manifest.quarantined.len(),
+        format_size(selected.iter().map(|r| r.size_bytes).sum::<u64>(), BINARY)
+    );
+    for receipt in &selected {
+        println!("  [{}] {}", receipt.id, receipt.original_path.display());
     }
+    Ok(())
+}
 
-    purge_quarantine(&manifest, &config).map_err(|e| e.to_string())?;
-    println!("Purged {} files.", manifest.quarantined.len());
+/// Purge only the receipts `selection` matches, after confirmation.
+fn purge_some(
+    manifest: &Manifest,
+    selection: &PurgeSelection,
+    config: &QuarantineConfig,
+    force: bool,
+) -> Result<(), String> {
+    check_purge_ids(manifest, selection)?;
+
+    let now = now_secs();
+    let (selected, _) = partition_selected(manifest, selection, now);
+    if selected.is_empty() {
+        println!("No quarantined files match.");
+        return Ok(());
+    }
 
+    println!(
+        "About to permanently delete {} of {} files ({}):",
+        selected.len(),
+        manifest.quarantined.len(),
+        format_size(selected.iter().map(|r| r.size_bytes).sum::<u64>(), BINARY)
+    );
+    for receipt in &selected {
+        println!("  [{}] {}", receipt.id, receipt.original_path.display());
+    }
+
+    if !force && !prompt_yes_no("Continue? [y/N] ", false)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    let purged = purge_selected(manifest, selection, config, now).map_err(|e| e.to_string())?;
+    println!("Purged {}.", count(Locale::English, purged.len(), Noun::File));
     Ok(())
 }
 
-fn cmd_status() -> Result<(), String> {
+/// Whether `command` may change the quarantine, so an interrupted run is
+/// settled and expired files are purged before it starts.
+fn changes_quarantine(command: &Option<Commands>) -> bool {
+    match command {
+        // The interactive browser can quarantine and restore
+        None => true,
+        Some(Commands::Quarantine { action: Some(QuarantineCommands::Import { .. }), .. }) => true,
+        Some(Commands::Quarantine { action: Some(QuarantineCommands::Export { .. }), .. }) => false,
+        Some(Commands::Quarantine { action: None, dry_run, .. }) => !dry_run,
+        Some(Commands::Restore { dry_run, .. } | Commands::Purge { dry_run, .. }) => !dry_run,
+        Some(
+            Commands::Scan { .. }
+            | Commands::Verify { .. }
+            | Commands::Status { .. }
+            | Commands::Stats { .. }
+            | Commands::Containers { .. }
+            | Commands::Evict { .. }
+            | Commands::Report { .. },
+        ) => false,
+    }
+}
+
+/// Finish or roll back the moves of a quarantine run that died before
+/// saving its manifest. Failures are reported but never stop the command.
+fn recover_quarantine(quarantine_dir: &Path) {
+    let config = QuarantineConfig {
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        ..Default::default()
+    };
+    if !journal_path(&config).exists() {
+        return;
+    }
+    // A journal under another run's lock is that run's, still in progress
+    let Ok(_lock) = lock_quarantine(&config) else {
+        return;
+    };
+    match recover_journal(&config) {
+        Ok(recovery) if recovery.is_empty() => {}
+        Ok(recovery) => {
+            eprintln!(
+                "Note: Recovered an interrupted quarantine run: {} moved, {} left in place",
+                count(Locale::English, recovery.finished, Noun::File),
+                count(Locale::English, recovery.rolled_back, Noun::File)
+            );
+            for path in &recovery.missing {
+                eprintln!("Warning: {} is neither at its original path nor in quarantine", path.display());
+            }
+        }
+        Err(e) => eprintln!("Warning: Couldn't recover an interrupted quarantine run: {}", e),
+    }
+}
+
+/// Purge quarantined files older than the retention period, warning
+/// about what went. Failures are reported but never stop the command.
+fn expire_quarantine(retention: Duration, quarantine_dir: &Path) {
+    let config = QuarantineConfig {
+        quarantine_dir: quarantine_dir.to_path_buf(),
+        ..Default::default()
+    };
+    if !manifest_path(&config).exists() {
+        return;
+    }
+    // Another run owns the quarantine; expiry can wait for the next one
+    let Ok(_lock) = lock_quarantine(&config) else {
+        return;
+    };
+    match purge_expired(&config, retention, now_secs()) {
+        Ok(purged) if purged.is_empty() => {}
+        Ok(purged) => eprintln!(
+            "Warning: Purged {} ({}) quarantined more than {} ago",
+            count(Locale::English, purged.len(), Noun::File),
+            format_size(purged.iter().map(|r| r.size_bytes).sum::<u64>(), BINARY),
+            format_age(retention.as_secs())
+        ),
+        Err(e) => eprintln!("Note: Couldn't purge expired quarantine: {}", e),
+    }
+}
+
+/// Format an age in whole days, or hours under a day: "3 days", "5 hours".
+fn format_age(secs: u64) -> String {
+    let (value, unit) = if secs >= 86400 { (secs / 86400, Noun::Day) } else { (secs / 3600, Noun::Hour) };
+    count(Locale::English, value as usize, unit)
+}
+
+fn cmd_status(
+    by_session: bool,
+    format: OutputFormat,
+    retention: Option<Duration>,
+    quarantine_dir: &Path,
+) -> Result<(), String> {
     let config = QuarantineConfig {
-        quarantine_dir: default_quarantine_dir(),
+        quarantine_dir: quarantine_dir.to_path_buf(),
         ..Default::default()
     };
 
+    if format == OutputFormat::Json {
+        let manifest = match load_manifest(&config) {
+            Ok(manifest) => manifest,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Manifest::default(),
+            Err(e) => return Err(format!("Cannot read quarantine manifest: {}", e)),
+        };
+        let status = quarantine_status(&manifest, &config, retention, now_secs());
+        let json = serde_json::to_string_pretty(&status).map_err(|e| e.to_string())?;
+        println!("{}", json);
+        return Ok(());
+    }
+
     println!("Quarantine location: {}", config.quarantine_dir.display());
     println!();
 
     let manifest = match load_manifest(&config) {
         Ok(m) => m,
+        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => return Err(e.to_string()),
         Err(_) => {
             println!("Quarantine is empty (no manifest found).");
             return Ok(());
@@ -480,60 +1975,633 @@ fn cmd_status() -> Result<(), String> {
     println!("Files: {}", manifest.quarantined.len());
     println!("Total size: {}", format_size(total_bytes, BINARY));
     println!();
+
+    if by_session {
+        println!("Sessions:");
+        for session in sessions(&manifest) {
+            println!(
+                "  [{}] {}, {} ({})",
+                session.id.as_deref().unwrap_or("no session"),
+                session.started_at,
+                count(Locale::English, session.files, Noun::File),
+                format_size(session.bytes, BINARY)
+            );
+        }
+        return Ok(());
+    }
+
     println!("Contents:");
 
+    let now = now_secs();
     for receipt in &manifest.quarantined {
+        let age = match (receipt_age(receipt, now), retention) {
+            (Some(age), Some(retention)) => format!(
+                ", {} old, expires in {}",
+                format_age(age),
+                format_age(retention.as_secs().saturating_sub(age))
+            ),
+            (Some(age), None) => format!(", {} old", format_age(age)),
+            (None, _) => String::new(),
+        };
         println!(
-            "  [{}] {} ({})",
+            "  [{}] {} ({}{})",
             receipt.id,
             receipt.original_path.display(),
-            format_size(receipt.size_bytes, BINARY)
+            format_size(receipt.size_bytes, BINARY),
+            age
         );
+        if let Some(provenance) = &receipt.provenance {
+            println!("      {}", describe_provenance(provenance));
+        }
     }
 
     Ok(())
 }
 
-fn cmd_interactive(path: Option<PathBuf>, max_depth: Option<usize>) -> Result<(), String> {
-    let resolved = resolve_scan_path(path)?;
-    let normalized = normalize_path(&resolved);
+fn cmd_stats(format: OutputFormat, quarantine_dir: &Path) -> Result<(), String> {
+    let path = history_log_path(quarantine_dir);
+    let entries = load_history_log(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let stats = summarize(&entries);
 
-    let config = ScanConfig {
-        roots: vec![normalized.path],
-        max_depth,
-        ..Default::default()
+    if format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let Some(since) = stats.since else {
+        println!("No quarantine history yet.");
+        return Ok(());
     };
 
+    println!("Since {}:", format_date(since));
+    println!("  Quarantined: {}", describe_tally(stats.quarantined));
+    println!("  Restored:    {}", describe_tally(stats.restored));
+    println!("  Purged:      {}", describe_tally(stats.purged));
+    println!("  Pending:     {}", describe_tally(stats.pending));
+    println!();
+    println!("Space recovered: {}", format_size(stats.purged.bytes, BINARY));
+
+    println!();
+    println!("By month:");
+    for month in &stats.months {
+        println!(
+            "  {}  quarantined {}, purged {}, {} recovered to date",
+            month.month,
+            format_size(month.quarantined.bytes, BINARY),
+            format_size(month.purged.bytes, BINARY),
+            format_size(month.recovered_to_date, BINARY)
+        );
+    }
+
+    println!();
+    println!("Sessions:");
+    for session in &stats.sessions {
+        println!(
+            "  [{}] {}: {} quarantined, {} restored, {} purged",
+            session.id.as_deref().unwrap_or("no session"),
+            format_date(session.started_at),
+            describe_tally(session.quarantined),
+            session.restored.files,
+            session.purged.files
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_containers(
+    pick: Option<String>,
+    format: OutputFormat,
+    output: ReportOutput,
+    args: ScanArgs,
+    settings: &Config,
+    data_dir: &Path,
+) -> Result<(), String> {
+    let state = detect_icloud().map_err(|e| e.to_string())?;
+    let Some(root) = scan_root(&state) else {
+        return Err(state.to_string());
+    };
+    let containers = list_containers(root).map_err(|e| format!("Cannot read {}: {}", root.display(), e))?;
+
+    if format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&containers).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else if containers.is_empty() {
+        println!("No app containers in {}.", root.display());
+    } else {
+        println!("App containers in {}:", root.display());
+        for (i, container) in containers.iter().enumerate() {
+            println!(
+                "  {:>2}. {} ({}): {}, {}, {}",
+                i + 1,
+                container.name,
+                container.bundle_id,
+                count(Locale::English, container.files, Noun::File),
+                format_size(container.size_bytes, BINARY),
+                count(Locale::English, container.conflict_candidates, Noun::PossibleConflict)
+            );
+        }
+    }
+
+    let interactive = format == OutputFormat::Human && std::io::stdin().is_terminal() && !containers.is_empty();
+    let choice = match pick {
+        Some(choice) => choice,
+        None if interactive => {
+            eprintln!();
+            let answer = prompt_line("Scan which container? (number, ID, or name; Enter to skip): ")?;
+            if answer.is_empty() {
+                return Ok(());
+            }
+            answer
+        }
+        None => return Ok(()),
+    };
+
+    let container = pick_container(&containers, &choice)
+        .ok_or_else(|| format!("No container matches \"{}\"", choice))?;
+    let path = container.path.clone();
+    with_run_metrics(settings, "scan", |run| {
+        cmd_scan(vec![path], output, args, settings, data_dir, run).map(|_| ())
+    })
+}
+
+fn cmd_evict(paths: &[PathBuf], min_size: u64, dry_run: bool) -> Result<(), String> {
+    let mut files: Vec<(PathBuf, u64)> = Vec::new();
+    for root in paths {
+        if !root.exists() {
+            return Err(format!("No such file or directory: {}", root.display()));
+        }
+        for entry in WalkDir::new(root).into_iter().flatten() {
+            if !entry.file_type().is_file() || placeholder_target(&entry.file_name().to_string_lossy()).is_some() {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else { continue };
+            // Already cloud-only: nothing local to free
+            if is_dataless(&meta) || meta.len() < min_size {
+                continue;
+            }
+            files.push((entry.into_path(), meta.len()));
+        }
+    }
+
+    if files.is_empty() {
+        println!("Nothing to evict.");
+        return Ok(());
+    }
+
+    if dry_run {
+        for (path, size) in &files {
+            println!("  {} ({})", path.display(), format_size(*size, BINARY));
+        }
+        let total: u64 = files.iter().map(|(_, size)| size).sum();
+        println!(
+            "Would evict {} ({}).",
+            count(Locale::English, files.len(), Noun::File),
+            format_size(total, BINARY)
+        );
+        return Ok(());
+    }
+
+    let mut evicted = Tally::default();
+    let mut failed = 0;
+    for (path, size) in &files {
+        match evict(path) {
+            Ok(()) => {
+                evicted.files += 1;
+                evicted.bytes += size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => return Err(e.to_string()),
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Evicted {}; they download again when opened.", describe_tally(evicted));
+    if failed > 0 {
+        return Err(format!("{} could not be evicted", count(Locale::English, failed, Noun::File)));
+    }
+    Ok(())
+}
+
+/// A tally as e.g. `3 files (1.2 MiB)`.
+fn describe_tally(tally: Tally) -> String {
+    format!("{} ({})", count(Locale::English, tally.files, Noun::File), format_size(tally.bytes, BINARY))
+}
+
+/// One line on why a file was quarantined, e.g.
+/// `"Copy 2" of /docs/a.txt (kept), scan 20261016T142301Z-3f2a`.
+fn describe_provenance(provenance: &Provenance) -> String {
+    let mut line = match &provenance.pattern {
+        Some(pattern) => format!("\"{}\" of {} (kept)", pattern, provenance.kept.display()),
+        None => format!("same content as {} (kept)", provenance.kept.display()),
+    };
+    if let Some(scan) = &provenance.scan {
+        line.push_str(&format!(", scan {}", scan));
+    }
+    line
+}
+
+fn cmd_report_view(file: &Path, output: ReportOutput) -> Result<(), String> {
+    let json = std::fs::read_to_string(file)
+        .map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+    let report = parse_report(&json)?;
+    output.emit(&report)?;
+    Ok(())
+}
+
+fn cmd_interactive(
+    path: Option<PathBuf>,
+    args: ScanArgs,
+    settings: &Config,
+    data_dir: &Path,
+) -> Result<(), String> {
+    if args.snapshot_mode() {
+        return Err("Snapshot mode isn't available interactively; use `scan --snapshot`".to_string());
+    }
+    if args.by_content {
+        return Err("Content mode isn't available interactively; use `scan --by-content`".to_string());
+    }
+
+    let roots = resolve_scan_roots(path.into_iter().collect(), args.provider)?;
+
+    let config = args.config(roots.clone(), settings, data_dir);
+
     // TUI takes ownership — scanning happens in a background thread
-    icloud_dedupe::tui::run::run(config).map_err(|e| e.to_string())
+    let history = Some(history_path_in(data_dir));
+    match icloud_dedupe::tui::run::run_with_history(config, history) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+            eprintln!("Interactive mode unavailable: {}", e);
+            if prompt_yes_no("Print a plain scan report instead? [Y/n] ", true)? {
+                let mut run = RunMetrics::new("scan");
+                let output = ReportOutput::new(OutputFormat::Human, ColorArg::Auto, &[], None);
+                cmd_scan(roots, output, args, settings, data_dir, &mut run).map(|_| ())
+            } else {
+                Ok(())
+            }
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Record a finished scan so the next one can show what changed (best-effort).
+fn remember_scan(data_dir: &Path, config: &ScanConfig, report: &ScanReport) {
+    let _ = record_scan(&history_path_in(data_dir), report, &config.roots);
+}
+
+/// Fail with "Interrupted: `what`" once Ctrl-C was pressed.
+fn stop_if_interrupted(cancel: &CancelToken, what: &str) -> Result<(), String> {
+    if cancel.is_cancelled() {
+        return Err(format!("Interrupted: {}", what));
+    }
+    Ok(())
+}
+
+/// Ask a question on stderr and read a line from stdin, trimmed.
+fn prompt_line(question: &str) -> Result<String, String> {
+    eprint!("{}", question);
+
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| e.to_string())?;
+    Ok(input.trim().to_string())
+}
+
+/// Ask a yes/no question on stderr and read the answer from stdin.
+///
+/// An empty answer (or closed stdin) yields `default`.
+fn prompt_yes_no(question: &str, default: bool) -> Result<bool, String> {
+    let answer = prompt_line(question)?;
+    if answer.is_empty() {
+        Ok(default)
+    } else {
+        Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+    }
+}
+
+// ============================================================================
+// RUN METRICS
+// ============================================================================
+
+/// Run a scanning command, then export its metrics if the config asks for it.
+fn with_run_metrics<F>(settings: &Config, command: &'static str, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut RunMetrics) -> Result<(), String>,
+{
+    let started = Instant::now();
+    let mut run = RunMetrics::new(command);
+
+    let result = f(&mut run);
+
+    run.success = result.is_ok();
+    run.duration = started.elapsed();
+    export_run_metrics(settings, &run);
+
+    result
+}
+
+/// Send run metrics to the configured statsd endpoint (best-effort).
+fn export_run_metrics(settings: &Config, run: &RunMetrics) {
+    let Some(metrics) = &settings.metrics else {
+        return;
+    };
+    let lines = format_statsd(run, metrics);
+
+    #[cfg(feature = "metrics")]
+    if let Err(e) = icloud_dedupe::metrics::emit_statsd(&lines, &metrics.statsd) {
+        eprintln!("Note: failed to send metrics to {}: {}", metrics.statsd, e);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    {
+        let _ = lines;
+        eprintln!("Note: metrics are configured but this build lacks the `metrics` feature");
+    }
 }
 
 // ============================================================================
 // REPORT BUILDING
 // ============================================================================
 
+/// Discover and verify conflict candidates for quarantine.
+///
+/// Returns None when no file matches a conflict pattern.
+fn conflict_report(
+    config: &ScanConfig,
+    args: &ScanArgs,
+    run: &mut RunMetrics,
+) -> Result<Option<ScanReport>, String> {
+    if let Some(batch_size) = args.batch_size {
+        let result = streamed_report(config, batch_size as usize, true, run);
+        stop_if_interrupted(&config.cancel, "scan incomplete, nothing was moved")?;
+        let report = result?;
+        for (path, reason) in &report.symlink_skips {
+            eprintln!("Note: Skipped {}: {}", path.display(), reason);
+        }
+        if run.candidates == 0 {
+            return Ok(None);
+        }
+        run.record_report(&report);
+        return Ok(Some(report));
+    }
+
+    // Phase 1: Discovery
+    let sp = spinner("Discovering conflict patterns...");
+
+    let discovery = match discover(config, |p| sp.set_message(discovery_message(p))) {
+        Ok(d) => d,
+        Err(e) => {
+            sp.finish_and_clear();
+            return Err(e.to_string());
+        }
+    };
+
+    stop_if_interrupted(&config.cancel, "scan incomplete, nothing was moved")?;
+    let candidates = discovery.candidates;
+    for (path, reason) in &discovery.symlink_skips {
+        eprintln!("Note: Skipped {}: {}", path.display(), reason);
+    }
+
+    sp.finish_with_message(format!("Found {} candidates", candidates.len()));
+    run.candidates = candidates.len();
+
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    // Phase 2: Verification (parallel)
+    let report = verify_report(&candidates, config, args, true)?;
+    run.record_report(&report);
+
+    Ok(Some(report))
+}
+
+/// Walk and verify in bounded batches (`--batch-size`).
+///
+/// Totals grow as the walk goes, so progress is a spinner with counts
+/// rather than a bar.
+fn streamed_report(
+    config: &ScanConfig,
+    batch_size: usize,
+    show_progress: bool,
+    run: &mut RunMetrics,
+) -> Result<ScanReport, String> {
+    let candidates = AtomicUsize::new(0);
+    let sp = show_progress.then(|| spinner("Scanning..."));
+
+    let result = scan_streaming(config, batch_size, |p| {
+        candidates.fetch_max(p.candidates_found, Ordering::Relaxed);
+        if let Some(sp) = &sp {
+            if p.downloading > 0 {
+                sp.set_message(downloading_message(p.downloading));
+                return;
+            }
+            sp.set_message(format!(
+                "Scanned {} files, verified {} of {} candidates...",
+                p.files_scanned, p.candidates_verified, p.candidates_found
+            ));
+        }
+    });
+
+    run.candidates = candidates.into_inner();
+    if let Some(sp) = sp {
+        sp.finish_with_message(format!("Verified {} candidates", run.candidates));
+    }
+    result.map_err(|e| e.to_string())
+}
+
+/// Scan in batches, writing each batch's findings as JSON lines as soon as
+/// they're verified, then a closing summary line.
+fn stream_jsonl(
+    config: &ScanConfig,
+    batch_size: usize,
+    warnings: Vec<String>,
+    output: &ReportOutput,
+    run: &mut RunMetrics,
+) -> Result<ScanReport, String> {
+    let mut sink = output.sink()?;
+    sink.write(&format_jsonl_records(&ScanReport { warnings, ..Default::default() }))?;
+
+    let candidates = AtomicUsize::new(0);
+    let mut write_error = None;
+    let result = scan_streaming_with(
+        config,
+        batch_size,
+        |p| {
+            candidates.fetch_max(p.candidates_found, Ordering::Relaxed);
+        },
+        |batch| {
+            if write_error.is_none()
+                && let Err(e) = sink.write(&format_jsonl_records(&output.filter(batch)))
+            {
+                write_error = Some(e);
+            }
+        },
+    );
+    run.candidates = candidates.into_inner();
+    stop_if_interrupted(&config.cancel, "scan incomplete, no summary written")?;
+    let report = result.map_err(|e| e.to_string())?;
+    if let Some(e) = write_error {
+        return Err(e);
+    }
+
+    // Symlink skips are only known once the walk is done
+    let skips = ScanReport {
+        symlink_skips: report.symlink_skips.clone(),
+        ..Default::default()
+    };
+    sink.write(&format_jsonl_records(&output.filter(&skips)))?;
+    sink.write(&format_jsonl_summary(&output.filter(&report)))?;
+    sink.finish()?;
+    run.record_report(&report);
+    Ok(report)
+}
+
+/// Group files by content instead of by conflict pattern.
+fn content_report(config: &ScanConfig, show_progress: bool) -> Result<ScanReport, String> {
+    if !show_progress {
+        return scan_by_content(config, |_| {}).map_err(|e| e.to_string());
+    }
+
+    let pb = byte_progress_bar("Scanning...");
+    let result = scan_by_content(config, |p| {
+        if p.bytes_to_verify == 0 {
+            pb.set_message(format!("Scanned {} files...", p.files_scanned));
+            return;
+        }
+        pb.set_length(p.bytes_to_verify);
+        // Workers report out of order; never move the bar backwards
+        pb.set_position(pb.position().max(p.bytes_verified));
+        match p.eta() {
+            Some(eta) => pb.set_message(format!("Hashing... ETA {}", format_eta(eta))),
+            None => pb.set_message("Hashing..."),
+        }
+    });
+    pb.finish_with_message("Done");
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Verify candidates, against an APFS snapshot when asked to.
+///
+/// Snapshot results are mapped back to live paths. The scan index and hash
+/// cache are bypassed: their entries describe live files.
+fn verify_report(
+    candidates: &[ConflictCandidate],
+    config: &ScanConfig,
+    args: &ScanArgs,
+    show_progress: bool,
+) -> Result<ScanReport, String> {
+    let build = |candidates: &[ConflictCandidate], config: &ScanConfig| {
+        if show_progress {
+            build_report_with_progress(candidates, config)
+        } else {
+            build_report(candidates, config)
+        }
+    };
+
+    if !args.snapshot_mode() {
+        let mut report = build(candidates, config);
+        finish_report(&mut report, config);
+        return Ok(report);
+    }
+
+    let snapshot = prepare_snapshot(&config.roots, args.use_snapshot.as_deref())
+        .map_err(|e| format!("Snapshot mode: {}", e))?;
+    if show_progress {
+        eprintln!("Verifying against snapshot {}", snapshot.name);
+    }
+
+    // Same settings, minus the caches: snapshot files aren't the live ones
+    let snapshot_config = ScanConfig {
+        roots: config.roots.iter().map(|r| snapshot.to_snapshot(r)).collect(),
+        index_path: None,
+        hash_cache_path: None,
+        hash_store: None,
+        ..config.clone()
+    };
+    let report = build(&snapshot.snapshot_candidates(candidates), &snapshot_config);
+
+    // Final passes look at the live files
+    let mut report = snapshot.live_report(report, &config.roots);
+    finish_report(&mut report, config);
+    Ok(report)
+}
+
+
 /// Build report with progress bar (parallel verification).
-fn build_report_with_progress(candidates: &[ConflictCandidate]) -> ScanReport {
-    let pb = progress_bar(candidates.len() as u64, "Verifying...");
+fn build_report_with_progress(candidates: &[ConflictCandidate], config: &ScanConfig) -> ScanReport {
+    let pb = byte_progress_bar("Verifying...");
 
-    let results: Vec<_> = candidates
-        .par_iter()
-        .progress_with(pb.clone())
-        .map(|candidate| (candidate.path.clone(), verify_candidate(candidate)))
-        .collect();
+    let results = verify_candidates(candidates, config, |p| {
+        if p.downloading > 0 {
+            pb.set_message(downloading_message(p.downloading));
+            return;
+        }
+        pb.set_length(p.bytes_to_verify);
+        // Workers report out of order; never move the bar backwards
+        pb.set_position(pb.position().max(p.bytes_verified));
+        if let Some(eta) = p.eta() {
+            pb.set_message(format!("Verifying... ETA {}", format_eta(eta)));
+        }
+    });
 
     pb.finish_with_message("Done");
 
     assemble_report(results)
 }
 
+/// Progress while `quarantine --wait-for-sync` waits for iCloud.
+fn syncing_message(files: usize) -> String {
+    format!("Waiting for iCloud to finish syncing {}...", count(Locale::English, files, Noun::File))
+}
+
+/// Progress while `--materialize` waits for iCloud downloads.
+fn downloading_message(files: usize) -> String {
+    format!("Waiting for {} to download from iCloud...", count(Locale::English, files, Noun::File))
+}
+
 /// Build report without progress (for JSON output).
-fn build_report(candidates: &[ConflictCandidate]) -> ScanReport {
-    let results: Vec<_> = candidates
-        .par_iter()
-        .map(|candidate| (candidate.path.clone(), verify_candidate(candidate)))
-        .collect();
+fn build_report(candidates: &[ConflictCandidate], config: &ScanConfig) -> ScanReport {
+    let results = verify_candidates(candidates, config, |_| {});
 
     assemble_report(results)
 }
 
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_status_maps_results_for_scripts() {
+        assert_eq!(exit_status(&Ok(()), false), 0);
+        assert_eq!(exit_status(&Ok(()), true), 2);
+        assert_eq!(exit_status(&Err("scan interrupted".to_string()), false), 1);
+        assert_eq!(exit_status(&Err("scan interrupted".to_string()), true), 1);
+    }
+
+    #[test]
+    fn dry_runs_and_read_only_commands_leave_the_quarantine_alone() {
+        let changes = |args: &[&str]| {
+            let cli = Cli::parse_from(std::iter::once("icloud-dedupe").chain(args.iter().copied()));
+            changes_quarantine(&cli.command)
+        };
+        assert!(changes(&["purge", "--force"]));
+        assert!(changes(&["restore", "--all"]));
+        assert!(changes(&["quarantine"]));
+        assert!(!changes(&["purge", "--dry-run"]));
+        assert!(!changes(&["restore", "--all", "--dry-run"]));
+        assert!(!changes(&["quarantine", "--dry-run"]));
+        assert!(!changes(&["status"]));
+        assert!(!changes(&["verify"]));
+        assert!(!changes(&["scan"]));
+    }
+}
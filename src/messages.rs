@@ -0,0 +1,504 @@
+//! User-facing text: locale selection, translations, pluralization.
+//!
+//! Report wording lives here instead of in scattered `format!` calls, so
+//! every count is pluralized correctly and the report can follow the
+//! user's locale. English, French, and German are available; anything else
+//! falls back to English.
+//!
+//! Only the report is translated. Command messages and the TUI are English,
+//! and use [`count`] with [`Locale::English`] for their plurals.
+//!
+//! Structure:
+//! - Types: locales, fixed texts, countable nouns
+//! - Pure functions: lookup, pluralization, locale parsing
+//! - Effect functions: locale from the environment
+
+use crate::types::KeepPolicy;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Language for human output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+    German,
+}
+
+/// Fixed pieces of text: section headers and summary labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    Warning,
+    PartialScan,
+    Original,
+    Conflict,
+    DuplicatesHeader,
+    ByFolderHeader,
+    ByParentHeader,
+    OrphansHeader,
+    DivergedHeader,
+    NotLocalHeader,
+    UnsyncedHeader,
+    HardLinksHeader,
+    ProbableHeader,
+    MetadataHeader,
+    SkippedHeader,
+    SymlinksHeader,
+    SummaryHeader,
+    DuplicateGroups,
+    TotalDuplicates,
+    OrphanedConflicts,
+    DivergedFiles,
+    NotDownloaded,
+    HardLinks,
+    ProbableDuplicates,
+    MetadataDiffers,
+    SkippedErrors,
+    SkippedSymlinks,
+    SpaceRecoverable,
+    ByExtension,
+    NoExtension,
+    Roots,
+    Ignoring,
+}
+
+/// Things that get counted in output ("1 file", "3 files").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Noun {
+    File,
+    DuplicateGroup,
+    ConfirmedDuplicate,
+    OrphanedConflict,
+    DivergedFile,
+    ProbableDuplicate,
+    MetadataMismatch,
+    PossibleConflict,
+    Copy,
+    Group,
+    Day,
+    Hour,
+}
+
+/// Summary labels, in display order (used to align their values).
+pub const SUMMARY_LABELS: [Text; 12] = [
+    Text::DuplicateGroups,
+    Text::TotalDuplicates,
+    Text::OrphanedConflicts,
+    Text::DivergedFiles,
+    Text::NotDownloaded,
+    Text::HardLinks,
+    Text::ProbableDuplicates,
+    Text::MetadataDiffers,
+    Text::SkippedErrors,
+    Text::SkippedSymlinks,
+    Text::SpaceRecoverable,
+    Text::ByExtension,
+];
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Translate a fixed text.
+pub fn text(locale: Locale, key: Text) -> &'static str {
+    use Locale::*;
+    use Text::*;
+
+    match (key, locale) {
+        (Warning, English) => "Warning",
+        (Warning, French) => "Avertissement",
+        (Warning, German) => "Warnung",
+
+        (PartialScan, English) => "Partial scan",
+        (PartialScan, French) => "Analyse partielle",
+        (PartialScan, German) => "Unvollständiger Scan",
+
+        (Original, _) => "Original",
+
+        (Conflict, English) => "Conflict",
+        (Conflict, French) => "Conflit",
+        (Conflict, German) => "Konflikt",
+
+        (DuplicatesHeader, English) => "=== Confirmed Duplicates ===",
+        (DuplicatesHeader, French) => "=== Doublons confirmés ===",
+        (DuplicatesHeader, German) => "=== Bestätigte Duplikate ===",
+
+        (ByFolderHeader, English) => "=== Duplicates by Folder ===",
+        (ByFolderHeader, French) => "=== Doublons par dossier ===",
+        (ByFolderHeader, German) => "=== Duplikate nach Ordner ===",
+
+        (ByParentHeader, English) => "=== Top Parent Directories ===",
+        (ByParentHeader, French) => "=== Principaux dossiers parents ===",
+        (ByParentHeader, German) => "=== Wichtigste übergeordnete Ordner ===",
+
+        (OrphansHeader, English) => "=== Orphaned Conflicts (no original found) ===",
+        (OrphansHeader, French) => "=== Conflits orphelins (original introuvable) ===",
+        (OrphansHeader, German) => "=== Verwaiste Konflikte (kein Original gefunden) ===",
+
+        (DivergedHeader, English) => "=== Content Diverged (different content) ===",
+        (DivergedHeader, French) => "=== Contenu divergent (contenu différent) ===",
+        (DivergedHeader, German) => "=== Abweichender Inhalt (anderer Inhalt) ===",
+
+        (NotLocalHeader, English) => "=== Not Downloaded (not checked) ===",
+        (NotLocalHeader, French) => "=== Non téléchargés (non vérifiés) ===",
+        (NotLocalHeader, German) => "=== Nicht geladen (nicht geprüft) ===",
+        (UnsyncedHeader, English) => "=== Still Syncing (check before acting) ===",
+        (UnsyncedHeader, French) => "=== Synchronisation en cours (à vérifier avant d'agir) ===",
+        (UnsyncedHeader, German) => "=== Noch in Synchronisierung (vorher prüfen) ===",
+
+        (HardLinksHeader, English) => "=== Hard Links (same file, nothing to recover) ===",
+        (HardLinksHeader, French) => "=== Liens physiques (même fichier, rien à récupérer) ===",
+        (HardLinksHeader, German) => "=== Harte Links (dieselbe Datei, nichts freizugeben) ===",
+
+        (ProbableHeader, English) => "=== Probable Duplicates (same size and date, not hashed) ===",
+        (ProbableHeader, French) => "=== Doublons probables (même taille et date, non hachés) ===",
+        (ProbableHeader, German) => "=== Wahrscheinliche Duplikate (gleiche Größe und Zeit, nicht gehasht) ===",
+
+        (MetadataHeader, English) => "=== Metadata Differs (same content, kept) ===",
+        (MetadataHeader, French) => "=== Métadonnées différentes (même contenu, conservés) ===",
+        (MetadataHeader, German) => "=== Abweichende Metadaten (gleicher Inhalt, behalten) ===",
+
+        (SkippedHeader, English) => "=== Skipped (read errors) ===",
+        (SkippedHeader, French) => "=== Ignorés (erreurs de lecture) ===",
+        (SkippedHeader, German) => "=== Übersprungen (Lesefehler) ===",
+
+        (SymlinksHeader, English) => "=== Skipped Symlinks ===",
+        (SymlinksHeader, French) => "=== Liens symboliques ignorés ===",
+        (SymlinksHeader, German) => "=== Übersprungene symbolische Links ===",
+
+        (SummaryHeader, English) => "=== Summary ===",
+        (SummaryHeader, French) => "=== Résumé ===",
+        (SummaryHeader, German) => "=== Zusammenfassung ===",
+
+        (DuplicateGroups, English) => "Duplicate groups",
+        (DuplicateGroups, French) => "Groupes de doublons",
+        (DuplicateGroups, German) => "Duplikatgruppen",
+
+        (TotalDuplicates, English) => "Total duplicates",
+        (TotalDuplicates, French) => "Total des doublons",
+        (TotalDuplicates, German) => "Duplikate gesamt",
+
+        (OrphanedConflicts, English) => "Orphaned conflicts",
+        (OrphanedConflicts, French) => "Conflits orphelins",
+        (OrphanedConflicts, German) => "Verwaiste Konflikte",
+
+        (DivergedFiles, English) => "Diverged files",
+        (DivergedFiles, French) => "Fichiers divergents",
+        (DivergedFiles, German) => "Abweichende Dateien",
+
+        (NotDownloaded, English) => "Not downloaded",
+        (NotDownloaded, French) => "Non téléchargés",
+        (NotDownloaded, German) => "Nicht geladen",
+
+        (HardLinks, English) => "Hard links",
+        (HardLinks, French) => "Liens physiques",
+        (HardLinks, German) => "Harte Links",
+
+        (ProbableDuplicates, English) => "Probable matches",
+        (ProbableDuplicates, French) => "Doublons probables",
+        (ProbableDuplicates, German) => "Wahrsch. Duplikate",
+
+        (MetadataDiffers, English) => "Metadata differs",
+        (MetadataDiffers, French) => "Métadonnées diff.",
+        (MetadataDiffers, German) => "Andere Metadaten",
+
+        (SkippedErrors, English) => "Skipped (errors)",
+        (SkippedErrors, French) => "Ignorés (erreurs)",
+        (SkippedErrors, German) => "Übersprungen (Fehler)",
+
+        (SkippedSymlinks, English) => "Skipped symlinks",
+        (SkippedSymlinks, French) => "Liens symb. ignorés",
+        (SkippedSymlinks, German) => "Übersprungene Links",
+
+        (SpaceRecoverable, English) => "Space recoverable",
+        (SpaceRecoverable, French) => "Espace récupérable",
+        (SpaceRecoverable, German) => "Freigebbarer Platz",
+
+        (ByExtension, English) => "By file type",
+        (ByExtension, French) => "Par type de fichier",
+        (ByExtension, German) => "Nach Dateityp",
+
+        (NoExtension, English) => "(no extension)",
+        (NoExtension, French) => "(sans extension)",
+        (NoExtension, German) => "(ohne Endung)",
+
+        (Roots, English) => "Roots",
+        (Roots, French) => "Racines",
+        (Roots, German) => "Wurzeln",
+
+        (Ignoring, English) => "Ignoring",
+        (Ignoring, French) => "Ignorés",
+        (Ignoring, German) => "Ignoriert",
+    }
+}
+
+/// Whether `n` takes the singular form.
+///
+/// French uses the singular for 0 and 1; English and German only for 1.
+pub fn is_singular(locale: Locale, n: u64) -> bool {
+    match locale {
+        Locale::French => n <= 1,
+        Locale::English | Locale::German => n == 1,
+    }
+}
+
+/// The form of `noun` that agrees with `n`, without the number.
+pub fn noun(locale: Locale, noun: Noun, n: u64) -> &'static str {
+    use Locale::*;
+    use Noun::*;
+
+    let (one, other) = match (noun, locale) {
+        (File, English) => ("file", "files"),
+        (File, French) => ("fichier", "fichiers"),
+        (File, German) => ("Datei", "Dateien"),
+
+        (DuplicateGroup, English) => ("duplicate group", "duplicate groups"),
+        (DuplicateGroup, French) => ("groupe de doublons", "groupes de doublons"),
+        (DuplicateGroup, German) => ("Duplikatgruppe", "Duplikatgruppen"),
+
+        (ConfirmedDuplicate, English) => ("confirmed duplicate", "confirmed duplicates"),
+        (ConfirmedDuplicate, French) => ("doublon confirmé", "doublons confirmés"),
+        (ConfirmedDuplicate, German) => ("bestätigtes Duplikat", "bestätigte Duplikate"),
+
+        (OrphanedConflict, English) => ("orphaned conflict", "orphaned conflicts"),
+        (OrphanedConflict, French) => ("conflit orphelin", "conflits orphelins"),
+        (OrphanedConflict, German) => ("verwaister Konflikt", "verwaiste Konflikte"),
+
+        (DivergedFile, English) => ("diverged file", "diverged files"),
+        (DivergedFile, French) => ("fichier divergent", "fichiers divergents"),
+        (DivergedFile, German) => ("abweichende Datei", "abweichende Dateien"),
+
+        (ProbableDuplicate, English) => ("probable duplicate", "probable duplicates"),
+        (ProbableDuplicate, French) => ("doublon probable", "doublons probables"),
+        (ProbableDuplicate, German) => ("wahrscheinliches Duplikat", "wahrscheinliche Duplikate"),
+
+        (MetadataMismatch, English) => ("metadata mismatch", "metadata mismatches"),
+        (MetadataMismatch, French) => ("écart de métadonnées", "écarts de métadonnées"),
+        (MetadataMismatch, German) => ("Metadaten-Abweichung", "Metadaten-Abweichungen"),
+
+        (PossibleConflict, English) => ("possible conflict copy", "possible conflict copies"),
+        (PossibleConflict, French) => ("copie en conflit possible", "copies en conflit possibles"),
+        (PossibleConflict, German) => ("mögliche Konfliktkopie", "mögliche Konfliktkopien"),
+
+        (Copy, English) => ("copy", "copies"),
+        (Copy, French) => ("copie", "copies"),
+        (Copy, German) => ("Kopie", "Kopien"),
+
+        (Group, English) => ("group", "groups"),
+        (Group, French) => ("groupe", "groupes"),
+        (Group, German) => ("Gruppe", "Gruppen"),
+
+        (Day, English) => ("day", "days"),
+        (Day, French) => ("jour", "jours"),
+        (Day, German) => ("Tag", "Tage"),
+
+        (Hour, English) => ("hour", "hours"),
+        (Hour, French) => ("heure", "heures"),
+        (Hour, German) => ("Stunde", "Stunden"),
+    };
+
+    if is_singular(locale, n) { one } else { other }
+}
+
+/// A number with its noun: "1 file", "3 files", "0 fichier".
+pub fn count(locale: Locale, n: usize, what: Noun) -> String {
+    format!("{} {}", n, noun(locale, what, n as u64))
+}
+
+/// Note on how each group's keeper was chosen, for non-default policies.
+pub fn keep_note(locale: Locale, policy: KeepPolicy) -> String {
+    use KeepPolicy::*;
+    use Locale::*;
+
+    let which = match (policy, locale) {
+        (OriginalName, English) => "the clean-named file",
+        (OriginalName, French) => "le fichier au nom d'origine",
+        (OriginalName, German) => "die Datei mit dem ursprünglichen Namen",
+        (NewestMtime, English) => "the newest copy",
+        (NewestMtime, French) => "la copie la plus récente",
+        (NewestMtime, German) => "die neueste Kopie",
+        (Largest, English) => "the largest copy",
+        (Largest, French) => "la copie la plus grande",
+        (Largest, German) => "die größte Kopie",
+    };
+
+    match locale {
+        English => format!("Keeping {} of each group", which),
+        French => format!("Conservation de {} de chaque groupe", which),
+        German => format!("Behalten wird {} jeder Gruppe", which),
+    }
+}
+
+/// How much a scan covered: "Scanned 182000 files in 1m 34s".
+pub fn scanned_note(locale: Locale, files: usize, took: &str) -> String {
+    let files = count(locale, files, Noun::File);
+    match locale {
+        Locale::English => format!("Scanned {} in {}", files, took),
+        Locale::French => format!("Analyse de {} en {}", files, took),
+        Locale::German => format!("{} in {} gescannt", files, took),
+    }
+}
+
+/// Hashing throughput: "Hashed 12 files (3.4 GiB) at 290 MiB/s, 120 MiB/s
+/// per thread".
+pub fn hashed_note(locale: Locale, files: usize, size: &str, rate: &str, per_thread: &str) -> String {
+    let files = count(locale, files, Noun::File);
+    match locale {
+        Locale::English => format!("Hashed {} ({}) at {}, {} per thread", files, size, rate, per_thread),
+        Locale::French => format!("{} hachés ({}) à {}, {} par thread", files, size, rate, per_thread),
+        Locale::German => format!("{} gehasht ({}) mit {}, {} pro Thread", files, size, rate, per_thread),
+    }
+}
+
+/// Time per scan phase: "Walk 2.1s, verify 11.8s".
+pub fn phases_note(locale: Locale, walk: &str, verify: &str) -> String {
+    match locale {
+        Locale::English => format!("Walk {}, verify {}", walk, verify),
+        Locale::French => format!("Parcours {}, vérification {}", walk, verify),
+        Locale::German => format!("Durchlauf {}, Prüfung {}", walk, verify),
+    }
+}
+
+/// When and by what a scan ran: "Scan started 2026-06-02T14:03:00Z, took
+/// 1m 34s (icloud-dedupe 0.5.0)".
+pub fn scan_header_note(locale: Locale, started: &str, took: &str, version: &str) -> String {
+    match locale {
+        Locale::English => format!("Scan started {}, took {} (icloud-dedupe {})", started, took, version),
+        Locale::French => format!("Analyse lancée le {}, durée {} (icloud-dedupe {})", started, took, version),
+        Locale::German => format!("Scan gestartet am {}, Dauer {} (icloud-dedupe {})", started, took, version),
+    }
+}
+
+/// How a scan walked its roots: "Depth unlimited, hidden files included,
+/// symlinks not followed".
+pub fn walk_note(locale: Locale, max_depth: Option<usize>, include_hidden: bool, follow_symlinks: bool) -> String {
+    let depth = max_depth.map(|d| d.to_string());
+    match locale {
+        Locale::English => format!(
+            "Depth {}, hidden files {}, symlinks {}",
+            depth.as_deref().unwrap_or("unlimited"),
+            if include_hidden { "included" } else { "skipped" },
+            if follow_symlinks { "followed" } else { "not followed" }
+        ),
+        Locale::French => format!(
+            "Profondeur {}, fichiers cachés {}, liens symboliques {}",
+            depth.as_deref().unwrap_or("illimitée"),
+            if include_hidden { "inclus" } else { "ignorés" },
+            if follow_symlinks { "suivis" } else { "non suivis" }
+        ),
+        Locale::German => format!(
+            "Tiefe {}, versteckte Dateien {}, symbolische Links {}",
+            depth.as_deref().unwrap_or("unbegrenzt"),
+            if include_hidden { "einbezogen" } else { "übersprungen" },
+            if follow_symlinks { "verfolgt" } else { "nicht verfolgt" }
+        ),
+    }
+}
+
+/// "Label:" padded so values line up after the longest summary label.
+pub fn summary_label(locale: Locale, key: Text) -> String {
+    let width = SUMMARY_LABELS
+        .iter()
+        .map(|&k| text(locale, k).chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let label = format!("{}:", text(locale, key));
+    let padding = width.saturating_sub(label.chars().count());
+    format!("{}{}", label, " ".repeat(padding))
+}
+
+/// Locale named by a POSIX locale string ("fr_FR.UTF-8", "de", "C").
+///
+/// None for languages without a translation.
+pub fn parse_locale(value: &str) -> Option<Locale> {
+    let language = value
+        .split(['_', '.', '@', '-'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match language.as_str() {
+        "en" | "c" | "posix" => Some(Locale::English),
+        "fr" => Some(Locale::French),
+        "de" => Some(Locale::German),
+        _ => None,
+    }
+}
+
+/// Pick the locale from `LC_ALL`, `LC_MESSAGES`, and `LANG` values, in
+/// POSIX precedence order: the first one set decides.
+pub fn locale_from_env(lc_all: Option<&str>, lc_messages: Option<&str>, lang: Option<&str>) -> Locale {
+    [lc_all, lc_messages, lang]
+        .into_iter()
+        .flatten()
+        .find(|v| !v.is_empty())
+        .and_then(parse_locale)
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// The locale the process environment asks for.
+pub fn current_locale() -> Locale {
+    let var = |name| std::env::var(name).ok();
+    locale_from_env(
+        var("LC_ALL").as_deref(),
+        var("LC_MESSAGES").as_deref(),
+        var("LANG").as_deref(),
+    )
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_pluralize_per_language() {
+        assert_eq!(count(Locale::English, 1, Noun::File), "1 file");
+        assert_eq!(count(Locale::English, 0, Noun::File), "0 files");
+        assert_eq!(count(Locale::French, 0, Noun::File), "0 fichier");
+        assert_eq!(count(Locale::French, 2, Noun::DuplicateGroup), "2 groupes de doublons");
+        assert_eq!(count(Locale::German, 1, Noun::OrphanedConflict), "1 verwaister Konflikt");
+        assert_eq!(count(Locale::English, 1, Noun::PossibleConflict), "1 possible conflict copy");
+    }
+
+    #[test]
+    fn summary_labels_align_values() {
+        assert_eq!(summary_label(Locale::English, Text::DuplicateGroups), "Duplicate groups:   ");
+        assert_eq!(summary_label(Locale::English, Text::OrphanedConflicts), "Orphaned conflicts: ");
+
+        for locale in [Locale::French, Locale::German] {
+            let widths: Vec<usize> = SUMMARY_LABELS
+                .iter()
+                .map(|&k| summary_label(locale, k).chars().count())
+                .collect();
+            assert!(widths.windows(2).all(|w| w[0] == w[1]));
+        }
+    }
+
+    #[test]
+    fn locale_parsing_understands_posix_names() {
+        assert_eq!(parse_locale("fr_FR.UTF-8"), Some(Locale::French));
+        assert_eq!(parse_locale("de_CH"), Some(Locale::German));
+        assert_eq!(parse_locale("C"), Some(Locale::English));
+        assert_eq!(parse_locale("ja_JP.UTF-8"), None);
+    }
+
+    #[test]
+    fn locale_env_precedence() {
+        assert_eq!(locale_from_env(Some("de_DE"), Some("fr_FR"), None), Locale::German);
+        assert_eq!(locale_from_env(Some(""), Some("fr_FR"), Some("de_DE")), Locale::French);
+        assert_eq!(locale_from_env(None, None, Some("ja_JP")), Locale::English);
+        assert_eq!(locale_from_env(None, None, None), Locale::English);
+    }
+}
@@ -0,0 +1,258 @@
+//! End-of-run metrics for fleet monitoring.
+//!
+//! Each `scan`/`quarantine` run produces a [`RunMetrics`] summary that can be
+//! exported as statsd lines (with DogStatsD tags), so admins running the tool
+//! on many machines can chart duplicates found and space recovered.
+//!
+//! Sending requires the `metrics` crate feature; formatting is always
+//! available.
+//!
+//! Structure:
+//! - Types: RunMetrics
+//! - Pure functions: statsd line formatting
+//! - Effect functions: UDP export (feature `metrics`)
+
+use std::time::Duration;
+
+use crate::config::MetricsConfig;
+use crate::types::ScanReport;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Summary of a single CLI run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunMetrics {
+    /// Subcommand that ran ("scan", "quarantine").
+    pub command: &'static str,
+    /// Whether the run finished without error.
+    pub success: bool,
+    /// Wall-clock duration of the run.
+    pub duration: Duration,
+    /// Conflict candidates discovered.
+    pub candidates: usize,
+    /// Confirmed duplicate files.
+    pub duplicates: usize,
+    /// Conflicts whose original is missing.
+    pub orphaned: usize,
+    /// Conflicts whose content differs from the original.
+    pub diverged: usize,
+    /// Conflicts not checked because content isn't downloaded.
+    pub not_local: usize,
+    /// Duplicates that are hard links to their original.
+    pub hardlinked: usize,
+    /// Files skipped due to read errors.
+    pub skipped: usize,
+    /// Bytes recoverable by removing duplicates.
+    pub bytes_recoverable: u64,
+    /// Files actually moved to quarantine.
+    pub files_quarantined: usize,
+    /// Bytes actually moved to quarantine.
+    pub bytes_quarantined: u64,
+}
+
+impl RunMetrics {
+    /// Start an empty summary for `command`.
+    pub fn new(command: &'static str) -> Self {
+        RunMetrics {
+            command,
+            ..Default::default()
+        }
+    }
+
+    /// Fill in the verification counts from a scan report.
+    pub fn record_report(&mut self, report: &ScanReport) {
+        self.duplicates = report
+            .confirmed_duplicates
+            .iter()
+            .map(|g| g.duplicates.len())
+            .sum();
+        self.orphaned = report.orphaned_conflicts.len();
+        self.diverged = report.content_diverged.len();
+        self.not_local = report.not_local.len();
+        self.hardlinked = report.hardlinked.len();
+        self.skipped = report.skipped.len();
+        self.bytes_recoverable = report.bytes_recoverable;
+    }
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Format a run summary as statsd lines.
+///
+/// Counts are gauges (`|g`), the duration is a timer (`|ms`), and each run
+/// bumps a `runs` counter tagged with its outcome.
+pub fn format_statsd(run: &RunMetrics, config: &MetricsConfig) -> Vec<String> {
+    let outcome = if run.success { "success" } else { "failure" };
+
+    let mut tags = vec![format!("command:{}", run.command)];
+    tags.extend(config.tags.iter().map(|(k, v)| format!("{}:{}", k, v)));
+    let tags = tags.join(",");
+
+    let gauges: [(&str, u64); 10] = [
+        ("candidates", run.candidates as u64),
+        ("duplicates", run.duplicates as u64),
+        ("orphaned", run.orphaned as u64),
+        ("diverged", run.diverged as u64),
+        ("not_local", run.not_local as u64),
+        ("hardlinked", run.hardlinked as u64),
+        ("skipped", run.skipped as u64),
+        ("bytes_recoverable", run.bytes_recoverable),
+        ("files_quarantined", run.files_quarantined as u64),
+        ("bytes_quarantined", run.bytes_quarantined),
+    ];
+
+    let mut lines = vec![format!(
+        "{}.runs:1|c|#{},outcome:{}",
+        config.prefix, tags, outcome
+    )];
+    lines.push(format!(
+        "{}.duration_ms:{}|ms|#{}",
+        config.prefix,
+        run.duration.as_millis(),
+        tags
+    ));
+    lines.extend(
+        gauges
+            .iter()
+            .map(|(name, value)| format!("{}.{}:{}|g|#{}", config.prefix, name, value, tags)),
+    );
+
+    lines
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Send statsd lines to `addr` over UDP, one datagram per line.
+#[cfg(feature = "metrics")]
+pub fn emit_statsd(lines: &[String], addr: &str) -> std::io::Result<()> {
+    use std::io;
+    use std::net::{ToSocketAddrs, UdpSocket};
+
+    let target = addr.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("statsd address resolved to nothing: {}", addr),
+        )
+    })?;
+
+    let bind = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind)?;
+
+    for line in lines {
+        socket.send_to(line.as_bytes(), target)?;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ContentHash, DuplicateGroup, HashAlgorithm, KeepPolicy, ScanStats};
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn metrics_config() -> MetricsConfig {
+        MetricsConfig {
+            statsd: "127.0.0.1:8125".to_string(),
+            prefix: "dedupe".to_string(),
+            tags: BTreeMap::from([("fleet".to_string(), "design".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_record_report_counts() {
+        let report = ScanReport {
+            confirmed_duplicates: vec![DuplicateGroup {
+                original: PathBuf::from("/a.txt"),
+                hash: ContentHash([0; 32]),
+                duplicates: vec![PathBuf::from("/a 2.txt"), PathBuf::from("/a 3.txt")],
+                size_bytes: 0,
+            }],
+            orphaned_conflicts: vec![PathBuf::from("/b 2.txt")],
+            content_diverged: vec![],
+            not_local: vec![PathBuf::from("/c 2.txt")],
+            hardlinked: vec![(PathBuf::from("/d 2.txt"), PathBuf::from("/d.txt"))],
+            probable_duplicates: vec![],
+            metadata_differs: vec![],
+            bytes_recoverable: 42,
+            keep_policy: KeepPolicy::OriginalName,
+            by_directory: vec![],
+            bytes_recoverable_physical: None,
+            skipped: vec![],
+            symlink_skips: vec![],
+            warnings: vec![],
+            partial: None,
+            stats: ScanStats::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            by_parent: vec![],
+            schema_version: 0,
+            by_extension: vec![],
+            unsynced: Vec::new(),
+        };
+
+        let mut run = RunMetrics::new("scan");
+        run.record_report(&report);
+
+        assert_eq!(run.duplicates, 2);
+        assert_eq!(run.orphaned, 1);
+        assert_eq!(run.diverged, 0);
+        assert_eq!(run.not_local, 1);
+        assert_eq!(run.hardlinked, 1);
+        assert_eq!(run.bytes_recoverable, 42);
+    }
+
+    #[test]
+    fn test_format_statsd_lines() {
+        let run = RunMetrics {
+            command: "quarantine",
+            success: true,
+            duration: Duration::from_millis(1500),
+            duplicates: 3,
+            bytes_quarantined: 2048,
+            ..Default::default()
+        };
+
+        let lines = format_statsd(&run, &metrics_config());
+
+        assert_eq!(
+            lines[0],
+            "dedupe.runs:1|c|#command:quarantine,fleet:design,outcome:success"
+        );
+        assert!(lines.contains(&"dedupe.duration_ms:1500|ms|#command:quarantine,fleet:design".to_string()));
+        assert!(lines.contains(&"dedupe.duplicates:3|g|#command:quarantine,fleet:design".to_string()));
+        assert!(lines.contains(&"dedupe.bytes_quarantined:2048|g|#command:quarantine,fleet:design".to_string()));
+    }
+
+    #[test]
+    fn test_format_statsd_failure_outcome() {
+        let run = RunMetrics::new("scan");
+        let lines = format_statsd(&run, &metrics_config());
+        assert!(lines[0].ends_with("outcome:failure"));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_emit_statsd_sends_datagrams() {
+        use std::net::UdpSocket;
+
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+
+        emit_statsd(&["a.b:1|c".to_string()], &addr).unwrap();
+
+        let mut buf = [0u8; 64];
+        let (n, _) = server.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"a.b:1|c");
+    }
+}
@@ -51,12 +51,24 @@ pub fn is_conflict_file(filename: &str) -> bool {
     detect_pattern(filename).is_some()
 }
 
+/// The suffix a pattern stands for, as iCloud writes it.
+///
+/// `Copy { index: None }` → "Copy", `Copy { index: Some(2) }` → "Copy 2",
+/// `Numbered { index: 3 }` → "3".
+pub fn pattern_label(pattern: &ConflictPattern) -> String {
+    match pattern {
+        ConflictPattern::Copy { index: None } => "Copy".to_string(),
+        ConflictPattern::Copy { index: Some(index) } => format!("Copy {}", index),
+        ConflictPattern::Numbered { index } => index.to_string(),
+    }
+}
+
 // ============================================================================
 // INTERNAL: Copy pattern ("foo Copy.txt", "foo Copy 2.txt")
 // ============================================================================
 
 fn detect_copy_pattern(filename: &str) -> Option<ConflictPattern> {
-    let filename_lower = filename.to_lowercase();
+    let filename_lower = filename.to_ascii_lowercase();
 
     // Pattern: " copy N." or " copy N" at end (N >= 2)
     // Look for " copy " followed by digits, then optional extension
@@ -64,10 +76,10 @@ fn detect_copy_pattern(filename: &str) -> Option<ConflictPattern> {
         let after_copy = &filename[pos + 6..]; // skip " copy "
         // Extract the number (everything before the first '.' or end)
         let num_part = after_copy.split('.').next().unwrap_or("");
-        if let Ok(index) = num_part.trim().parse::<u32>() {
-            if index >= MIN_CONFLICT_INDEX {
-                return Some(ConflictPattern::Copy { index: Some(index) });
-            }
+        if let Ok(index) = num_part.trim().parse::<u32>()
+            && index >= MIN_CONFLICT_INDEX
+        {
+            return Some(ConflictPattern::Copy { index: Some(index) });
         }
     }
 
@@ -90,7 +102,7 @@ fn detect_copy_pattern(filename: &str) -> Option<ConflictPattern> {
 }
 
 fn derive_original_from_copy(filename: &str, index: Option<u32>) -> String {
-    let filename_lower = filename.to_lowercase();
+    let filename_lower = filename.to_ascii_lowercase();
 
     if index.is_some() {
         // "foo Copy 2.txt" → find " copy " and take everything before + extension after number
@@ -132,10 +144,10 @@ fn detect_numbered_pattern(filename: &str) -> Option<ConflictPattern> {
     // Must have a space before the number
     if let Some(pos) = stem.rfind(' ') {
         let after_space = &stem[pos + 1..];
-        if let Ok(index) = after_space.parse::<u32>() {
-            if index >= MIN_CONFLICT_INDEX {
-                return Some(ConflictPattern::Numbered { index });
-            }
+        if let Ok(index) = after_space.parse::<u32>()
+            && index >= MIN_CONFLICT_INDEX
+        {
+            return Some(ConflictPattern::Numbered { index });
         }
     }
 
@@ -313,6 +325,14 @@ mod tests {
         assert!(!is_conflict_file("Copy.txt"));
     }
 
+    #[test]
+    fn test_pattern_label() {
+        let label = |name: &str| pattern_label(&detect_pattern(name).unwrap());
+        assert_eq!(label("foo Copy.txt"), "Copy");
+        assert_eq!(label("foo Copy 2.txt"), "Copy 2");
+        assert_eq!(label("foo 3.txt"), "3");
+    }
+
     // --- Edge cases ---
 
     #[test]
@@ -344,4 +364,12 @@ mod tests {
             PathBuf::from("my file.txt")
         );
     }
+
+    #[test]
+    fn test_non_ascii_name_with_case_changing_lowercase() {
+        // 'Ⱥ' lowercases to a longer UTF-8 sequence; offsets must still line up
+        let path = Path::new("Ⱥ Copy.txt");
+        let pattern = detect_pattern("Ⱥ Copy.txt").unwrap();
+        assert_eq!(derive_original(path, &pattern), PathBuf::from("Ⱥ.txt"));
+    }
 }
@@ -1,6 +1,7 @@
-//! Platform-specific iCloud detection for macOS.
+//! Platform-specific sync-root detection: iCloud on macOS, and OneDrive on
+//! macOS and Windows.
 //!
-//! Encodes assumptions about iCloud file locations as named constants.
+//! Encodes assumptions about sync folder locations as named constants.
 //! Provides detection with explicit state representation.
 //!
 //! Structure:
@@ -9,8 +10,13 @@
 //! - Pure functions: path construction
 //! - Effect functions: filesystem detection
 
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::types::{FileId, ItemState};
+
 // ============================================================================
 // CONSTANTS (Documented Invariants)
 // ============================================================================
@@ -27,6 +33,28 @@ pub const ICLOUD_CONTAINER_REL: &str = "Library/Mobile Documents";
 /// User's iCloud Drive files (including Desktop/Documents if enabled) live here.
 pub const ICLOUD_DRIVE_BUNDLE: &str = "com~apple~CloudDocs";
 
+/// Folders iCloud Drive keeps when "Desktop & Documents Folders" is on.
+///
+/// Both live directly under the Drive root and are switched on and off
+/// together in System Settings.
+pub const DESKTOP_FOLDER: &str = "Desktop";
+pub const DOCUMENTS_FOLDER: &str = "Documents";
+
+/// Relative path from home to the File Provider sync roots (macOS 12.1+).
+///
+/// OneDrive with Files On-Demand keeps its folders here, one per account:
+/// `OneDrive-Personal`, `OneDrive-<Organization>`.
+pub const CLOUD_STORAGE_REL: &str = "Library/CloudStorage";
+
+/// Name every OneDrive sync folder starts with.
+///
+/// Older macOS clients and Windows use `~/OneDrive` and
+/// `~/OneDrive - <Organization>`; File Provider folders use a hyphen.
+pub const ONEDRIVE_PREFIX: &str = "OneDrive";
+
+/// Environment variables the Windows OneDrive client sets to its roots.
+pub const ONEDRIVE_ENV_VARS: &[&str] = &["OneDrive", "OneDriveConsumer", "OneDriveCommercial"];
+
 /// Common app container prefixes for reference.
 ///
 /// Not exhaustive — apps register their own containers.
@@ -37,6 +65,52 @@ pub mod app_containers {
     pub const PREVIEW: &str = "com~apple~Preview";
 }
 
+/// Extension of the stub iCloud leaves in place of an evicted file.
+///
+/// Before macOS Sonoma, an evicted "report.pdf" is replaced on disk by a
+/// hidden ".report.pdf.icloud" plist. Newer releases keep the real name and
+/// mark the file dataless instead (see [`SF_DATALESS`]).
+pub const PLACEHOLDER_STUB_EXT: &str = ".icloud";
+
+/// Extensions of directories that Finder shows as a single file.
+///
+/// Documents from iWork, GarageBand, Logic and friends are package
+/// directories. They are compared and quarantined whole: removing a file
+/// from inside one would corrupt the document.
+pub const BUNDLE_EXTENSIONS: &[&str] = &[
+    "app",
+    "band",
+    "bundle",
+    "fcpbundle",
+    "key",
+    "logicx",
+    "numbers",
+    "pages",
+    "photoslibrary",
+    "playground",
+    "rtfd",
+    "scriptd",
+];
+
+/// Extended attribute holding a file's Finder tags (a binary plist).
+pub const FINDER_TAGS_XATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+
+/// Extended attributes macOS sets on its own as files are opened,
+/// downloaded, or checked by Gatekeeper. They say nothing about the file
+/// the user made, so metadata comparisons leave them out.
+pub const BOOKKEEPING_XATTRS: &[&str] = &[
+    "com.apple.lastuseddate#PS",
+    "com.apple.macl",
+    "com.apple.provenance",
+    "com.apple.quarantine",
+];
+
+/// `st_flags` bit for a dataless file: metadata is local, content is not.
+///
+/// Defined in `<sys/stat.h>` (macOS 10.15+) but not exported by libc.
+/// Reading such a file makes the system download it first.
+pub const SF_DATALESS: u32 = 0x4000_0000;
+
 // ============================================================================
 // TYPES (State Representation)
 // ============================================================================
@@ -54,6 +128,8 @@ pub enum ICloudState {
         container: PathBuf,
         /// ~/Library/Mobile Documents/com~apple~CloudDocs
         drive_root: PathBuf,
+        /// Synced Desktop & Documents, when that option is on
+        desktop_documents: Option<DesktopDocuments>,
     },
 
     /// iCloud signed in but Drive disabled.
@@ -69,6 +145,38 @@ pub enum ICloudState {
         /// Path we expected to find
         expected: PathBuf,
     },
+
+    /// Not macOS: there is no iCloud Drive folder to find.
+    /// Synced folders can still be scanned by path.
+    Unsupported,
+}
+
+/// Desktop and Documents folders synced through iCloud Drive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopDocuments {
+    /// ~/Library/Mobile Documents/com~apple~CloudDocs/Desktop
+    pub desktop: PathBuf,
+    /// ~/Library/Mobile Documents/com~apple~CloudDocs/Documents
+    pub documents: PathBuf,
+}
+
+/// Detected OneDrive configuration state.
+///
+/// A machine can sync several accounts (personal and work), so a found
+/// state carries every root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OneDriveState {
+    /// One or more sync folders exist.
+    Found {
+        /// Sync roots, sorted, without repeats
+        roots: Vec<PathBuf>,
+    },
+
+    /// No sync folder in any of the usual places.
+    NotFound {
+        /// Directories we looked in
+        searched: Vec<PathBuf>,
+    },
 }
 
 /// Error during iCloud detection.
@@ -99,6 +207,16 @@ pub fn icloud_drive_path(container: &Path) -> PathBuf {
     container.join(ICLOUD_DRIVE_BUNDLE)
 }
 
+/// Compute where synced Desktop & Documents live within the Drive root.
+///
+/// Pure function — no I/O.
+pub fn desktop_documents_paths(drive_root: &Path) -> DesktopDocuments {
+    DesktopDocuments {
+        desktop: drive_root.join(DESKTOP_FOLDER),
+        documents: drive_root.join(DOCUMENTS_FOLDER),
+    }
+}
+
 /// Compute both paths from home directory.
 ///
 /// Pure function — no I/O.
@@ -108,6 +226,25 @@ pub fn icloud_paths(home: &Path) -> (PathBuf, PathBuf) {
     (container, drive)
 }
 
+/// Whether a directory name is a OneDrive sync folder.
+///
+/// Matches `OneDrive`, `OneDrive-Personal`, and `OneDrive - Contoso`.
+/// Pure function — no I/O.
+pub fn is_onedrive_root_name(name: &str) -> bool {
+    match name.strip_prefix(ONEDRIVE_PREFIX) {
+        Some("") => true,
+        Some(rest) => rest.starts_with('-') || rest.starts_with(" - "),
+        None => false,
+    }
+}
+
+/// Directories that may hold OneDrive sync folders.
+///
+/// Pure function — no I/O.
+pub fn onedrive_search_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![home.join(CLOUD_STORAGE_REL), home.to_path_buf()]
+}
+
 // ============================================================================
 // EFFECT FUNCTIONS (Detection)
 // ============================================================================
@@ -117,6 +254,9 @@ pub fn icloud_paths(home: &Path) -> (PathBuf, PathBuf) {
 /// Checks filesystem to determine current iCloud setup.
 /// Returns a state enum, not Option/bool — exhaustive handling required.
 pub fn detect_icloud() -> Result<ICloudState, DetectionError> {
+    if !cfg!(target_os = "macos") {
+        return Ok(ICloudState::Unsupported);
+    }
     let home = dirs::home_dir().ok_or(DetectionError::HomeNotFound)?;
     detect_icloud_with_home(&home)
 }
@@ -141,24 +281,817 @@ pub fn detect_icloud_with_home(home: &Path) -> Result<ICloudState, DetectionErro
 
     // Check if Drive is enabled
     if drive_root.exists() && drive_root.is_dir() {
+        let folders = desktop_documents_paths(&drive_root);
+        let desktop_documents = (folders.desktop.is_dir() && folders.documents.is_dir()).then_some(folders);
         Ok(ICloudState::DriveEnabled {
             container,
             drive_root,
+            desktop_documents,
         })
     } else {
         Ok(ICloudState::DriveDisabled { container })
     }
 }
 
+/// Detect OneDrive sync roots.
+///
+/// On Windows, the roots the client advertises in its environment
+/// variables count too.
+pub fn detect_onedrive() -> Result<OneDriveState, DetectionError> {
+    let home = dirs::home_dir().ok_or(DetectionError::HomeNotFound)?;
+    let advertised: Vec<PathBuf> = if cfg!(windows) {
+        ONEDRIVE_ENV_VARS
+            .iter()
+            .filter_map(std::env::var_os)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    detect_onedrive_with_home(&home, &advertised)
+}
+
+/// Detect OneDrive roots given a home directory and any roots the client
+/// advertised.
+///
+/// Separated for testability — can inject test home path.
+pub fn detect_onedrive_with_home(home: &Path, advertised: &[PathBuf]) -> Result<OneDriveState, DetectionError> {
+    let searched = onedrive_search_dirs(home);
+    let mut roots: Vec<PathBuf> = advertised.iter().filter(|p| p.is_dir()).cloned().collect();
+
+    for dir in &searched {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(_) if !dir.is_dir() => return Err(DetectionError::NotADirectory { path: dir.clone() }),
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if name.to_str().is_some_and(is_onedrive_root_name) && entry.path().is_dir() {
+                roots.push(entry.path());
+            }
+        }
+    }
+
+    roots.sort();
+    roots.dedup();
+    if roots.is_empty() {
+        Ok(OneDriveState::NotFound { searched })
+    } else {
+        Ok(OneDriveState::Found { roots })
+    }
+}
+
 /// Get the best path to scan based on detected state.
 ///
 /// Returns the container path if iCloud is configured.
-/// Caller decides how to handle NotConfigured.
+/// Caller decides how to handle NotConfigured and Unsupported.
 pub fn scan_root(state: &ICloudState) -> Option<&Path> {
     match state {
         ICloudState::DriveEnabled { container, .. } => Some(container),
         ICloudState::DriveDisabled { container } => Some(container),
-        ICloudState::NotConfigured { .. } => None,
+        ICloudState::NotConfigured { .. } | ICloudState::Unsupported => None,
+    }
+}
+
+/// Logical filename behind a legacy placeholder stub.
+///
+/// ".report.pdf.icloud" → Some("report.pdf"). Pure function — no I/O.
+pub fn placeholder_target(stub_name: &str) -> Option<&str> {
+    let name = stub_name
+        .strip_prefix('.')?
+        .strip_suffix(PLACEHOLDER_STUB_EXT)?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Whether a directory name marks a bundle (see [`BUNDLE_EXTENSIONS`]).
+///
+/// "Report Copy.pages" → true. Case-insensitive. Pure function — no I/O.
+pub fn is_bundle_name(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            BUNDLE_EXTENSIONS
+                .iter()
+                .any(|b| b.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Path of the legacy placeholder stub that would stand in for `path`.
+///
+/// "/dir/report.pdf" → "/dir/.report.pdf.icloud". Pure function — no I/O.
+pub fn placeholder_stub_path(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    Some(path.with_file_name(format!(".{}{}", name, PLACEHOLDER_STUB_EXT)))
+}
+
+/// Whether file metadata marks the content as not downloaded.
+#[cfg(target_os = "macos")]
+pub fn is_dataless(meta: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    meta.st_flags() & SF_DATALESS != 0
+}
+
+/// Whether file metadata marks the content as not downloaded.
+///
+/// Only macOS has dataless files.
+#[cfg(not(target_os = "macos"))]
+pub fn is_dataless(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+/// Device and inode of a file: hard links share them.
+#[cfg(unix)]
+pub fn file_id(meta: &fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileId {
+        dev: meta.dev(),
+        ino: meta.ino(),
+    })
+}
+
+/// Device and inode of a file: hard links share them.
+///
+/// Not exposed by stable Rust elsewhere, so links go undetected.
+#[cfg(not(unix))]
+pub fn file_id(_meta: &fs::Metadata) -> Option<FileId> {
+    None
+}
+
+/// Whether `path` is an iCloud file whose content isn't on this Mac.
+///
+/// True for dataless files, and for missing files that a legacy
+/// ".name.icloud" stub stands in for. Never triggers a download.
+pub fn is_not_local(path: &Path) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(meta) => is_dataless(&meta),
+        Err(_) => placeholder_stub_path(path).is_some_and(|stub| stub.is_file()),
+    }
+}
+
+/// Where `path` stands in iCloud syncing.
+///
+/// Asks the file's ubiquitous item attributes (`NSURLUbiquitousItem…`).
+/// Files outside iCloud, and every file where those can't be asked, are
+/// `Local` unless their content is evidently not on disk. Never triggers
+/// a download.
+#[cfg(target_os = "macos")]
+pub fn sync_state(path: &Path) -> ItemState {
+    // SAFETY: the pool brackets every Objective-C object created here.
+    let state = unsafe {
+        let pool = file_manager::objc_autoreleasePoolPush();
+        let state = file_manager::item_state(path);
+        file_manager::objc_autoreleasePoolPop(pool);
+        state
+    };
+    state.unwrap_or_else(|| local_state(path))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn sync_state(path: &Path) -> ItemState {
+    local_state(path)
+}
+
+/// Sync state from what's on disk alone.
+fn local_state(path: &Path) -> ItemState {
+    if is_not_local(path) {
+        ItemState::Evicted
+    } else {
+        ItemState::Local
+    }
+}
+
+/// Bytes that removing `path` would actually free.
+///
+/// On APFS, copies made by Finder are clones that share blocks with their
+/// source until either is modified; only the file's private blocks are
+/// freed. Asks `getattrlist(ATTR_CMNEXT_PRIVATESIZE)`. None when the
+/// volume can't say (not APFS, or the query failed).
+#[cfg(target_os = "macos")]
+pub fn private_size(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    // From <sys/attr.h>
+    const ATTR_BIT_MAP_COUNT: u16 = 5;
+    const ATTR_CMNEXT_PRIVATESIZE: u32 = 0x0000_0008;
+    const FSOPT_NOFOLLOW: u32 = 0x0000_0001;
+    const FSOPT_ATTR_CMN_EXTENDED: u32 = 0x0000_0020;
+
+    #[repr(C)]
+    struct AttrList {
+        bitmapcount: u16,
+        reserved: u16,
+        commonattr: u32,
+        volattr: u32,
+        dirattr: u32,
+        fileattr: u32,
+        forkattr: u32,
+    }
+
+    // Attribute buffers are 4-byte aligned, even for 8-byte values
+    #[repr(C, packed(4))]
+    struct PrivateSizeBuf {
+        length: u32,
+        private_size: libc::off_t,
+    }
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut request = AttrList {
+        bitmapcount: ATTR_BIT_MAP_COUNT,
+        reserved: 0,
+        commonattr: 0,
+        volattr: 0,
+        dirattr: 0,
+        fileattr: 0,
+        // With FSOPT_ATTR_CMN_EXTENDED, forkattr carries ATTR_CMNEXT_* bits
+        forkattr: ATTR_CMNEXT_PRIVATESIZE,
+    };
+    let mut buf = PrivateSizeBuf {
+        length: 0,
+        private_size: 0,
+    };
+
+    let result = unsafe {
+        libc::getattrlist(
+            c_path.as_ptr(),
+            &mut request as *mut AttrList as *mut libc::c_void,
+            &mut buf as *mut PrivateSizeBuf as *mut libc::c_void,
+            std::mem::size_of::<PrivateSizeBuf>(),
+            FSOPT_NOFOLLOW | FSOPT_ATTR_CMN_EXTENDED,
+        )
+    };
+
+    // Volumes without the attribute return only the length word
+    if result != 0 || (buf.length as usize) < std::mem::size_of::<PrivateSizeBuf>() {
+        return None;
+    }
+    let private_size = buf.private_size;
+    u64::try_from(private_size).ok()
+}
+
+/// Bytes that removing `path` would actually free.
+///
+/// Only APFS reports shared blocks; elsewhere this is unknown.
+#[cfg(not(target_os = "macos"))]
+pub fn private_size(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Whether the volume holding `path` compares filenames case-sensitively.
+///
+/// APFS and HFS+ are case-insensitive by default, so "Report.pdf" and
+/// "report.pdf" name the same file. Asks the filesystem via
+/// `pathconf(_PC_CASE_SENSITIVE)`; an unanswered query is treated as the
+/// macOS default (insensitive).
+#[cfg(target_os = "macos")]
+pub fn is_case_sensitive_volume(path: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+
+    let result = unsafe { libc::pathconf(c_path.as_ptr(), libc::_PC_CASE_SENSITIVE) };
+    result == 1
+}
+
+/// Whether the volume holding `path` compares filenames case-sensitively.
+///
+/// Non-macOS filesystems are assumed case-sensitive.
+#[cfg(not(target_os = "macos"))]
+pub fn is_case_sensitive_volume(_path: &Path) -> bool {
+    true
+}
+
+/// Bytes available to unprivileged writers on the volume holding `path`.
+///
+/// Asks `statvfs`, which macOS and Linux both provide.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: c_path is NUL-terminated and stats is written on success.
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), stats.as_mut_ptr()) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let stats = unsafe { stats.assume_init() };
+    #[allow(clippy::unnecessary_cast)] // field widths differ by platform
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Checking free space is only supported on macOS and Linux",
+    ))
+}
+
+/// True if another process holds an exclusive `flock` on `path`.
+///
+/// Tries a shared lock without waiting, released when the file closes.
+/// Failures other than contention (unreadable file, no lock support)
+/// count as unlocked: the read that follows will report them.
+#[cfg(unix)]
+pub fn is_locked(path: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    // SAFETY: the descriptor stays open for the duration of the call.
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) };
+    result != 0 && io::Error::last_os_error().raw_os_error() == Some(libc::EWOULDBLOCK)
+}
+
+/// True if another process holds an exclusive `flock` on `path`.
+///
+/// There is no `flock` elsewhere: nothing counts as locked.
+#[cfg(not(unix))]
+pub fn is_locked(_path: &Path) -> bool {
+    false
+}
+
+/// Extended attributes of `path` (not following symlinks), by name.
+///
+/// Empty on filesystems without xattr support. Attributes removed while
+/// being read are left out.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub fn extended_attributes(path: &Path) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let names = match read_xattr(|buf, size| unsafe { list_xattrs(&c_path, buf, size) }) {
+        Ok(names) => names,
+        Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut attrs = BTreeMap::new();
+    for name in names.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+        let Ok(c_name) = CString::new(name) else {
+            continue;
+        };
+        match read_xattr(|buf, size| unsafe { get_xattr(&c_path, &c_name, buf, size) }) {
+            Ok(value) => {
+                attrs.insert(String::from_utf8_lossy(name).into_owned(), value);
+            }
+            Err(e) if e.raw_os_error() == Some(NO_XATTR) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(attrs)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn extended_attributes(_path: &Path) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    Ok(BTreeMap::new())
+}
+
+/// Set one extended attribute on `path` (not following symlinks).
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub fn set_extended_attribute(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // SAFETY: both strings are NUL-terminated and `value` outlives the call.
+    #[cfg(target_os = "macos")]
+    let result = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+            libc::XATTR_NOFOLLOW,
+        )
+    };
+    #[cfg(target_os = "linux")]
+    let result = unsafe {
+        libc::lsetxattr(c_path.as_ptr(), c_name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn set_extended_attribute(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Extended attributes aren't supported"))
+}
+
+/// Set the creation (birth) time of `path`.
+///
+/// No-op on platforms where creation times can't be set.
+#[cfg(target_os = "macos")]
+pub fn set_creation_time(path: &Path, created: std::time::SystemTime) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::time::UNIX_EPOCH;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let since_epoch = created
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut attrs: libc::attrlist = unsafe { std::mem::zeroed() };
+    attrs.bitmapcount = libc::ATTR_BIT_MAP_COUNT;
+    attrs.commonattr = libc::ATTR_CMN_CRTIME;
+    let mut time = libc::timespec {
+        tv_sec: since_epoch.as_secs() as libc::time_t,
+        tv_nsec: since_epoch.subsec_nanos() as libc::c_long,
+    };
+
+    // SAFETY: `attrs` requests exactly one timespec, which `time` holds.
+    let result = unsafe {
+        libc::setattrlist(
+            c_path.as_ptr(),
+            (&mut attrs as *mut libc::attrlist).cast(),
+            (&mut time as *mut libc::timespec).cast(),
+            std::mem::size_of::<libc::timespec>(),
+            libc::FSOPT_NOFOLLOW,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_creation_time(_path: &Path, _created: std::time::SystemTime) -> io::Result<()> {
+    Ok(())
+}
+
+/// errno for "no such attribute".
+#[cfg(target_os = "macos")]
+const NO_XATTR: i32 = libc::ENOATTR;
+#[cfg(target_os = "linux")]
+const NO_XATTR: i32 = libc::ENODATA;
+
+/// Run an xattr call that reports the size it needs when given no buffer,
+/// growing the buffer if the attribute grows between the two calls.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn read_xattr<F>(call: F) -> io::Result<Vec<u8>>
+where
+    F: Fn(*mut libc::c_void, usize) -> isize,
+{
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut buf = vec![0u8; size as usize];
+        let read = call(buf.as_mut_ptr().cast(), buf.len());
+        if read >= 0 {
+            buf.truncate(read as usize);
+            return Ok(buf);
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}
+
+/// SAFETY: `buf` must be null or valid for `size` bytes.
+#[cfg(target_os = "macos")]
+unsafe fn list_xattrs(path: &std::ffi::CStr, buf: *mut libc::c_void, size: usize) -> isize {
+    unsafe { libc::listxattr(path.as_ptr(), buf.cast(), size, libc::XATTR_NOFOLLOW) }
+}
+
+/// SAFETY: `buf` must be null or valid for `size` bytes.
+#[cfg(target_os = "macos")]
+unsafe fn get_xattr(
+    path: &std::ffi::CStr,
+    name: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    size: usize,
+) -> isize {
+    unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf, size, 0, libc::XATTR_NOFOLLOW) }
+}
+
+/// SAFETY: `buf` must be null or valid for `size` bytes.
+#[cfg(target_os = "linux")]
+unsafe fn list_xattrs(path: &std::ffi::CStr, buf: *mut libc::c_void, size: usize) -> isize {
+    unsafe { libc::llistxattr(path.as_ptr(), buf.cast(), size) }
+}
+
+/// SAFETY: `buf` must be null or valid for `size` bytes.
+#[cfg(target_os = "linux")]
+unsafe fn get_xattr(
+    path: &std::ffi::CStr,
+    name: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    size: usize,
+) -> isize {
+    unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), buf, size) }
+}
+
+/// Clone `from` to `to` with `clonefile(2)`: an instant copy that shares
+/// its blocks with the source until either changes. Directories are
+/// cloned with everything in them.
+///
+/// Fails with `Unsupported` on volumes without clones (anything but APFS)
+/// and `CrossesDevices` across volumes.
+#[cfg(target_os = "macos")]
+pub fn clone_file(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// Clone a symlink itself rather than what it points to.
+    const CLONE_NOFOLLOW: u32 = 0x0001;
+
+    let c_from = CString::new(from.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_to = CString::new(to.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // SAFETY: both paths are NUL-terminated.
+    let result = unsafe { libc::clonefile(c_from.as_ptr(), c_to.as_ptr(), CLONE_NOFOLLOW) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn clone_file(_from: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Cloning files is only supported on macOS",
+    ))
+}
+
+/// Move `path` to the user's Trash, returning where it ended up.
+///
+/// Goes through `NSFileManager`, so Finder's "Put Back" knows where the
+/// item came from.
+#[cfg(target_os = "macos")]
+pub fn move_to_trash(path: &Path) -> io::Result<PathBuf> {
+    // SAFETY: the pool brackets every Objective-C object created here.
+    unsafe {
+        let pool = file_manager::objc_autoreleasePoolPush();
+        let result = file_manager::trash_item(path);
+        file_manager::objc_autoreleasePoolPop(pool);
+        result
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn move_to_trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Moving to the Trash is only supported on macOS",
+    ))
+}
+
+/// Remove the local copy of an iCloud file, leaving it in the cloud.
+///
+/// The file stays listed and downloads again when opened. Goes through
+/// `NSFileManager`, like Finder's "Remove Download"; fails for files
+/// outside iCloud or not yet uploaded.
+#[cfg(target_os = "macos")]
+pub fn evict(path: &Path) -> io::Result<()> {
+    // SAFETY: the pool brackets every Objective-C object created here.
+    unsafe {
+        let pool = file_manager::objc_autoreleasePoolPush();
+        let result = file_manager::evict_item(path);
+        file_manager::objc_autoreleasePoolPop(pool);
+        result
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn evict(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Evicting iCloud files is only supported on macOS",
+    ))
+}
+
+/// Ask iCloud to download an evicted file, without waiting for it.
+///
+/// `path` is the file's logical path, also for legacy ".name.icloud"
+/// stubs. Check [`is_not_local`] to see when the download has landed.
+#[cfg(target_os = "macos")]
+pub fn request_download(path: &Path) -> io::Result<()> {
+    // SAFETY: the pool brackets every Objective-C object created here.
+    unsafe {
+        let pool = file_manager::objc_autoreleasePoolPush();
+        let result = file_manager::start_download(path);
+        file_manager::objc_autoreleasePoolPop(pool);
+        result
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn request_download(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Downloading iCloud files is only supported on macOS",
+    ))
+}
+
+/// Minimal Objective-C runtime calls for `NSFileManager`'s trash,
+/// eviction, and download methods.
+#[cfg(target_os = "macos")]
+mod file_manager {
+    use std::ffi::{c_char, c_void, CStr, CString};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    use crate::types::ItemState;
+
+    type Id = *mut c_void;
+    type Sel = *mut c_void;
+
+    #[link(name = "Foundation", kind = "framework")]
+    unsafe extern "C" {}
+
+    #[link(name = "objc")]
+    unsafe extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn sel_registerName(name: *const c_char) -> Sel;
+        fn objc_msgSend();
+        pub fn objc_autoreleasePoolPush() -> *mut c_void;
+        pub fn objc_autoreleasePoolPop(pool: *mut c_void);
+    }
+
+    unsafe fn class(name: &CStr) -> Id {
+        unsafe { objc_getClass(name.as_ptr()) }
+    }
+
+    unsafe fn sel(name: &CStr) -> Sel {
+        unsafe { sel_registerName(name.as_ptr()) }
+    }
+
+    /// `[receiver selector]` for selectors returning an object.
+    unsafe fn send(receiver: Id, selector: &CStr) -> Id {
+        let f: unsafe extern "C" fn(Id, Sel) -> Id = unsafe { std::mem::transmute(objc_msgSend as unsafe extern "C" fn()) };
+        unsafe { f(receiver, sel(selector)) }
+    }
+
+    /// `[receiver selector:arg]` for one pointer argument.
+    unsafe fn send1(receiver: Id, selector: &CStr, arg: *const c_void) -> Id {
+        let f: unsafe extern "C" fn(Id, Sel, *const c_void) -> Id =
+            unsafe { std::mem::transmute(objc_msgSend as unsafe extern "C" fn()) };
+        unsafe { f(receiver, sel(selector), arg) }
+    }
+
+    /// Rust string from an `NSString` (empty for nil).
+    unsafe fn string(ns: Id) -> String {
+        if ns.is_null() {
+            return String::new();
+        }
+        let utf8 = unsafe { send(ns, c"UTF8String") } as *const c_char;
+        if utf8.is_null() {
+            return String::new();
+        }
+        unsafe { CStr::from_ptr(utf8) }.to_string_lossy().into_owned()
+    }
+
+    /// `NSURL` for a path. SAFETY: must run inside an autorelease pool.
+    unsafe fn file_url(path: &Path) -> io::Result<Id> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        unsafe {
+            let ns_path = send1(class(c"NSString"), c"stringWithUTF8String:", c_path.as_ptr().cast());
+            Ok(send1(class(c"NSURL"), c"fileURLWithPath:", ns_path))
+        }
+    }
+
+    /// SAFETY: must run inside an autorelease pool.
+    pub unsafe fn trash_item(path: &Path) -> io::Result<PathBuf> {
+        unsafe {
+            let url = file_url(path)?;
+            let manager = send(class(c"NSFileManager"), c"defaultManager");
+
+            let mut resulting: Id = std::ptr::null_mut();
+            let mut error: Id = std::ptr::null_mut();
+            let trash: unsafe extern "C" fn(Id, Sel, Id, *mut Id, *mut Id) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            let ok = trash(
+                manager,
+                sel(c"trashItemAtURL:resultingItemURL:error:"),
+                url,
+                &mut resulting,
+                &mut error,
+            );
+
+            if ok == 0 {
+                let message = string(send(error, c"localizedDescription"));
+                return Err(io::Error::other(format!("Cannot move {} to the Trash: {}", path.display(), message)));
+            }
+            Ok(PathBuf::from(string(send(resulting, c"path"))))
+        }
+    }
+
+    /// `[[NSFileManager defaultManager] selector:url error:&error]` for
+    /// methods returning BOOL. SAFETY: must run inside an autorelease pool.
+    unsafe fn url_action(path: &Path, selector: &CStr, what: &str) -> io::Result<()> {
+        unsafe {
+            let url = file_url(path)?;
+            let manager = send(class(c"NSFileManager"), c"defaultManager");
+
+            let mut error: Id = std::ptr::null_mut();
+            let action: unsafe extern "C" fn(Id, Sel, Id, *mut Id) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            let ok = action(manager, sel(selector), url, &mut error);
+
+            if ok == 0 {
+                let message = string(send(error, c"localizedDescription"));
+                return Err(io::Error::other(format!("Cannot {} {}: {}", what, path.display(), message)));
+            }
+            Ok(())
+        }
+    }
+
+    /// SAFETY: must run inside an autorelease pool.
+    pub unsafe fn evict_item(path: &Path) -> io::Result<()> {
+        unsafe { url_action(path, c"evictUbiquitousItemAtURL:error:", "evict") }
+    }
+
+    /// SAFETY: must run inside an autorelease pool.
+    pub unsafe fn start_download(path: &Path) -> io::Result<()> {
+        unsafe { url_action(path, c"startDownloadingUbiquitousItemAtURL:error:", "download") }
+    }
+
+    /// `[url getResourceValue:&value forKey:key error:nil]`, nil when
+    /// unavailable. The `NSURL…Key` constants' values are their names.
+    unsafe fn resource_value(url: Id, key: &CStr) -> Id {
+        unsafe {
+            let ns_key = send1(class(c"NSString"), c"stringWithUTF8String:", key.as_ptr().cast());
+            let mut value: Id = std::ptr::null_mut();
+            let get: unsafe extern "C" fn(Id, Sel, *mut Id, Id, *mut Id) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            let ok = get(url, sel(c"getResourceValue:forKey:error:"), &mut value, ns_key, std::ptr::null_mut());
+            if ok == 0 { std::ptr::null_mut() } else { value }
+        }
+    }
+
+    /// A boolean resource value; false when unavailable.
+    unsafe fn flag(url: Id, key: &CStr) -> bool {
+        unsafe {
+            let value = resource_value(url, key);
+            if value.is_null() {
+                return false;
+            }
+            let bool_value: unsafe extern "C" fn(Id, Sel) -> i8 =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            bool_value(value, sel(c"boolValue")) != 0
+        }
+    }
+
+    /// None for files iCloud doesn't manage. SAFETY: must run inside an
+    /// autorelease pool.
+    pub unsafe fn item_state(path: &Path) -> Option<ItemState> {
+        unsafe {
+            let url = file_url(path).ok()?;
+            if !flag(url, c"NSURLIsUbiquitousItemKey") {
+                return None;
+            }
+            let status = string(resource_value(url, c"NSURLUbiquitousItemDownloadingStatusKey"));
+            Some(if flag(url, c"NSURLUbiquitousItemHasUnresolvedConflictsKey") {
+                ItemState::Conflicted
+            } else if flag(url, c"NSURLUbiquitousItemIsUploadingKey") || !flag(url, c"NSURLUbiquitousItemIsUploadedKey") {
+                ItemState::Uploading
+            } else if flag(url, c"NSURLUbiquitousItemIsDownloadingKey") {
+                ItemState::Downloading
+            } else if status == "NSURLUbiquitousItemDownloadingStatusNotDownloaded" {
+                ItemState::Evicted
+            } else {
+                ItemState::Local
+            })
+        }
     }
 }
 
@@ -169,6 +1102,9 @@ pub fn scan_root(state: &ICloudState) -> Option<&Path> {
 impl std::fmt::Display for ICloudState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            ICloudState::DriveEnabled { container, desktop_documents: Some(_), .. } => {
+                write!(f, "iCloud Drive enabled, Desktop & Documents synced ({})", container.display())
+            }
             ICloudState::DriveEnabled { container, .. } => {
                 write!(f, "iCloud Drive enabled ({})", container.display())
             }
@@ -186,6 +1122,24 @@ impl std::fmt::Display for ICloudState {
                     expected.display()
                 )
             }
+            ICloudState::Unsupported => {
+                write!(f, "iCloud Drive is only detected on macOS; specify a path to scan")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for OneDriveState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OneDriveState::Found { roots } => {
+                let shown: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+                write!(f, "OneDrive found ({})", shown.join(", "))
+            }
+            OneDriveState::NotFound { searched } => {
+                let shown: Vec<String> = searched.iter().map(|r| r.display().to_string()).collect();
+                write!(f, "OneDrive not found (searched: {})", shown.join(", "))
+            }
         }
     }
 }
@@ -254,6 +1208,16 @@ mod tests {
         assert!(scan_root(&state).is_none());
     }
 
+    #[test]
+    fn test_unsupported_asks_for_a_path() {
+        let state = ICloudState::Unsupported;
+        assert!(scan_root(&state).is_none());
+        assert!(state.to_string().contains("specify a path"));
+        if !cfg!(target_os = "macos") {
+            assert_eq!(detect_icloud().unwrap(), ICloudState::Unsupported);
+        }
+    }
+
     #[test]
     fn test_detect_drive_disabled() {
         let temp = TempDir::new().unwrap();
@@ -277,9 +1241,10 @@ mod tests {
         let state = detect_icloud_with_home(temp.path()).unwrap();
 
         match &state {
-            ICloudState::DriveEnabled { container: c, drive_root: d } => {
+            ICloudState::DriveEnabled { container: c, drive_root: d, desktop_documents } => {
                 assert!(c.exists());
                 assert!(d.exists());
+                assert_eq!(*desktop_documents, None);
             }
             _ => panic!("Expected DriveEnabled, got {:?}", state),
         }
@@ -301,8 +1266,133 @@ mod tests {
         let enabled = ICloudState::DriveEnabled {
             container: PathBuf::from("/test"),
             drive_root: PathBuf::from("/test/drive"),
+            desktop_documents: None,
         };
         assert!(enabled.to_string().contains("Drive enabled"));
+        assert!(!enabled.to_string().contains("Desktop"));
+    }
+
+    #[test]
+    fn test_detect_desktop_documents() {
+        let temp = TempDir::new().unwrap();
+        let drive = temp.path().join(ICLOUD_CONTAINER_REL).join(ICLOUD_DRIVE_BUNDLE);
+        fs::create_dir_all(drive.join(DESKTOP_FOLDER)).unwrap();
+
+        // Desktop alone is an ordinary folder someone made, not the feature
+        let state = detect_icloud_with_home(temp.path()).unwrap();
+        assert!(matches!(state, ICloudState::DriveEnabled { desktop_documents: None, .. }));
+
+        fs::create_dir(drive.join(DOCUMENTS_FOLDER)).unwrap();
+        let state = detect_icloud_with_home(temp.path()).unwrap();
+        match &state {
+            ICloudState::DriveEnabled { desktop_documents: Some(folders), .. } => {
+                assert_eq!(*folders, desktop_documents_paths(&drive));
+            }
+            _ => panic!("Expected Desktop & Documents, got {:?}", state),
+        }
+        assert!(state.to_string().contains("Desktop & Documents synced"));
+    }
+
+    #[test]
+    fn test_is_onedrive_root_name() {
+        assert!(is_onedrive_root_name("OneDrive"));
+        assert!(is_onedrive_root_name("OneDrive-Personal"));
+        assert!(is_onedrive_root_name("OneDrive - Contoso"));
+        assert!(!is_onedrive_root_name("OneDriveBackup"));
+        assert!(!is_onedrive_root_name("GoogleDrive-me@example.com"));
+    }
+
+    #[test]
+    fn test_detect_onedrive_finds_every_account() {
+        let temp = TempDir::new().unwrap();
+        let storage = temp.path().join(CLOUD_STORAGE_REL);
+        fs::create_dir_all(storage.join("OneDrive-Personal")).unwrap();
+        fs::create_dir_all(storage.join("GoogleDrive-me")).unwrap();
+        fs::create_dir_all(temp.path().join("OneDrive - Contoso")).unwrap();
+        fs::write(temp.path().join("OneDrive"), "not a folder").unwrap();
+
+        let state = detect_onedrive_with_home(temp.path(), &[]).unwrap();
+        assert_eq!(
+            state,
+            OneDriveState::Found {
+                roots: vec![storage.join("OneDrive-Personal"), temp.path().join("OneDrive - Contoso")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_detect_onedrive_uses_advertised_roots() {
+        let temp = TempDir::new().unwrap();
+        let elsewhere = temp.path().join("D").join("OneDrive");
+        fs::create_dir_all(&elsewhere).unwrap();
+        let home = temp.path().join("home");
+        fs::create_dir(&home).unwrap();
+
+        let state = detect_onedrive_with_home(&home, &[elsewhere.clone(), temp.path().join("gone")]).unwrap();
+        assert_eq!(state, OneDriveState::Found { roots: vec![elsewhere] });
+    }
+
+    #[test]
+    fn test_detect_onedrive_not_found() {
+        let temp = TempDir::new().unwrap();
+        let state = detect_onedrive_with_home(temp.path(), &[]).unwrap();
+        assert_eq!(
+            state,
+            OneDriveState::NotFound {
+                searched: onedrive_search_dirs(temp.path()),
+            }
+        );
+        assert!(state.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_sync_state_of_local_and_evicted_files() {
+        let temp = TempDir::new().unwrap();
+        let local = temp.path().join("local.pdf");
+        fs::write(&local, "content").unwrap();
+        fs::write(temp.path().join(".report.pdf.icloud"), "plist").unwrap();
+
+        assert_eq!(sync_state(&local), ItemState::Local);
+        assert_eq!(sync_state(&temp.path().join("report.pdf")), ItemState::Evicted);
+    }
+
+    #[test]
+    fn test_placeholder_target() {
+        assert_eq!(placeholder_target(".report.pdf.icloud"), Some("report.pdf"));
+        assert_eq!(placeholder_target(".notes 2.txt.icloud"), Some("notes 2.txt"));
+        assert_eq!(placeholder_target("report.pdf.icloud"), None);
+        assert_eq!(placeholder_target(".report.pdf"), None);
+        assert_eq!(placeholder_target("..icloud"), None);
+    }
+
+    #[test]
+    fn test_is_bundle_name() {
+        assert!(is_bundle_name("Report.pages"));
+        assert!(is_bundle_name("Report Copy.PAGES"));
+        assert!(is_bundle_name("Song 2.logicx"));
+        assert!(!is_bundle_name("Photos 2"));
+        assert!(!is_bundle_name("notes.txt"));
+    }
+
+    #[test]
+    fn test_placeholder_stub_path() {
+        assert_eq!(
+            placeholder_stub_path(Path::new("/a/report.pdf")),
+            Some(PathBuf::from("/a/.report.pdf.icloud"))
+        );
+    }
+
+    #[test]
+    fn test_is_not_local_detects_stub_for_missing_file() {
+        let temp = TempDir::new().unwrap();
+        let evicted = temp.path().join("report.pdf");
+        let local = temp.path().join("local.pdf");
+        fs::write(temp.path().join(".report.pdf.icloud"), "plist").unwrap();
+        fs::write(&local, "content").unwrap();
+
+        assert!(is_not_local(&evicted));
+        assert!(!is_not_local(&local));
+        assert!(!is_not_local(&temp.path().join("missing.pdf")));
     }
 
     #[test]
@@ -313,4 +1403,29 @@ mod tests {
         assert!(!ICLOUD_CONTAINER_REL.starts_with('/'));
         assert!(!ICLOUD_CONTAINER_REL.starts_with('~'));
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_is_locked_sees_exclusive_flock() {
+        use std::os::unix::io::AsRawFd;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("busy.txt");
+        fs::write(&path, "x").unwrap();
+        assert!(!is_locked(&path));
+
+        let holder = fs::File::open(&path).unwrap();
+        assert_eq!(unsafe { libc::flock(holder.as_raw_fd(), libc::LOCK_EX) }, 0);
+        assert!(is_locked(&path));
+
+        drop(holder);
+        assert!(!is_locked(&path));
+    }
+
+    #[test]
+    fn test_available_space_reports_volume() {
+        let dir = TempDir::new().unwrap();
+        assert!(available_space(dir.path()).unwrap() > 0);
+        assert!(available_space(&dir.path().join("missing")).is_err());
+    }
 }
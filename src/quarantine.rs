@@ -3,23 +3,133 @@
 //! Moves confirmed duplicates to a staging area for safe removal.
 //! Supports restore and purge operations.
 //!
+//! Every move into the quarantine directory is first appended to a
+//! journal, which is removed once the manifest is saved. A journal found
+//! later means a run died in between; [`recover_journal`] finishes or
+//! rolls back each move it lists, so no file is left without a receipt.
+//!
 //! Structure:
-//! - Pure functions: path computation, ID generation
-//! - Effect functions: file moves, manifest I/O
+//! - Pure functions: path computation, directory planning, ID generation
+//! - Effect functions: file moves, journal and manifest I/O
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-
-use crate::hash::hash_file;
-use crate::types::{ContentHash, DuplicateGroup, Manifest, QuarantineConfig, QuarantineReceipt};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use humansize::{format_size, BINARY};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::CancelToken;
+use crate::config::default_data_dir;
+use crate::hash::{hash_bytes, hash_path_with, tree_size};
+use crate::pattern::{detect_pattern, pattern_label};
+use crate::platform::{
+    available_space, clone_file, extended_attributes, file_id, move_to_trash, set_creation_time, set_extended_attribute,
+};
+use crate::history::now_secs;
+use crate::report::format_date;
+use crate::stats::{append_history, entries_for, history_log_path, Outcome};
+use crate::types::{
+    ContentHash, Destination, DuplicateGroup, HashAlgorithm, JournalRecovery, KeptFileChange,
+    KeptFileCheck, Manifest, ManifestExport, ManifestVerification, PreservedMetadata, Provenance,
+    PurgeSelection, QuarantineConfig, QuarantineReceipt, QuarantineStatus, ReceiptCheck, ReceiptStatus,
+    ReceiptVerification, RestoreConflict, RestoreSummary, RunSettings, ScanConfig, SessionSummary,
+};
 
 /// Current manifest format version.
-const MANIFEST_VERSION: u32 = 1;
+///
+/// - v1: receipts only, each carrying its own run settings.
+/// - v2: adds a session table holding each run's settings once; receipts
+///   carry provenance and metadata (extended attribute payloads included).
+const MANIFEST_VERSION: u32 = 2;
 
 /// Manifest filename within quarantine directory.
 const MANIFEST_FILENAME: &str = "manifest.json";
 
+/// Current export format version.
+const EXPORT_VERSION: u32 = 1;
+
+/// Where an export with files keeps them, relative to the export directory.
+const EXPORT_FILES_DIR: &str = "files";
+
+/// Journal filename within quarantine directory.
+const JOURNAL_FILENAME: &str = "journal.jsonl";
+
+/// Advisory lock filename within quarantine directory.
+const LOCK_FILENAME: &str = ".lock";
+
+/// Headroom kept free on the quarantine volume beyond the planned moves.
+const SPACE_MARGIN: u64 = 1024 * 1024;
+
+/// Rough manifest growth per quarantined file.
+const RECEIPT_BYTES: u64 = 1024;
+
+/// Largest extended attribute kept on a receipt, in bytes.
+const MAX_PRESERVED_XATTR: usize = 64 * 1024;
+
+/// Exclusive hold on a quarantine directory; released when dropped.
+#[derive(Debug)]
+pub struct QuarantineLock {
+    _file: fs::File,
+}
+
+/// Append-only record of moves in flight: one receipt per line, synced
+/// before its file moves.
+struct Journal {
+    file: fs::File,
+}
+
+impl Journal {
+    fn open(config: &QuarantineConfig) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(journal_path(config))?;
+        Ok(Self { file })
+    }
+
+    fn record(&mut self, receipt: &QuarantineReceipt) -> io::Result<()> {
+        let line = serde_json::to_string(receipt)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writeln!(self.file, "{}", line)?;
+        self.file.sync_data()
+    }
+}
+
+/// What a quarantine run records on each receipt besides the file itself.
+#[derive(Default)]
+struct ReceiptStamp<'a> {
+    settings: Option<&'a RunSettings>,
+    session: Option<&'a str>,
+    provenance: Option<Provenance>,
+}
+
+/// The manifest as written to disk: its contents plus a checksum over them
+/// (absent in manifests written before checksums).
+///
+/// The checksum covers the manifest as loaded, with session settings put
+/// back on their receipts, so it doesn't depend on the on-disk layout.
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+    version: u32,
+    /// Settings shared by every receipt of a session, by session ID (v2).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    sessions: BTreeMap<String, SessionRecord>,
+    quarantined: Vec<QuarantineReceipt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+}
+
+/// One quarantine run in a v2 manifest's session table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SessionRecord {
+    settings: RunSettings,
+}
+
 // ============================================================================
 // PURE FUNCTIONS (Computations)
 // ============================================================================
@@ -28,10 +138,39 @@ const MANIFEST_FILENAME: &str = "manifest.json";
 ///
 /// On macOS: ~/Library/Application Support/icloud-dedupe/quarantine/
 pub fn default_quarantine_dir() -> PathBuf {
-    dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("icloud-dedupe")
-        .join("quarantine")
+    quarantine_dir_in(&default_data_dir())
+}
+
+/// Quarantine directory within a given state directory.
+pub fn quarantine_dir_in(data_dir: &Path) -> PathBuf {
+    data_dir.join("quarantine")
+}
+
+/// Snapshot the settings a quarantine runs under, for its receipts.
+pub fn run_settings(scan: &ScanConfig, config: &QuarantineConfig) -> RunSettings {
+    RunSettings {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        hash_algorithm: config.hash_algorithm.name().to_string(),
+        roots: scan.roots.clone(),
+        max_depth: scan.max_depth,
+        follow_symlinks: scan.follow_symlinks,
+        include_hidden: scan.include_hidden,
+        used_index: scan.index_path.is_some(),
+        used_hash_cache: scan.hash_cache_path.is_some(),
+        preserve_structure: config.preserve_structure,
+    }
+}
+
+/// The settings recorded for a quarantine run without any: enough for its
+/// receipts to name the hash algorithm, so restore and verify re-hash
+/// with the right one.
+fn minimal_settings(config: &QuarantineConfig) -> RunSettings {
+    RunSettings {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        hash_algorithm: config.hash_algorithm.name().to_string(),
+        preserve_structure: config.preserve_structure,
+        ..Default::default()
+    }
 }
 
 /// Compute where a file should be stored in quarantine.
@@ -60,21 +199,207 @@ pub fn compute_quarantine_path(
     }
 }
 
+/// A quarantine path next to `taken` with the receipt ID in its name, for
+/// when an earlier session already quarantined a file from the same path.
+pub fn disambiguate_quarantine_path(taken: &Path, receipt_id: &str) -> PathBuf {
+    let filename = taken
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown");
+    taken.with_file_name(format!("{}_{}", receipt_id, filename))
+}
+
+/// Compute the directories a quarantine session will move files into.
+///
+/// Returns only the deepest ones: creating those with `create_dir_all`
+/// brings every ancestor into existence as well.
+pub fn plan_directories(groups: &[DuplicateGroup], config: &QuarantineConfig) -> Vec<PathBuf> {
+    // Receipt IDs only affect the filename, never the parent directory
+    let dirs: BTreeSet<PathBuf> = groups
+        .iter()
+        .flat_map(|g| &g.duplicates)
+        .filter_map(|path| {
+            compute_quarantine_path(path, "", config)
+                .parent()
+                .map(Path::to_path_buf)
+        })
+        .collect();
+
+    // Component-wise ordering puts descendants right after their ancestor,
+    // so a directory is an ancestor iff the next one starts with it.
+    let sorted: Vec<PathBuf> = dirs.into_iter().collect();
+    sorted
+        .iter()
+        .enumerate()
+        .filter(|(i, dir)| sorted.get(i + 1).is_none_or(|next| !next.starts_with(dir)))
+        .map(|(_, dir)| dir.clone())
+        .collect()
+}
+
+/// Bytes the quarantine volume needs to take `files`, given as
+/// (size, already on the quarantine volume).
+///
+/// Moves within a volume are renames and need no room for content; files
+/// from other volumes must be copied. Either way the manifest grows, and
+/// [`SPACE_MARGIN`] is kept free.
+pub fn required_space(files: &[(u64, bool)]) -> u64 {
+    let copied: u64 = files
+        .iter()
+        .filter(|(_, same_volume)| !same_volume)
+        .map(|(size, _)| size)
+        .sum();
+    copied + RECEIPT_BYTES * files.len() as u64 + SPACE_MARGIN
+}
+
+/// Originals whose duplicates were all quarantined by `manifest`.
+pub fn completed_groups(groups: &[DuplicateGroup], manifest: &Manifest) -> Vec<PathBuf> {
+    let moved: HashSet<&Path> = manifest
+        .quarantined
+        .iter()
+        .map(|r| r.original_path.as_path())
+        .collect();
+
+    groups
+        .iter()
+        .filter(|g| g.duplicates.iter().all(|d| moved.contains(d.as_path())))
+        .map(|g| g.original.clone())
+        .collect()
+}
+
+/// Add a session's receipts to an existing manifest.
+///
+/// Earlier receipts are all kept: each session's files get their own
+/// quarantine paths, even when quarantined from the same original path.
+pub fn merge_receipts(existing: Manifest, receipts: Vec<QuarantineReceipt>) -> Manifest {
+    let mut quarantined = existing.quarantined;
+    quarantined.extend(receipts);
+
+    Manifest {
+        version: MANIFEST_VERSION,
+        quarantined,
+    }
+}
+
+/// Quarantine runs in a manifest, in the order they were recorded.
+pub fn sessions(manifest: &Manifest) -> Vec<SessionSummary> {
+    let mut sessions: Vec<SessionSummary> = Vec::new();
+    for receipt in &manifest.quarantined {
+        match sessions.iter_mut().find(|s| s.id == receipt.session) {
+            Some(session) => {
+                session.files += 1;
+                session.bytes += receipt.size_bytes;
+            }
+            None => sessions.push(SessionSummary {
+                id: receipt.session.clone(),
+                started_at: receipt.quarantined_at.clone(),
+                files: 1,
+                bytes: receipt.size_bytes,
+            }),
+        }
+    }
+    sessions
+}
+
+/// The quarantine's contents and totals at `now`, as `status` reports them.
+pub fn quarantine_status(
+    manifest: &Manifest,
+    config: &QuarantineConfig,
+    retention: Option<Duration>,
+    now: u64,
+) -> QuarantineStatus {
+    let receipts = manifest
+        .quarantined
+        .iter()
+        .map(|receipt| {
+            let age_secs = receipt_age(receipt, now);
+            ReceiptStatus {
+                receipt: receipt.clone(),
+                age_secs,
+                expires_in_secs: age_secs
+                    .zip(retention)
+                    .map(|(age, retention)| retention.as_secs().saturating_sub(age)),
+            }
+        })
+        .collect();
+
+    QuarantineStatus {
+        quarantine_dir: config.quarantine_dir.clone(),
+        files: manifest.quarantined.len(),
+        total_bytes: manifest.quarantined.iter().map(|r| r.size_bytes).sum(),
+        retention_secs: retention.map(|r| r.as_secs()),
+        sessions: sessions(manifest),
+        receipts,
+    }
+}
+
+/// Receipts recorded by one quarantine run.
+pub fn session_receipts<'a>(manifest: &'a Manifest, session: &str) -> Vec<&'a QuarantineReceipt> {
+    manifest
+        .quarantined
+        .iter()
+        .filter(|r| r.session.as_deref() == Some(session))
+        .collect()
+}
+
+/// Replace the `from` prefix of `path` with `to`; other paths are unchanged.
+pub fn remap_prefix(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(rest) => to.join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Re-map every receipt's original path from one home directory to another.
+pub fn remap_originals(manifest: &mut Manifest, from: &Path, to: &Path) {
+    for receipt in &mut manifest.quarantined {
+        receipt.original_path = remap_prefix(&receipt.original_path, from, to);
+    }
+}
+
+/// Name for a restore that can't use the original path: "doc (restored).txt",
+/// then "doc (restored 2).txt" and so on.
+pub fn restored_name(original: &Path, attempt: u32) -> PathBuf {
+    let stem = original.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let suffix = if attempt <= 1 {
+        " (restored)".to_string()
+    } else {
+        format!(" (restored {})", attempt)
+    };
+    let name = match original.extension() {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+    original.with_file_name(name)
+}
+
+/// Generate a session ID for a quarantine run or a scan.
+///
+/// Format: compact UTC timestamp plus the random suffix of a receipt ID,
+/// e.g. `20261016T142301Z-3f2a`.
+pub fn generate_session_id() -> String {
+    let stamp: String = current_timestamp().chars().filter(|c| *c != '-' && *c != ':').collect();
+    let receipt = generate_receipt_id();
+    let suffix = receipt.rsplit('-').next().unwrap_or_default();
+    format!("{}-{}", stamp, suffix)
+}
+
 /// Generate a unique receipt ID.
 ///
 /// Format: timestamp + random suffix for uniqueness.
 pub fn generate_receipt_id() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    static ISSUED: AtomicU32 = AtomicU32::new(0);
 
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis())
         .unwrap_or(0);
 
-    // Add random suffix for uniqueness within same millisecond
+    // Add random suffix for uniqueness within same millisecond; the counter
+    // keeps IDs issued by this process in the same millisecond apart
     let random: u32 = std::process::id() ^ (timestamp as u32);
+    let suffix = random.wrapping_add(ISSUED.fetch_add(1, Ordering::Relaxed));
 
-    format!("{:x}-{:04x}", timestamp, random & 0xFFFF)
+    format!("{:x}-{:04x}", timestamp, suffix & 0xFFFF)
 }
 
 /// Path to the manifest file.
@@ -82,35 +407,141 @@ pub fn manifest_path(config: &QuarantineConfig) -> PathBuf {
     config.quarantine_dir.join(MANIFEST_FILENAME)
 }
 
+/// Path to the journal of moves in flight.
+pub fn journal_path(config: &QuarantineConfig) -> PathBuf {
+    config.quarantine_dir.join(JOURNAL_FILENAME)
+}
+
 /// Get current timestamp as ISO 8601 string.
 fn current_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
     let secs = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
+    format_timestamp(secs)
+}
 
-    // Simple ISO 8601 format (UTC)
-    let days = secs / 86400;
+/// Format seconds since the Unix epoch as ISO 8601 (UTC).
+pub fn format_timestamp(secs: u64) -> String {
     let time = secs % 86400;
-    let hours = time / 3600;
-    let mins = (time % 3600) / 60;
-    let secs = time % 60;
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_date(secs),
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    )
+}
 
-    // Days since 1970-01-01
-    // This is a simplified calculation, not handling leap years perfectly
-    let years = 1970 + (days / 365);
-    let day_of_year = days % 365;
-    let month = day_of_year / 30 + 1;
-    let day = day_of_year % 30 + 1;
+/// Parse a receipt timestamp (`YYYY-MM-DDTHH:MM:SSZ`) back to seconds
+/// since the Unix epoch.
+pub fn parse_timestamp(stamp: &str) -> Option<u64> {
+    let (date, time) = stamp.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.splitn(3, ':').map(|p| p.parse::<u64>().ok());
+    let (hours, mins, secs) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hours > 23 || mins > 59 || secs > 60 {
+        return None;
+    }
 
-    format!(
-        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
-        years, month.min(12), day.min(31), hours, mins, secs
+    // Days-from-civil (proleptic Gregorian), the inverse of `format_date`
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146_097 + day_of_era - 719_468).ok()?;
+
+    Some(days * 86400 + hours * 3600 + mins * 60 + secs)
+}
+
+/// Age of a receipt in seconds at `now`, if its timestamp parses.
+pub fn receipt_age(receipt: &QuarantineReceipt, now: u64) -> Option<u64> {
+    parse_timestamp(&receipt.quarantined_at).map(|at| now.saturating_sub(at))
+}
+
+/// Split a manifest into receipts older than `retention` and the rest.
+///
+/// Receipts whose timestamp doesn't parse are kept.
+pub fn partition_expired(manifest: &Manifest, retention: Duration, now: u64) -> (Vec<QuarantineReceipt>, Manifest) {
+    let selection = PurgeSelection {
+        older_than: Some(retention),
+        ..Default::default()
+    };
+    partition_selected(manifest, &selection, now)
+}
+
+/// Split a manifest into the receipts `selection` matches and the rest.
+pub fn partition_selected(
+    manifest: &Manifest,
+    selection: &PurgeSelection,
+    now: u64,
+) -> (Vec<QuarantineReceipt>, Manifest) {
+    let (selected, kept) = manifest
+        .quarantined
+        .iter()
+        .cloned()
+        .partition(|r| selects(selection, r, now));
+
+    (
+        selected,
+        Manifest {
+            version: manifest.version,
+            quarantined: kept,
+        },
     )
 }
 
+fn selects(selection: &PurgeSelection, receipt: &QuarantineReceipt, now: u64) -> bool {
+    let id_matches = selection.ids.is_empty() || selection.ids.contains(&receipt.id);
+    let old_enough = selection
+        .older_than
+        .is_none_or(|min| receipt_age(receipt, now).is_some_and(|age| age >= min.as_secs()));
+    let path_matches = selection
+        .pattern
+        .as_deref()
+        .is_none_or(|pattern| path_matches(pattern, &receipt.original_path));
+    id_matches && old_enough && path_matches
+}
+
+/// Whether `path` matches `pattern`: the whole path if the pattern has a
+/// `/`, otherwise just the file name.
+pub fn path_matches(pattern: &str, path: &Path) -> bool {
+    if pattern.contains('/') {
+        glob_matches(pattern, &path.to_string_lossy())
+    } else {
+        path.file_name()
+            .is_some_and(|name| glob_matches(pattern, &name.to_string_lossy()))
+    }
+}
+
+/// Whether `path` is covered by one of the protected patterns.
+pub fn is_protected(path: &Path, protected: &[String]) -> bool {
+    protected.iter().any(|pattern| path_matches(pattern, path))
+}
+
+/// Shell-style match of the whole of `text`: `?` is one character, `*`
+/// any run within a path component, `**` any run across components.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_from(&pattern, &text)
+}
+
+fn glob_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|skip| glob_from(rest, &text[skip..])),
+        ['*', rest @ ..] => {
+            let component = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=component).any(|skip| glob_from(rest, &text[skip..]))
+        }
+        ['?', rest @ ..] => text.first().is_some_and(|&c| c != '/') && glob_from(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_from(rest, &text[1..]),
+    }
+}
+
 // ============================================================================
 // EFFECT FUNCTIONS (Actions)
 // ============================================================================
@@ -131,41 +562,139 @@ pub fn init_quarantine(config: &QuarantineConfig) -> io::Result<QuarantineConfig
         quarantine_dir,
         dry_run: config.dry_run,
         preserve_structure: config.preserve_structure,
+        hash_algorithm: config.hash_algorithm,
+        destination: config.destination,
+        clone_files: config.clone_files,
+        protected: config.protected.clone(),
+        sync_wait: config.sync_wait,
     })
 }
 
+/// Take the quarantine's advisory lock, so concurrent quarantine, restore,
+/// and purge runs can't race on the manifest or the files.
+///
+/// Fails at once with `WouldBlock` while another process holds it. The
+/// lock is per open file, so take it once per process.
+pub fn lock_quarantine(config: &QuarantineConfig) -> io::Result<QuarantineLock> {
+    fs::create_dir_all(&config.quarantine_dir)?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(config.quarantine_dir.join(LOCK_FILENAME))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(QuarantineLock { _file: file }),
+        Err(fs::TryLockError::WouldBlock) => Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            format!(
+                "Another icloud-dedupe instance is using the quarantine at {}; try again once it finishes",
+                config.quarantine_dir.display()
+            ),
+        )),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Fail with `StorageFull` unless the quarantine volume has room for
+/// moving every duplicate in `groups` (see [`required_space`]).
+pub fn check_free_space(groups: &[DuplicateGroup], config: &QuarantineConfig) -> io::Result<()> {
+    // Without a device number, assume another volume: moves then need room
+    let quarantine_dev = file_id(&fs::metadata(&config.quarantine_dir)?).map(|id| id.dev);
+    let files: Vec<(u64, bool)> = groups
+        .iter()
+        .flat_map(|g| &g.duplicates)
+        .filter_map(|path| fs::metadata(path).ok().map(|m| (path, m)))
+        .map(|(path, meta)| {
+            let size = if meta.is_dir() { tree_size(path) } else { meta.len() };
+            let dev = file_id(&meta).map(|id| id.dev);
+            (size, dev.is_some() && dev == quarantine_dev)
+        })
+        .collect();
+
+    let needed = required_space(&files);
+    let available = match available_space(&config.quarantine_dir) {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => return Ok(()),
+        result => result?,
+    };
+    if available < needed {
+        return Err(io::Error::new(
+            io::ErrorKind::StorageFull,
+            format!(
+                "Quarantine needs {} but only {} is free on the volume holding {}",
+                format_size(needed, BINARY),
+                format_size(available, BINARY),
+                config.quarantine_dir.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
 /// Move a single file to quarantine.
 ///
+/// The file is hashed again first: one that changed since the scan (say,
+/// a newer version synced in) no longer matches `hash` and is left alone
+/// with an `InvalidData` error. A path matching one of the config's
+/// protected patterns is refused with `PermissionDenied`.
+///
 /// Returns a receipt for restoration.
 pub fn quarantine_file(
     path: &Path,
     hash: &ContentHash,
     config: &QuarantineConfig,
 ) -> io::Result<QuarantineReceipt> {
+    quarantine_file_into(path, hash, config, ReceiptStamp::default(), &mut KnownDirs::default(), None)
+}
+
+/// Move a single file to quarantine, skipping directory creation for
+/// directories already known to exist, and journaling the move first
+/// when given a journal.
+fn quarantine_file_into(
+    path: &Path,
+    hash: &ContentHash,
+    config: &QuarantineConfig,
+    stamp: ReceiptStamp,
+    dirs: &mut KnownDirs,
+    journal: Option<&mut Journal>,
+) -> io::Result<QuarantineReceipt> {
+    // Whatever the scan concluded, protected paths stay put
+    if is_protected(path, &config.protected) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "protected by the config; left in place",
+        ));
+    }
+
+    // The scan's verdict only holds for the content it hashed
+    if hash_path_with(path, config.hash_algorithm)? != *hash {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "content changed since the scan; left in place",
+        ));
+    }
+
     let id = generate_receipt_id();
-    let quarantine_path = compute_quarantine_path(path, &id, config);
+    let mut quarantine_path = compute_quarantine_path(path, &id, config);
+    // An earlier session's file from the same path keeps its place
+    if fs::symlink_metadata(&quarantine_path).is_ok() {
+        quarantine_path = disambiguate_quarantine_path(&quarantine_path, &id);
+    }
 
-    // Get file metadata before moving
+    // Get file metadata before moving (bundles count their contents)
     let metadata = fs::metadata(path)?;
-    let size_bytes = metadata.len();
+    let size_bytes = if metadata.is_dir() {
+        tree_size(path)
+    } else {
+        metadata.len()
+    };
 
     // Check for extended attributes (macOS)
     #[cfg(target_os = "macos")]
     let had_xattrs = has_xattrs(path);
     #[cfg(not(target_os = "macos"))]
     let had_xattrs = false;
-
-    if !config.dry_run {
-        // Create parent directories
-        if let Some(parent) = quarantine_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        // Move the file
-        fs::rename(path, &quarantine_path)?;
-    }
-
-    Ok(QuarantineReceipt {
+    let mut receipt = QuarantineReceipt {
         id,
         original_path: path.to_path_buf(),
         quarantine_path,
@@ -173,23 +702,199 @@ pub fn quarantine_file(
         quarantined_at: current_timestamp(),
         size_bytes,
         had_xattrs,
-    })
+        settings: Some(stamp.settings.cloned().unwrap_or_else(|| minimal_settings(config))),
+        session: stamp.session.map(str::to_string),
+        metadata: Some(capture_metadata(path)),
+        provenance: stamp.provenance,
+        file_id: fs::symlink_metadata(path).ok().and_then(|m| file_id(&m)),
+    };
+
+    if config.dry_run {
+        // Nothing moves
+    } else if config.destination == Destination::Trash {
+        // The Trash picks the name; the receipt records where it went
+        receipt.quarantine_path = move_to_trash(path)?;
+    } else {
+        // Create parent directories (usually already done up front)
+        if let Some(parent) = receipt.quarantine_path.parent() {
+            dirs.ensure(parent)?;
+        }
+
+        if let Some(journal) = journal {
+            journal.record(&receipt)?;
+        }
+
+        // Move the file (copied and verified when on another volume)
+        if config.clone_files {
+            clone_then_remove(path, &receipt.quarantine_path, config.hash_algorithm)?;
+        } else {
+            move_path(path, &receipt.quarantine_path, config.hash_algorithm)?;
+        }
+    }
+
+    Ok(receipt)
+}
+
+/// Narrow `groups` to the duplicates listed in `paths`, e.g. to keep some
+/// copies on purpose. Groups left without duplicates are dropped.
+///
+/// # Errors
+/// The first path that isn't a duplicate in any group, such as a group's
+/// original.
+pub fn select_duplicates(groups: &[DuplicateGroup], paths: &[PathBuf]) -> Result<Vec<DuplicateGroup>, PathBuf> {
+    if let Some(stray) = paths
+        .iter()
+        .find(|p| !groups.iter().any(|g| g.duplicates.contains(p)))
+    {
+        return Err(stray.clone());
+    }
+
+    Ok(groups
+        .iter()
+        .map(|g| DuplicateGroup {
+            duplicates: g.duplicates.iter().filter(|d| paths.contains(d)).cloned().collect(),
+            ..g.clone()
+        })
+        .filter(|g| !g.duplicates.is_empty())
+        .collect())
+}
+
+/// Why `path` from `group` is being quarantined.
+fn provenance(path: &Path, group: &DuplicateGroup, scan: Option<&str>) -> Provenance {
+    Provenance {
+        pattern: path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(detect_pattern)
+            .map(|pattern| pattern_label(&pattern)),
+        kept: group.original.clone(),
+        scan: scan.map(str::to_string),
+        kept_id: fs::symlink_metadata(&group.original).ok().and_then(|m| file_id(&m)),
+    }
 }
 
 /// Quarantine all duplicates from scan results.
 ///
-/// Returns a manifest with all receipts.
+/// Returns a manifest with this session's receipts, which all share one
+/// session ID. The saved manifest also keeps the receipts of earlier
+/// sessions.
 pub fn quarantine_duplicates(
     groups: &[DuplicateGroup],
     config: &QuarantineConfig,
+) -> io::Result<Manifest> {
+    quarantine_groups(groups, config, None, None, &CancelToken::new())
+}
+
+/// Quarantine only the duplicates in `paths`, leaving the rest of their
+/// groups in place (see [`select_duplicates`]).
+///
+/// # Errors
+/// `InvalidInput`, before anything moves, if a path isn't a duplicate in
+/// `groups`.
+pub fn quarantine_paths(
+    paths: &[PathBuf],
+    groups: &[DuplicateGroup],
+    config: &QuarantineConfig,
+) -> io::Result<Manifest> {
+    let selected = select_duplicates(groups, paths).map_err(|path| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Not a confirmed duplicate: {}", path.display()),
+        )
+    })?;
+    quarantine_duplicates(&selected, config)
+}
+
+/// Like [`quarantine_duplicates`], recording `settings` on every receipt.
+pub fn quarantine_duplicates_with_settings(
+    groups: &[DuplicateGroup],
+    config: &QuarantineConfig,
+    settings: &RunSettings,
+) -> io::Result<Manifest> {
+    quarantine_groups(groups, config, Some(settings), None, &CancelToken::new())
+}
+
+/// Like [`quarantine_duplicates_with_settings`], stopping between files
+/// once `cancel` is cancelled.
+///
+/// The file being moved always finishes, and the manifest is written for
+/// whatever was moved, so an interrupted run can be restored like any
+/// other. Compare the receipt count with the duplicates to tell.
+///
+/// Running out of space mid-run stops the same way but returns a
+/// `StorageFull` error; the saved manifest lists what was moved.
+pub fn quarantine_duplicates_cancellable(
+    groups: &[DuplicateGroup],
+    config: &QuarantineConfig,
+    settings: &RunSettings,
+    scan: Option<&str>,
+    cancel: &CancelToken,
+) -> io::Result<Manifest> {
+    quarantine_groups(groups, config, Some(settings), scan, cancel)
+}
+
+fn quarantine_groups(
+    groups: &[DuplicateGroup],
+    config: &QuarantineConfig,
+    settings: Option<&RunSettings>,
+    scan: Option<&str>,
+    cancel: &CancelToken,
 ) -> io::Result<Manifest> {
     let config = init_quarantine(config)?;
     let mut receipts = Vec::new();
 
-    for group in groups {
+    // Load earlier sessions first: a manifest we can't read must not be
+    // overwritten, or the files it lists could no longer be restored.
+    // A journal left by a run that died mid-way is settled before this
+    // run starts its own.
+    let existing = if config.dry_run {
+        Manifest::default()
+    } else {
+        recover_journal(&config)?;
+        load_existing_manifest(&config)?
+    };
+
+    // Refuse up front rather than fail file by file once the volume fills.
+    // The Trash is on each file's own volume, so it never needs room.
+    let moving = !config.dry_run && config.destination == Destination::Quarantine;
+    if moving {
+        check_free_space(groups, &config)?;
+    }
+
+    // Create the whole directory tree in one pass instead of per file.
+    // Failures here are retried (and reported) by the individual moves.
+    let mut dirs = KnownDirs::default();
+    if moving {
+        for dir in plan_directories(groups, &config) {
+            let _ = dirs.ensure(&dir);
+        }
+    }
+
+    let total: usize = groups.iter().map(|g| g.duplicates.len()).sum();
+    let mut volume_full = false;
+    let session = generate_session_id();
+    let mut journal = if moving { Some(Journal::open(&config)?) } else { None };
+
+    'groups: for group in groups {
         for dup_path in &group.duplicates {
-            match quarantine_file(dup_path, &group.hash, &config) {
+            if cancel.is_cancelled() {
+                break 'groups;
+            }
+            let stamp = ReceiptStamp {
+                settings,
+                session: Some(&session),
+                provenance: Some(provenance(dup_path, group, scan)),
+            };
+            match quarantine_file_into(dup_path, &group.hash, &config, stamp, &mut dirs, journal.as_mut()) {
                 Ok(receipt) => receipts.push(receipt),
+                Err(e) if e.kind() == io::ErrorKind::StorageFull => {
+                    // Every later move would fail the same way
+                    volume_full = true;
+                    break 'groups;
+                }
+                Err(e) if matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::PermissionDenied) => {
+                    eprintln!("Warning: Skipped {}: {}", dup_path.display(), e);
+                }
                 Err(e) => {
                     // Log error but continue with other files
                     eprintln!("Warning: Failed to quarantine {}: {}", dup_path.display(), e);
@@ -203,16 +908,160 @@ pub fn quarantine_duplicates(
         quarantined: receipts,
     };
 
-    // Save manifest
+    // Save manifest alongside earlier sessions; the journal is then moot
     if !config.dry_run {
-        save_manifest(&manifest, &config)?;
+        save_manifest(&merge_receipts(existing, manifest.quarantined.clone()), &config)?;
+        log_history(&config, &manifest.quarantined, Outcome::Quarantined);
+    }
+    if journal.is_some() {
+        drop(journal);
+        fs::remove_file(journal_path(&config))?;
+    }
+
+    if volume_full {
+        return Err(io::Error::new(
+            io::ErrorKind::StorageFull,
+            format!(
+                "Quarantine volume is full: {} of {} files moved before it ran out of space; the manifest lists them",
+                manifest.quarantined.len(),
+                total
+            ),
+        ));
     }
 
     Ok(manifest)
 }
 
+/// Algorithm a receipt's hash was made with (BLAKE3 for receipts older
+/// than the setting).
+///
+/// # Errors
+/// `InvalidData` for an algorithm this version doesn't know.
+pub fn receipt_algorithm(receipt: &QuarantineReceipt) -> io::Result<HashAlgorithm> {
+    let Some(settings) = receipt.settings.as_ref().filter(|s| !s.hash_algorithm.is_empty()) else {
+        return Ok(HashAlgorithm::Blake3);
+    };
+    HashAlgorithm::from_name(&settings.hash_algorithm).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown hash algorithm in receipt: {}", settings.hash_algorithm),
+        )
+    })
+}
+
 /// Restore a single file from quarantine.
 pub fn restore_file(receipt: &QuarantineReceipt) -> io::Result<()> {
+    restore_file_with(receipt, RestoreConflict::Fail).map(|_| ())
+}
+
+/// Restore `receipts` on up to `jobs` threads (0 = one per CPU), then drop
+/// the restored ones from the manifest, even when others failed.
+///
+/// Receipts for the same original path are restored one after another, in
+/// order, so their renames can't race. `on_done` is called after each
+/// attempt, on the thread that made it. Once `cancel` is cancelled no new
+/// restores start; those in progress finish.
+pub fn restore_receipts<F>(
+    receipts: &[&QuarantineReceipt],
+    on_conflict: RestoreConflict,
+    config: &QuarantineConfig,
+    jobs: usize,
+    cancel: &CancelToken,
+    on_done: F,
+) -> io::Result<RestoreSummary>
+where
+    F: Fn(&QuarantineReceipt, &io::Result<PathBuf>) + Sync,
+{
+    let mut by_original: Vec<Vec<&QuarantineReceipt>> = Vec::new();
+    let mut slots: HashMap<&Path, usize> = HashMap::new();
+    for receipt in receipts {
+        let slot = *slots.entry(&receipt.original_path).or_insert_with(|| {
+            by_original.push(Vec::new());
+            by_original.len() - 1
+        });
+        by_original[slot].push(receipt);
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(io::Error::other)?;
+    let outcomes: Vec<(&QuarantineReceipt, Option<io::Result<PathBuf>>)> = pool.install(|| {
+        by_original
+            .par_iter()
+            .flat_map_iter(|same_path| {
+                same_path.iter().map(|receipt| {
+                    if cancel.is_cancelled() {
+                        return (*receipt, None);
+                    }
+                    let result = restore_file_with(receipt, on_conflict);
+                    on_done(receipt, &result);
+                    (*receipt, Some(result))
+                })
+            })
+            .collect()
+    });
+
+    let mut summary = RestoreSummary::default();
+    let mut restored_receipts = Vec::new();
+    for (receipt, outcome) in outcomes {
+        match outcome {
+            Some(Ok(path)) => {
+                summary.restored.push((receipt.id.clone(), path));
+                restored_receipts.push(receipt.clone());
+            }
+            Some(Err(e)) => summary.failed.push((receipt.id.clone(), e.to_string())),
+            None => summary.not_attempted += 1,
+        }
+    }
+
+    if !summary.restored.is_empty() {
+        let restored: HashSet<&str> = summary.restored.iter().map(|(id, _)| id.as_str()).collect();
+        let mut manifest = load_manifest(config)?;
+        manifest.quarantined.retain(|r| !restored.contains(r.id.as_str()));
+        save_manifest(&manifest, config)?;
+        log_history(config, &restored_receipts, Outcome::Restored);
+    }
+    Ok(summary)
+}
+
+/// Like [`restore_file`], handling an occupied original path per
+/// `on_conflict`. Returns where the file was restored to.
+pub fn restore_file_with(receipt: &QuarantineReceipt, on_conflict: RestoreConflict) -> io::Result<PathBuf> {
+    let target = plan_restore(receipt, on_conflict)?;
+
+    // Only an identical overwrite gets here with the target taken, and a
+    // rename replaces a file but not a directory
+    if target.is_dir() {
+        fs::remove_dir_all(&target)?;
+    }
+
+    // Create parent directories if needed
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Move file back
+    move_path(&receipt.quarantine_path, &target, receipt_algorithm(receipt)?)?;
+
+    // The file is back either way; missing metadata is worth a warning only
+    if let Some(metadata) = &receipt.metadata
+        && let Err(e) = reapply_metadata(&target, metadata)
+    {
+        eprintln!(
+            "Warning: Restored {} but couldn't put back its metadata: {}",
+            target.display(),
+            e
+        );
+    }
+
+    Ok(target)
+}
+
+/// Where [`restore_file_with`] would put a receipt's file, running the
+/// same checks (quarantined copy intact, original path free or handled by
+/// `on_conflict`) without touching anything.
+pub fn plan_restore(receipt: &QuarantineReceipt, on_conflict: RestoreConflict) -> io::Result<PathBuf> {
     // Verify file still exists in quarantine
     if !receipt.quarantine_path.exists() {
         return Err(io::Error::new(
@@ -221,8 +1070,8 @@ pub fn restore_file(receipt: &QuarantineReceipt) -> io::Result<()> {
         ));
     }
 
-    // Verify hash matches (file wasn't corrupted)
-    let current_hash = hash_file(&receipt.quarantine_path)?;
+    // Verify hash matches (file wasn't corrupted), with the receipt's algorithm
+    let current_hash = hash_path_with(&receipt.quarantine_path, receipt_algorithm(receipt)?)?;
     if current_hash != receipt.hash {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -231,60 +1080,623 @@ pub fn restore_file(receipt: &QuarantineReceipt) -> io::Result<()> {
     }
 
     // Check if original location is available
-    if receipt.original_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::AlreadyExists,
-            format!("Original path already exists: {}", receipt.original_path.display()),
-        ));
-    }
-
-    // Create parent directories if needed
-    if let Some(parent) = receipt.original_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    // Move file back
-    fs::rename(&receipt.quarantine_path, &receipt.original_path)?;
+    let occupied = fs::symlink_metadata(&receipt.original_path).is_ok();
+    let target = match on_conflict {
+        _ if !occupied => receipt.original_path.clone(),
+        RestoreConflict::Fail => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Original path already exists: {}", receipt.original_path.display()),
+            ));
+        }
+        RestoreConflict::Rename => (1..)
+            .map(|n| restored_name(&receipt.original_path, n))
+            .find(|candidate| fs::symlink_metadata(candidate).is_err())
+            .expect("some numbered name is free"),
+        RestoreConflict::OverwriteIfIdentical => {
+            if hash_path_with(&receipt.original_path, receipt_algorithm(receipt)?)? != receipt.hash {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "Original path already exists with different content: {}",
+                        receipt.original_path.display()
+                    ),
+                ));
+            }
+            receipt.original_path.clone()
+        }
+    };
 
-    Ok(())
+    Ok(target)
 }
 
-/// Permanently delete all quarantined files.
+/// Write the manifest to `dest` for archiving or another machine.
+///
+/// Without files, `dest` is a single JSON file. With files, `dest` is a new
+/// directory holding `manifest.json` and a copy of every quarantined file;
+/// the quarantine itself is left as it is. Returns the receipts exported.
+pub fn export_quarantine(config: &QuarantineConfig, dest: &Path, include_files: bool) -> io::Result<usize> {
+    let manifest = load_manifest(config)?;
+    let export = ManifestExport {
+        version: EXPORT_VERSION,
+        home: dirs::home_dir(),
+        quarantine_dir: config.quarantine_dir.clone(),
+        files_included: include_files,
+        manifest,
+    };
+
+    let manifest_file = if include_files {
+        fs::create_dir(dest)?;
+        for receipt in &export.manifest.quarantined {
+            let dir = dest.join(EXPORT_FILES_DIR).join(&receipt.id);
+            fs::create_dir_all(&dir)?;
+            copy_preserving(&receipt.quarantine_path, &dir.join(exported_name(receipt)))?;
+        }
+        dest.join(MANIFEST_FILENAME)
+    } else {
+        dest.to_path_buf()
+    };
+
+    let contents = serde_json::to_string_pretty(&export).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize export: {}", e))
+    })?;
+    fs::write(manifest_file, contents)?;
+    Ok(export.manifest.quarantined.len())
+}
+
+/// Read an export written by [`export_quarantine`]: a JSON file, or a
+/// directory holding one.
+pub fn load_export(src: &Path) -> io::Result<ManifestExport> {
+    let file = if src.is_dir() { src.join(MANIFEST_FILENAME) } else { src.to_path_buf() };
+    let contents = fs::read_to_string(&file)?;
+    let export: ManifestExport = serde_json::from_str(&contents).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid quarantine export: {}", e))
+    })?;
+    if export.version > EXPORT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Quarantine export version {} is newer than this tool supports", export.version),
+        ));
+    }
+    Ok(export)
+}
+
+/// Add an export's receipts to this quarantine. Returns the receipts added.
+///
+/// Exported files are copied from `src` into the quarantine and checked
+/// against their receipts; a file that is missing, can't be copied, or
+/// doesn't match is left out with a warning. Files already in the
+/// quarantine are never overwritten. Without files, quarantine paths are
+/// rebased onto this quarantine directory. Receipts already in the
+/// manifest are skipped, so importing twice is harmless.
+///
+/// The manifest is saved for whatever was imported, even when an error
+/// stops the import part-way.
+pub fn import_quarantine(
+    src: &Path,
+    export: &ManifestExport,
+    config: &QuarantineConfig,
+) -> io::Result<Vec<QuarantineReceipt>> {
+    let config = init_quarantine(config)?;
+    let existing = load_existing_manifest(&config)?;
+    let known: HashSet<&str> = existing.quarantined.iter().map(|r| r.id.as_str()).collect();
+
+    let mut imported = Vec::new();
+    let mut failure = None;
+    let mut dirs = KnownDirs::default();
+    for receipt in &export.manifest.quarantined {
+        if known.contains(receipt.id.as_str()) {
+            continue;
+        }
+        let receipt = if export.files_included {
+            match import_file(src, receipt, &config, &mut dirs) {
+                Ok(Some(receipt)) => receipt,
+                Ok(None) => continue,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        } else {
+            QuarantineReceipt {
+                quarantine_path: remap_prefix(&receipt.quarantine_path, &export.quarantine_dir, &config.quarantine_dir),
+                ..receipt.clone()
+            }
+        };
+        imported.push(receipt);
+    }
+
+    save_manifest(&merge_receipts(existing, imported.clone()), &config)?;
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(imported),
+    }
+}
+
+/// Copy one exported file into the quarantine and check it against its
+/// receipt. None, with a warning, when it was left out.
+fn import_file(
+    src: &Path,
+    receipt: &QuarantineReceipt,
+    config: &QuarantineConfig,
+    dirs: &mut KnownDirs,
+) -> io::Result<Option<QuarantineReceipt>> {
+    let source = src.join(EXPORT_FILES_DIR).join(&receipt.id).join(exported_name(receipt));
+    let mut dest = compute_quarantine_path(&receipt.original_path, &receipt.id, config);
+    // A file quarantined here from the same path keeps its place
+    if fs::symlink_metadata(&dest).is_ok() {
+        dest = disambiguate_quarantine_path(&dest, &receipt.id);
+    }
+    if fs::symlink_metadata(&dest).is_ok() {
+        eprintln!("Warning: Skipped {}: {} already exists", source.display(), dest.display());
+        return Ok(None);
+    }
+    if let Some(parent) = dest.parent() {
+        dirs.ensure(parent)?;
+    }
+
+    let receipt = QuarantineReceipt {
+        quarantine_path: dest,
+        ..receipt.clone()
+    };
+    // Only this import wrote to dest, so a failed copy is safe to clean up
+    if let Err(e) = copy_preserving(&source, &receipt.quarantine_path) {
+        eprintln!("Warning: Skipped {}: {}", source.display(), e);
+        let _ = remove_quarantined(&receipt);
+        return Ok(None);
+    }
+    if check_receipt(&receipt) != ReceiptCheck::Ok {
+        eprintln!("Warning: Skipped {}: copy doesn't match its receipt", source.display());
+        remove_quarantined(&receipt)?;
+        return Ok(None);
+    }
+    Ok(Some(receipt))
+}
+
+/// File name an exported receipt's copy is stored under.
+fn exported_name(receipt: &QuarantineReceipt) -> std::ffi::OsString {
+    receipt
+        .original_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "file".into())
+}
+
+/// Re-hash every quarantined file against its receipt, in parallel.
+///
+/// Read-only: nothing is moved or deleted, whatever is found.
+pub fn verify_manifest(manifest: &Manifest) -> ManifestVerification {
+    let problems = manifest
+        .quarantined
+        .par_iter()
+        .map(|receipt| (receipt, check_receipt(receipt)))
+        .filter(|(_, check)| *check != ReceiptCheck::Ok)
+        .map(|(receipt, check)| ReceiptVerification {
+            id: receipt.id.clone(),
+            original_path: receipt.original_path.clone(),
+            quarantine_path: receipt.quarantine_path.clone(),
+            check,
+        })
+        .collect();
+
+    let kept_changed = manifest
+        .quarantined
+        .iter()
+        .filter_map(|receipt| {
+            let check = check_kept_file(receipt).filter(|c| *c != KeptFileCheck::Unchanged)?;
+            Some(KeptFileChange {
+                id: receipt.id.clone(),
+                kept: receipt.provenance.as_ref()?.kept.clone(),
+                check,
+            })
+        })
+        .collect();
+
+    ManifestVerification {
+        checked: manifest.quarantined.len(),
+        problems,
+        kept_changed,
+    }
+}
+
+/// Check whether the file a receipt's group kept is still the one that
+/// was there at quarantine time. None for receipts that didn't record it.
+pub fn check_kept_file(receipt: &QuarantineReceipt) -> Option<KeptFileCheck> {
+    let provenance = receipt.provenance.as_ref()?;
+    let recorded = provenance.kept_id?;
+    Some(match fs::symlink_metadata(&provenance.kept) {
+        Err(_) => KeptFileCheck::Gone,
+        Ok(meta) if file_id(&meta) == Some(recorded) => KeptFileCheck::Unchanged,
+        Ok(_) => KeptFileCheck::Replaced,
+    })
+}
+
+/// Check one receipt's file.
+pub fn check_receipt(receipt: &QuarantineReceipt) -> ReceiptCheck {
+    if fs::symlink_metadata(&receipt.quarantine_path).is_err() {
+        return ReceiptCheck::Missing;
+    }
+    let hashed = receipt_algorithm(receipt).and_then(|alg| hash_path_with(&receipt.quarantine_path, alg));
+    match hashed {
+        Ok(actual) if actual == receipt.hash => ReceiptCheck::Ok,
+        Ok(actual) => ReceiptCheck::Corrupted { actual },
+        Err(e) => ReceiptCheck::Unreadable { error: e.to_string() },
+    }
+}
+
+/// Permanently delete all quarantined files.
 pub fn purge_quarantine(manifest: &Manifest, config: &QuarantineConfig) -> io::Result<()> {
-    for receipt in &manifest.quarantined {
-        if receipt.quarantine_path.exists() {
-            fs::remove_file(&receipt.quarantine_path)?;
+    purge_quarantine_cancellable(manifest, config, &CancelToken::new()).map(|_| ())
+}
+
+/// Like [`purge_quarantine`], stopping between files once `cancel` is
+/// cancelled. Returns how many receipts were purged.
+///
+/// When stopped early, or when a file can't be removed, the manifest is
+/// rewritten to list only the files still in quarantine.
+pub fn purge_quarantine_cancellable(
+    manifest: &Manifest,
+    config: &QuarantineConfig,
+    cancel: &CancelToken,
+) -> io::Result<usize> {
+    for (purged, receipt) in manifest.quarantined.iter().enumerate() {
+        if cancel.is_cancelled() {
+            let remaining = Manifest {
+                version: manifest.version,
+                quarantined: manifest.quarantined[purged..].to_vec(),
+            };
+            save_manifest(&remaining, config)?;
+            log_history(config, &manifest.quarantined[..purged], Outcome::Purged);
+            return Ok(purged);
+        }
+        if let Err(e) = remove_quarantined(receipt) {
+            let remaining = Manifest {
+                version: manifest.version,
+                quarantined: manifest.quarantined[purged..].to_vec(),
+            };
+            save_manifest(&remaining, config)?;
+            log_history(config, &manifest.quarantined[..purged], Outcome::Purged);
+            return Err(e);
         }
     }
+    log_history(config, &manifest.quarantined, Outcome::Purged);
 
-    // Remove manifest
-    let manifest_file = manifest_path(config);
-    if manifest_file.exists() {
-        fs::remove_file(manifest_file)?;
+    // Remove manifest and its backup
+    for manifest_file in [manifest_path(config), backup_path(config)] {
+        if manifest_file.exists() {
+            fs::remove_file(manifest_file)?;
+        }
     }
 
     // Try to clean up empty directories
     cleanup_empty_dirs(&config.quarantine_dir)?;
 
-    Ok(())
+    Ok(manifest.quarantined.len())
+}
+
+/// Permanently delete receipts older than `retention`, keeping the rest
+/// in the manifest. Returns the purged receipts.
+///
+/// No manifest means nothing to expire.
+pub fn purge_expired(config: &QuarantineConfig, retention: Duration, now: u64) -> io::Result<Vec<QuarantineReceipt>> {
+    let manifest = match load_manifest(config) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        result => result?,
+    };
+    let (expired, kept) = partition_expired(&manifest, retention, now);
+    if !expired.is_empty() {
+        remove_and_keep(&expired, kept, config)?;
+    }
+    Ok(expired)
+}
+
+/// Permanently delete the receipts `selection` matches, keeping the rest
+/// in the manifest. Returns the purged receipts.
+pub fn purge_selected(
+    manifest: &Manifest,
+    selection: &PurgeSelection,
+    config: &QuarantineConfig,
+    now: u64,
+) -> io::Result<Vec<QuarantineReceipt>> {
+    let (selected, kept) = partition_selected(manifest, selection, now);
+    if !selected.is_empty() {
+        remove_and_keep(&selected, kept, config)?;
+    }
+    Ok(selected)
+}
+
+/// Delete `doomed` receipts' files, then save `kept` as the manifest.
+///
+/// On failure the manifest still lists everything not yet deleted.
+fn remove_and_keep(doomed: &[QuarantineReceipt], kept: Manifest, config: &QuarantineConfig) -> io::Result<()> {
+    for (purged, receipt) in doomed.iter().enumerate() {
+        if let Err(e) = remove_quarantined(receipt) {
+            let mut remaining = kept;
+            remaining.quarantined.extend_from_slice(&doomed[purged..]);
+            save_manifest(&remaining, config)?;
+            log_history(config, &doomed[..purged], Outcome::Purged);
+            return Err(e);
+        }
+    }
+    save_manifest(&kept, config)?;
+    log_history(config, doomed, Outcome::Purged);
+    cleanup_empty_dirs(&config.quarantine_dir)
+}
+
+/// Append what just happened to `receipts` to the history log. The log is
+/// informational, so failing to write it only warns.
+fn log_history(config: &QuarantineConfig, receipts: &[QuarantineReceipt], outcome: Outcome) {
+    let entries = entries_for(receipts, outcome, now_secs());
+    if let Err(e) = append_history(&history_log_path(&config.quarantine_dir), &entries) {
+        eprintln!("Warning: Couldn't update the quarantine history: {}", e);
+    }
+}
+
+/// Delete a receipt's file or bundle, if still there.
+fn remove_quarantined(receipt: &QuarantineReceipt) -> io::Result<()> {
+    if receipt.quarantine_path.is_dir() {
+        fs::remove_dir_all(&receipt.quarantine_path)
+    } else if receipt.quarantine_path.exists() {
+        fs::remove_file(&receipt.quarantine_path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Settle a journal left by a quarantine run that died before saving its
+/// manifest. Call with the quarantine locked.
+///
+/// Each journaled move whose receipt isn't in the manifest is finished if
+/// the file made it into quarantine, and rolled back if it's still at its
+/// original path. When it's in both places (a copy to another volume was
+/// cut short), a complete copy wins and a partial one is removed; the file
+/// at the original path is only removed if it still holds the quarantined
+/// content. A file at
+/// a quarantine path that a manifest receipt already owns is never this
+/// move's: the move is rolled back and that file left alone. Finished
+/// moves are added to the manifest; then the journal is removed.
+pub fn recover_journal(config: &QuarantineConfig) -> io::Result<JournalRecovery> {
+    let path = journal_path(config);
+    let contents = match fs::read_to_string(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(JournalRecovery::default()),
+        result => result?,
+    };
+
+    let mut manifest = load_existing_manifest(config)?;
+    let known: HashSet<String> = manifest.quarantined.iter().map(|r| r.id.clone()).collect();
+    let owned: HashSet<PathBuf> = manifest.quarantined.iter().map(|r| r.quarantine_path.clone()).collect();
+    let mut recovery = JournalRecovery::default();
+    let mut finished = Vec::new();
+
+    // A torn last line is a move that was never started
+    for receipt in contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<QuarantineReceipt>(line).ok())
+        .filter(|r| !known.contains(&r.id))
+    {
+        // Died before the move: whatever sits there is an earlier session's
+        let quarantined =
+            !owned.contains(&receipt.quarantine_path) && fs::symlink_metadata(&receipt.quarantine_path).is_ok();
+        let original = fs::symlink_metadata(&receipt.original_path).is_ok();
+        match (quarantined, original) {
+            (true, false) => finished.push(receipt),
+            (false, true) => recovery.rolled_back += 1,
+            (false, false) => recovery.missing.push(receipt.original_path),
+            (true, true) => {
+                let matches = |path: &Path| {
+                    receipt_algorithm(&receipt)
+                        .and_then(|alg| hash_path_with(path, alg))
+                        .is_ok_and(|hash| hash == receipt.hash)
+                };
+                if matches(&receipt.quarantine_path) {
+                    // Only the interrupted copy's source goes; anything that
+                    // has appeared there since is the user's
+                    if matches(&receipt.original_path) {
+                        remove_tree(&receipt.original_path)?;
+                    }
+                    finished.push(receipt);
+                } else {
+                    remove_tree(&receipt.quarantine_path)?;
+                    recovery.rolled_back += 1;
+                }
+            }
+        }
+    }
+
+    recovery.finished = finished.len();
+    if !finished.is_empty() {
+        manifest = merge_receipts(manifest, finished.clone());
+        save_manifest(&manifest, config)?;
+        log_history(config, &finished, Outcome::Quarantined);
+    }
+    fs::remove_file(&path)?;
+    Ok(recovery)
 }
 
 /// Load manifest from disk.
+///
+/// A manifest that doesn't parse or fails its checksum is replaced by the
+/// backup kept from the previous save, with a warning. Manifests written
+/// before checksums were added load as they are, and older versions are
+/// upgraded in memory. One from a newer tool fails with `Unsupported`.
 pub fn load_manifest(config: &QuarantineConfig) -> io::Result<Manifest> {
     let path = manifest_path(config);
     let contents = fs::read_to_string(&path)?;
-    serde_json::from_str(&contents).map_err(|e| {
-        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid manifest: {}", e))
-    })
+    let error = match parse_manifest(&contents) {
+        Ok(manifest) => return Ok(manifest),
+        // An older backup would hide the newer manifest, and get saved over it
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => return Err(e),
+        Err(e) => e,
+    };
+
+    let backup = backup_path(config);
+    match fs::read_to_string(&backup).ok().map(|c| parse_manifest(&c)) {
+        Some(Ok(manifest)) => {
+            eprintln!(
+                "Warning: {} ({}); using the backup from the previous save",
+                error,
+                path.display()
+            );
+            Ok(manifest)
+        }
+        _ => Err(error),
+    }
+}
+
+/// Parse manifest contents, checking the checksum when there is one and
+/// upgrading older versions.
+///
+/// A manifest from a newer version of the tool is refused with
+/// `Unsupported` rather than guessed at.
+fn parse_manifest(contents: &str) -> io::Result<Manifest> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let file: ManifestFile =
+        serde_json::from_str(contents).map_err(|e| invalid(format!("Invalid manifest: {}", e)))?;
+    if file.version > MANIFEST_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "Manifest version {} is newer than this tool supports (up to {}); \
+                 upgrade icloud-dedupe to use this quarantine",
+                file.version, MANIFEST_VERSION
+            ),
+        ));
+    }
+    let checksum = file.checksum.clone();
+    let manifest = unpack_manifest(file);
+    if let Some(checksum) = checksum
+        && checksum != manifest_checksum(&manifest)?
+    {
+        return Err(invalid("Manifest checksum mismatch".to_string()));
+    }
+    Ok(upgrade_manifest(manifest))
+}
+
+/// Bring a manifest of any older version up to the current one.
+///
+/// v1 receipts already have the fields v2 names, just optional, so the
+/// upgrade only restamps the version; the session table is built on the
+/// next save.
+pub fn upgrade_manifest(manifest: Manifest) -> Manifest {
+    Manifest {
+        version: MANIFEST_VERSION,
+        quarantined: manifest.quarantined,
+    }
+}
+
+/// Lay a manifest out for disk, moving each session's settings into the
+/// session table when all its receipts share them.
+fn pack_manifest(manifest: &Manifest, checksum: Option<String>) -> ManifestFile {
+    let mut shared: BTreeMap<&str, Option<&RunSettings>> = BTreeMap::new();
+    for receipt in &manifest.quarantined {
+        if let Some(session) = &receipt.session {
+            let settings = shared.entry(session).or_insert(receipt.settings.as_ref());
+            if *settings != receipt.settings.as_ref() {
+                *settings = None;
+            }
+        }
+    }
+    let sessions: BTreeMap<String, SessionRecord> = shared
+        .into_iter()
+        .filter_map(|(id, settings)| Some((id.to_string(), SessionRecord { settings: settings?.clone() })))
+        .collect();
+
+    let quarantined = manifest
+        .quarantined
+        .iter()
+        .map(|receipt| {
+            let mut receipt = receipt.clone();
+            if receipt.session.as_ref().is_some_and(|s| sessions.contains_key(s)) {
+                receipt.settings = None;
+            }
+            receipt
+        })
+        .collect();
+
+    ManifestFile {
+        version: manifest.version,
+        sessions,
+        quarantined,
+        checksum,
+    }
+}
+
+/// Put each session's settings back on its receipts.
+fn unpack_manifest(file: ManifestFile) -> Manifest {
+    let quarantined = file
+        .quarantined
+        .into_iter()
+        .map(|mut receipt| {
+            if receipt.settings.is_none()
+                && let Some(record) = receipt.session.as_ref().and_then(|s| file.sessions.get(s))
+            {
+                receipt.settings = Some(record.settings.clone());
+            }
+            receipt
+        })
+        .collect();
+    Manifest {
+        version: file.version,
+        quarantined,
+    }
+}
+
+/// Checksum over a manifest's contents.
+fn manifest_checksum(manifest: &Manifest) -> io::Result<String> {
+    let bytes = serde_json::to_vec(manifest).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize manifest: {}", e))
+    })?;
+    Ok(hash_bytes(&bytes).to_hex())
+}
+
+/// Path of the copy of the previous manifest.
+fn backup_path(config: &QuarantineConfig) -> PathBuf {
+    manifest_path(config).with_extension("json.bak")
 }
 
-/// Save manifest to disk.
+/// Load the manifest, or an empty one if none exists yet.
+fn load_existing_manifest(config: &QuarantineConfig) -> io::Result<Manifest> {
+    match load_manifest(config) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: Vec::new(),
+        }),
+        result => result,
+    }
+}
+
+/// Save manifest to disk, with a checksum.
+///
+/// Written to a temporary file, synced, and renamed into place, so an
+/// interrupted write leaves the previous manifest intact. The previous
+/// manifest is also kept as `manifest.json.bak` for [`load_manifest`] to
+/// fall back on. Always written in the current version.
 pub fn save_manifest(manifest: &Manifest, config: &QuarantineConfig) -> io::Result<()> {
     let path = manifest_path(config);
-    let contents = serde_json::to_string_pretty(manifest).map_err(|e| {
+    let manifest = upgrade_manifest(manifest.clone());
+    let file = pack_manifest(&manifest, Some(manifest_checksum(&manifest)?));
+    let contents = serde_json::to_string_pretty(&file).map_err(|e| {
         io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize manifest: {}", e))
     })?;
-    fs::write(path, contents)
+
+    let temp = path.with_extension("json.tmp");
+    let mut out = fs::File::create(&temp)?;
+    out.write_all(contents.as_bytes())?;
+    out.sync_all()?;
+
+    // Only a manifest that still loads is worth backing up
+    if let Ok(previous) = fs::read_to_string(&path)
+        && parse_manifest(&previous).is_ok()
+    {
+        fs::write(backup_path(config), previous)?;
+    }
+    fs::rename(&temp, &path)?;
+
+    // Make the rename itself durable; not every filesystem allows this
+    if let Ok(dir) = fs::File::open(&config.quarantine_dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
 }
 
 // ============================================================================
@@ -315,6 +1727,186 @@ fn has_xattrs(path: &Path) -> bool {
     size > 0
 }
 
+/// Move `from` to `to`, across volumes if need be.
+///
+/// A plain rename when both are on the same volume. Otherwise the file
+/// (or bundle) is copied, the copy is hashed against the source, and only
+/// then is the source removed.
+fn move_path(from: &Path, to: &Path, algorithm: HashAlgorithm) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => copy_then_remove(from, to, algorithm),
+        result => result,
+    }
+}
+
+/// Clone `from` to `to` (sharing its blocks on APFS), then remove `from`.
+///
+/// Where clones aren't possible (another volume, a file system without
+/// them, or another OS) this is [`move_path`].
+fn clone_then_remove(from: &Path, to: &Path, algorithm: HashAlgorithm) -> io::Result<()> {
+    match clone_file(from, to) {
+        Ok(()) => remove_tree(from),
+        Err(e) if matches!(e.kind(), io::ErrorKind::Unsupported | io::ErrorKind::CrossesDevices) => {
+            move_path(from, to, algorithm)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Copy `from` to `to`, check the copy hashes the same, then remove `from`.
+///
+/// A copy that fails or doesn't match is removed again, leaving `from`
+/// untouched.
+fn copy_then_remove(from: &Path, to: &Path, algorithm: HashAlgorithm) -> io::Result<()> {
+    let copied = copy_preserving(from, to).and_then(|()| {
+        if hash_path_with(from, algorithm)? == hash_path_with(to, algorithm)? {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Copy of {} doesn't match the source", from.display()),
+            ))
+        }
+    });
+    if let Err(e) = copied {
+        let _ = if to.is_dir() { fs::remove_dir_all(to) } else { fs::remove_file(to) };
+        return Err(e);
+    }
+
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+/// Copy a file or directory tree, keeping permissions, timestamps, and
+/// extended attributes.
+///
+/// `fs::copy` carries permissions (and on macOS, extended attributes);
+/// the rest is copied afterwards, best-effort where the destination
+/// volume doesn't support it.
+fn copy_preserving(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_preserving(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, metadata.permissions())?;
+    } else if metadata.file_type().is_symlink() {
+        return copy_symlink(from, to);
+    } else {
+        fs::copy(from, to)?;
+    }
+
+    for (name, value) in extended_attributes(from).unwrap_or_default() {
+        let _ = set_extended_attribute(to, &name, &value);
+    }
+    if let Ok(created) = metadata.created() {
+        let _ = set_creation_time(to, created);
+    }
+
+    let times = fs::FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+    fs::File::open(to)?.set_times(times)
+}
+
+/// Record a file's extended attributes and creation time, best-effort.
+fn capture_metadata(path: &Path) -> PreservedMetadata {
+    let xattrs = extended_attributes(path)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, value)| value.len() <= MAX_PRESERVED_XATTR)
+        .map(|(name, value)| (name, to_hex(&value)))
+        .collect();
+    let created_nanos = fs::symlink_metadata(path)
+        .and_then(|m| m.created())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .and_then(|d| u64::try_from(d.as_nanos()).ok());
+
+    PreservedMetadata { xattrs, created_nanos }
+}
+
+/// Put back recorded attributes that are missing or changed, and the
+/// creation time. Attributes added since are left alone.
+fn reapply_metadata(path: &Path, metadata: &PreservedMetadata) -> io::Result<()> {
+    let current = extended_attributes(path)?;
+    for (name, hex) in &metadata.xattrs {
+        let Some(value) = from_hex(hex) else {
+            continue;
+        };
+        if current.get(name) != Some(&value) {
+            set_extended_attribute(path, name, &value)?;
+        }
+    }
+
+    if let Some(nanos) = metadata.created_nanos {
+        set_creation_time(path, UNIX_EPOCH + Duration::from_nanos(nanos))?;
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Recreate the symlink at `from` as `to`, pointing at the same target.
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Cannot copy symlink {} to another volume", from.display()),
+    ))
+}
+
+/// Remove a file, or a directory and everything in it.
+fn remove_tree(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Directories known to exist during a quarantine session.
+#[derive(Default)]
+struct KnownDirs(HashSet<PathBuf>);
+
+impl KnownDirs {
+    /// Create `dir` (and ancestors) unless it's already known to exist.
+    fn ensure(&mut self, dir: &Path) -> io::Result<()> {
+        if self.0.contains(dir) {
+            return Ok(());
+        }
+        fs::create_dir_all(dir)?;
+        for ancestor in dir.ancestors() {
+            if !self.0.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Recursively remove empty directories.
 fn cleanup_empty_dirs(dir: &Path) -> io::Result<()> {
     if !dir.is_dir() {
@@ -344,7 +1936,7 @@ fn cleanup_empty_dirs(dir: &Path) -> io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
+    use crate::hash::{hash_file, hash_tree};
     use tempfile::TempDir;
 
     fn sample_hash() -> ContentHash {
@@ -379,6 +1971,7 @@ mod tests {
             quarantine_dir: PathBuf::from("/tmp/quarantine"),
             preserve_structure: true,
             dry_run: false,
+            ..Default::default()
         };
 
         let original = PathBuf::from("/Users/test/Documents/file.txt");
@@ -394,6 +1987,7 @@ mod tests {
             quarantine_dir: PathBuf::from("/tmp/quarantine"),
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         let original = PathBuf::from("/Users/test/Documents/file.txt");
@@ -402,6 +1996,102 @@ mod tests {
         assert_eq!(qpath, PathBuf::from("/tmp/quarantine/abc123_file.txt"));
     }
 
+    #[test]
+    fn test_plan_directories_keeps_only_deepest() {
+        let config = QuarantineConfig {
+            quarantine_dir: PathBuf::from("/q"),
+            preserve_structure: true,
+            dry_run: false,
+            ..Default::default()
+        };
+        let groups = vec![DuplicateGroup {
+            original: PathBuf::from("/a/doc.txt"),
+            hash: sample_hash(),
+            duplicates: vec![
+                PathBuf::from("/a/doc Copy.txt"),
+                PathBuf::from("/a/b/c/x Copy.txt"),
+                PathBuf::from("/a/b/c/y Copy.txt"),
+                PathBuf::from("/a b/z Copy.txt"),
+            ],
+            size_bytes: 0,
+        }];
+
+        let dirs = plan_directories(&groups, &config);
+
+        assert_eq!(
+            dirs,
+            vec![PathBuf::from("/q/a/b/c"), PathBuf::from("/q/a b")]
+        );
+    }
+
+    #[test]
+    fn test_plan_directories_flat_is_quarantine_dir() {
+        let config = QuarantineConfig {
+            quarantine_dir: PathBuf::from("/q"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        let groups = vec![DuplicateGroup {
+            original: PathBuf::from("/a/doc.txt"),
+            hash: sample_hash(),
+            duplicates: vec![
+                PathBuf::from("/a/doc Copy.txt"),
+                PathBuf::from("/a/b/doc Copy.txt"),
+            ],
+            size_bytes: 0,
+        }];
+
+        assert_eq!(plan_directories(&groups, &config), vec![PathBuf::from("/q")]);
+    }
+
+    #[test]
+    fn test_required_space_counts_only_copied_content() {
+        let same_volume = required_space(&[(1000, true), (2000, true)]);
+        assert_eq!(same_volume, 2 * RECEIPT_BYTES + SPACE_MARGIN);
+
+        let mixed = required_space(&[(1000, true), (2000, false)]);
+        assert_eq!(mixed, 2000 + 2 * RECEIPT_BYTES + SPACE_MARGIN);
+    }
+
+    #[test]
+    fn test_completed_groups_needs_every_duplicate() {
+        let groups = vec![
+            DuplicateGroup {
+                original: PathBuf::from("/a.txt"),
+                hash: sample_hash(),
+                duplicates: vec![PathBuf::from("/a 2.txt")],
+                size_bytes: 0,
+            },
+            DuplicateGroup {
+                original: PathBuf::from("/b.txt"),
+                hash: sample_hash(),
+                duplicates: vec![PathBuf::from("/b 2.txt"), PathBuf::from("/b 3.txt")],
+                size_bytes: 0,
+            },
+        ];
+        let receipt = |path: &str| QuarantineReceipt {
+            id: String::new(),
+            original_path: PathBuf::from(path),
+            quarantine_path: PathBuf::new(),
+            hash: sample_hash(),
+            quarantined_at: String::new(),
+            size_bytes: 0,
+            had_xattrs: false,
+            settings: None,
+            session: None,
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("/a 2.txt"), receipt("/b 2.txt")],
+        };
+
+        assert_eq!(completed_groups(&groups, &manifest), vec![PathBuf::from("/a.txt")]);
+    }
+
     #[test]
     fn test_generate_receipt_id_is_unique() {
         let id1 = generate_receipt_id();
@@ -411,8 +2101,8 @@ mod tests {
         assert!(!id1.is_empty());
         assert!(!id2.is_empty());
 
-        // Might be same if called in same millisecond with same PID,
-        // but the format should be valid
+        // Distinct even within the same millisecond
+        assert_ne!(id1, id2);
         assert!(id1.contains('-'));
     }
 
@@ -433,6 +2123,7 @@ mod tests {
             quarantine_dir: quarantine_dir.clone(),
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         let receipt = quarantine_file(&file_path, &hash, &config).unwrap();
@@ -458,6 +2149,7 @@ mod tests {
             quarantine_dir: temp.path().join("quarantine"),
             preserve_structure: false,
             dry_run: true,
+            ..Default::default()
         };
 
         let receipt = quarantine_file(&file_path, &hash, &config).unwrap();
@@ -484,6 +2176,7 @@ mod tests {
             quarantine_dir,
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         // Quarantine
@@ -498,6 +2191,40 @@ mod tests {
         assert!(!receipt.quarantine_path.exists());
     }
 
+    #[test]
+    fn test_restore_verifies_with_receipt_algorithm() {
+        let temp = TempDir::new().unwrap();
+        let file_path = create_test_file(temp.path(), "test.txt", b"hello");
+        let hash = hash_path_with(&file_path, HashAlgorithm::Sha256).unwrap();
+
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..Default::default()
+        };
+        let settings = run_settings(&ScanConfig::default(), &config);
+        let mut receipt = quarantine_file(&file_path, &hash, &config).unwrap();
+        receipt.settings = Some(settings.clone());
+        assert_eq!(receipt_algorithm(&receipt).unwrap(), HashAlgorithm::Sha256);
+
+        // Checked as BLAKE3, the SHA-256 hash would look like corruption
+        let mut unlabelled = receipt.clone();
+        unlabelled.settings = None;
+        assert_eq!(restore_file(&unlabelled).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let mut unknown = receipt.clone();
+        unknown.settings = Some(RunSettings {
+            hash_algorithm: "md5".to_string(),
+            ..settings
+        });
+        assert_eq!(receipt_algorithm(&unknown).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        restore_file(&receipt).unwrap();
+        assert!(file_path.exists());
+    }
+
     #[test]
     fn test_restore_file_fails_if_original_exists() {
         let temp = TempDir::new().unwrap();
@@ -508,6 +2235,7 @@ mod tests {
             quarantine_dir: temp.path().join("quarantine"),
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         let receipt = quarantine_file(&file_path, &hash, &config).unwrap();
@@ -529,6 +2257,7 @@ mod tests {
             quarantine_dir: temp.path().to_path_buf(),
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         let manifest = Manifest {
@@ -541,6 +2270,11 @@ mod tests {
                 quarantined_at: "2024-01-01T00:00:00Z".to_string(),
                 size_bytes: 1024,
                 had_xattrs: false,
+                settings: None,
+                session: None,
+                metadata: None,
+                provenance: None,
+                file_id: None,
             }],
         };
 
@@ -548,7 +2282,7 @@ mod tests {
 
         let loaded = load_manifest(&config).unwrap();
 
-        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.version, MANIFEST_VERSION);
         assert_eq!(loaded.quarantined.len(), 1);
         assert_eq!(loaded.quarantined[0].id, "test-id");
         assert_eq!(loaded.quarantined[0].hash, sample_hash());
@@ -567,12 +2301,14 @@ mod tests {
             original: source_dir.join("doc.txt"),
             hash: hash.clone(),
             duplicates: vec![file1.clone(), file2.clone()],
+            size_bytes: 0,
         }];
 
         let config = QuarantineConfig {
             quarantine_dir: temp.path().join("quarantine"),
             preserve_structure: false,
             dry_run: false,
+            ..Default::default()
         };
 
         let manifest = quarantine_duplicates(&groups, &config).unwrap();
@@ -586,4 +2322,1532 @@ mod tests {
         let loaded = load_manifest(&config).unwrap();
         assert_eq!(loaded.quarantined.len(), 2);
     }
+
+    #[test]
+    fn test_quarantine_records_settings_on_receipts() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let file = create_test_file(&source_dir, "doc Copy.txt", b"content");
+
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        let scan = ScanConfig {
+            roots: vec![source_dir.clone()],
+            max_depth: Some(3),
+            ..Default::default()
+        };
+        let settings = run_settings(&scan, &config);
+
+        quarantine_duplicates_with_settings(&groups, &config, &settings).unwrap();
+
+        let loaded = load_manifest(&config).unwrap();
+        let recorded = loaded.quarantined[0].settings.as_ref().unwrap();
+        assert_eq!(recorded, &settings);
+        assert_eq!(recorded.hash_algorithm, "blake3");
+        assert_eq!(recorded.roots, vec![source_dir]);
+        assert_eq!(recorded.max_depth, Some(3));
+        assert!(!recorded.preserve_structure);
+    }
+
+    #[test]
+    fn test_manifest_without_settings_still_loads() {
+        let json = r#"{"version":1,"quarantined":[{"id":"a","original_path":"/a","quarantine_path":"/q/a","hash":"0000000000000000000000000000000000000000000000000000000000000000","quarantined_at":"0","size_bytes":1,"had_xattrs":false}]}"#;
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        assert!(manifest.quarantined[0].settings.is_none());
+    }
+
+    #[test]
+    fn test_select_duplicates_keeps_only_listed() {
+        let group = |original: &str, dups: &[&str]| DuplicateGroup {
+            original: PathBuf::from(original),
+            hash: ContentHash([0; 32]),
+            duplicates: dups.iter().map(PathBuf::from).collect(),
+            size_bytes: 0,
+        };
+        let groups = vec![
+            group("/a.txt", &["/a 2.txt", "/a 3.txt", "/a Copy.txt"]),
+            group("/b.txt", &["/b 2.txt"]),
+        ];
+
+        let selected =
+            select_duplicates(&groups, &[PathBuf::from("/a 3.txt"), PathBuf::from("/a Copy.txt")]).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].original, PathBuf::from("/a.txt"));
+        assert_eq!(
+            selected[0].duplicates,
+            vec![PathBuf::from("/a 3.txt"), PathBuf::from("/a Copy.txt")]
+        );
+
+        assert_eq!(
+            select_duplicates(&groups, &[PathBuf::from("/b.txt")]).unwrap_err(),
+            PathBuf::from("/b.txt")
+        );
+    }
+
+    #[test]
+    fn test_quarantine_paths_moves_only_those() {
+        let temp = TempDir::new().unwrap();
+        let keep = create_test_file(temp.path(), "doc 2.txt", b"content");
+        let remove = create_test_file(temp.path(), "doc 3.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: create_test_file(temp.path(), "doc.txt", b"content"),
+            hash: hash_file(&keep).unwrap(),
+            duplicates: vec![keep.clone(), remove.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+
+        let err = quarantine_paths(&[groups[0].original.clone()], &groups, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let manifest = quarantine_paths(std::slice::from_ref(&remove), &groups, &config).unwrap();
+        assert_eq!(manifest.quarantined.len(), 1);
+        assert!(keep.exists());
+        assert!(!remove.exists());
+    }
+
+    #[test]
+    fn test_quarantine_skips_files_changed_since_scan() {
+        let temp = TempDir::new().unwrap();
+        let changed = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let unchanged = create_test_file(temp.path(), "doc 2.txt", b"content");
+        let hash = hash_file(&changed).unwrap();
+        fs::write(&changed, b"newer version").unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+
+        let err = quarantine_file(&changed, &hash, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash,
+            duplicates: vec![changed.clone(), unchanged.clone()],
+            size_bytes: 0,
+        }];
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        assert_eq!(manifest.quarantined.len(), 1);
+        assert_eq!(manifest.quarantined[0].original_path, unchanged);
+        assert_eq!(fs::read(&changed).unwrap(), b"newer version");
+    }
+
+    #[test]
+    fn test_history_log_records_quarantine_restore_and_purge() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let groups: Vec<DuplicateGroup> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let file = create_test_file(temp.path(), &format!("{} Copy.txt", name), b"1234");
+                DuplicateGroup {
+                    original: temp.path().join(format!("{}.txt", name)),
+                    hash: hash_file(&file).unwrap(),
+                    duplicates: vec![file],
+                    size_bytes: 0,
+                }
+            })
+            .collect();
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        let first: Vec<&QuarantineReceipt> = manifest.quarantined.iter().take(1).collect();
+        restore_receipts(&first, RestoreConflict::Fail, &config, 1, &CancelToken::new(), |_, _| {}).unwrap();
+        purge_quarantine(&load_manifest(&config).unwrap(), &config).unwrap();
+
+        let log = crate::stats::load_history_log(&history_log_path(&config.quarantine_dir)).unwrap();
+        let stats = crate::stats::summarize(&log);
+        assert_eq!(stats.quarantined.files, 3);
+        assert_eq!(stats.restored.files, 1);
+        assert_eq!(stats.purged.bytes, 8);
+        assert_eq!(stats.pending.files, 0);
+        assert_eq!(stats.sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_quarantine_never_moves_protected_paths() {
+        let temp = TempDir::new().unwrap();
+        let taxes = temp.path().join("Taxes");
+        fs::create_dir(&taxes).unwrap();
+        let protected = create_test_file(&taxes, "return Copy.pdf", b"content");
+        let by_name = create_test_file(temp.path(), "keys Copy.key", b"content");
+        let other = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            protected: vec![format!("{}/**", taxes.display()), "*.key".to_string()],
+            ..Default::default()
+        };
+        let hash = hash_file(&other).unwrap();
+
+        let err = quarantine_file(&protected, &hash, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash,
+            duplicates: vec![protected.clone(), by_name.clone(), other.clone()],
+            size_bytes: 0,
+        }];
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        assert_eq!(manifest.quarantined.len(), 1);
+        assert_eq!(manifest.quarantined[0].original_path, other);
+        assert!(protected.exists());
+        assert!(by_name.exists());
+    }
+
+    #[test]
+    fn test_clone_mode_quarantines_and_restores() {
+        let temp = TempDir::new().unwrap();
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            clone_files: true,
+            protected: Vec::new(),
+            ..Default::default()
+        };
+
+        // A clone where supported, a rename everywhere else
+        let receipt = quarantine_duplicates(&groups, &config).unwrap().quarantined.remove(0);
+        assert!(!file.exists());
+        assert_eq!(fs::read(&receipt.quarantine_path).unwrap(), b"content");
+
+        restore_file(&receipt).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"content");
+    }
+
+    #[test]
+    fn test_check_kept_file_detects_replaced_and_gone() {
+        let temp = TempDir::new().unwrap();
+        let kept = create_test_file(temp.path(), "doc.txt", b"content");
+        let file = create_test_file(temp.path(), "doc 2.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: kept.clone(),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        let receipt = &manifest.quarantined[0];
+        assert!(receipt.file_id.is_some());
+        assert_eq!(check_kept_file(receipt), Some(KeptFileCheck::Unchanged));
+        assert!(verify_manifest(&manifest).kept_changed.is_empty());
+
+        // Written beside it first, so the replacement can't reuse the inode
+        let replacement = create_test_file(temp.path(), "doc.new", b"content");
+        fs::rename(&replacement, &kept).unwrap();
+        assert_eq!(check_kept_file(receipt), Some(KeptFileCheck::Replaced));
+
+        fs::remove_file(&kept).unwrap();
+        assert_eq!(check_kept_file(receipt), Some(KeptFileCheck::Gone));
+        let verification = verify_manifest(&manifest);
+        assert!(verification.is_ok());
+        assert_eq!(verification.kept_changed[0].check, KeptFileCheck::Gone);
+
+        let mut older = receipt.clone();
+        older.provenance = None;
+        assert_eq!(check_kept_file(&older), None);
+    }
+
+    #[test]
+    fn test_quarantine_removes_journal_once_saved() {
+        let temp = TempDir::new().unwrap();
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+
+        quarantine_duplicates(&groups, &config).unwrap();
+        assert!(!journal_path(&config).exists());
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 1);
+    }
+
+    #[test]
+    fn test_recover_journal_finishes_and_rolls_back() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        // Receipts as journaled just before each move
+        let planned = |name: &str| {
+            let file = create_test_file(temp.path(), name, b"content");
+            let dry_run = QuarantineConfig {
+                quarantine_dir: config.quarantine_dir.clone(),
+                dry_run: true,
+                preserve_structure: false,
+                ..Default::default()
+            };
+            let receipt = quarantine_file(&file, &hash_file(&file).unwrap(), &dry_run).unwrap();
+            fs::create_dir_all(receipt.quarantine_path.parent().unwrap()).unwrap();
+            receipt
+        };
+
+        let moved = planned("a Copy.txt");
+        fs::rename(&moved.original_path, &moved.quarantine_path).unwrap();
+        let not_moved = planned("b Copy.txt");
+        let partial = planned("c Copy.txt");
+        fs::write(&partial.quarantine_path, b"cont").unwrap();
+        let copied = planned("d Copy.txt");
+        fs::copy(&copied.original_path, &copied.quarantine_path).unwrap();
+        let lost = planned("e Copy.txt");
+        fs::remove_file(&lost.original_path).unwrap();
+
+        let mut journal: String = [&moved, &not_moved, &partial, &copied, &lost]
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap() + "\n")
+            .collect();
+        journal.push_str("{\"id\": \"torn");
+        fs::write(journal_path(&config), journal).unwrap();
+
+        let recovery = recover_journal(&config).unwrap();
+        assert_eq!(recovery.finished, 2);
+        assert_eq!(recovery.rolled_back, 2);
+        assert_eq!(recovery.missing, vec![lost.original_path.clone()]);
+
+        let ids: Vec<_> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![moved.id, copied.id]);
+        assert!(!copied.original_path.exists());
+        assert!(partial.original_path.exists());
+        assert!(!partial.quarantine_path.exists());
+        assert!(not_moved.original_path.exists());
+        assert!(!journal_path(&config).exists());
+        assert!(recover_journal(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recover_journal_keeps_new_file_at_original_path() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let file = create_test_file(temp.path(), "a Copy.txt", b"content");
+        let dry_run = QuarantineConfig {
+            quarantine_dir: config.quarantine_dir.clone(),
+            dry_run: true,
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let receipt = quarantine_file(&file, &hash_file(&file).unwrap(), &dry_run).unwrap();
+        fs::create_dir_all(receipt.quarantine_path.parent().unwrap()).unwrap();
+        fs::copy(&file, &receipt.quarantine_path).unwrap();
+        // Something new landed at the original path after the crash
+        fs::write(&file, b"re-downloaded").unwrap();
+        fs::write(journal_path(&config), serde_json::to_string(&receipt).unwrap() + "\n").unwrap();
+
+        let recovery = recover_journal(&config).unwrap();
+        assert_eq!(recovery.finished, 1);
+        assert_eq!(fs::read(&file).unwrap(), b"re-downloaded");
+        assert_eq!(fs::read(&receipt.quarantine_path).unwrap(), b"content");
+        let ids: Vec<_> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![receipt.id]);
+    }
+
+    #[test]
+    fn test_recover_journal_leaves_files_owned_by_the_manifest() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let dup = create_test_file(&source_dir, "foo 2.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("foo.txt"),
+            hash: hash_file(&dup).unwrap(),
+            duplicates: vec![dup.clone()],
+            size_bytes: 0,
+        }];
+        let earlier = quarantine_duplicates(&groups, &config).unwrap().quarantined.remove(0);
+
+        // The same path again, journaled onto the earlier file's quarantine
+        // path, and the run died before its rename
+        create_test_file(&source_dir, "foo 2.txt", b"content");
+        let crashed = QuarantineReceipt {
+            id: "crashed".to_string(),
+            ..earlier.clone()
+        };
+        fs::write(journal_path(&config), serde_json::to_string(&crashed).unwrap() + "\n").unwrap();
+
+        let recovery = recover_journal(&config).unwrap();
+        assert_eq!(recovery.finished, 0);
+        assert_eq!(recovery.rolled_back, 1);
+        assert!(dup.exists());
+        assert!(earlier.quarantine_path.exists());
+        let ids: Vec<_> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![earlier.id]);
+    }
+
+    #[test]
+    fn test_receipts_record_provenance() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let copy = create_test_file(&source_dir, "doc Copy 2.txt", b"content");
+        let orphan = create_test_file(&source_dir, "notes.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash: hash_file(&copy).unwrap(),
+            duplicates: vec![copy, orphan],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+
+        let manifest = quarantine_duplicates_cancellable(
+            &groups,
+            &config,
+            &RunSettings::default(),
+            Some("20261016T142301Z-3f2a"),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+        let provenance: Vec<_> =
+            manifest.quarantined.iter().map(|r| r.provenance.clone().unwrap()).collect();
+        assert_eq!(provenance[0].pattern.as_deref(), Some("Copy 2"));
+        assert_eq!(provenance[0].kept, source_dir.join("doc.txt"));
+        assert_eq!(provenance[0].scan.as_deref(), Some("20261016T142301Z-3f2a"));
+        assert_eq!(provenance[1].pattern, None);
+        assert_eq!(
+            load_manifest(&config).unwrap().quarantined[0].provenance,
+            Some(provenance[0].clone())
+        );
+    }
+
+    #[test]
+    fn test_quarantine_duplicates_preserves_nested_structure() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+
+        let shallow = create_test_file(&source_dir, "doc Copy.txt", b"content");
+        let deep = create_test_file(&source_dir, "x/y/z/doc Copy.txt", b"content");
+        let hash = hash_file(&shallow).unwrap();
+
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash,
+            duplicates: vec![shallow.clone(), deep.clone()],
+            size_bytes: 0,
+        }];
+
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: true,
+            dry_run: false,
+            ..Default::default()
+        };
+
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+
+        assert_eq!(manifest.quarantined.len(), 2);
+        for receipt in &manifest.quarantined {
+            assert!(receipt.quarantine_path.exists());
+            assert!(!receipt.original_path.exists());
+        }
+        assert!(manifest.quarantined[1].quarantine_path.ends_with("x/y/z/doc Copy.txt"));
+    }
+
+    #[test]
+    fn test_bundle_quarantine_restore_and_purge() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path().join("source/Doc Copy.pages");
+        create_test_file(&bundle, "Index.xml", b"<doc/>");
+        create_test_file(&bundle, "Data/img.png", b"png");
+        let hash = hash_tree(&bundle).unwrap();
+
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("source/Doc.pages"),
+            hash,
+            duplicates: vec![bundle.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        assert_eq!(manifest.quarantined[0].size_bytes, 9);
+        assert!(!bundle.exists());
+
+        restore_file(&manifest.quarantined[0]).unwrap();
+        assert!(bundle.join("Data/img.png").is_file());
+
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        purge_quarantine(&manifest, &config).unwrap();
+        assert!(!manifest.quarantined[0].quarantine_path.exists());
+    }
+
+    #[test]
+    fn test_cancelled_quarantine_moves_nothing_but_writes_manifest() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let file1 = create_test_file(&source_dir, "doc Copy.txt", b"content");
+        let hash = hash_file(&file1).unwrap();
+
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash,
+            duplicates: vec![file1.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let manifest =
+            quarantine_duplicates_cancellable(&groups, &config, &RunSettings::default(), None, &cancel)
+                .unwrap();
+
+        assert!(manifest.quarantined.is_empty());
+        assert!(file1.exists());
+        assert!(load_manifest(&config).unwrap().quarantined.is_empty());
+    }
+
+    #[test]
+    fn test_cancelled_purge_keeps_remaining_receipts() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let file1 = create_test_file(&source_dir, "doc Copy.txt", b"content");
+        let hash = hash_file(&file1).unwrap();
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash,
+            duplicates: vec![file1],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let purged = purge_quarantine_cancellable(&manifest, &config, &cancel).unwrap();
+
+        assert_eq!(purged, 0);
+        assert!(manifest.quarantined[0].quarantine_path.exists());
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_failed_purge_keeps_unpurged_receipts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let receipt = |name: &str| QuarantineReceipt {
+            id: name.to_string(),
+            original_path: PathBuf::from("/docs").join(name),
+            quarantine_path: create_test_file(&config.quarantine_dir, name, b"content"),
+            hash: sample_hash(),
+            quarantined_at: format_timestamp(1_780_358_400),
+            size_bytes: 7,
+            had_xattrs: false,
+            settings: None,
+            session: None,
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("a.txt"), receipt("locked/b.txt"), receipt("c.txt")],
+        };
+        save_manifest(&manifest, &config).unwrap();
+        let locked = config.quarantine_dir.join("locked");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        // Permissions don't bind everyone (root, for one)
+        if fs::write(locked.join("probe"), b"").is_ok() {
+            return;
+        }
+
+        let result = purge_quarantine_cancellable(&manifest, &config, &CancelToken::new());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(result.is_err());
+        assert!(!manifest.quarantined[0].quarantine_path.exists());
+        let ids: Vec<String> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["locked/b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn test_quarantine_sessions_accumulate_in_manifest() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: true,
+            dry_run: false,
+            ..Default::default()
+        };
+        let session = |name: &str| {
+            let file = create_test_file(&source_dir, name, b"content");
+            let groups = vec![DuplicateGroup {
+                original: source_dir.join("doc.txt"),
+                hash: hash_file(&file).unwrap(),
+                duplicates: vec![file],
+                size_bytes: 0,
+            }];
+            quarantine_duplicates(&groups, &config).unwrap()
+        };
+
+        let first = session("doc Copy.txt");
+        let second = session("doc Copy 2.txt");
+
+        assert_eq!(first.quarantined.len(), 1);
+        assert_eq!(second.quarantined.len(), 1);
+        let loaded = load_manifest(&config).unwrap();
+        assert_eq!(loaded.quarantined.len(), 2);
+        assert_eq!(loaded.quarantined[0].id, first.quarantined[0].id);
+        assert_eq!(loaded.quarantined[1].id, second.quarantined[0].id);
+        assert!(!manifest_path(&config).with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_quarantine_refuses_to_overwrite_unreadable_manifest() {
+        let temp = TempDir::new().unwrap();
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        fs::create_dir_all(&config.quarantine_dir).unwrap();
+        fs::write(manifest_path(&config), "not json").unwrap();
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file.clone()],
+            size_bytes: 0,
+        }];
+
+        let err = quarantine_duplicates(&groups, &config).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(file.exists());
+        assert_eq!(fs::read_to_string(manifest_path(&config)).unwrap(), "not json");
+    }
+
+    #[test]
+    fn test_same_path_quarantined_in_two_sessions_restores_both() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let quarantine_once = |content: &[u8]| {
+            let dup = create_test_file(&source_dir, "foo 2.txt", content);
+            let groups = vec![DuplicateGroup {
+                original: source_dir.join("foo.txt"),
+                hash: hash_file(&dup).unwrap(),
+                duplicates: vec![dup],
+                size_bytes: 0,
+            }];
+            quarantine_duplicates(&groups, &config).unwrap().quarantined.remove(0)
+        };
+
+        let first = quarantine_once(b"first");
+        let second = quarantine_once(b"second");
+        assert_ne!(first.quarantine_path, second.quarantine_path);
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 2);
+
+        let dup = source_dir.join("foo 2.txt");
+        restore_file(&first).unwrap();
+        assert_eq!(fs::read(&dup).unwrap(), b"first");
+        let renamed = restore_file_with(&second, RestoreConflict::Rename).unwrap();
+        assert_eq!(fs::read(renamed).unwrap(), b"second");
+    }
+
+    #[test]
+    fn test_library_quarantine_restores_with_its_algorithm() {
+        let temp = TempDir::new().unwrap();
+        let dup = create_test_file(&temp.path().join("source"), "doc Copy.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("source/doc.txt"),
+            hash: hash_path_with(&dup, HashAlgorithm::Sha256).unwrap(),
+            duplicates: vec![dup.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..Default::default()
+        };
+
+        quarantine_duplicates(&groups, &config).unwrap();
+        let receipt = load_manifest(&config).unwrap().quarantined.remove(0);
+
+        assert_eq!(receipt_algorithm(&receipt).unwrap(), HashAlgorithm::Sha256);
+        restore_file(&receipt).unwrap();
+        assert_eq!(fs::read(&dup).unwrap(), b"content");
+    }
+
+    #[test]
+    fn test_quarantine_run_shares_one_session() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let file1 = create_test_file(&source_dir, "doc Copy.txt", b"content");
+        let file2 = create_test_file(&source_dir, "doc Copy 2.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash: hash_file(&file1).unwrap(),
+            duplicates: vec![file1, file2],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+
+        let session = manifest.quarantined[0].session.clone().unwrap();
+        assert!(session.contains('T') && !session.contains(':'));
+        assert_eq!(manifest.quarantined[1].session.as_deref(), Some(session.as_str()));
+        assert_eq!(session_receipts(&load_manifest(&config).unwrap(), &session).len(), 2);
+    }
+
+    #[test]
+    fn test_sessions_group_receipts_in_order() {
+        let receipt = |session: Option<&str>, at: &str, size: u64| QuarantineReceipt {
+            id: format!("{}-{}", at, size),
+            original_path: PathBuf::from("/a.txt"),
+            quarantine_path: PathBuf::from("/q/a.txt"),
+            hash: sample_hash(),
+            quarantined_at: at.to_string(),
+            size_bytes: size,
+            had_xattrs: false,
+            settings: None,
+            session: session.map(str::to_string),
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![
+                receipt(None, "old", 1),
+                receipt(Some("s1"), "t1", 2),
+                receipt(Some("s2"), "t2", 4),
+                receipt(Some("s1"), "t1b", 8),
+            ],
+        };
+
+        let listed = sessions(&manifest);
+
+        let ids: Vec<Option<&str>> = listed.iter().map(|s| s.id.as_deref()).collect();
+        assert_eq!(ids, [None, Some("s1"), Some("s2")]);
+        assert_eq!((listed[1].files, listed[1].bytes), (2, 10));
+        assert_eq!(listed[1].started_at, "t1");
+        assert_eq!(session_receipts(&manifest, "s1").len(), 2);
+        assert!(session_receipts(&manifest, "nope").is_empty());
+    }
+
+    #[test]
+    fn test_quarantine_status_totals_and_ages() {
+        let receipt = |id: &str, at: u64, size: u64| QuarantineReceipt {
+            id: id.to_string(),
+            original_path: PathBuf::from("/a.txt"),
+            quarantine_path: PathBuf::from("/q/a.txt"),
+            hash: sample_hash(),
+            quarantined_at: format_timestamp(at),
+            size_bytes: size,
+            had_xattrs: false,
+            settings: None,
+            session: Some("s1".to_string()),
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("a", 1_000, 3), receipt("b", 5_000, 4)],
+        };
+        let config = QuarantineConfig {
+            quarantine_dir: PathBuf::from("/q"),
+            ..Default::default()
+        };
+
+        let status = quarantine_status(&manifest, &config, Some(Duration::from_secs(10_000)), 6_000);
+        assert_eq!((status.files, status.total_bytes), (2, 7));
+        assert_eq!(status.sessions.len(), 1);
+        assert_eq!(status.receipts[0].age_secs, Some(5_000));
+        assert_eq!(status.receipts[1].expires_in_secs, Some(9_000));
+
+        let json: serde_json::Value = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["receipts"][0]["id"], "a");
+        assert_eq!(json["receipts"][0]["age_secs"], 5_000);
+        assert_eq!(json["sessions"][0]["files"], 2);
+        assert_eq!(json["retention_secs"], 10_000);
+
+        let status = quarantine_status(&manifest, &config, None, 6_000);
+        assert_eq!(status.receipts[0].expires_in_secs, None);
+    }
+
+    #[test]
+    fn test_copy_then_remove_moves_file_with_times() {
+        let temp = TempDir::new().unwrap();
+        let source = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let dest = temp.path().join("elsewhere.txt");
+
+        copy_then_remove(&source, &dest, HashAlgorithm::Blake3).unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(fs::read(&dest).unwrap(), b"content");
+        assert_eq!(fs::metadata(&dest).unwrap().modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn test_copy_then_remove_moves_bundle() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path().join("Report Copy.pages");
+        create_test_file(&bundle, "Index/Document.iwa", b"doc");
+        create_test_file(&bundle, "Data/image.png", b"png");
+        let hash = hash_tree(&bundle).unwrap();
+        let dest = temp.path().join("moved.pages");
+
+        copy_then_remove(&bundle, &dest, HashAlgorithm::Blake3).unwrap();
+
+        assert!(!bundle.exists());
+        assert_eq!(hash_tree(&dest).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_copy_then_remove_keeps_source_when_copy_fails() {
+        let temp = TempDir::new().unwrap();
+        let source = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let dest = temp.path().join("missing-dir").join("doc.txt");
+
+        assert!(copy_then_remove(&source, &dest, HashAlgorithm::Blake3).is_err());
+        assert!(source.exists());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn test_trash_destination_leaves_files_where_unsupported() {
+        let temp = TempDir::new().unwrap();
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: true,
+            dry_run: false,
+            destination: Destination::Trash,
+            ..Default::default()
+        };
+
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+
+        assert!(manifest.quarantined.is_empty());
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xff, 0x10];
+        assert_eq!(to_hex(&bytes), "007fff10");
+        assert_eq!(from_hex(&to_hex(&bytes)).unwrap(), bytes);
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+
+    #[test]
+    fn test_restore_puts_back_lost_xattrs() {
+        let temp = TempDir::new().unwrap();
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"content");
+        if set_extended_attribute(&file, "user.tag", b"red").is_err() {
+            return; // filesystem without xattr support
+        }
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file.clone()],
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            dry_run: false,
+            ..Default::default()
+        };
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        let receipt = &manifest.quarantined[0];
+        assert_eq!(receipt.metadata.as_ref().unwrap().xattrs["user.tag"], "726564");
+
+        // Simulate a copy that dropped the attribute
+        let moved = fs::read(&receipt.quarantine_path).unwrap();
+        fs::remove_file(&receipt.quarantine_path).unwrap();
+        fs::write(&receipt.quarantine_path, moved).unwrap();
+        restore_file(receipt).unwrap();
+
+        assert_eq!(extended_attributes(&file).unwrap()["user.tag"], b"red");
+    }
+
+    #[test]
+    fn test_timestamps_round_trip() {
+        assert_eq!(format_timestamp(1_780_358_400), "2026-06-02T00:00:00Z");
+        assert_eq!(format_timestamp(951_825_599), "2000-02-29T11:59:59Z");
+        for secs in [0, 951_825_599, 1_780_358_400 + 3661, 4_102_444_800] {
+            assert_eq!(parse_timestamp(&format_timestamp(secs)), Some(secs));
+        }
+        assert_eq!(parse_timestamp("2026-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_purge_expired_keeps_recent_receipts() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let receipt = |name: &str, at: u64| {
+            let path = create_test_file(&config.quarantine_dir, name, b"content");
+            QuarantineReceipt {
+                id: name.to_string(),
+                original_path: temp.path().join(name),
+                quarantine_path: path,
+                hash: sample_hash(),
+                quarantined_at: format_timestamp(at),
+                size_bytes: 7,
+                had_xattrs: false,
+                settings: None,
+                session: None,
+                metadata: None,
+                provenance: None,
+                file_id: None,
+            }
+        };
+        let now = 1_780_358_400;
+        let day = 86400;
+        let mut unparseable = receipt("odd.txt", 0);
+        unparseable.quarantined_at = "someday".to_string();
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("old.txt", now - 31 * day), receipt("new.txt", now - day), unparseable],
+        };
+        save_manifest(&manifest, &config).unwrap();
+
+        let purged = purge_expired(&config, Duration::from_secs(30 * day), now).unwrap();
+
+        assert_eq!(purged.len(), 1);
+        assert!(!purged[0].quarantine_path.exists());
+        let ids: Vec<String> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["new.txt", "odd.txt"]);
+        assert!(config.quarantine_dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_purge_expired_without_manifest_is_empty() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        assert!(purge_expired(&config, Duration::ZERO, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.pdf", "Invoice 2.pdf"));
+        assert!(!glob_matches("*.pdf", "Invoice.pdf.txt"));
+        assert!(glob_matches("doc ?.txt", "doc 2.txt"));
+        assert!(glob_matches("/Users/*/Documents/*", "/Users/marc/Documents/a.txt"));
+        assert!(!glob_matches("/Users/*/a.txt", "/Users/marc/Documents/a.txt"));
+        assert!(glob_matches("/Users/**/a.txt", "/Users/marc/Documents/a.txt"));
+    }
+
+    #[test]
+    fn test_purge_selected_removes_only_matches() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+        let now = 1_780_358_400;
+        let receipt = |name: &str, age_days: u64| {
+            let path = create_test_file(&config.quarantine_dir, name, b"content");
+            QuarantineReceipt {
+                id: name.to_string(),
+                original_path: PathBuf::from("/docs").join(name),
+                quarantine_path: path,
+                hash: sample_hash(),
+                quarantined_at: format_timestamp(now - age_days * 86400),
+                size_bytes: 7,
+                had_xattrs: false,
+                settings: None,
+                session: None,
+                metadata: None,
+                provenance: None,
+                file_id: None,
+            }
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("a.pdf", 40), receipt("b.pdf", 1), receipt("c.txt", 40)],
+        };
+        save_manifest(&manifest, &config).unwrap();
+        let selection = PurgeSelection {
+            older_than: Some(Duration::from_secs(30 * 86400)),
+            pattern: Some("*.pdf".to_string()),
+            ..Default::default()
+        };
+
+        let purged = purge_selected(&manifest, &selection, &config, now).unwrap();
+
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].id, "a.pdf");
+        assert!(!config.quarantine_dir.join("a.pdf").exists());
+        let ids: Vec<String> = load_manifest(&config).unwrap().quarantined.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["b.pdf", "c.txt"]);
+
+        let by_id = PurgeSelection {
+            ids: vec!["c.txt".to_string()],
+            ..Default::default()
+        };
+        let (selected, kept) = partition_selected(&load_manifest(&config).unwrap(), &by_id, now);
+        assert_eq!(selected[0].id, "c.txt");
+        assert_eq!(kept.quarantined.len(), 1);
+    }
+
+    #[test]
+    fn test_restored_name_keeps_extension() {
+        let path = Path::new("/docs/Report.pages");
+        assert_eq!(restored_name(path, 1), PathBuf::from("/docs/Report (restored).pages"));
+        assert_eq!(restored_name(path, 3), PathBuf::from("/docs/Report (restored 3).pages"));
+        assert_eq!(restored_name(Path::new("/docs/Makefile"), 1), PathBuf::from("/docs/Makefile (restored)"));
+    }
+
+    #[test]
+    fn test_restore_conflict_strategies() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let quarantine = |name: &str, content: &[u8]| {
+            let file = create_test_file(temp.path(), name, content);
+            let groups = vec![DuplicateGroup {
+                original: temp.path().join("doc.txt"),
+                hash: hash_file(&file).unwrap(),
+                duplicates: vec![file],
+                size_bytes: 0,
+            }];
+            quarantine_duplicates(&groups, &config).unwrap().quarantined.remove(0)
+        };
+
+        // Same content back in place: overwrite is fine, fail refuses
+        let receipt = quarantine("doc Copy.txt", b"same");
+        create_test_file(temp.path(), "doc Copy.txt", b"same");
+        let err = restore_file_with(&receipt, RestoreConflict::Fail).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let restored = restore_file_with(&receipt, RestoreConflict::OverwriteIfIdentical).unwrap();
+        assert_eq!(restored, receipt.original_path);
+        assert!(!receipt.quarantine_path.exists());
+
+        // Different content: overwrite refuses, rename restores alongside
+        let receipt = quarantine("doc Copy.txt", b"old");
+        create_test_file(temp.path(), "doc Copy.txt", b"new");
+        assert!(restore_file_with(&receipt, RestoreConflict::OverwriteIfIdentical).is_err());
+        let restored = restore_file_with(&receipt, RestoreConflict::Rename).unwrap();
+        assert_eq!(restored, temp.path().join("doc Copy (restored).txt"));
+        assert_eq!(fs::read(&restored).unwrap(), b"old");
+        assert_eq!(fs::read(&receipt.original_path).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_restore_receipts_drops_restored_from_manifest() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let groups: Vec<DuplicateGroup> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let file = create_test_file(temp.path(), &format!("{} Copy.txt", name), name.as_bytes());
+                DuplicateGroup {
+                    original: temp.path().join(format!("{}.txt", name)),
+                    hash: hash_file(&file).unwrap(),
+                    duplicates: vec![file],
+                    size_bytes: 0,
+                }
+            })
+            .collect();
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        create_test_file(temp.path(), "b Copy.txt", b"in the way");
+
+        let receipts: Vec<&QuarantineReceipt> = manifest.quarantined.iter().collect();
+        let done = std::sync::atomic::AtomicUsize::new(0);
+        let summary = restore_receipts(&receipts, RestoreConflict::Fail, &config, 2, &CancelToken::new(), |_, _| {
+            done.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        })
+        .unwrap();
+
+        assert_eq!(done.into_inner(), 3);
+        assert_eq!(summary.restored.len(), 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.not_attempted, 0);
+        let remaining = load_manifest(&config).unwrap().quarantined;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, summary.failed[0].0);
+        assert_eq!(remaining[0].original_path, temp.path().join("b Copy.txt"));
+    }
+
+    #[test]
+    fn test_restore_receipts_serializes_same_original_path() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let quarantine = |content: &[u8]| {
+            let file = create_test_file(temp.path(), "doc Copy.txt", content);
+            let groups = vec![DuplicateGroup {
+                original: temp.path().join("doc.txt"),
+                hash: hash_file(&file).unwrap(),
+                duplicates: vec![file],
+                size_bytes: 0,
+            }];
+            quarantine_duplicates(&groups, &config).unwrap();
+        };
+        quarantine(b"first");
+        quarantine(b"second");
+
+        let manifest = load_manifest(&config).unwrap();
+        let receipts: Vec<&QuarantineReceipt> = manifest.quarantined.iter().collect();
+        let summary =
+            restore_receipts(&receipts, RestoreConflict::Rename, &config, 4, &CancelToken::new(), |_, _| {}).unwrap();
+
+        assert_eq!(summary.restored.len(), 2);
+        assert!(summary.failed.is_empty());
+        assert!(temp.path().join("doc Copy.txt").exists());
+        assert!(temp.path().join("doc Copy (restored).txt").exists());
+        assert!(load_manifest(&config).unwrap().quarantined.is_empty());
+    }
+
+    #[test]
+    fn test_cancelled_restore_attempts_nothing() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"data");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file],
+            size_bytes: 0,
+        }];
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let receipts: Vec<&QuarantineReceipt> = manifest.quarantined.iter().collect();
+        let summary = restore_receipts(&receipts, RestoreConflict::Fail, &config, 0, &cancel, |_, _| {}).unwrap();
+
+        assert_eq!(summary.not_attempted, 1);
+        assert!(summary.restored.is_empty());
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_restore_touches_nothing() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let file = create_test_file(temp.path(), "doc Copy.txt", b"old");
+        let groups = vec![DuplicateGroup {
+            original: temp.path().join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file],
+            size_bytes: 0,
+        }];
+        let receipt = quarantine_duplicates(&groups, &config).unwrap().quarantined.remove(0);
+
+        assert_eq!(plan_restore(&receipt, RestoreConflict::Fail).unwrap(), receipt.original_path);
+
+        create_test_file(temp.path(), "doc Copy.txt", b"new");
+        let err = plan_restore(&receipt, RestoreConflict::Fail).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            plan_restore(&receipt, RestoreConflict::Rename).unwrap(),
+            temp.path().join("doc Copy (restored).txt")
+        );
+
+        assert!(receipt.quarantine_path.exists());
+        assert_eq!(fs::read(&receipt.original_path).unwrap(), b"new");
+        assert!(!temp.path().join("doc Copy (restored).txt").exists());
+    }
+
+    #[test]
+    fn test_save_manifest_checksums_and_keeps_backup() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        let receipt = |id: &str| QuarantineReceipt {
+            id: id.to_string(),
+            original_path: PathBuf::from("/a.txt"),
+            quarantine_path: PathBuf::from("/q/a.txt"),
+            hash: sample_hash(),
+            quarantined_at: "2026-06-02T00:00:00Z".to_string(),
+            size_bytes: 1,
+            had_xattrs: false,
+            settings: None,
+            session: None,
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let first = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("first")],
+        };
+        let second = Manifest {
+            version: MANIFEST_VERSION,
+            quarantined: vec![receipt("first"), receipt("second")],
+        };
+        save_manifest(&first, &config).unwrap();
+        save_manifest(&second, &config).unwrap();
+
+        let written = fs::read_to_string(manifest_path(&config)).unwrap();
+        assert!(written.contains("\"checksum\""));
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 2);
+
+        // A tampered manifest fails its checksum and the backup takes over
+        fs::write(manifest_path(&config), written.replace("second", "other")).unwrap();
+        assert_eq!(load_manifest(&config).unwrap().quarantined.len(), 1);
+
+        // With no usable backup the corruption is reported
+        fs::remove_file(backup_path(&config)).unwrap();
+        let err = load_manifest(&config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_manifest_without_checksum_still_loads() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        fs::write(manifest_path(&config), r#"{"version": 1, "quarantined": []}"#).unwrap();
+        assert!(load_manifest(&config).unwrap().quarantined.is_empty());
+    }
+
+    #[test]
+    fn test_v1_manifest_upgrades_and_saves_as_v2() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        let settings = run_settings(&ScanConfig::default(), &config);
+        let receipt = |id: &str, session: Option<&str>| QuarantineReceipt {
+            id: id.to_string(),
+            original_path: PathBuf::from(format!("/{}.txt", id)),
+            quarantine_path: PathBuf::from(format!("/q/{}.txt", id)),
+            hash: sample_hash(),
+            quarantined_at: "2026-06-02T00:00:00Z".to_string(),
+            size_bytes: 1,
+            had_xattrs: false,
+            settings: Some(settings.clone()),
+            session: session.map(str::to_string),
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        };
+        let v1 = Manifest {
+            version: 1,
+            quarantined: vec![receipt("a", Some("s1")), receipt("b", Some("s1")), receipt("c", None)],
+        };
+        let mut written = serde_json::to_value(&v1).unwrap();
+        written["checksum"] = manifest_checksum(&v1).unwrap().into();
+        fs::write(manifest_path(&config), written.to_string()).unwrap();
+
+        let loaded = load_manifest(&config).unwrap();
+        assert_eq!(loaded.version, MANIFEST_VERSION);
+        let as_json = |m: &Manifest| serde_json::to_value(&m.quarantined).unwrap();
+        assert_eq!(as_json(&loaded), as_json(&v1));
+
+        // Saved as v2, the session's settings are stored once
+        save_manifest(&loaded, &config).unwrap();
+        let raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(manifest_path(&config)).unwrap()).unwrap();
+        assert_eq!(raw["version"], MANIFEST_VERSION);
+        assert!(raw["sessions"]["s1"]["settings"].is_object());
+        assert!(raw["quarantined"][0].get("settings").is_none());
+        assert!(raw["quarantined"][2]["settings"].is_object());
+        assert_eq!(as_json(&load_manifest(&config).unwrap()), as_json(&v1));
+    }
+
+    #[test]
+    fn test_newer_manifest_is_refused() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        save_manifest(&Manifest::default(), &config).unwrap();
+        save_manifest(&Manifest::default(), &config).unwrap();
+        fs::write(manifest_path(&config), r#"{"version": 99, "quarantined": []}"#).unwrap();
+
+        // Not even the backup is used in its place
+        let err = load_manifest(&config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(err.to_string().contains("upgrade icloud-dedupe"));
+    }
+
+    #[test]
+    fn test_quarantine_lock_is_exclusive_until_dropped() {
+        let temp = TempDir::new().unwrap();
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            ..Default::default()
+        };
+
+        let lock = lock_quarantine(&config).unwrap();
+        let err = lock_quarantine(&config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(err.to_string().contains("Another icloud-dedupe instance"));
+
+        drop(lock);
+        assert!(lock_quarantine(&config).is_ok());
+    }
+
+    #[test]
+    fn test_verify_manifest_reports_missing_and_corrupted() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let files: Vec<PathBuf> = ["a Copy.txt", "b Copy.txt", "c Copy.txt"]
+            .iter()
+            .map(|name| create_test_file(&source_dir, name, b"content"))
+            .collect();
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("a.txt"),
+            hash: hash_file(&files[0]).unwrap(),
+            duplicates: files,
+            size_bytes: 0,
+        }];
+        let config = QuarantineConfig {
+            quarantine_dir: temp.path().join("quarantine"),
+            preserve_structure: false,
+            ..Default::default()
+        };
+        let manifest = quarantine_duplicates(&groups, &config).unwrap();
+        assert!(verify_manifest(&manifest).is_ok());
+
+        fs::remove_file(&manifest.quarantined[1].quarantine_path).unwrap();
+        fs::write(&manifest.quarantined[2].quarantine_path, b"tampered").unwrap();
+        let verification = verify_manifest(&manifest);
+
+        assert_eq!(verification.checked, 3);
+        assert_eq!(verification.problems.len(), 2);
+        let check = |id: &str| &verification.problems.iter().find(|p| p.id == id).unwrap().check;
+        assert_eq!(*check(&manifest.quarantined[1].id), ReceiptCheck::Missing);
+        assert!(matches!(check(&manifest.quarantined[2].id), ReceiptCheck::Corrupted { .. }));
+    }
+
+    #[test]
+    fn test_remap_prefix_only_touches_paths_under_it() {
+        let (old, new) = (Path::new("/Users/marc"), Path::new("/Users/m"));
+        assert_eq!(remap_prefix(Path::new("/Users/marc/a.txt"), old, new), PathBuf::from("/Users/m/a.txt"));
+        assert_eq!(remap_prefix(Path::new("/Users/marcel/a.txt"), old, new), PathBuf::from("/Users/marcel/a.txt"));
+    }
+
+    #[test]
+    fn test_export_with_files_imports_into_another_quarantine() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let file = create_test_file(&source_dir, "doc Copy.txt", b"content");
+        let groups = vec![DuplicateGroup {
+            original: source_dir.join("doc.txt"),
+            hash: hash_file(&file).unwrap(),
+            duplicates: vec![file.clone()],
+            size_bytes: 0,
+        }];
+        let here = QuarantineConfig {
+            quarantine_dir: temp.path().join("here"),
+            ..Default::default()
+        };
+        let there = QuarantineConfig {
+            quarantine_dir: temp.path().join("there"),
+            ..Default::default()
+        };
+        let receipt = quarantine_duplicates(&groups, &here).unwrap().quarantined.remove(0);
+
+        let archive = temp.path().join("archive");
+        assert_eq!(export_quarantine(&here, &archive, true).unwrap(), 1);
+        let mut export = load_export(&archive).unwrap();
+        assert!(export.files_included);
+        remap_originals(&mut export.manifest, &source_dir, &temp.path().join("moved"));
+
+        let imported = import_quarantine(&archive, &export, &there).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].id, receipt.id);
+        assert_eq!(imported[0].original_path, temp.path().join("moved/doc Copy.txt"));
+        assert!(imported[0].quarantine_path.starts_with(&there.quarantine_dir));
+        assert_eq!(check_receipt(&imported[0]), ReceiptCheck::Ok);
+        assert!(receipt.quarantine_path.exists(), "export leaves the quarantine alone");
+
+        // Importing again adds nothing
+        assert!(import_quarantine(&archive, &export, &there).unwrap().is_empty());
+        assert_eq!(load_manifest(&there).unwrap().quarantined.len(), 1);
+    }
+
+    #[test]
+    fn test_import_keeps_local_files_and_skips_missing_exports() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("source");
+        let here = QuarantineConfig {
+            quarantine_dir: temp.path().join("here"),
+            ..Default::default()
+        };
+        let there = QuarantineConfig {
+            quarantine_dir: temp.path().join("there"),
+            ..Default::default()
+        };
+        let quarantine_into = |name: &str, content: &[u8], config: &QuarantineConfig| {
+            let dup = create_test_file(&source_dir, name, content);
+            let groups = vec![DuplicateGroup {
+                original: source_dir.join("doc.txt"),
+                hash: hash_file(&dup).unwrap(),
+                duplicates: vec![dup],
+                size_bytes: 0,
+            }];
+            quarantine_duplicates(&groups, config).unwrap().quarantined.remove(0)
+        };
+        quarantine_into("doc Copy.txt", b"exported", &there);
+        let gone = quarantine_into("notes Copy.txt", b"notes", &there);
+        let local = quarantine_into("doc Copy.txt", b"local", &here);
+
+        let archive = temp.path().join("archive");
+        export_quarantine(&there, &archive, true).unwrap();
+        fs::remove_dir_all(archive.join(EXPORT_FILES_DIR).join(&gone.id)).unwrap();
+
+        let imported = import_quarantine(&archive, &load_export(&archive).unwrap(), &here).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_ne!(imported[0].quarantine_path, local.quarantine_path);
+        assert_eq!(fs::read(&imported[0].quarantine_path).unwrap(), b"exported");
+        assert_eq!(fs::read(&local.quarantine_path).unwrap(), b"local");
+        assert_eq!(load_manifest(&here).unwrap().quarantined.len(), 2);
+    }
+
+    #[test]
+    fn test_manifest_only_import_rebases_quarantine_paths() {
+        let temp = TempDir::new().unwrap();
+        let there = QuarantineConfig {
+            quarantine_dir: temp.path().join("there"),
+            ..Default::default()
+        };
+        let export = ManifestExport {
+            version: EXPORT_VERSION,
+            home: None,
+            quarantine_dir: PathBuf::from("/old/quarantine"),
+            files_included: false,
+            manifest: Manifest {
+                version: MANIFEST_VERSION,
+                quarantined: vec![QuarantineReceipt {
+                    id: "r1".to_string(),
+                    original_path: PathBuf::from("/docs/a.txt"),
+                    quarantine_path: PathBuf::from("/old/quarantine/docs/a.txt"),
+                    hash: sample_hash(),
+                    quarantined_at: String::new(),
+                    size_bytes: 1,
+                    had_xattrs: false,
+                    settings: None,
+                    session: None,
+                    metadata: None,
+                    provenance: None,
+                    file_id: None,
+                }],
+            },
+        };
+        let file = temp.path().join("export.json");
+        fs::write(&file, serde_json::to_string(&export).unwrap()).unwrap();
+
+        let imported = import_quarantine(&file, &load_export(&file).unwrap(), &there).unwrap();
+
+        assert_eq!(imported[0].quarantine_path, there.quarantine_dir.join("docs/a.txt"));
+    }
 }
@@ -1,19 +1,85 @@
 //! Report formatting for scan results.
 //!
-//! Pure functions — (ScanReport, OutputFormat) → String.
-//! No I/O, no side effects.
+//! Pure functions — (ScanReport, OutputFormat) → String, and back from
+//! saved JSON. No I/O, no side effects.
+
+use std::path::Path;
+use std::time::Duration;
 
 use humansize::{format_size, BINARY};
+use serde::{Deserialize, Serialize};
 
-use crate::types::{OutputFormat, ScanReport};
+use crate::history::ScanDelta;
+use crate::messages::{
+    count, hashed_note, keep_note, noun, phases_note, scan_header_note, scanned_note, summary_label, text,
+    walk_note, Locale, Noun, Text,
+};
+use crate::quarantine::format_timestamp;
+use crate::types::{
+    ContentHash, DivergedFile, DivergedPair, KeepPolicy, MetadataField, OutputFormat, ReportCategory, ScanReport, ScanStats,
+    REPORT_SCHEMA_VERSION,
+};
 
 /// Format a scan report for output.
 ///
 /// Pure function: takes data, returns formatted string.
 pub fn format_report(report: &ScanReport, format: OutputFormat) -> String {
+    format_report_in(report, format, Locale::English)
+}
+
+/// Like [`format_report`], with human, Markdown, and HTML output in `locale`.
+pub fn format_report_in(report: &ScanReport, format: OutputFormat, locale: Locale) -> String {
+    format_report_colored(report, format, locale, false)
+}
+
+/// Like [`format_report_in`], with ANSI color in human output when `color`
+/// is set. Other formats are never colored.
+pub fn format_report_colored(report: &ScanReport, format: OutputFormat, locale: Locale, color: bool) -> String {
+    let paint = Paint { color };
     match format {
-        OutputFormat::Human => format_human(report),
+        OutputFormat::Human => format_human(report, locale, paint),
         OutputFormat::Json => format_json(report),
+        OutputFormat::Csv => format_csv(report),
+        OutputFormat::Markdown => format_markdown(report, locale),
+        OutputFormat::Html => format_html(report, locale),
+        OutputFormat::Jsonl => format_jsonl_records(report) + &format_jsonl_summary(report),
+        OutputFormat::Summary => scan_problems(report, locale) + &format_summary(report, locale, paint),
+    }
+}
+
+// ============================================================================
+// CATEGORY FILTER
+// ============================================================================
+
+/// Drop every finding outside `only`, so any format shows just those
+/// categories. Warnings, partial-scan notes, and stats stay. An empty
+/// `only` keeps everything.
+pub fn retain_categories(report: &mut ScanReport, only: &[ReportCategory]) {
+    if only.is_empty() {
+        return;
+    }
+    if !only.contains(&ReportCategory::Duplicates) {
+        report.confirmed_duplicates.clear();
+        report.by_directory.clear();
+        report.by_parent.clear();
+        report.by_extension.clear();
+        report.bytes_recoverable = 0;
+        report.bytes_recoverable_physical = None;
+        report.hardlinked.clear();
+        report.probable_duplicates.clear();
+        report.metadata_differs.clear();
+        report.unsynced.clear();
+    }
+    if !only.contains(&ReportCategory::Orphans) {
+        report.orphaned_conflicts.clear();
+    }
+    if !only.contains(&ReportCategory::Diverged) {
+        report.content_diverged.clear();
+    }
+    if !only.contains(&ReportCategory::Skipped) {
+        report.skipped.clear();
+        report.symlink_skips.clear();
+        report.not_local.clear();
     }
 }
 
@@ -21,24 +87,89 @@ pub fn format_report(report: &ScanReport, format: OutputFormat) -> String {
 // HUMAN FORMAT
 // ============================================================================
 
-fn format_human(report: &ScanReport) -> String {
-    let mut out = String::new();
+/// ANSI styling for human output; leaves text as is when color is off.
+#[derive(Debug, Clone, Copy)]
+struct Paint {
+    color: bool,
+}
+
+impl Paint {
+    fn style(self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Category headers: bold cyan.
+    fn header(self, text: &str) -> String {
+        self.style("1;36", text)
+    }
+
+    /// The file kept: green.
+    fn keep(self, text: &str) -> String {
+        self.style("32", text)
+    }
+
+    /// Files to remove: red.
+    fn remove(self, text: &str) -> String {
+        self.style("31", text)
+    }
+
+    /// Byte counts: bold yellow.
+    fn size(self, text: &str) -> String {
+        self.style("1;33", text)
+    }
+}
+
+fn format_human(report: &ScanReport, locale: Locale, paint: Paint) -> String {
+    let t = |key| text(locale, key);
+    let mut out = scan_header(&report.stats, locale);
+    out.push_str(&scan_problems(report, locale));
 
     // Confirmed duplicates
     if !report.confirmed_duplicates.is_empty() {
-        out.push_str("=== Confirmed Duplicates ===\n");
+        out.push_str(&format!("{}\n", paint.header(t(Text::DuplicatesHeader))));
+        if report.keep_policy != KeepPolicy::OriginalName {
+            out.push_str(&format!("{}\n", keep_note(locale, report.keep_policy)));
+        }
         for group in &report.confirmed_duplicates {
-            out.push_str(&format!("Original: {}\n", group.original.display()));
+            out.push_str(&format!(
+                "{} {}\n",
+                paint.keep(&format!("{}:", t(Text::Original))),
+                group.original.display()
+            ));
             for dup in &group.duplicates {
-                out.push_str(&format!("  └─ {}\n", dup.display()));
+                out.push_str(&format!("  {} {}\n", paint.remove("└─"), dup.display()));
             }
         }
         out.push('\n');
     }
 
+    // Where the duplicates live
+    for (header, rollup) in [
+        (Text::ByFolderHeader, &report.by_directory),
+        (Text::ByParentHeader, &report.by_parent),
+    ] {
+        if rollup.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("{}\n", paint.header(t(header))));
+        for dir in rollup {
+            out.push_str(&format!(
+                "  {}  {} ({})\n",
+                paint.size(&format!("{:>10}", format_size(dir.bytes_recoverable, BINARY))),
+                dir.path.display(),
+                count(locale, dir.duplicates, Noun::File)
+            ));
+        }
+        out.push('\n');
+    }
+
     // Orphaned conflicts
     if !report.orphaned_conflicts.is_empty() {
-        out.push_str("=== Orphaned Conflicts (no original found) ===\n");
+        out.push_str(&format!("{}\n", paint.header(t(Text::OrphansHeader))));
         for path in &report.orphaned_conflicts {
             out.push_str(&format!("  {}\n", path.display()));
         }
@@ -47,29 +178,94 @@ fn format_human(report: &ScanReport) -> String {
 
     // Diverged content
     if !report.content_diverged.is_empty() {
-        out.push_str("=== Content Diverged (different content) ===\n");
-        for (conflict, original) in &report.content_diverged {
-            out.push_str(&format!("  {} ≠ {}\n", conflict.display(), original.display()));
+        out.push_str(&format!("{}\n", paint.header(t(Text::DivergedHeader))));
+        let width = t(Text::Conflict).chars().count().max(t(Text::Original).chars().count()) + 1;
+        for pair in &report.content_diverged {
+            out.push_str(&format!("  {} ≠ {}\n", pair.conflict.path.display(), pair.original.path.display()));
+            for (key, side) in [(Text::Conflict, &pair.conflict), (Text::Original, &pair.original)] {
+                let label = format!("{}:", t(key));
+                out.push_str(&format!("    {:<width$} {}\n", label, diverged_side(side, paint)));
+            }
+        }
+        out.push('\n');
+    }
+
+    // Placeholders
+    if !report.not_local.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::NotLocalHeader))));
+        for path in &report.not_local {
+            out.push_str(&format!("  {}\n", path.display()));
+        }
+        out.push('\n');
+    }
+
+    // Duplicates iCloud is still working on
+    if !report.unsynced.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::UnsyncedHeader))));
+        for (path, state) in &report.unsynced {
+            out.push_str(&format!("  {} ({})\n", path.display(), state.name()));
+        }
+        out.push('\n');
+    }
+
+    // Hard links
+    if !report.hardlinked.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::HardLinksHeader))));
+        for (link, original) in &report.hardlinked {
+            out.push_str(&format!("  {} = {}\n", link.display(), original.display()));
+        }
+        out.push('\n');
+    }
+
+    // Unhashed matches from a fast scan
+    if !report.probable_duplicates.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::ProbableHeader))));
+        for (conflict, original) in &report.probable_duplicates {
+            out.push_str(&format!("  {} ≈ {}\n", conflict.display(), original.display()));
+        }
+        out.push('\n');
+    }
+
+    // Same content, different metadata
+    if !report.metadata_differs.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::MetadataHeader))));
+        for mismatch in &report.metadata_differs {
+            let fields: Vec<&str> = mismatch.fields.iter().map(|f| f.name()).collect();
+            out.push_str(&format!(
+                "  {} = {} ({})\n",
+                mismatch.conflict.display(),
+                mismatch.original.display(),
+                fields.join(", ")
+            ));
         }
         out.push('\n');
     }
 
     // Skipped files
     if !report.skipped.is_empty() {
-        out.push_str("=== Skipped (read errors) ===\n");
+        out.push_str(&format!("{}\n", paint.header(t(Text::SkippedHeader))));
         for (path, error) in &report.skipped {
             out.push_str(&format!("  {} - {}\n", path.display(), error));
         }
         out.push('\n');
     }
 
+    // Symlinks not followed
+    if !report.symlink_skips.is_empty() {
+        out.push_str(&format!("{}\n", paint.header(t(Text::SymlinksHeader))));
+        for (path, reason) in &report.symlink_skips {
+            out.push_str(&format!("  {} - {}\n", path.display(), reason));
+        }
+        out.push('\n');
+    }
+
     // Summary
-    out.push_str(&format_summary(report));
+    out.push_str(&format_summary(report, locale, paint));
 
     out
 }
 
-fn format_summary(report: &ScanReport) -> String {
+fn format_summary(report: &ScanReport, locale: Locale, paint: Paint) -> String {
     let total_duplicates: usize = report
         .confirmed_duplicates
         .iter()
@@ -77,31 +273,271 @@ fn format_summary(report: &ScanReport) -> String {
         .sum();
 
     let mut out = String::new();
-    out.push_str("=== Summary ===\n");
-    out.push_str(&format!(
-        "Duplicate groups:   {}\n",
-        report.confirmed_duplicates.len()
-    ));
-    out.push_str(&format!("Total duplicates:   {}\n", total_duplicates));
-    out.push_str(&format!(
-        "Orphaned conflicts: {}\n",
-        report.orphaned_conflicts.len()
-    ));
-    out.push_str(&format!(
-        "Diverged files:     {}\n",
-        report.content_diverged.len()
-    ));
+    let mut line = |key, value: String| {
+        out.push_str(&format!("{}{}\n", summary_label(locale, key), value));
+    };
+
+    line(Text::DuplicateGroups, report.confirmed_duplicates.len().to_string());
+    line(Text::TotalDuplicates, total_duplicates.to_string());
+    line(Text::OrphanedConflicts, report.orphaned_conflicts.len().to_string());
+    line(Text::DivergedFiles, report.content_diverged.len().to_string());
+    if !report.not_local.is_empty() {
+        line(Text::NotDownloaded, report.not_local.len().to_string());
+    }
+    if !report.hardlinked.is_empty() {
+        line(Text::HardLinks, report.hardlinked.len().to_string());
+    }
+    if !report.probable_duplicates.is_empty() {
+        line(Text::ProbableDuplicates, report.probable_duplicates.len().to_string());
+    }
+    if !report.metadata_differs.is_empty() {
+        line(Text::MetadataDiffers, report.metadata_differs.len().to_string());
+    }
     if !report.skipped.is_empty() {
-        out.push_str(&format!("Skipped (errors):   {}\n", report.skipped.len()));
+        line(Text::SkippedErrors, report.skipped.len().to_string());
+    }
+    if !report.symlink_skips.is_empty() {
+        line(Text::SkippedSymlinks, report.symlink_skips.len().to_string());
+    }
+    line(Text::SpaceRecoverable, paint.size(&format_recoverable(report)));
+    if !report.by_extension.is_empty() {
+        line(Text::ByExtension, extension_breakdown(report, locale));
     }
-    out.push_str(&format!(
-        "Space recoverable:  {}\n",
-        format_size(report.bytes_recoverable, BINARY)
-    ));
 
+    // Reports not produced by a scan have no stats to show
+    let stats = &report.stats;
+    if stats.files_scanned > 0 {
+        out.push_str(&format!(
+            "{}\n",
+            scanned_note(locale, stats.files_scanned, &format_eta(stats.duration()))
+        ));
+        if let (Some(rate), Some(per_thread)) = (stats.verify_bytes_per_sec(), stats.thread_bytes_per_sec()) {
+            out.push_str(&format!(
+                "{}\n",
+                hashed_note(
+                    locale,
+                    stats.files_hashed,
+                    &format_size(stats.bytes_hashed, BINARY),
+                    &format_rate(rate),
+                    &format_rate(per_thread),
+                )
+            ));
+        }
+        if stats.walk_ms > 0 || stats.verify_ms > 0 {
+            out.push_str(&format!(
+                "{}\n",
+                phases_note(
+                    locale,
+                    &format_phase(Duration::from_millis(stats.walk_ms)),
+                    &format_phase(Duration::from_millis(stats.verify_ms)),
+                )
+            ));
+        }
+    }
+
+    format!("{}\n{}", paint.header(text(locale, Text::SummaryHeader)), out)
+}
+
+/// When, where, and how a scan ran, so a saved report still says what it
+/// covers. Empty for reports not produced by a scan.
+fn scan_header(stats: &ScanStats, locale: Locale) -> String {
+    let t = |key| text(locale, key);
+    if stats.started_at == 0 {
+        return String::new();
+    }
+
+    let mut out = format!(
+        "{}\n",
+        scan_header_note(
+            locale,
+            &format_timestamp(stats.started_at),
+            &format_phase(stats.duration()),
+            &stats.version
+        )
+    );
+    let roots: Vec<String> = stats.roots.iter().map(|r| r.display().to_string()).collect();
+    out.push_str(&format!("{}: {}\n", t(Text::Roots), roots.join(", ")));
+    if let Some(walk) = &stats.walk {
+        out.push_str(&format!(
+            "{}\n",
+            walk_note(locale, walk.max_depth, walk.include_hidden, walk.follow_symlinks)
+        ));
+        if !walk.ignore.is_empty() {
+            out.push_str(&format!("{}: {}\n", t(Text::Ignoring), walk.ignore.join(", ")));
+        }
+    }
+    out.push('\n');
     out
 }
 
+/// Extensions shown in the summary line; JSON output has them all.
+const EXTENSION_SUMMARY_LIMIT: usize = 5;
+
+/// The kinds of file holding the most recoverable space: ".mov: 18 GiB,
+/// .psd: 4 GiB, …".
+fn extension_breakdown(report: &ScanReport, locale: Locale) -> String {
+    let mut parts: Vec<String> = report
+        .by_extension
+        .iter()
+        .take(EXTENSION_SUMMARY_LIMIT)
+        .map(|e| {
+            let name = if e.extension.is_empty() { text(locale, Text::NoExtension) } else { &e.extension };
+            format!("{}: {}", name, format_size(e.bytes_recoverable, BINARY))
+        })
+        .collect();
+    if report.by_extension.len() > EXTENSION_SUMMARY_LIMIT {
+        parts.push("…".to_string());
+    }
+    parts.join(", ")
+}
+
+/// What is known about one side of a diverged pair, "?" where nothing is:
+/// "    12 KiB  2026-06-02T14:03:00Z  3fa9c2e1d0b4".
+fn diverged_side(side: &DivergedFile, paint: Paint) -> String {
+    let size = side.size.map_or("?".to_string(), |s| format_size(s, BINARY));
+    let modified = side.modified.map_or("?".to_string(), format_timestamp);
+    let hash = side.hash.as_ref().map_or("-".to_string(), |h| h.to_hex()[..12].to_string());
+    format!("{}  {}  {}", paint.size(&format!("{:>10}", size)), modified, hash)
+}
+
+/// How the two sides of a diverged pair compare, for a one-line listing:
+/// "12 KiB vs 10 KiB, conflict newer".
+pub fn diverged_summary(pair: &DivergedPair) -> String {
+    let size = |s: Option<u64>| s.map_or("?".to_string(), |s| format_size(s, BINARY));
+    let mut summary = format!("{} vs {}", size(pair.conflict.size), size(pair.original.size));
+    if let (Some(conflict), Some(original)) = (pair.conflict.modified, pair.original.modified) {
+        match conflict.cmp(&original) {
+            std::cmp::Ordering::Greater => summary.push_str(", conflict newer"),
+            std::cmp::Ordering::Less => summary.push_str(", original newer"),
+            std::cmp::Ordering::Equal => summary.push_str(", same date"),
+        }
+    }
+    summary
+}
+
+/// Problems with the scan itself: why it stopped early, and warnings.
+fn scan_problems(report: &ScanReport, locale: Locale) -> String {
+    let mut out = String::new();
+    if let Some(reason) = &report.partial {
+        out.push_str(&format!("{}: {}\n", text(locale, Text::PartialScan), reason));
+    }
+    for warning in &report.warnings {
+        out.push_str(&format!("{}: {}\n", text(locale, Text::Warning), warning));
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Recoverable space, split into logical and actual when APFS clones mean
+/// removing the duplicates would free less than their apparent size.
+///
+/// "5 MiB", or "5 MiB logical, 1 MiB actual".
+pub fn format_recoverable(report: &ScanReport) -> String {
+    let logical = format_size(report.bytes_recoverable, BINARY);
+    match report.bytes_recoverable_physical {
+        Some(physical) if physical < report.bytes_recoverable => format!(
+            "{} logical, {} actual",
+            logical,
+            format_size(physical, BINARY)
+        ),
+        _ => logical,
+    }
+}
+
+// ============================================================================
+// CHANGES SINCE LAST SCAN
+// ============================================================================
+
+/// Describe a scan delta, one line per changed figure.
+///
+/// "+3 new duplicate groups", "1.2 GiB more recoverable", ending with
+/// "since 2026-06-02". A delta with no changes yields a single line.
+pub fn format_delta(delta: &ScanDelta) -> Vec<String> {
+    let since = format_date(delta.since);
+    if delta.is_empty() {
+        return vec![format!("No changes since {}", since)];
+    }
+
+    let count = |n: i64, what: Noun| {
+        let what = noun(Locale::English, what, n.unsigned_abs());
+        match n {
+            0 => None,
+            1.. => Some(format!("+{} new {}", n, what)),
+            _ => Some(format!("{} fewer {}", -n, what)),
+        }
+    };
+
+    let mut lines: Vec<String> = [
+        count(delta.duplicate_groups, Noun::DuplicateGroup),
+        count(delta.orphaned, Noun::OrphanedConflict),
+        count(delta.diverged, Noun::DivergedFile),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let bytes = delta.bytes_recoverable;
+    if bytes != 0 {
+        let direction = if bytes > 0 { "more" } else { "less" };
+        lines.push(format!(
+            "{} {} recoverable",
+            format_size(bytes.unsigned_abs(), BINARY),
+            direction
+        ));
+    }
+
+    if let Some(last) = lines.last_mut() {
+        last.push_str(&format!(" since {}", since));
+    }
+    lines
+}
+
+/// Format seconds since the Unix epoch as a UTC date: "2026-06-02".
+pub fn format_date(secs: u64) -> String {
+    // Civil-from-days (proleptic Gregorian), shifted so years start in March
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// ============================================================================
+// PROGRESS
+// ============================================================================
+
+/// Format a phase's duration: "0.4s", "12.3s", then as [`format_eta`].
+pub fn format_phase(took: Duration) -> String {
+    if took.as_secs() < 60 {
+        format!("{:.1}s", took.as_secs_f64())
+    } else {
+        format_eta(took)
+    }
+}
+
+/// Format a throughput: "290 MiB/s".
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", format_size(bytes_per_sec as u64, BINARY))
+}
+
+/// Format a time estimate compactly: "42s", "3m 05s", "1h 12m".
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
 // ============================================================================
 // JSON FORMAT
 // ============================================================================
@@ -114,6 +550,566 @@ fn format_json(report: &ScanReport) -> String {
     })
 }
 
+/// Parse a report saved with `--format json` by this or an earlier
+/// version. Reports from a newer schema are refused rather than misread.
+pub fn parse_report(json: &str) -> Result<ScanReport, String> {
+    #[derive(Deserialize)]
+    struct Schema {
+        #[serde(default)]
+        schema_version: u32,
+    }
+
+    let invalid = |e: serde_json::Error| format!("Not a valid scan report: {}", e);
+    let schema: Schema = serde_json::from_str(json).map_err(invalid)?;
+    if schema.schema_version > REPORT_SCHEMA_VERSION {
+        return Err(format!(
+            "Report schema version {} is newer than this tool supports (up to {}); \
+             upgrade icloud-dedupe to read it",
+            schema.schema_version, REPORT_SCHEMA_VERSION
+        ));
+    }
+    serde_json::from_str(json).map_err(invalid)
+}
+
+// ============================================================================
+// JSONL FORMAT
+// ============================================================================
+
+/// One line of `--format jsonl` output.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReportRecord<'a> {
+    Warning {
+        message: &'a str,
+    },
+    Duplicate {
+        original: &'a Path,
+        duplicate: &'a Path,
+        hash: &'a ContentHash,
+        size_bytes: u64,
+    },
+    Orphan {
+        path: &'a Path,
+    },
+    Diverged {
+        conflict: &'a DivergedFile,
+        original: &'a DivergedFile,
+        ends_only: bool,
+    },
+    NotLocal {
+        path: &'a Path,
+    },
+    Hardlink {
+        path: &'a Path,
+        original: &'a Path,
+    },
+    Probable {
+        path: &'a Path,
+        original: &'a Path,
+    },
+    MetadataDiffers {
+        conflict: &'a Path,
+        original: &'a Path,
+        fields: &'a [MetadataField],
+    },
+    Skipped {
+        path: &'a Path,
+        error: &'a str,
+    },
+    SymlinkSkip {
+        path: &'a Path,
+        reason: &'a str,
+    },
+    /// Always the last line of a complete scan.
+    Summary {
+        schema_version: u32,
+        duplicate_groups: usize,
+        duplicates: usize,
+        orphaned: usize,
+        diverged: usize,
+        bytes_recoverable: u64,
+        bytes_recoverable_physical: Option<u64>,
+        partial: Option<&'a str>,
+        stats: &'a ScanStats,
+    },
+}
+
+/// A line per warning and finding in `report`, with no summary. Streaming
+/// scans write this for each batch as it's verified.
+pub fn format_jsonl_records(report: &ScanReport) -> String {
+    let mut records = Vec::new();
+    records.extend(report.warnings.iter().map(|message| ReportRecord::Warning { message }));
+    for group in &report.confirmed_duplicates {
+        records.extend(group.duplicates.iter().map(|duplicate| ReportRecord::Duplicate {
+            original: &group.original,
+            duplicate,
+            hash: &group.hash,
+            size_bytes: group.size_bytes,
+        }));
+    }
+    records.extend(report.orphaned_conflicts.iter().map(|path| ReportRecord::Orphan { path }));
+    records.extend(
+        report
+            .content_diverged
+            .iter()
+            .map(|pair| ReportRecord::Diverged {
+                conflict: &pair.conflict,
+                original: &pair.original,
+                ends_only: pair.ends_only,
+            }),
+    );
+    records.extend(report.not_local.iter().map(|path| ReportRecord::NotLocal { path }));
+    records.extend(report.hardlinked.iter().map(|(path, original)| ReportRecord::Hardlink { path, original }));
+    records.extend(
+        report
+            .probable_duplicates
+            .iter()
+            .map(|(path, original)| ReportRecord::Probable { path, original }),
+    );
+    records.extend(report.metadata_differs.iter().map(|m| ReportRecord::MetadataDiffers {
+        conflict: &m.conflict,
+        original: &m.original,
+        fields: &m.fields,
+    }));
+    records.extend(report.skipped.iter().map(|(path, error)| ReportRecord::Skipped { path, error }));
+    records.extend(
+        report
+            .symlink_skips
+            .iter()
+            .map(|(path, reason)| ReportRecord::SymlinkSkip { path, reason }),
+    );
+
+    records.iter().map(jsonl_line).collect()
+}
+
+/// The closing summary line, with totals after groups are merged.
+pub fn format_jsonl_summary(report: &ScanReport) -> String {
+    jsonl_line(&ReportRecord::Summary {
+        schema_version: REPORT_SCHEMA_VERSION,
+        duplicate_groups: report.confirmed_duplicates.len(),
+        duplicates: report.confirmed_duplicates.iter().map(|g| g.duplicates.len()).sum(),
+        orphaned: report.orphaned_conflicts.len(),
+        diverged: report.content_diverged.len(),
+        bytes_recoverable: report.bytes_recoverable,
+        bytes_recoverable_physical: report.bytes_recoverable_physical,
+        partial: report.partial.as_deref(),
+        stats: &report.stats,
+    })
+}
+
+fn jsonl_line(record: &ReportRecord) -> String {
+    let json = serde_json::to_string(record).unwrap_or_else(|e| panic!("Failed to serialize report record: {}", e));
+    json + "\n"
+}
+
+// ============================================================================
+// MARKDOWN FORMAT
+// ============================================================================
+
+/// A summary list, then one table per non-empty category, for pasting into
+/// notes or issues.
+fn format_markdown(report: &ScanReport, locale: Locale) -> String {
+    let t = |key| text(locale, key);
+    let mut out = format!("## {}\n\n", heading(t(Text::SummaryHeader)));
+
+    if let Some(reason) = &report.partial {
+        out.push_str(&format!("> **{}:** {}\n\n", t(Text::PartialScan), md_cell(reason)));
+    }
+    for warning in &report.warnings {
+        out.push_str(&format!("> **{}:** {}\n\n", t(Text::Warning), md_cell(warning)));
+    }
+
+    let total_duplicates: usize = report.confirmed_duplicates.iter().map(|g| g.duplicates.len()).sum();
+    let mut item = |key, value: String| out.push_str(&format!("- **{}:** {}\n", t(key), value));
+    item(Text::DuplicateGroups, report.confirmed_duplicates.len().to_string());
+    item(Text::TotalDuplicates, total_duplicates.to_string());
+    item(Text::OrphanedConflicts, report.orphaned_conflicts.len().to_string());
+    item(Text::DivergedFiles, report.content_diverged.len().to_string());
+    item(Text::SpaceRecoverable, format_recoverable(report));
+    if !report.by_extension.is_empty() {
+        item(Text::ByExtension, extension_breakdown(report, locale));
+    }
+    if report.stats.files_scanned > 0 {
+        out.push_str(&format!(
+            "- {}\n",
+            scanned_note(locale, report.stats.files_scanned, &format_eta(report.stats.duration()))
+        ));
+    }
+
+    let path = |p: &std::path::Path| md_cell(&p.display().to_string());
+    md_table(
+        &mut out,
+        t(Text::DuplicatesHeader),
+        &["Original", "Duplicate", "Size"],
+        report.confirmed_duplicates.iter().flat_map(|g| {
+            g.duplicates
+                .iter()
+                .map(move |d| vec![path(&g.original), path(d), format_size(g.size_bytes, BINARY)])
+        }),
+    );
+    for (header, rollup) in [
+        (Text::ByFolderHeader, &report.by_directory),
+        (Text::ByParentHeader, &report.by_parent),
+    ] {
+        md_table(
+            &mut out,
+            t(header),
+            &["Folder", "Files", "Recoverable"],
+            rollup.iter().map(|d| {
+                vec![path(&d.path), d.duplicates.to_string(), format_size(d.bytes_recoverable, BINARY)]
+            }),
+        );
+    }
+    md_table(
+        &mut out,
+        t(Text::OrphansHeader),
+        &["File"],
+        report.orphaned_conflicts.iter().map(|p| vec![path(p)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::DivergedHeader),
+        &["Conflict", "Original"],
+        report
+            .content_diverged
+            .iter()
+            .map(|d| vec![path(&d.conflict.path), path(&d.original.path)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::NotLocalHeader),
+        &["File"],
+        report.not_local.iter().map(|p| vec![path(p)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::HardLinksHeader),
+        &["Link", "Original"],
+        report.hardlinked.iter().map(|(l, o)| vec![path(l), path(o)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::ProbableHeader),
+        &["Conflict", "Original"],
+        report.probable_duplicates.iter().map(|(c, o)| vec![path(c), path(o)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::MetadataHeader),
+        &["Conflict", "Original", "Differs"],
+        report.metadata_differs.iter().map(|m| {
+            let fields: Vec<&str> = m.fields.iter().map(|f| f.name()).collect();
+            vec![path(&m.conflict), path(&m.original), fields.join(", ")]
+        }),
+    );
+    md_table(
+        &mut out,
+        t(Text::SkippedHeader),
+        &["File", "Error"],
+        report.skipped.iter().map(|(p, e)| vec![path(p), md_cell(e)]),
+    );
+    md_table(
+        &mut out,
+        t(Text::SymlinksHeader),
+        &["Link", "Reason"],
+        report.symlink_skips.iter().map(|(p, r)| vec![path(p), md_cell(r)]),
+    );
+    out
+}
+
+/// A section header without its `===` decoration.
+fn heading(header: &str) -> &str {
+    header.trim_matches('=').trim()
+}
+
+/// Append a titled table, or nothing when there are no rows.
+fn md_table(out: &mut String, title: &str, columns: &[&str], rows: impl Iterator<Item = Vec<String>>) {
+    let mut rows = rows.peekable();
+    if rows.peek().is_none() {
+        return;
+    }
+    out.push_str(&format!("\n## {}\n\n", heading(title)));
+    out.push_str(&format!("| {} |\n", columns.join(" | ")));
+    out.push_str(&format!("|{}\n", "---|".repeat(columns.len())));
+    for row in rows {
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+}
+
+/// Text safe inside a table cell: pipes escaped, line breaks flattened.
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+// ============================================================================
+// HTML FORMAT
+// ============================================================================
+
+/// Styles for the HTML report.
+const HTML_STYLE: &str = "\
+body{font:14px -apple-system,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:.5em 0 1.5em}\
+th,td{border:1px solid #ddd;padding:4px 8px;text-align:left}\
+th{background:#f4f4f4;cursor:pointer;user-select:none}\
+details{margin:.3em 0}summary{cursor:pointer}\
+.warning{color:#a60}";
+
+/// Sorts a table by the clicked column, by `data-sort` when a cell has one.
+const HTML_SCRIPT: &str = "\
+document.querySelectorAll('table.sortable th').forEach((th,i)=>th.addEventListener('click',()=>{\
+const body=th.closest('table').tBodies[0];const asc=th.dataset.asc!=='1';th.dataset.asc=asc?'1':'0';\
+const key=r=>r.cells[i].dataset.sort??r.cells[i].textContent;\
+[...body.rows].sort((a,b)=>{const x=key(a),y=key(b);const n=x-y;\
+const d=isNaN(n)?x.localeCompare(y):n;return asc?d:-d}).forEach(r=>body.appendChild(r))}));";
+
+/// A self-contained page: summary, one collapsible entry per duplicate
+/// group, and sortable tables for the other categories. Paths link to
+/// their files with `file://` URLs.
+fn format_html(report: &ScanReport, locale: Locale) -> String {
+    let t = |key| text(locale, key);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>icloud-dedupe report</title>\n\
+         <style>{}</style>\n</head>\n<body>\n",
+        HTML_STYLE
+    );
+
+    out.push_str(&format!("<h2>{}</h2>\n", html_escape(heading(t(Text::SummaryHeader)))));
+    if let Some(reason) = &report.partial {
+        out.push_str(&format!(
+            "<p class=\"warning\"><b>{}:</b> {}</p>\n",
+            html_escape(t(Text::PartialScan)),
+            html_escape(reason)
+        ));
+    }
+    for warning in &report.warnings {
+        out.push_str(&format!(
+            "<p class=\"warning\"><b>{}:</b> {}</p>\n",
+            html_escape(t(Text::Warning)),
+            html_escape(warning)
+        ));
+    }
+
+    let total_duplicates: usize = report.confirmed_duplicates.iter().map(|g| g.duplicates.len()).sum();
+    out.push_str("<ul>\n");
+    let mut item = |key, value: String| {
+        out.push_str(&format!("<li><b>{}:</b> {}</li>\n", html_escape(t(key)), html_escape(&value)));
+    };
+    item(Text::DuplicateGroups, report.confirmed_duplicates.len().to_string());
+    item(Text::TotalDuplicates, total_duplicates.to_string());
+    item(Text::OrphanedConflicts, report.orphaned_conflicts.len().to_string());
+    item(Text::DivergedFiles, report.content_diverged.len().to_string());
+    item(Text::SpaceRecoverable, format_recoverable(report));
+    if !report.by_extension.is_empty() {
+        item(Text::ByExtension, extension_breakdown(report, locale));
+    }
+    out.push_str("</ul>\n");
+
+    if !report.confirmed_duplicates.is_empty() {
+        out.push_str(&format!("<h2>{}</h2>\n", html_escape(heading(t(Text::DuplicatesHeader)))));
+        for group in &report.confirmed_duplicates {
+            out.push_str(&format!(
+                "<details>\n<summary>{} ({}, {} each)</summary>\n<table class=\"sortable\">\n\
+                 <thead><tr><th>Duplicate</th><th>Size</th></tr></thead>\n<tbody>\n",
+                html_link(&group.original),
+                html_escape(&count(locale, group.duplicates.len(), Noun::ConfirmedDuplicate)),
+                format_size(group.size_bytes, BINARY)
+            ));
+            for dup in &group.duplicates {
+                out.push_str(&format!(
+                    "<tr><td>{}</td>{}</tr>\n",
+                    html_link(dup),
+                    html_size(group.size_bytes)
+                ));
+            }
+            out.push_str("</tbody>\n</table>\n</details>\n");
+        }
+    }
+
+    let path = |p: &std::path::Path| format!("<td>{}</td>", html_link(p));
+    let cell = |s: &str| format!("<td>{}</td>", html_escape(s));
+    for (header, rollup) in [
+        (Text::ByFolderHeader, &report.by_directory),
+        (Text::ByParentHeader, &report.by_parent),
+    ] {
+        html_table(
+            &mut out,
+            t(header),
+            &["Folder", "Files", "Recoverable"],
+            rollup.iter().map(|d| {
+                vec![
+                    path(&d.path),
+                    format!("<td data-sort=\"{0}\">{0}</td>", d.duplicates),
+                    html_size(d.bytes_recoverable),
+                ]
+            }),
+        );
+    }
+    html_table(
+        &mut out,
+        t(Text::OrphansHeader),
+        &["File"],
+        report.orphaned_conflicts.iter().map(|p| vec![path(p)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::DivergedHeader),
+        &["Conflict", "Original"],
+        report
+            .content_diverged
+            .iter()
+            .map(|d| vec![path(&d.conflict.path), path(&d.original.path)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::NotLocalHeader),
+        &["File"],
+        report.not_local.iter().map(|p| vec![path(p)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::HardLinksHeader),
+        &["Link", "Original"],
+        report.hardlinked.iter().map(|(l, o)| vec![path(l), path(o)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::ProbableHeader),
+        &["Conflict", "Original"],
+        report.probable_duplicates.iter().map(|(c, o)| vec![path(c), path(o)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::MetadataHeader),
+        &["Conflict", "Original", "Differs"],
+        report.metadata_differs.iter().map(|m| {
+            let fields: Vec<&str> = m.fields.iter().map(|f| f.name()).collect();
+            vec![path(&m.conflict), path(&m.original), cell(&fields.join(", "))]
+        }),
+    );
+    html_table(
+        &mut out,
+        t(Text::SkippedHeader),
+        &["File", "Error"],
+        report.skipped.iter().map(|(p, e)| vec![path(p), cell(e)]),
+    );
+    html_table(
+        &mut out,
+        t(Text::SymlinksHeader),
+        &["Link", "Reason"],
+        report.symlink_skips.iter().map(|(p, r)| vec![path(p), cell(r)]),
+    );
+
+    out.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", HTML_SCRIPT));
+    out
+}
+
+/// Append a titled sortable table of pre-rendered `<td>` cells, or nothing
+/// when there are no rows.
+fn html_table(out: &mut String, title: &str, columns: &[&str], rows: impl Iterator<Item = Vec<String>>) {
+    let mut rows = rows.peekable();
+    if rows.peek().is_none() {
+        return;
+    }
+    out.push_str(&format!("<h2>{}</h2>\n<table class=\"sortable\">\n<thead><tr>", html_escape(heading(title))));
+    for column in columns {
+        out.push_str(&format!("<th>{}</th>", html_escape(column)));
+    }
+    out.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        out.push_str(&format!("<tr>{}</tr>\n", row.concat()));
+    }
+    out.push_str("</tbody>\n</table>\n");
+}
+
+/// A size cell that sorts by its byte count.
+fn html_size(bytes: u64) -> String {
+    format!("<td data-sort=\"{}\">{}</td>", bytes, format_size(bytes, BINARY))
+}
+
+/// A path as a link to the file.
+fn html_link(path: &std::path::Path) -> String {
+    format!("<a href=\"{}\">{}</a>", html_escape(&file_url(path)), html_escape(&path.display().to_string()))
+}
+
+/// `file://` URL for an absolute path, percent-encoding all but unreserved
+/// characters and `/`.
+fn file_url(path: &std::path::Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// Text safe in HTML content and attribute values.
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ============================================================================
+// CSV FORMAT
+// ============================================================================
+
+/// One row per finding, for spreadsheets: `category,original,duplicate,size,hash`.
+///
+/// Orphans have no original; diverged pairs have no shared size or hash.
+fn format_csv(report: &ScanReport) -> String {
+    let mut out = String::from("category,original,duplicate,size,hash\n");
+    let mut row = |fields: [&str; 5]| {
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    };
+
+    for group in &report.confirmed_duplicates {
+        let original = group.original.to_string_lossy();
+        let size = group.size_bytes.to_string();
+        let hash = group.hash.to_hex();
+        for dup in &group.duplicates {
+            row(["duplicate", &original, &dup.to_string_lossy(), &size, &hash]);
+        }
+    }
+    for path in &report.orphaned_conflicts {
+        row(["orphan", "", &path.to_string_lossy(), "", ""]);
+    }
+    for pair in &report.content_diverged {
+        let size = pair.conflict.size.map_or(String::new(), |s| s.to_string());
+        let hash = pair.conflict.hash.as_ref().map_or(String::new(), |h| h.to_hex());
+        row([
+            "diverged",
+            &pair.original.path.to_string_lossy(),
+            &pair.conflict.path.to_string_lossy(),
+            &size,
+            &hash,
+        ]);
+    }
+    out
+}
+
+/// Quote a CSV field when it holds a comma, quote, or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -121,7 +1117,10 @@ fn format_json(report: &ScanReport) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ContentHash, DuplicateGroup};
+    use crate::types::{
+        ContentHash, DirectoryRollup, DuplicateGroup, ExtensionRollup, HashAlgorithm, ItemState, MetadataField, MetadataMismatch, ScanStats,
+        WalkSettings,
+    };
     use std::path::PathBuf;
 
     fn sample_hash() -> ContentHash {
@@ -137,22 +1136,64 @@ mod tests {
                     PathBuf::from("/docs/report Copy.txt"),
                     PathBuf::from("/docs/report Copy 2.txt"),
                 ],
+                size_bytes: 2048,
             }],
             orphaned_conflicts: vec![PathBuf::from("/old/orphan Copy.txt")],
-            content_diverged: vec![(
-                PathBuf::from("/work/draft 2.txt"),
-                PathBuf::from("/work/draft.txt"),
+            content_diverged: vec![DivergedPair {
+                conflict: DivergedFile {
+                    path: PathBuf::from("/work/draft 2.txt"),
+                    size: Some(12 * 1024),
+                    modified: Some(1_780_411_380), // 2026-06-02 14:43 UTC
+                    hash: None,
+                },
+                original: DivergedFile {
+                    path: PathBuf::from("/work/draft.txt"),
+                    size: Some(10 * 1024),
+                    modified: Some(1_780_131_600),
+                    hash: None,
+                },
+                ends_only: false,
+            }],
+            not_local: vec![PathBuf::from("/cloud/photo 2.heic")],
+            hardlinked: vec![(
+                PathBuf::from("/docs/notes 2.txt"),
+                PathBuf::from("/docs/notes.txt"),
             )],
+            probable_duplicates: vec![],
+            metadata_differs: vec![],
             bytes_recoverable: 1024 * 1024 * 5, // 5 MiB
+            keep_policy: KeepPolicy::OriginalName,
+            by_directory: vec![],
+            bytes_recoverable_physical: None,
             skipped: vec![(
                 PathBuf::from("/locked/file.txt"),
                 "Permission denied".to_string(),
             )],
+            symlink_skips: vec![(
+                PathBuf::from("/docs/loop"),
+                "symlink loop back to /docs".to_string(),
+            )],
+            warnings: vec!["/home/docs resolves to /icloud/docs (into iCloud)".to_string()],
+            partial: None,
+            stats: ScanStats::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            by_parent: vec![],
+            schema_version: 0,
+            by_extension: vec![],
+            unsynced: Vec::new(),
         }
     }
 
     // --- Human format tests ---
 
+    #[test]
+    fn human_format_starts_with_warnings() {
+        let report = sample_report();
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.starts_with("Warning: /home/docs resolves to /icloud/docs (into iCloud)\n"));
+    }
+
     #[test]
     fn human_format_includes_duplicates() {
         let report = sample_report();
@@ -164,6 +1205,15 @@ mod tests {
         assert!(output.contains("└─ /docs/report Copy 2.txt"));
     }
 
+    #[test]
+    fn human_format_notes_non_default_keep_policy() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("Keeping"));
+
+        report.keep_policy = KeepPolicy::NewestMtime;
+        assert!(format_report(&report, OutputFormat::Human).contains("Keeping the newest copy of each group"));
+    }
+
     #[test]
     fn human_format_includes_orphans() {
         let report = sample_report();
@@ -183,6 +1233,54 @@ mod tests {
         assert!(output.contains("≠"));
     }
 
+    #[test]
+    fn human_format_includes_not_local() {
+        let report = sample_report();
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Not Downloaded"));
+        assert!(output.contains("/cloud/photo 2.heic"));
+        assert!(output.contains("Not downloaded:     1"));
+    }
+
+    #[test]
+    fn human_format_includes_unsynced() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("=== Still Syncing"));
+
+        report.unsynced = vec![(PathBuf::from("/docs/report Copy.txt"), ItemState::Uploading)];
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.contains("=== Still Syncing"));
+        assert!(output.contains("/docs/report Copy.txt (uploading)"));
+
+        retain_categories(&mut report, &[ReportCategory::Orphans]);
+        assert!(report.unsynced.is_empty());
+    }
+
+    #[test]
+    fn recoverable_shows_actual_space_only_when_clones_share_blocks() {
+        let mut report = sample_report();
+        assert_eq!(format_recoverable(&report), "5 MiB");
+
+        report.bytes_recoverable_physical = Some(report.bytes_recoverable);
+        assert_eq!(format_recoverable(&report), "5 MiB");
+
+        report.bytes_recoverable_physical = Some(1024 * 1024);
+        assert_eq!(format_recoverable(&report), "5 MiB logical, 1 MiB actual");
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.contains("Space recoverable:  5 MiB logical, 1 MiB actual"));
+    }
+
+    #[test]
+    fn human_format_includes_hardlinks() {
+        let report = sample_report();
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Hard Links"));
+        assert!(output.contains("/docs/notes 2.txt = /docs/notes.txt"));
+        assert!(output.contains("Hard links:         1"));
+    }
+
     #[test]
     fn human_format_includes_skipped() {
         let report = sample_report();
@@ -193,6 +1291,16 @@ mod tests {
         assert!(output.contains("Permission denied"));
     }
 
+    #[test]
+    fn human_format_includes_symlink_skips() {
+        let report = sample_report();
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Skipped Symlinks ==="));
+        assert!(output.contains("/docs/loop - symlink loop back to /docs"));
+        assert!(output.contains("Skipped symlinks:   1"));
+    }
+
     #[test]
     fn human_format_includes_summary() {
         let report = sample_report();
@@ -206,6 +1314,14 @@ mod tests {
         assert!(output.contains("5 MiB")); // humansize output
     }
 
+    #[test]
+    fn human_format_follows_locale() {
+        let output = format_report_in(&sample_report(), OutputFormat::Human, Locale::French);
+        assert!(output.contains("=== Doublons confirmés ==="));
+        assert!(output.contains("=== Résumé ==="));
+        assert!(output.contains("Groupes de doublons: 1"));
+    }
+
     #[test]
     fn human_format_empty_report() {
         let report = ScanReport::default();
@@ -215,11 +1331,59 @@ mod tests {
         assert!(!output.contains("=== Confirmed Duplicates"));
         assert!(!output.contains("=== Orphaned"));
         assert!(!output.contains("=== Content Diverged"));
+        assert!(!output.contains("=== Not Downloaded"));
         assert!(!output.contains("=== Skipped"));
         assert!(output.contains("=== Summary ==="));
         assert!(output.contains("Duplicate groups:   0"));
     }
 
+    #[test]
+    fn eta_format_picks_largest_units() {
+        assert_eq!(format_eta(Duration::from_secs(42)), "42s");
+        assert_eq!(format_eta(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_eta(Duration::from_secs(4320)), "1h 12m");
+    }
+
+    #[test]
+    fn date_format_is_utc_calendar_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_780_358_400), "2026-06-02");
+    }
+
+    #[test]
+    fn delta_format_lists_changes_then_date() {
+        let delta = ScanDelta {
+            since: 1_780_358_400,
+            duplicate_groups: 3,
+            duplicate_files: 4,
+            orphaned: -1,
+            diverged: 0,
+            bytes_recoverable: 2 * 1024 * 1024,
+        };
+        assert_eq!(
+            format_delta(&delta),
+            vec![
+                "+3 new duplicate groups".to_string(),
+                "1 fewer orphaned conflict".to_string(),
+                "2 MiB more recoverable since 2026-06-02".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn delta_format_without_changes() {
+        let delta = ScanDelta {
+            since: 0,
+            duplicate_groups: 0,
+            duplicate_files: 0,
+            orphaned: 0,
+            diverged: 0,
+            bytes_recoverable: 0,
+        };
+        assert_eq!(format_delta(&delta), vec!["No changes since 1970-01-01".to_string()]);
+    }
+
     // --- JSON format tests ---
 
     #[test]
@@ -241,6 +1405,7 @@ mod tests {
         assert!(parsed["confirmed_duplicates"].is_array());
         assert!(parsed["orphaned_conflicts"].is_array());
         assert!(parsed["content_diverged"].is_array());
+        assert!(parsed["not_local"].is_array());
         assert!(parsed["bytes_recoverable"].is_number());
         assert!(parsed["skipped"].is_array());
     }
@@ -267,4 +1432,426 @@ mod tests {
         assert_eq!(parsed["confirmed_duplicates"].as_array().unwrap().len(), 0);
         assert_eq!(parsed["bytes_recoverable"], 0);
     }
+
+    #[test]
+    fn saved_json_report_renders_like_the_original() {
+        let report = sample_report();
+        let saved = format_report(&report, OutputFormat::Json);
+
+        let loaded = parse_report(&saved).unwrap();
+        assert_eq!(
+            format_report(&loaded, OutputFormat::Human),
+            format_report(&report, OutputFormat::Human)
+        );
+    }
+
+    #[test]
+    fn parse_report_tolerates_missing_fields() {
+        let loaded = parse_report(r#"{"bytes_recoverable": 7}"#).unwrap();
+        assert_eq!(loaded.bytes_recoverable, 7);
+        assert!(loaded.hardlinked.is_empty());
+    }
+
+    #[test]
+    fn json_reports_carry_their_schema_version() {
+        let saved = format_report(&ScanReport::default(), OutputFormat::Json);
+        let parsed: serde_json::Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(parsed["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(parse_report(&saved).unwrap().schema_version, REPORT_SCHEMA_VERSION);
+
+        // Reports saved before versions were recorded still load
+        assert_eq!(parse_report(r#"{"bytes_recoverable": 7}"#).unwrap().schema_version, 0);
+    }
+
+    #[test]
+    fn parse_report_refuses_newer_schemas() {
+        let newer = format!(r#"{{"schema_version": {}, "renamed": []}}"#, REPORT_SCHEMA_VERSION + 1);
+        let err = parse_report(&newer).unwrap_err();
+        assert!(err.contains("newer than this tool supports"), "{}", err);
+    }
+
+    #[test]
+    fn human_format_lists_folders_when_rolled_up() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("by Folder"));
+
+        report.by_directory = vec![DirectoryRollup {
+            path: PathBuf::from("/docs"),
+            duplicates: 2,
+            bytes_recoverable: 2048,
+        }];
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Duplicates by Folder ==="));
+        assert!(output.contains("2 KiB  /docs (2 files)"));
+    }
+
+    #[test]
+    fn human_format_lists_top_parent_directories() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("Parent Directories"));
+
+        report.by_parent = vec![DirectoryRollup {
+            path: PathBuf::from("/docs/2024/scans"),
+            duplicates: 3,
+            bytes_recoverable: 3072,
+        }];
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.contains("=== Top Parent Directories ==="));
+        assert!(output.contains("3 KiB  /docs/2024/scans (3 files)"));
+
+        let json: serde_json::Value = serde_json::from_str(&format_report(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(json["by_parent"][0]["path"], "/docs/2024/scans");
+        assert_eq!(json["by_parent"][0]["bytes_recoverable"], 3072);
+    }
+
+    #[test]
+    fn colored_human_format_marks_headers_keeps_and_removals() {
+        let report = sample_report();
+        let output = format_report_colored(&report, OutputFormat::Human, Locale::English, true);
+
+        assert!(output.contains("\x1b[1;36m=== Confirmed Duplicates ===\x1b[0m\n"));
+        assert!(output.contains("\x1b[32mOriginal:\x1b[0m /docs/report.txt\n"));
+        assert!(output.contains("  \x1b[31m└─\x1b[0m /docs/report Copy.txt\n"));
+        assert!(output.contains("\x1b[1;33m5 MiB\x1b[0m"));
+
+        assert!(!format_report(&report, OutputFormat::Human).contains('\x1b'));
+        assert!(!format_report_colored(&report, OutputFormat::Markdown, Locale::English, true).contains('\x1b'));
+    }
+
+    #[test]
+    fn retain_categories_limits_every_format() {
+        let mut report = sample_report();
+        retain_categories(&mut report, &[ReportCategory::Orphans, ReportCategory::Diverged]);
+
+        assert!(report.confirmed_duplicates.is_empty());
+        assert_eq!(report.bytes_recoverable, 0);
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.orphaned_conflicts.len(), 1);
+        assert_eq!(report.content_diverged.len(), 1);
+        assert!(!report.warnings.is_empty());
+
+        let human = format_report(&report, OutputFormat::Human);
+        assert!(!human.contains("Confirmed Duplicates"));
+        assert!(human.contains("Orphaned Conflicts"));
+        let csv = format_report(&report, OutputFormat::Csv);
+        assert!(csv.lines().skip(1).all(|l| l.starts_with("orphan,") || l.starts_with("diverged,")));
+
+        let mut all = sample_report();
+        retain_categories(&mut all, &[]);
+        assert_eq!(all.confirmed_duplicates.len(), 1);
+    }
+
+    #[test]
+    fn summary_breaks_space_down_by_extension() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("By file type"));
+
+        let ext = |extension: &str, bytes_recoverable| ExtensionRollup {
+            extension: extension.to_string(),
+            duplicates: 1,
+            bytes_recoverable,
+        };
+        report.by_extension = vec![
+            ext(".mov", 18 << 30),
+            ext(".psd", 4 << 30),
+            ext("", 3 << 20),
+            ext(".jpg", 2 << 20),
+            ext(".pdf", 1 << 20),
+            ext(".txt", 10),
+        ];
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.contains(
+            "By file type:       .mov: 18 GiB, .psd: 4 GiB, (no extension): 3 MiB, .jpg: 2 MiB, .pdf: 1 MiB, …\n"
+        ));
+
+        let parsed: serde_json::Value = serde_json::from_str(&format_report(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(parsed["by_extension"].as_array().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn summary_format_leaves_out_listings() {
+        let mut report = sample_report();
+        report.partial = Some("stopped after 10 files (file budget)".to_string());
+        let output = format_report(&report, OutputFormat::Summary);
+
+        assert!(output.starts_with("Partial scan: stopped after 10 files (file budget)\nWarning: "));
+        assert!(output.contains("(into iCloud)\n\n=== Summary ===\n"));
+        assert!(output.contains("Duplicate groups:   1"));
+        assert!(!output.contains("Confirmed Duplicates"));
+        assert!(!output.contains("/docs/"));
+    }
+
+    #[test]
+    fn human_format_flags_partial_scans() {
+        let mut report = sample_report();
+        report.partial = Some("stopped after 10 files (file budget)".to_string());
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.starts_with("Partial scan: stopped after 10 files (file budget)\n"));
+    }
+
+    #[test]
+    fn human_format_lists_metadata_mismatches_with_fields() {
+        let mut report = sample_report();
+        report.metadata_differs = vec![MetadataMismatch {
+            conflict: PathBuf::from("/docs/photo 2.jpg"),
+            original: PathBuf::from("/docs/photo.jpg"),
+            fields: vec![MetadataField::Tags, MetadataField::Created],
+        }];
+
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Metadata Differs (same content, kept) ==="));
+        assert!(output.contains("  /docs/photo 2.jpg = /docs/photo.jpg (tags, created)"));
+        assert!(output.contains("Metadata differs:   1"));
+    }
+
+    #[test]
+    fn human_format_lists_probable_duplicates_apart() {
+        let mut report = sample_report();
+        report.probable_duplicates = vec![(
+            PathBuf::from("/docs/plan 2.txt"),
+            PathBuf::from("/docs/plan.txt"),
+        )];
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.contains("=== Probable Duplicates (same size and date, not hashed) ==="));
+        assert!(output.contains("  /docs/plan 2.txt ≈ /docs/plan.txt"));
+        assert!(output.contains("Probable matches:   1"));
+    }
+
+    #[test]
+    fn human_format_ends_with_scan_stats() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("Scanned"));
+
+        report.stats = ScanStats {
+            files_scanned: 182_000,
+            duration_ms: 94_000,
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.ends_with("Scanned 182000 files in 1m 34s\n"));
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_report(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(parsed["stats"]["files_scanned"], 182_000);
+    }
+
+    #[test]
+    fn human_format_opens_with_scan_metadata() {
+        let mut report = sample_report();
+        assert!(!format_report(&report, OutputFormat::Human).contains("Scan started"));
+
+        report.stats = ScanStats {
+            version: "0.5.0".to_string(),
+            started_at: 1_780_411_380, // 2026-06-02 14:43 UTC
+            duration_ms: 3_200,
+            roots: vec![PathBuf::from("/docs"), PathBuf::from("/desk")],
+            walk: Some(WalkSettings {
+                max_depth: Some(4),
+                follow_symlinks: false,
+                include_hidden: true,
+                ignore: vec![".git".to_string(), "node_modules".to_string()],
+            }),
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Human);
+        assert!(output.starts_with(
+            "Scan started 2026-06-02T14:43:00Z, took 3.2s (icloud-dedupe 0.5.0)\n\
+             Roots: /docs, /desk\n\
+             Depth 4, hidden files included, symlinks not followed\n\
+             Ignoring: .git, node_modules\n\n"
+        ));
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_report(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(parsed["stats"]["started_at"], 1_780_411_380u64);
+        assert_eq!(parsed["stats"]["walk"]["max_depth"], 4);
+        assert_eq!(parsed["stats"]["walk"]["ignore"][1], "node_modules");
+    }
+
+    #[test]
+    fn human_format_shows_hashing_throughput_and_phases() {
+        let mut report = sample_report();
+        report.stats = ScanStats {
+            files_scanned: 500,
+            duration_ms: 12_000,
+            files_hashed: 40,
+            bytes_hashed: 3 * 1024 * 1024 * 1024,
+            hash_ms: 40_000,
+            walk_ms: 1_500,
+            verify_ms: 10_000,
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Human);
+
+        assert!(output.ends_with(
+            "Scanned 500 files in 12s\n\
+             Hashed 40 files (3 GiB) at 307.20 MiB/s, 76.80 MiB/s per thread\n\
+             Walk 1.5s, verify 10.0s\n"
+        ));
+    }
+
+    #[test]
+    fn parse_report_rejects_garbage() {
+        assert!(parse_report("not json").unwrap_err().contains("Not a valid scan report"));
+    }
+
+    // --- Markdown format tests ---
+
+    #[test]
+    fn markdown_format_has_summary_and_tables() {
+        let output = format_report(&sample_report(), OutputFormat::Markdown);
+
+        assert!(output.starts_with("## Summary\n\n> **Warning:** /home/docs resolves"));
+        assert!(output.contains("- **Duplicate groups:** 1\n"));
+        assert!(output.contains(
+            "## Confirmed Duplicates\n\n\
+             | Original | Duplicate | Size |\n\
+             |---|---|---|\n\
+             | /docs/report.txt | /docs/report Copy.txt | 2 KiB |\n"
+        ));
+        assert!(output.contains("| /work/draft 2.txt | /work/draft.txt |\n"));
+        assert!(output.contains("| /locked/file.txt | Permission denied |\n"));
+        // Empty categories get no table
+        assert!(!output.contains("Probable"));
+    }
+
+    #[test]
+    fn markdown_format_escapes_pipes() {
+        let report = ScanReport {
+            orphaned_conflicts: vec![PathBuf::from("/docs/a|b 2.txt")],
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Markdown);
+        assert!(output.contains("| /docs/a\\|b 2.txt |\n"));
+    }
+
+    // --- HTML format tests ---
+
+    #[test]
+    fn html_format_is_self_contained() {
+        let output = format_report(&sample_report(), OutputFormat::Html);
+
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.trim_end().ends_with("</html>"));
+        assert!(!output.contains("http://") && !output.contains("https://") && !output.contains(" src="));
+        assert_eq!(output.matches("<details>").count(), 1);
+        assert!(output.contains("<table class=\"sortable\">"));
+        assert!(output.contains("<td data-sort=\"2048\">2 KiB</td>"));
+    }
+
+    #[test]
+    fn html_format_links_and_escapes_paths() {
+        let report = ScanReport {
+            orphaned_conflicts: vec![PathBuf::from("/docs/Q&A <draft> 2.txt")],
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Html);
+        assert!(output.contains(
+            "<a href=\"file:///docs/Q%26A%20%3Cdraft%3E%202.txt\">/docs/Q&amp;A &lt;draft&gt; 2.txt</a>"
+        ));
+    }
+
+    // --- JSONL format tests ---
+
+    #[test]
+    fn jsonl_format_has_a_record_per_finding_then_summary() {
+        let output = format_report(&sample_report(), OutputFormat::Jsonl);
+        let records: Vec<serde_json::Value> =
+            output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let kinds: Vec<&str> = records.iter().map(|r| r["kind"].as_str().unwrap()).collect();
+
+        assert_eq!(
+            kinds,
+            [
+                "warning",
+                "duplicate",
+                "duplicate",
+                "orphan",
+                "diverged",
+                "not_local",
+                "hardlink",
+                "skipped",
+                "symlink_skip",
+                "summary"
+            ]
+        );
+        assert_eq!(records[1]["duplicate"], "/docs/report Copy.txt");
+        assert_eq!(records[1]["size_bytes"], 2048);
+        assert_eq!(records[9]["duplicates"], 2);
+        assert_eq!(records[9]["bytes_recoverable"], 5 * 1024 * 1024);
+    }
+
+    #[test]
+    fn jsonl_records_skip_the_summary() {
+        let report = ScanReport {
+            orphaned_conflicts: vec![PathBuf::from("/a 2.txt")],
+            ..Default::default()
+        };
+        assert_eq!(format_jsonl_records(&report), "{\"kind\":\"orphan\",\"path\":\"/a 2.txt\"}\n");
+    }
+
+    // --- CSV format tests ---
+
+    #[test]
+    fn human_format_details_both_sides_of_diverged_files() {
+        let output = format_report(&sample_report(), OutputFormat::Human);
+        assert!(output.contains(
+            "  /work/draft 2.txt ≠ /work/draft.txt\n\
+             \x20   Conflict:     12 KiB  2026-06-02T14:43:00Z  -\n\
+             \x20   Original:     10 KiB  2026-05-30T09:00:00Z  -\n"
+        ));
+        assert_eq!(
+            diverged_summary(&sample_report().content_diverged[0]),
+            "12 KiB vs 10 KiB, conflict newer"
+        );
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_report(&sample_report(), OutputFormat::Json)).unwrap();
+        let diverged = &parsed["content_diverged"][0];
+        assert_eq!(diverged["conflict"]["size"], 12 * 1024);
+        assert_eq!(diverged["original"]["modified"], 1_780_131_600u64);
+    }
+
+    #[test]
+    fn parse_report_reads_diverged_path_pairs_from_older_reports() {
+        let loaded = parse_report(
+            r#"{"schema_version": 1, "content_diverged": [["/w/draft 2.txt", "/w/draft.txt"]]}"#,
+        )
+        .unwrap();
+        let pair = &loaded.content_diverged[0];
+        assert_eq!(pair.conflict.path, PathBuf::from("/w/draft 2.txt"));
+        assert_eq!(pair.original.path, PathBuf::from("/w/draft.txt"));
+        assert_eq!(pair.conflict.size, None);
+    }
+
+    #[test]
+    fn csv_format_has_one_row_per_finding() {
+        let output = format_report(&sample_report(), OutputFormat::Csv);
+        let lines: Vec<&str> = output.lines().collect();
+        let hash = sample_hash().to_hex();
+
+        assert_eq!(lines[0], "category,original,duplicate,size,hash");
+        assert_eq!(
+            lines[1],
+            format!("duplicate,/docs/report.txt,/docs/report Copy.txt,2048,{}", hash)
+        );
+        assert!(lines[2].starts_with("duplicate,/docs/report.txt,/docs/report Copy 2.txt,"));
+        assert_eq!(lines[3], "orphan,,/old/orphan Copy.txt,,");
+        assert_eq!(lines[4], "diverged,/work/draft.txt,/work/draft 2.txt,12288,");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn csv_format_quotes_awkward_paths() {
+        let report = ScanReport {
+            orphaned_conflicts: vec![PathBuf::from("/docs/Q1, \"final\" 2.txt")],
+            ..Default::default()
+        };
+        let output = format_report(&report, OutputFormat::Csv);
+        assert_eq!(output.lines().nth(1), Some("orphan,,\"/docs/Q1, \"\"final\"\" 2.txt\",,"));
+    }
 }
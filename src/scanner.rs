@@ -2,17 +2,36 @@
 //!
 //! Orchestrates pattern detection and hash verification.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use walkdir::WalkDir;
+use rayon::prelude::*;
+use walkdir::{DirEntry, WalkDir};
 
-use crate::hash::{files_match, hash_file};
+use crate::cancel::{cancelled_error, CancelToken};
+use crate::hash::{
+    files_match, hash_ends, hash_file_tuned, hash_file_with_retry, hash_tree_with, is_not_local_error,
+    tree_size, DEFAULT_PARALLEL_HASH_MIN_SIZE, PARTIAL_HASH_SPAN,
+};
+use crate::hash_cache::{configured_store, hash_through, HashStore};
+use crate::index::{load_index, save_index, CandidateStamps, ScanIndex};
 use crate::pattern::{derive_original, detect_pattern};
+use crate::quarantine::generate_session_id;
+use crate::platform::{
+    extended_attributes, file_id, is_bundle_name, is_case_sensitive_volume, is_dataless, is_locked,
+    is_not_local, placeholder_target, private_size, request_download, sync_state, BOOKKEEPING_XATTRS,
+    FINDER_TAGS_XATTR,
+};
 use crate::types::{
-    ConflictCandidate, DuplicateGroup, FileKind, ScanConfig, ScanReport, VerificationResult,
+    ConflictCandidate, ContentHash, DirectoryRollup, Divergence, DivergedPair, DuplicateGroup, ExtensionRollup,
+    FileKind,
+    HashAlgorithm, ItemState, KeepPolicy, MetadataField, MetadataMismatch, ReadTuning, RetryPolicy, ScanConfig, ScanProgress,
+    ScanReport, ScanStats, VerificationLevel, VerificationResult, WalkSettings,
 };
 #[cfg(test)]
 use crate::types::ConflictPattern;
@@ -56,19 +75,17 @@ pub fn normalize_path(path: &Path) -> NormalizedPath {
     // Check for unexpanded tilde
     let needs_tilde_expansion = normalized.starts_with("~/") || normalized == "~";
 
-    if needs_tilde_expansion {
-        if let Some(home) = dirs::home_dir() {
-            warnings.push(format!(
-                "Expanded '~' to '{}' (shell didn't expand it due to quoting)",
-                home.display()
-            ));
-            let path = if normalized == "~" {
-                home
-            } else {
-                home.join(&normalized[2..])
-            };
-            return NormalizedPath { path, warnings };
-        }
+    if needs_tilde_expansion && let Some(home) = dirs::home_dir() {
+        warnings.push(format!(
+            "Expanded '~' to '{}' (shell didn't expand it due to quoting)",
+            home.display()
+        ));
+        let path = if normalized == "~" {
+            home
+        } else {
+            home.join(&normalized[2..])
+        };
+        return NormalizedPath { path, warnings };
     }
 
     NormalizedPath {
@@ -77,35 +94,254 @@ pub fn normalize_path(path: &Path) -> NormalizedPath {
     }
 }
 
+/// Remove duplicate and overlapping scan roots (pure function).
+///
+/// A root nested under another root would be walked twice, so it is dropped.
+/// Comparison is component-wise on the paths as given — callers should
+/// normalize first. Order of the surviving roots is preserved.
+pub fn dedupe_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut kept: Vec<PathBuf> = Vec::new();
+
+    for root in roots {
+        if kept.iter().any(|k| root.starts_with(k)) {
+            continue;
+        }
+        // A new, broader root supersedes any narrower ones already kept
+        kept.retain(|k| !k.starts_with(root));
+        kept.push(root.clone());
+    }
+
+    kept
+}
+
+/// A scan root as given and as it really is on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRoot {
+    /// The path as the user gave it (normalized, made absolute).
+    pub given: PathBuf,
+    /// The path with every symlink resolved.
+    pub real: PathBuf,
+}
+
+impl ResolvedRoot {
+    /// True if resolving symlinks moved the root somewhere else.
+    pub fn is_redirected(&self) -> bool {
+        self.given != self.real
+    }
+}
+
+/// Resolve each root's symlinks. Roots that can't be resolved (e.g. they
+/// don't exist yet) are kept as given.
+pub fn resolve_roots(roots: &[PathBuf]) -> Vec<ResolvedRoot> {
+    roots
+        .iter()
+        .map(|root| {
+            let given = std::path::absolute(root).unwrap_or_else(|_| root.clone());
+            let real = given.canonicalize().unwrap_or_else(|_| given.clone());
+            ResolvedRoot { given, real }
+        })
+        .collect()
+}
+
+/// Like [`dedupe_roots`], but compares roots by where they really are, so a
+/// relative or symlinked spelling of a root already covered is dropped too.
+/// Returns the surviving roots as given.
+pub fn dedupe_resolved_roots(roots: &[ResolvedRoot]) -> Vec<PathBuf> {
+    let mut kept: Vec<&ResolvedRoot> = Vec::new();
+
+    for root in roots {
+        if kept.iter().any(|k| root.real.starts_with(&k.real)) {
+            continue;
+        }
+        kept.retain(|k| !k.real.starts_with(&root.real));
+        kept.push(root);
+    }
+
+    kept.into_iter().map(|r| r.given.clone()).collect()
+}
+
+/// Paths already seen, compared by where they really are.
+///
+/// Overlapping roots reach the same file under two spellings; this keeps
+/// it from being verified and reported twice.
+#[derive(Debug, Default)]
+pub(crate) struct SeenPaths {
+    real: HashSet<PathBuf>,
+}
+
+impl SeenPaths {
+    /// True the first time a file is seen. Paths that can't be resolved
+    /// (placeholders' logical names) are compared as given.
+    pub(crate) fn first_visit(&mut self, path: &Path) -> bool {
+        let real = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.real.insert(real)
+    }
+}
+
+/// Warnings about roots that don't live where they appear to (pure function).
+///
+/// Flags roots that are symlinks into or out of the iCloud container
+/// (e.g. `~/Documents` with Desktop & Documents sync on), and roots that
+/// overlap on disk once resolved, which would report the same files twice.
+/// `icloud_container` should itself be resolved.
+pub fn root_warnings(roots: &[ResolvedRoot], icloud_container: Option<&Path>) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for root in roots.iter().filter(|r| r.is_redirected()) {
+        let in_icloud = |p: &Path| icloud_container.is_some_and(|c| p.starts_with(c));
+        let direction = match (in_icloud(&root.given), in_icloud(&root.real)) {
+            (false, true) => " (into iCloud)",
+            (true, false) => " (out of iCloud; files there are not synced)",
+            _ => "",
+        };
+        warnings.push(format!(
+            "{} resolves to {}{}",
+            root.given.display(),
+            root.real.display(),
+            direction
+        ));
+    }
+
+    for (i, a) in roots.iter().enumerate() {
+        for b in &roots[i + 1..] {
+            if a.real.starts_with(&b.real) || b.real.starts_with(&a.real) {
+                warnings.push(format!(
+                    "{} and {} overlap on disk; files may be reported twice",
+                    a.given.display(),
+                    b.given.display()
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
 // ============================================================================
 // PUBLIC API
 // ============================================================================
 
+/// Output of the discovery phase.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    /// Files matching a conflict pattern.
+    pub candidates: Vec<ConflictCandidate>,
+    /// Paths not walked because of symlinks (path, reason).
+    pub symlink_skips: Vec<(PathBuf, String)>,
+}
+
+/// Candidates verified per batch by [`scan`].
+///
+/// Large enough to keep every core busy hashing, small enough that a tree
+/// with millions of conflicts never holds them all at once.
+pub const DEFAULT_BATCH_SIZE: usize = 4096;
+
 /// Scan directories for iCloud conflict duplicates.
 ///
 /// Walks the directory tree, identifies conflict-patterned files,
 /// verifies originals exist, and validates content via hashing.
+/// Candidates are verified in batches of [`DEFAULT_BATCH_SIZE`] as the walk
+/// finds them (see [`scan_streaming`]).
 ///
 /// # Errors
-/// Returns an error if a root directory cannot be read.
+/// Returns an error if a root directory cannot be read, or `Interrupted`
+/// if the scan was cancelled.
 pub fn scan(config: &ScanConfig) -> io::Result<ScanReport> {
-    let mut report = ScanReport::default();
+    scan_streaming(config, DEFAULT_BATCH_SIZE, |_| {})
+}
+
+/// Scan, verifying candidates in batches of `batch_size` while the walk
+/// runs and folding each batch into the report.
+///
+/// Memory stays bounded by the batch and the report instead of growing
+/// with every candidate in the tree. The trade-off is progress: totals
+/// only cover what has been found so far, so there is no overall ETA.
+/// `on_progress` gets discovery counts after each file and verification
+/// counts after each candidate.
+///
+/// # Errors
+/// Returns an error if a root directory cannot be read, or `Interrupted`
+/// if the scan was cancelled.
+pub fn scan_streaming<F>(config: &ScanConfig, batch_size: usize, on_progress: F) -> io::Result<ScanReport>
+where
+    F: Fn(&ScanProgress) + Sync,
+{
+    scan_streaming_with(config, batch_size, on_progress, |_| {})
+}
+
+/// Like [`scan_streaming`], handing each verified batch's findings to
+/// `on_batch` as soon as they're known.
+///
+/// Batches are as verified: duplicates of one original may arrive in
+/// several batches, and groups are only merged across case variants and
+/// chains in the final report.
+pub fn scan_streaming_with<F, B>(
+    config: &ScanConfig,
+    batch_size: usize,
+    on_progress: F,
+    mut on_batch: B,
+) -> io::Result<ScanReport>
+where
+    F: Fn(&ScanProgress) + Sync,
+    B: FnMut(&ScanReport),
+{
+    let batch_size = batch_size.max(1);
+    let mut builder = ReportBuilder::default();
+    let mut verifier = Verifier::new(config);
+    let mut batch = Vec::with_capacity(batch_size.min(DEFAULT_BATCH_SIZE));
+    let mut seen = SeenPaths::default();
+    let mut symlink_skips = Vec::new();
+    let started = Instant::now();
+    let mut progress = ScanProgress::default();
+
+    let mut verify = |batch: &mut Vec<ConflictCandidate>, files_scanned: usize| {
+        let results = verifier.verify_batch(batch, |p| {
+            on_progress(&ScanProgress {
+                files_scanned,
+                ..*p
+            })
+        });
+        on_batch(&builder.add_batch(results));
+        batch.clear();
+    };
 
-    for root in &config.roots {
-        let normalized = normalize_path(root);
-        let mut walker = WalkDir::new(&normalized.path);
+    for walked in walk_roots(config) {
+        let entry = match walked {
+            Walked::Entry(entry) => entry,
+            Walked::SymlinkSkip(path, reason) => {
+                symlink_skips.push((path, reason));
+                continue;
+            }
+            Walked::Error(_) => continue,
+        };
+        if !is_file_like(&entry) {
+            continue;
+        }
 
-        if let Some(max_depth) = config.max_depth {
-            walker = walker.max_depth(max_depth);
+        progress.files_scanned += 1;
+        progress.elapsed = started.elapsed();
+        if let Some(candidate) = candidate_for_path(entry.path(), config)
+            && seen.first_visit(&candidate.path)
+        {
+            progress.candidates_found += 1;
+            batch.push(candidate);
         }
+        on_progress(&progress);
 
-        if !config.follow_symlinks {
-            walker = walker.follow_links(false);
+        if batch.len() >= batch_size {
+            verify(&mut batch, progress.files_scanned);
         }
+    }
+    verify(&mut batch, progress.files_scanned);
+    verifier.finish();
 
-        scan_walker(walker, &mut report, config)?;
+    if config.cancel.is_cancelled() {
+        return Err(cancelled_error());
     }
 
+    let mut report = builder.finish();
+    report.symlink_skips = symlink_skips;
+    finish_report(&mut report, config);
     Ok(report)
 }
 
@@ -138,84 +374,214 @@ pub fn find_candidates(config: &ScanConfig) -> io::Result<Vec<ConflictCandidate>
 /// The callback receives (files_scanned, candidates_found) after each file.
 pub fn find_candidates_with_progress<F>(
     config: &ScanConfig,
-    mut on_progress: F,
+    on_progress: F,
 ) -> io::Result<Vec<ConflictCandidate>>
 where
     F: FnMut(usize, usize),
 {
-    let mut candidates = Vec::new();
-    let mut files_scanned: usize = 0;
-
-    for root in &config.roots {
-        let normalized = normalize_path(root);
-        let mut walker = WalkDir::new(&normalized.path);
-
-        if let Some(max_depth) = config.max_depth {
-            walker = walker.max_depth(max_depth);
-        }
-
-        if !config.follow_symlinks {
-            walker = walker.follow_links(false);
-        }
-
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
+    let mut on_progress = on_progress;
+    discover(config, |p| on_progress(p.files_scanned, p.candidates_found)).map(|d| d.candidates)
+}
 
-            // Skip directories
-            if !path.is_file() {
+/// Like [`find_candidates_with_progress`], also returning the paths the
+/// walk didn't follow because of symlinks, for [`ScanReport::symlink_skips`].
+///
+/// The callback receives the discovery fields of [`ScanProgress`] after
+/// each file.
+pub fn discover<F>(config: &ScanConfig, mut on_progress: F) -> io::Result<Discovery>
+where
+    F: FnMut(&ScanProgress),
+{
+    let mut candidates = Vec::new();
+    let mut symlink_skips = Vec::new();
+    let mut seen = SeenPaths::default();
+    let started = Instant::now();
+    let mut progress = ScanProgress::default();
+
+    for walked in walk_roots(config) {
+        let entry = match walked {
+            Walked::Entry(entry) => entry,
+            Walked::SymlinkSkip(path, reason) => {
+                symlink_skips.push((path, reason));
                 continue;
             }
+            Walked::Error(_) => continue,
+        };
+        let path = entry.path();
 
-            files_scanned += 1;
+        // Skip directories (bundles count as files)
+        if !is_file_like(&entry) {
+            continue;
+        }
 
-            // Skip hidden files if configured
-            let filename = match path.file_name().and_then(|s| s.to_str()) {
-                Some(name) => name,
-                None => continue,
-            };
+        progress.files_scanned += 1;
 
-            if !config.include_hidden && filename.starts_with('.') {
-                on_progress(files_scanned, candidates.len());
-                continue;
-            }
+        if let Some(candidate) = candidate_for_path(path, config)
+            && seen.first_visit(&candidate.path)
+        {
+            progress.candidates_found += 1;
+            progress.bytes_discovered += candidate_size(&candidate);
+            candidates.push(candidate);
+        }
 
-            // Check for conflict pattern
-            if let Some(pattern) = detect_pattern(filename) {
-                let presumed_original = derive_original(path, &pattern);
-                let kind = if path.is_dir() {
-                    FileKind::Bundle
-                } else {
-                    FileKind::Regular
-                };
+        progress.elapsed = started.elapsed();
+        on_progress(&progress);
+    }
 
-                candidates.push(ConflictCandidate {
-                    path: path.to_path_buf(),
-                    pattern,
-                    presumed_original,
-                    kind,
-                });
-            }
+    Ok(Discovery {
+        candidates,
+        symlink_skips,
+    })
+}
 
-            on_progress(files_scanned, candidates.len());
+/// Stream conflict candidates as they are discovered (no hash verification).
+///
+/// Lazy counterpart of [`find_candidates`]: nothing is buffered, so callers
+/// can verify (or discard) each candidate while the walk is still running.
+/// Directory entries that cannot be read are yielded as errors; callers
+/// decide whether to skip them or stop.
+pub fn candidates_iter(
+    config: &ScanConfig,
+) -> impl Iterator<Item = io::Result<ConflictCandidate>> + '_ {
+    let mut seen = SeenPaths::default();
+    walk_roots(config).filter_map(move |walked| match walked {
+        Walked::Entry(entry) => {
+            if !is_file_like(&entry) {
+                return None;
+            }
+            candidate_for_path(entry.path(), config)
+                .filter(|c| seen.first_visit(&c.path))
+                .map(Ok)
         }
-    }
-
-    Ok(candidates)
+        Walked::SymlinkSkip(path, reason) => Some(Err(io::Error::other(format!(
+            "Skipped {}: {}",
+            path.display(),
+            reason
+        )))),
+        Walked::Error(e) => Some(Err(e.into())),
+    })
 }
 
 /// Verify a single conflict candidate against its presumed original.
 ///
 /// Checks:
-/// 1. Does the original exist?
-/// 2. Are both regular files (not bundles)?
-/// 3. Do contents match (via hash)?
+/// 1. Are both downloaded? (If not, nothing is read.)
+/// 2. Does the original exist?
+/// 3. Are both regular files? (Bundles are compared with [`hash_tree_with`].)
+/// 4. Are they hard links to one file? (If so, nothing to recover.)
+/// 5. Do sizes match? (If not, no hashing needed.)
+/// 6. Do contents match (via hash)?
+///
+/// Reads that fail transiently are retried per [`RetryPolicy::default`].
 ///
 /// # Errors
 /// Returns an error if files cannot be read.
 pub fn verify_candidate(candidate: &ConflictCandidate) -> io::Result<VerificationResult> {
+    verify_with(candidate, VerificationLevel::Strict, HashAlgorithm::Blake3, default_hash)
+}
+
+/// Hash with default settings, for the config-free verification helpers.
+fn default_hash(path: &Path) -> io::Result<ContentHash> {
+    hash_file_with_retry(
+        path,
+        HashAlgorithm::Blake3,
+        ReadTuning {
+            parallel_min_size: Some(DEFAULT_PARALLEL_HASH_MIN_SIZE),
+            ..ReadTuning::default()
+        },
+        &RetryPolicy::default(),
+    )
+}
+
+/// Verify every conflict copy of one original, hashing the original once.
+///
+/// Candidates are checked as by [`verify_candidate`], in parallel; results
+/// come back in candidate order. Candidates of other originals are
+/// verified too, just without the shared hash.
+pub fn verify_group(
+    original: &Path,
+    candidates: &[ConflictCandidate],
+) -> Vec<(PathBuf, io::Result<VerificationResult>)> {
+    let shared = SharedHashes::of([original]);
+    candidates
+        .par_iter()
+        .map(|c| {
+            let result = verify_with(c, VerificationLevel::Strict, HashAlgorithm::Blake3, |path| {
+                shared.hash_with(path, default_hash)
+            });
+            (c.path.clone(), result)
+        })
+        .collect()
+}
+
+/// Hashes of originals, each computed at most once however many conflict
+/// copies are compared against it.
+///
+/// Errors are kept as kind and message, since `io::Error` can't be cloned.
+struct SharedHashes {
+    hashes: HashMap<PathBuf, OnceLock<SharedHash>>,
+}
+
+type SharedHash = Result<ContentHash, (io::ErrorKind, String)>;
+
+impl SharedHashes {
+    fn of<'p>(originals: impl IntoIterator<Item = &'p Path>) -> Self {
+        Self {
+            hashes: originals
+                .into_iter()
+                .map(|p| (p.to_path_buf(), OnceLock::new()))
+                .collect(),
+        }
+    }
+
+    /// The shared hash for an original, or `read(path)` for anything else.
+    fn hash_with<R>(&self, path: &Path, read: R) -> io::Result<ContentHash>
+    where
+        R: FnOnce(&Path) -> io::Result<ContentHash>,
+    {
+        let Some(slot) = self.hashes.get(path) else {
+            return read(path);
+        };
+        slot.get_or_init(|| read(path).map_err(|e| (e.kind(), e.to_string())))
+            .clone()
+            .map_err(|(kind, message)| io::Error::new(kind, message))
+    }
+}
+
+/// Like [`verify_candidate`], but hashes through a [`HashStore`].
+pub fn verify_candidate_cached(
+    candidate: &ConflictCandidate,
+    store: &dyn HashStore,
+) -> io::Result<VerificationResult> {
+    verify_with(candidate, VerificationLevel::Strict, HashAlgorithm::Blake3, |path| {
+        hash_through(store, path, HashAlgorithm::Blake3, default_hash)
+    })
+}
+
+fn verify_with<H>(
+    candidate: &ConflictCandidate,
+    level: VerificationLevel,
+    algorithm: HashAlgorithm,
+    hash: H,
+) -> io::Result<VerificationResult>
+where
+    H: Fn(&Path) -> io::Result<ContentHash>,
+{
     let original = &candidate.presumed_original;
     let conflict = &candidate.path;
 
+    // Reading a placeholder would make iCloud download it: leave it alone
+    if candidate.kind == FileKind::CloudPlaceholder || is_not_local(original) {
+        return Ok(VerificationResult::NotLocal {
+            path: conflict.clone(),
+            presumed_original: original.clone(),
+        });
+    }
+
+    if candidate.kind == FileKind::Bundle {
+        return verify_bundle(candidate, algorithm);
+    }
+
     // Check if original exists and is a regular file
     if !original.exists() || !original.is_file() {
         return Ok(VerificationResult::OrphanedConflict {
@@ -224,9 +590,57 @@ pub fn verify_candidate(candidate: &ConflictCandidate) -> io::Result<Verificatio
         });
     }
 
-    // Hash both files
-    let original_hash = hash_file(original)?;
-    let conflict_hash = hash_file(conflict)?;
+    // Another name for the same file: nothing to hash, nothing to recover
+    let original_meta = fs::metadata(original)?;
+    let conflict_meta = fs::metadata(conflict)?;
+    if same_file(&original_meta, &conflict_meta) {
+        return Ok(VerificationResult::Hardlinked {
+            path: conflict.clone(),
+            original: original.clone(),
+        });
+    }
+
+    // Different sizes can never be duplicates: skip hashing entirely
+    let original_size = original_meta.len();
+    let conflict_size = conflict_meta.len();
+    if original_size != conflict_size {
+        return Ok(VerificationResult::ContentDiverged {
+            conflict_path: conflict.clone(),
+            original_path: original.clone(),
+            divergence: Divergence::Size {
+                conflict_size,
+                original_size,
+            },
+        });
+    }
+
+    // Fast mode takes matching size and mtime as good enough to report
+    if level == VerificationLevel::Fast && mtimes_close(&original_meta, &conflict_meta) {
+        return Ok(VerificationResult::ProbableDuplicate {
+            path: conflict.clone(),
+            original: original.clone(),
+        });
+    }
+
+    // Large files that differ at either end need no full read
+    if original_size > 2 * PARTIAL_HASH_SPAN {
+        let original_ends = check_at_rest(original, &original_meta, || hash_ends(original))?;
+        let conflict_ends = check_at_rest(conflict, &conflict_meta, || hash_ends(conflict))?;
+        if original_ends != conflict_ends {
+            return Ok(VerificationResult::ContentDiverged {
+                conflict_path: conflict.clone(),
+                original_path: original.clone(),
+                divergence: Divergence::Ends {
+                    conflict_hash: conflict_ends,
+                    original_hash: original_ends,
+                },
+            });
+        }
+    }
+
+    // Hash both files, unless something is writing to them
+    let original_hash = hash_at_rest(original, &original_meta, &hash)?;
+    let conflict_hash = hash_at_rest(conflict, &conflict_meta, &hash)?;
 
     if original_hash == conflict_hash {
         Ok(VerificationResult::ConfirmedDuplicate {
@@ -238,430 +652,3064 @@ pub fn verify_candidate(candidate: &ConflictCandidate) -> io::Result<Verificatio
         Ok(VerificationResult::ContentDiverged {
             conflict_path: conflict.clone(),
             original_path: original.clone(),
-            conflict_hash,
-            original_hash,
+            divergence: Divergence::Content {
+                conflict_hash,
+                original_hash,
+            },
         })
     }
 }
 
-/// Assemble a ScanReport from individual verification results.
+/// How often to check whether requested downloads have landed.
+const DOWNLOAD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often waiting for iCloud to finish syncing re-checks.
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Request downloads of the evicted files among `candidates` (conflicts and
+/// originals), and wait up to `timeout` for them to arrive.
 ///
-/// Used by both the CLI (batch mode with rayon progress) and the TUI
-/// (scanner thread). Centralizes the grouping logic.
-pub fn assemble_report(results: Vec<(PathBuf, io::Result<VerificationResult>)>) -> ScanReport {
-    let mut report = ScanReport::default();
-
-    for (path, result) in results {
-        match result {
-            Ok(VerificationResult::ConfirmedDuplicate { keep, remove, hash }) => {
-                let size = fs::metadata(&remove).map(|m| m.len()).unwrap_or(0);
-                report.bytes_recoverable += size;
-
-                if let Some(group) = report
-                    .confirmed_duplicates
-                    .iter_mut()
-                    .find(|g| g.original == keep)
-                {
-                    group.duplicates.push(remove);
-                } else {
-                    report.confirmed_duplicates.push(DuplicateGroup {
-                        original: keep,
-                        hash,
-                        duplicates: vec![remove],
-                    });
-                }
-            }
-            Ok(VerificationResult::OrphanedConflict { path, .. }) => {
-                report.orphaned_conflicts.push(path);
-            }
-            Ok(VerificationResult::ContentDiverged {
-                conflict_path,
-                original_path,
-                ..
-            }) => {
-                report.content_diverged.push((conflict_path, original_path));
-            }
-            Err(e) => {
-                report.skipped.push((path, e.to_string()));
-            }
+/// Returns the candidates with placeholders that arrived reclassified, so
+/// verification reads them; the rest stay not local and are skipped.
+/// `on_waiting` gets the number still downloading after each check. Where
+/// downloads are unsupported, nothing changes.
+fn materialize_candidates<R, W>(
+    candidates: &[ConflictCandidate],
+    timeout: Duration,
+    cancel: &CancelToken,
+    request: R,
+    mut on_waiting: W,
+) -> Vec<ConflictCandidate>
+where
+    R: Fn(&Path) -> io::Result<()>,
+    W: FnMut(usize),
+{
+    let mut evicted: Vec<&Path> = Vec::new();
+    for candidate in candidates.iter().filter(|c| c.kind != FileKind::Bundle) {
+        if candidate.kind == FileKind::CloudPlaceholder {
+            evicted.push(&candidate.path);
+        }
+        if is_not_local(&candidate.presumed_original) {
+            evicted.push(&candidate.presumed_original);
+        }
+    }
+    evicted.sort();
+    evicted.dedup();
+
+    let mut pending = Vec::with_capacity(evicted.len());
+    for path in evicted {
+        match request(path) {
+            Ok(()) => pending.push(path),
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return candidates.to_vec(),
+            // Not an iCloud file, or iCloud refused: it stays skipped
+            Err(_) => {}
+        }
+    }
+
+    let started = Instant::now();
+    while !pending.is_empty() {
+        pending.retain(|p| is_not_local(p));
+        on_waiting(pending.len());
+        let waited = started.elapsed();
+        if pending.is_empty() || waited >= timeout || cancel.is_cancelled() {
+            break;
         }
+        std::thread::sleep(DOWNLOAD_POLL_INTERVAL.min(timeout - waited));
     }
 
-    report
+    candidates
+        .iter()
+        .map(|c| {
+            let mut c = c.clone();
+            if c.kind == FileKind::CloudPlaceholder && !is_not_local(&c.path) {
+                c.kind = if c.path.is_dir() { FileKind::Bundle } else { FileKind::Regular };
+            }
+            c
+        })
+        .collect()
 }
 
-// ============================================================================
-// INTERNAL
-// ============================================================================
+/// Turn a read refused because a file was evicted mid-scan into a
+/// [`VerificationResult::NotLocal`]. Other errors pass through.
+fn evicted_while_verifying(candidate: &ConflictCandidate, err: io::Error) -> io::Result<VerificationResult> {
+    if !is_not_local_error(&err) {
+        return Err(err);
+    }
+    Ok(VerificationResult::NotLocal {
+        path: candidate.path.clone(),
+        presumed_original: candidate.presumed_original.clone(),
+    })
+}
 
-fn scan_walker(
-    walker: WalkDir,
-    report: &mut ScanReport,
-    config: &ScanConfig,
-) -> io::Result<()> {
-    // Collect conflict candidates grouped by their presumed original
-    let mut candidates: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+/// Downgrade a confirmed duplicate whose metadata differs in any of
+/// `fields` to [`VerificationResult::MetadataDiffers`]. Other results pass
+/// through.
+fn compare_metadata(
+    result: VerificationResult,
+    fields: &[MetadataField],
+) -> io::Result<VerificationResult> {
+    let VerificationResult::ConfirmedDuplicate { keep, remove, .. } = &result else {
+        return Ok(result);
+    };
+    if fields.is_empty() {
+        return Ok(result);
+    }
 
-    for entry in walker.into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
+    let differing = metadata_differences(keep, remove, fields)?;
+    if differing.is_empty() {
+        return Ok(result);
+    }
+    Ok(VerificationResult::MetadataDiffers {
+        path: remove.clone(),
+        original: keep.clone(),
+        fields: differing,
+    })
+}
 
-        // Skip directories
-        if !path.is_file() {
-            continue;
-        }
+/// Which of `fields` differ between `a` and `b`, in `fields` order.
+pub fn metadata_differences(
+    a: &Path,
+    b: &Path,
+    fields: &[MetadataField],
+) -> io::Result<Vec<MetadataField>> {
+    let needs_xattrs = fields.iter().any(|f| matches!(f, MetadataField::Xattrs | MetadataField::Tags));
+    let (xattrs_a, xattrs_b) = if needs_xattrs {
+        (extended_attributes(a)?, extended_attributes(b)?)
+    } else {
+        Default::default()
+    };
+    let user_xattrs = |attrs: &BTreeMap<String, Vec<u8>>| -> Vec<(String, Vec<u8>)> {
+        attrs
+            .iter()
+            .filter(|(name, _)| !BOOKKEEPING_XATTRS.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    };
+    let created = |path: &Path| -> io::Result<Option<u64>> {
+        Ok(fs::symlink_metadata(path)?
+            .created()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()))
+    };
 
-        // Skip hidden files if configured
-        let filename = match path.file_name().and_then(|s| s.to_str()) {
-            Some(name) => name,
-            None => continue,
+    let mut differing = Vec::new();
+    for &field in fields {
+        let differs = match field {
+            MetadataField::Xattrs => user_xattrs(&xattrs_a) != user_xattrs(&xattrs_b),
+            MetadataField::Tags => xattrs_a.get(FINDER_TAGS_XATTR) != xattrs_b.get(FINDER_TAGS_XATTR),
+            MetadataField::Created => created(a)? != created(b)?,
         };
-
-        if !config.include_hidden && filename.starts_with('.') {
-            continue;
+        if differs && !differing.contains(&field) {
+            differing.push(field);
         }
+    }
+    Ok(differing)
+}
 
-        // Check for conflict pattern
-        if let Some(pattern) = detect_pattern(filename) {
-            let original_path = derive_original(path, &pattern);
-            candidates
-                .entry(original_path)
-                .or_default()
-                .push(path.to_path_buf());
-        }
+/// Compare a bundle with its original by [`hash_tree_with`].
+///
+/// Bundles bypass the hash cache: a directory's stamp doesn't change when
+/// a file deep inside it does.
+fn verify_bundle(
+    candidate: &ConflictCandidate,
+    algorithm: HashAlgorithm,
+) -> io::Result<VerificationResult> {
+    let original = &candidate.presumed_original;
+    let conflict = &candidate.path;
+
+    if !original.is_dir() {
+        return Ok(VerificationResult::OrphanedConflict {
+            path: conflict.clone(),
+            presumed_original: original.clone(),
+        });
     }
 
-    // Process each group of candidates
-    for (original_path, conflict_paths) in candidates {
-        process_candidate_group(&original_path, &conflict_paths, report)?;
+    let original_hash = hash_tree_with(original, algorithm)?;
+    let conflict_hash = hash_tree_with(conflict, algorithm)?;
+
+    if original_hash == conflict_hash {
+        Ok(VerificationResult::ConfirmedDuplicate {
+            keep: original.clone(),
+            remove: conflict.clone(),
+            hash: original_hash,
+        })
+    } else {
+        Ok(VerificationResult::ContentDiverged {
+            conflict_path: conflict.clone(),
+            original_path: original.clone(),
+            divergence: Divergence::Content {
+                conflict_hash,
+                original_hash,
+            },
+        })
     }
+}
 
-    Ok(())
+/// Verify candidates in parallel, consulting the scan index and hash cache
+/// when configured.
+///
+/// With `config.index_path` set, candidates whose conflict file and original
+/// are unchanged since the last scan reuse the stored verdict instead of
+/// being hashed, and the index is refreshed afterwards. With
+/// a hash store configured (`config.hash_store`, or the file at
+/// `config.hash_cache_path`), files that do need verifying reuse stored
+/// hashes. Both are caches: failing to read or write them never fails
+/// verification.
+///
+/// `on_progress` is called once per candidate (from worker threads) with
+/// the verification fields of [`ScanProgress`] filled in.
+/// Results are returned in candidate order, ready for [`assemble_report`].
+/// Once `config.max_duration` is spent, the remaining candidates are left
+/// out and [`finish_report`] marks the report partial.
+pub fn verify_candidates<F>(
+    candidates: &[ConflictCandidate],
+    config: &ScanConfig,
+    on_progress: F,
+) -> Vec<(PathBuf, io::Result<VerificationResult>)>
+where
+    F: Fn(&ScanProgress) + Sync,
+{
+    let mut verifier = Verifier::new(config);
+    let results = verifier.verify_batch(candidates, on_progress);
+    verifier.finish();
+    results
 }
 
-fn process_candidate_group(
-    original_path: &Path,
-    conflict_paths: &[PathBuf],
-    report: &mut ScanReport,
-) -> io::Result<()> {
-    // Check if original exists and is a file (not a directory/bundle)
-    if !original_path.exists() || !original_path.is_file() {
-        // Orphaned conflicts (or original is a bundle we can't hash)
-        for path in conflict_paths {
-            report.orphaned_conflicts.push(path.clone());
+/// Verification across one or more batches of candidates.
+///
+/// Holds the hash cache and scan index for the whole scan, so batches share
+/// them and they are loaded and written back once. Progress totals grow
+/// with each batch.
+pub struct Verifier<'a> {
+    config: &'a ScanConfig,
+    cache: Option<Arc<dyn HashStore>>,
+    index: Option<ScanIndex>,
+    /// Candidates verified so far, for pruning the index.
+    seen: HashSet<PathBuf>,
+    started: Instant,
+    candidates_found: usize,
+    bytes_to_verify: u64,
+    verified: AtomicUsize,
+    bytes_verified: AtomicU64,
+    bytes_hashed: AtomicU64,
+}
+
+impl<'a> Verifier<'a> {
+    /// Load the caches `config` asks for.
+    pub fn new(config: &'a ScanConfig) -> Self {
+        Self {
+            config,
+            cache: configured_store(config),
+            index: config.index_path.as_deref().map(load_index),
+            seen: HashSet::new(),
+            started: Instant::now(),
+            candidates_found: 0,
+            bytes_to_verify: 0,
+            verified: AtomicUsize::new(0),
+            bytes_verified: AtomicU64::new(0),
+            bytes_hashed: AtomicU64::new(0),
         }
-        return Ok(());
     }
 
-    // Hash the original
-    let original_hash = hash_file(original_path)?;
-
-    let mut confirmed_duplicates: Vec<PathBuf> = Vec::new();
+    /// Verify one batch, as [`verify_candidates`] does.
+    pub fn verify_batch<F>(
+        &mut self,
+        candidates: &[ConflictCandidate],
+        on_progress: F,
+    ) -> Vec<(PathBuf, io::Result<VerificationResult>)>
+    where
+        F: Fn(&ScanProgress) + Sync,
+    {
+        let started = Instant::now();
+        let results = self.verify_timed_batch(candidates, on_progress);
+        self.config.budget.count_verify(started.elapsed());
+        results
+    }
 
-    for conflict_path in conflict_paths {
-        // Compare content
-        match files_match(original_path, conflict_path) {
-            Ok(true) => {
-                // Confirmed duplicate
-                let size = fs::metadata(conflict_path).map(|m| m.len()).unwrap_or(0);
-                report.bytes_recoverable += size;
-                confirmed_duplicates.push(conflict_path.clone());
-            }
-            Ok(false) => {
-                // Content diverged
-                report
-                    .content_diverged
-                    .push((conflict_path.clone(), original_path.to_path_buf()));
+    fn verify_timed_batch<F>(
+        &mut self,
+        candidates: &[ConflictCandidate],
+        on_progress: F,
+    ) -> Vec<(PathBuf, io::Result<VerificationResult>)>
+    where
+        F: Fn(&ScanProgress) + Sync,
+    {
+        let materialized;
+        let candidates = match self.config.materialize {
+            Some(timeout) => {
+                let started = self.started;
+                materialized =
+                    materialize_candidates(candidates, timeout, &self.config.cancel, request_download, |downloading| {
+                        on_progress(&ScanProgress {
+                            downloading,
+                            elapsed: started.elapsed(),
+                            ..Default::default()
+                        })
+                    });
+                &materialized[..]
             }
-            Err(e) => {
-                // Track skipped files instead of silent continue
-                report
-                    .skipped
-                    .push((conflict_path.clone(), e.to_string()));
+            None => candidates,
+        };
+
+        self.candidates_found += candidates.len();
+        self.bytes_to_verify += candidates.iter().map(pair_size).sum::<u64>();
+        if self.index.is_some() {
+            self.seen.extend(candidates.iter().map(|c| c.path.clone()));
+        }
+
+        let config = self.config;
+        let this = &*self;
+        // Copies of one original hash it once per batch
+        let shared = SharedHashes::of(candidates.iter().map(|c| c.presumed_original.as_path()));
+        let hash = |path: &Path| {
+            let read = |p: &Path| {
+                let reading = Instant::now();
+                let hash = hash_file_with_retry(p, config.hash_algorithm, config.read_tuning(), &config.retry)?;
+                let size = fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+                this.bytes_hashed.fetch_add(size, Ordering::Relaxed);
+                config.budget.count_hashed(size, reading.elapsed());
+                Ok(hash)
+            };
+            shared.hash_with(path, |p| match &this.cache {
+                Some(store) => hash_through(store.as_ref(), p, config.hash_algorithm, read),
+                None => read(p),
+            })
+        };
+        let verify = |c: &ConflictCandidate| {
+            check_settled(c, config.ignore_newer_than)?;
+            verify_with(c, config.verification_level, config.hash_algorithm, hash)
+                .or_else(|e| evicted_while_verifying(c, e))
+        };
+        // Metadata is cheap to read and unstamped by the index: always fresh
+        let compare = |result: io::Result<VerificationResult>| {
+            result.and_then(|r| compare_metadata(r, &config.compare_metadata))
+        };
+        let on_verified = |c: &ConflictCandidate| {
+            let candidates_verified = this.verified.fetch_add(1, Ordering::Relaxed) + 1;
+            let size = pair_size(c);
+            let done = this.bytes_verified.fetch_add(size, Ordering::Relaxed) + size;
+            on_progress(&ScanProgress {
+                candidates_found: this.candidates_found,
+                candidates_verified,
+                bytes_verified: done,
+                bytes_to_verify: this.bytes_to_verify,
+                bytes_hashed: this.bytes_hashed.load(Ordering::Relaxed),
+                elapsed: this.started.elapsed(),
+                ..Default::default()
+            });
+        };
+
+        let Some(index) = &self.index else {
+            return candidates
+                .par_iter()
+                .filter_map(|c| {
+                    if config.cancel.is_cancelled() {
+                        return Some((c.path.clone(), Err(cancelled_error())));
+                    }
+                    // Out of time: leave the rest out of a partial report
+                    if out_of_time(config) {
+                        return None;
+                    }
+                    let result = compare(verify(c));
+                    on_verified(c);
+                    Some((c.path.clone(), result))
+                })
+                .collect();
+        };
+
+        let verified: Vec<Option<(CandidateStamps, io::Result<VerificationResult>)>> = candidates
+            .par_iter()
+            .map(|c| {
+                // Stamp before hashing so a mid-hash edit invalidates the entry
+                let stamps = CandidateStamps::capture(c);
+                // A cached verdict may predate a recent edit
+                let cached = index
+                    .lookup(c, &stamps, config.hash_algorithm)
+                    .filter(|_| check_settled(c, config.ignore_newer_than).is_ok());
+                let result = match cached {
+                    Some(cached) => Ok(cached),
+                    None if config.cancel.is_cancelled() => Err(cancelled_error()),
+                    None if out_of_time(config) => return None,
+                    None => verify(c),
+                };
+                on_verified(c);
+                Some((stamps, result))
+            })
+            .collect();
+
+        if let Some(index) = &mut self.index {
+            for (candidate, (stamps, result)) in candidates
+                .iter()
+                .zip(&verified)
+                .filter_map(|(c, v)| Some((c, v.as_ref()?)))
+            {
+                if let Ok(result) = result {
+                    index.record(candidate, stamps, config.hash_algorithm, result);
+                }
             }
         }
+
+        candidates
+            .iter()
+            .zip(verified)
+            .filter_map(|(c, v)| v.map(|(_, result)| (c.path.clone(), compare(result))))
+            .collect()
     }
 
-    // Add duplicate group if any confirmed
-    if !confirmed_duplicates.is_empty() {
-        report.confirmed_duplicates.push(DuplicateGroup {
-            original: original_path.to_path_buf(),
-            hash: original_hash,
-            duplicates: confirmed_duplicates,
-        });
+    /// Write the caches back (best-effort).
+    pub fn finish(mut self) {
+        if let Some(store) = &self.cache {
+            let _ = store.flush();
+        }
+        if let (Some(index), Some(path)) = (&mut self.index, &self.config.index_path) {
+            // A partial scan didn't see everything: keep entries it never reached
+            if self.config.budget.exhausted().is_none() {
+                let seen: HashSet<&Path> = self.seen.iter().map(|p| p.as_path()).collect();
+                index.prune(&self.config.roots, &seen);
+            }
+            let _ = save_index(index, path);
+        }
     }
+}
 
-    Ok(())
+/// Assemble a ScanReport from individual verification results.
+///
+/// Used by both the CLI (batch mode with rayon progress) and the TUI
+/// (scanner thread). Centralizes the grouping logic.
+pub fn assemble_report(results: Vec<(PathBuf, io::Result<VerificationResult>)>) -> ScanReport {
+    let mut builder = ReportBuilder::default();
+    builder.add(results);
+    builder.finish()
 }
 
-// ============================================================================
-// TESTS
+/// Folds verification results into a report, one batch at a time.
+#[derive(Debug, Default)]
+pub struct ReportBuilder {
+    report: ScanReport,
+    /// Index of each original's group in `report.confirmed_duplicates`.
+    groups: HashMap<PathBuf, usize>,
+}
+
+impl ReportBuilder {
+    /// Fold in a batch of results.
+    pub fn add(&mut self, results: Vec<(PathBuf, io::Result<VerificationResult>)>) {
+        let batch = Self::fold(results);
+        self.merge(batch);
+    }
+
+    /// Like [`ReportBuilder::add`], also returning the batch's own findings.
+    pub fn add_batch(&mut self, results: Vec<(PathBuf, io::Result<VerificationResult>)>) -> ScanReport {
+        let batch = Self::fold(results);
+        self.merge(batch.clone());
+        batch
+    }
+
+    /// Results as a report of their own.
+    fn fold(results: Vec<(PathBuf, io::Result<VerificationResult>)>) -> ScanReport {
+        let mut report = ScanReport::default();
+        let mut groups: HashMap<PathBuf, usize> = HashMap::new();
+        for (path, result) in results {
+            match result {
+                Ok(VerificationResult::ConfirmedDuplicate { keep, remove, hash }) => {
+                    let size_bytes = tree_size(&remove);
+                    report.bytes_recoverable += size_bytes;
+
+                    match groups.get(&keep) {
+                        Some(&i) => report.confirmed_duplicates[i].duplicates.push(remove),
+                        None => {
+                            groups.insert(keep.clone(), report.confirmed_duplicates.len());
+                            report.confirmed_duplicates.push(DuplicateGroup {
+                                original: keep,
+                                hash,
+                                duplicates: vec![remove],
+                                size_bytes,
+                            });
+                        }
+                    }
+                }
+                Ok(VerificationResult::OrphanedConflict { path, .. }) => {
+                    report.orphaned_conflicts.push(path);
+                }
+                Ok(VerificationResult::ContentDiverged {
+                    conflict_path,
+                    original_path,
+                    divergence,
+                }) => {
+                    report
+                        .content_diverged
+                        .push(DivergedPair::new(conflict_path, original_path, &divergence));
+                }
+                Ok(VerificationResult::NotLocal { path, .. }) => {
+                    report.not_local.push(path);
+                }
+                Ok(VerificationResult::Hardlinked { path, original }) => {
+                    report.hardlinked.push((path, original));
+                }
+                Ok(VerificationResult::ProbableDuplicate { path, original }) => {
+                    report.probable_duplicates.push((path, original));
+                }
+                Ok(VerificationResult::MetadataDiffers {
+                    path,
+                    original,
+                    fields,
+                }) => {
+                    report.metadata_differs.push(MetadataMismatch {
+                        conflict: path,
+                        original,
+                        fields,
+                    });
+                }
+                Err(e) => {
+                    report.skipped.push((path, e.to_string()));
+                }
+            }
+        }
+        report
+    }
+
+    /// Add a batch's findings to the report, joining groups by original.
+    fn merge(&mut self, batch: ScanReport) {
+        let report = &mut self.report;
+        report.bytes_recoverable += batch.bytes_recoverable;
+        for group in batch.confirmed_duplicates {
+            match self.groups.get(&group.original) {
+                Some(&i) => report.confirmed_duplicates[i].duplicates.extend(group.duplicates),
+                None => {
+                    self.groups.insert(group.original.clone(), report.confirmed_duplicates.len());
+                    report.confirmed_duplicates.push(group);
+                }
+            }
+        }
+        report.orphaned_conflicts.extend(batch.orphaned_conflicts);
+        report.content_diverged.extend(batch.content_diverged);
+        report.not_local.extend(batch.not_local);
+        report.hardlinked.extend(batch.hardlinked);
+        report.probable_duplicates.extend(batch.probable_duplicates);
+        report.metadata_differs.extend(batch.metadata_differs);
+        report.skipped.extend(batch.skipped);
+    }
+
+    /// The report, with groups merged across case variants and chains.
+    pub fn finish(self) -> ScanReport {
+        let mut report = self.report;
+        report.confirmed_duplicates = collapse_chains(merge_case_variant_groups(
+            report.confirmed_duplicates,
+            cached_case_sensitivity(),
+        ));
+        report.bytes_recoverable_physical = physical_recoverable(&report.confirmed_duplicates);
+        report
+    }
+}
+
+/// Bytes removing every duplicate would actually free.
+///
+/// Clones on APFS share blocks with their source, so a duplicate made by a
+/// Finder copy may free almost nothing. None if no duplicate's private
+/// size is known (e.g. not APFS).
+pub fn physical_recoverable(groups: &[DuplicateGroup]) -> Option<u64> {
+    combine_physical(groups.iter().flat_map(|g| &g.duplicates).map(|path| {
+        let logical = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        (logical, private_size(path))
+    }))
+}
+
+/// Final passes over a verified report, as `config` asks: adopt orphans,
+/// pick keepers by policy, roll duplicates up by folder, note whether
+/// a budget cut the scan short, and record the scan's stats.
+pub fn finish_report(report: &mut ScanReport, config: &ScanConfig) {
+    if config.search_orphans {
+        adopt_orphans(report, config);
+    }
+    apply_keep_policy(report, config.keep_policy);
+    describe_diverged(&mut report.content_diverged);
+    report.unsynced = unsynced_files(&report.confirmed_duplicates);
+    report.by_directory = rollup_by_directory(&report.confirmed_duplicates, &config.roots);
+    report.by_parent = rollup_by_parent(&report.confirmed_duplicates, PARENT_ROLLUP_LIMIT);
+    report.by_extension = rollup_by_extension(&report.confirmed_duplicates);
+    report.partial = config.budget.exhausted();
+    report.stats = scan_stats(config);
+    report.hash_algorithm = config.hash_algorithm;
+}
+
+/// Fill in what verification didn't need to learn about diverged pairs:
+/// each side's size and modification time. Files gone since are left
+/// as they are.
+fn describe_diverged(pairs: &mut [DivergedPair]) {
+    for side in pairs.iter_mut().flat_map(|p| [&mut p.conflict, &mut p.original]) {
+        let Ok(meta) = fs::metadata(&side.path) else {
+            continue;
+        };
+        side.size = side.size.or(Some(meta.len()));
+        side.modified = side.modified.or_else(|| {
+            meta.modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+        });
+    }
+}
+
+/// Files of `groups` iCloud is still syncing (see [`ItemState::is_syncing`]),
+/// with their state.
+pub fn syncing_files<S>(groups: &[DuplicateGroup], state_of: S) -> Vec<(PathBuf, ItemState)>
+where
+    S: Fn(&Path) -> ItemState,
+{
+    groups
+        .iter()
+        .flat_map(|g| std::iter::once(&g.original).chain(&g.duplicates))
+        .map(|path| (path.clone(), state_of(path)))
+        .filter(|(_, state)| state.is_syncing())
+        .collect()
+}
+
+/// Wait up to `timeout` for iCloud to finish syncing the files of `groups`.
+///
+/// Returns the files still syncing when it stopped: empty once everything
+/// settled. `on_waiting` gets their number after each check.
+pub fn wait_for_sync<S, W>(
+    groups: &[DuplicateGroup],
+    timeout: Duration,
+    cancel: &CancelToken,
+    state_of: S,
+    mut on_waiting: W,
+) -> Vec<(PathBuf, ItemState)>
+where
+    S: Fn(&Path) -> ItemState,
+    W: FnMut(usize),
+{
+    let started = Instant::now();
+    loop {
+        let syncing = syncing_files(groups, &state_of);
+        on_waiting(syncing.len());
+        let waited = started.elapsed();
+        if syncing.is_empty() || waited >= timeout || cancel.is_cancelled() {
+            return syncing;
+        }
+        std::thread::sleep(SYNC_POLL_INTERVAL.min(timeout - waited));
+    }
+}
+
+/// Groups none of whose files are in `syncing`.
+pub fn settled_groups(groups: &[DuplicateGroup], syncing: &[(PathBuf, ItemState)]) -> Vec<DuplicateGroup> {
+    let unsettled = |path: &PathBuf| syncing.iter().any(|(p, _)| p == path);
+    groups
+        .iter()
+        .filter(|g| !unsettled(&g.original) && !g.duplicates.iter().any(unsettled))
+        .cloned()
+        .collect()
+}
+
+/// Files of duplicate groups whose sync hasn't settled, with their state.
+fn unsynced_files(groups: &[DuplicateGroup]) -> Vec<(PathBuf, ItemState)> {
+    groups
+        .iter()
+        .flat_map(|g| std::iter::once(&g.original).chain(&g.duplicates))
+        .filter_map(|path| match sync_state(path) {
+            ItemState::Local => None,
+            state => Some((path.clone(), state)),
+        })
+        .collect()
+}
+
+/// Stats of the scan `config.budget` has been timing.
+fn scan_stats(config: &ScanConfig) -> ScanStats {
+    let budget = &config.budget;
+    ScanStats {
+        id: generate_session_id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at: budget
+            .started_at()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        duration_ms: budget.elapsed().as_millis() as u64,
+        roots: config.roots.clone(),
+        files_scanned: budget.files(),
+        files_hashed: budget.files_hashed(),
+        bytes_hashed: budget.bytes_hashed(),
+        hash_ms: budget.hash_time().as_millis() as u64,
+        walk_ms: budget.walk_time().as_millis() as u64,
+        verify_ms: budget.verify_time().as_millis() as u64,
+        walk: Some(WalkSettings {
+            max_depth: config.max_depth,
+            follow_symlinks: config.follow_symlinks,
+            include_hidden: config.include_hidden,
+            ignore: config.ignore.clone(),
+        }),
+    }
+}
+
+/// Parent directories kept in a report's `by_parent` rollup.
+pub const PARENT_ROLLUP_LIMIT: usize = 10;
+
+/// Sum duplicates and their sizes per top-level folder, largest first.
+pub fn rollup_by_directory(groups: &[DuplicateGroup], roots: &[PathBuf]) -> Vec<DirectoryRollup> {
+    rollup(groups, |dup| top_level_dir(dup, roots))
+}
+
+/// Sum duplicates and their sizes per parent directory, keeping the
+/// `limit` largest.
+pub fn rollup_by_parent(groups: &[DuplicateGroup], limit: usize) -> Vec<DirectoryRollup> {
+    let mut rollup = rollup(groups, |dup| dup.parent().unwrap_or(dup).to_path_buf());
+    rollup.truncate(limit);
+    rollup
+}
+
+/// Sum duplicates and their sizes per lowercase file extension, largest
+/// first.
+pub fn rollup_by_extension(groups: &[DuplicateGroup]) -> Vec<ExtensionRollup> {
+    let mut by_ext: HashMap<String, ExtensionRollup> = HashMap::new();
+    for group in groups {
+        for dup in &group.duplicates {
+            let extension = dup
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy().to_lowercase()))
+                .unwrap_or_default();
+            let entry = by_ext.entry(extension.clone()).or_insert_with(|| ExtensionRollup {
+                extension,
+                ..Default::default()
+            });
+            entry.duplicates += 1;
+            entry.bytes_recoverable += group.size_bytes;
+        }
+    }
+
+    let mut rollup: Vec<ExtensionRollup> = by_ext.into_values().collect();
+    rollup.sort_by(|a, b| {
+        b.bytes_recoverable
+            .cmp(&a.bytes_recoverable)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    rollup
+}
+
+/// Sum duplicates and their sizes per folder `folder_of` puts them in,
+/// largest first.
+fn rollup(groups: &[DuplicateGroup], folder_of: impl Fn(&Path) -> PathBuf) -> Vec<DirectoryRollup> {
+    let mut by_dir: HashMap<PathBuf, DirectoryRollup> = HashMap::new();
+    for dup in groups.iter().flat_map(|g| &g.duplicates) {
+        let dir = folder_of(dup);
+        let entry = by_dir.entry(dir.clone()).or_insert_with(|| DirectoryRollup {
+            path: dir,
+            ..Default::default()
+        });
+        entry.duplicates += 1;
+        entry.bytes_recoverable += tree_size(dup);
+    }
+
+    let mut rollup: Vec<DirectoryRollup> = by_dir.into_values().collect();
+    rollup.sort_by(|a, b| {
+        b.bytes_recoverable
+            .cmp(&a.bytes_recoverable)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    rollup
+}
+
+/// The folder directly below the deepest root containing `path`, or the
+/// root itself when `path` sits at its top level. Paths outside every root
+/// roll up to their parent directory.
+pub fn top_level_dir(path: &Path, roots: &[PathBuf]) -> PathBuf {
+    let parent = path.parent().unwrap_or(path);
+    let Some(root) = roots
+        .iter()
+        .filter(|r| path.starts_with(r))
+        .max_by_key(|r| r.components().count())
+    else {
+        return parent.to_path_buf();
+    };
+
+    match parent.strip_prefix(root).ok().and_then(|rel| rel.components().next()) {
+        Some(first) => root.join(first),
+        None => root.clone(),
+    }
+}
+
+/// Directory levels above an orphan searched for its original.
+const ORPHAN_SEARCH_ANCESTORS: usize = 2;
+
+/// Depth below each searched directory.
+const ORPHAN_SEARCH_DEPTH: usize = 2;
+
+/// Entries looked at per orphan before giving up.
+const ORPHAN_SEARCH_MAX_ENTRIES: usize = 10_000;
+
+/// Turn orphaned conflicts into duplicates of identical files found nearby.
+///
+/// When an orphan's presumed original is missing, the file it conflicted
+/// with was often renamed or moved. See [`find_original_by_content`] for
+/// where it looks. Orphans still unmatched stay orphans.
+pub fn adopt_orphans(report: &mut ScanReport, config: &ScanConfig) {
+    let orphans = std::mem::take(&mut report.orphaned_conflicts);
+    let mut adopted = false;
+
+    for orphan in orphans {
+        if config.cancel.is_cancelled() {
+            report.orphaned_conflicts.push(orphan);
+            continue;
+        }
+
+        let root = config
+            .roots
+            .iter()
+            .filter(|r| orphan.starts_with(r))
+            .max_by_key(|r| r.components().count());
+        let found = root.and_then(|root| find_original_by_content(&orphan, root, config.hash_algorithm).ok().flatten());
+
+        let Some((original, hash)) = found else {
+            report.orphaned_conflicts.push(orphan);
+            continue;
+        };
+
+        let size_bytes = fs::metadata(&orphan).map(|m| m.len()).unwrap_or(0);
+        report.bytes_recoverable += size_bytes;
+        match report
+            .confirmed_duplicates
+            .iter_mut()
+            .find(|g| g.original == original)
+        {
+            Some(group) => group.duplicates.push(orphan),
+            None => report.confirmed_duplicates.push(DuplicateGroup {
+                original,
+                hash,
+                duplicates: vec![orphan],
+                size_bytes,
+            }),
+        }
+        adopted = true;
+    }
+
+    if adopted {
+        report.bytes_recoverable_physical = physical_recoverable(&report.confirmed_duplicates);
+    }
+}
+
+/// Look near `conflict` for a clean-named file with identical content.
+///
+/// Searches the conflict's directory, then up to two ancestors (never
+/// above `root`), each down to two levels, in name order. Conflict-named
+/// files, hard links to the conflict, and files not downloaded are
+/// skipped; empty files never match. Returns the first match and its
+/// `algorithm` hash.
+pub fn find_original_by_content(
+    conflict: &Path,
+    root: &Path,
+    algorithm: HashAlgorithm,
+) -> io::Result<Option<(PathBuf, ContentHash)>> {
+    let conflict_meta = fs::metadata(conflict)?;
+    let size = conflict_meta.len();
+    if size == 0 || !conflict_meta.is_file() {
+        return Ok(None);
+    }
+
+    let levels = conflict
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .take(ORPHAN_SEARCH_ANCESTORS + 1);
+
+    let mut examined = 0;
+    for dir in levels {
+        let walker = WalkDir::new(dir)
+            .max_depth(ORPHAN_SEARCH_DEPTH)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !is_bundle_entry(e));
+
+        for entry in walker.filter_map(Result::ok) {
+            examined += 1;
+            if examined > ORPHAN_SEARCH_MAX_ENTRIES {
+                return Ok(None);
+            }
+
+            let path = entry.path();
+            let clean_name = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| detect_pattern(name).is_none());
+            if !entry.file_type().is_file() || !clean_name || path == conflict {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.len() != size || same_file(&meta, &conflict_meta) || is_not_local(path) {
+                continue;
+            }
+
+            // Bytes settle it; the conflict is only hashed once it has a match
+            if files_match(conflict, path).unwrap_or(false) {
+                let hash = hash_file_tuned(conflict, algorithm, ReadTuning::default())?;
+                return Ok(Some((path.to_path_buf(), hash)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Re-pick each duplicate group's keeper according to `policy`.
+///
+/// Groups come out of verification keeping the clean-named file; other
+/// policies may keep a conflict copy instead, moving the clean-named file
+/// to the duplicates. Records the policy in the report and refreshes the
+/// physical recoverable size if any keeper changed.
+pub fn apply_keep_policy(report: &mut ScanReport, policy: KeepPolicy) {
+    report.keep_policy = policy;
+    if policy == KeepPolicy::OriginalName {
+        return;
+    }
+
+    let mut changed = false;
+    for group in &mut report.confirmed_duplicates {
+        let stats: Vec<(Option<SystemTime>, u64)> = std::iter::once(&group.original)
+            .chain(&group.duplicates)
+            .map(|path| {
+                let modified = fs::symlink_metadata(path).and_then(|m| m.modified()).ok();
+                (modified, tree_size(path))
+            })
+            .collect();
+
+        let keep = keeper_index(&stats, policy);
+        if keep > 0 {
+            let new_original = std::mem::replace(&mut group.duplicates[keep - 1], group.original.clone());
+            group.original = new_original;
+            changed = true;
+        }
+    }
+
+    if changed {
+        report.bytes_recoverable_physical = physical_recoverable(&report.confirmed_duplicates);
+    }
+}
+
+/// Index of the member to keep, given each member's (mtime, size) with
+/// the current keeper first. Ties keep the earliest member, so the
+/// clean-named file stays unless another one strictly wins.
+pub fn keeper_index(stats: &[(Option<SystemTime>, u64)], policy: KeepPolicy) -> usize {
+    let better = |a: &(Option<SystemTime>, u64), b: &(Option<SystemTime>, u64)| match policy {
+        KeepPolicy::OriginalName => false,
+        KeepPolicy::NewestMtime => a.0 > b.0,
+        KeepPolicy::Largest => a.1 > b.1,
+    };
+
+    let mut keep = 0;
+    for (i, candidate) in stats.iter().enumerate().skip(1) {
+        if better(candidate, &stats[keep]) {
+            keep = i;
+        }
+    }
+    keep
+}
+
+/// Sum (logical, private) sizes, using the logical size where the private
+/// size is unknown. None if no private size is known at all.
+pub fn combine_physical<I>(sizes: I) -> Option<u64>
+where
+    I: IntoIterator<Item = (u64, Option<u64>)>,
+{
+    let mut any_known = false;
+    let total = sizes
+        .into_iter()
+        .map(|(logical, private)| {
+            any_known |= private.is_some();
+            private.unwrap_or(logical)
+        })
+        .sum();
+    any_known.then_some(total)
+}
+
+/// Fold conflict-of-conflict chains into the group of the true original.
+///
+/// "doc 2 Copy.txt" derives "doc 2.txt" as its original, which is itself a
+/// duplicate of "doc.txt". When a group's original is a duplicate in
+/// another group with the same hash, its duplicates move to that group, so
+/// "doc.txt" anchors one group holding every copy. Chains of any length
+/// collapse; groups with a different hash stay separate.
+pub fn collapse_chains(groups: Vec<DuplicateGroup>) -> Vec<DuplicateGroup> {
+    // Which group lists each path as a duplicate
+    let owner: HashMap<&Path, usize> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(i, g)| g.duplicates.iter().map(move |d| (d.as_path(), i)))
+        .collect();
+
+    // Follow each group up its chain; the step bound guards against cycles
+    let anchors: Vec<usize> = (0..groups.len())
+        .map(|start| {
+            let mut current = start;
+            for _ in 0..groups.len() {
+                match owner.get(groups[current].original.as_path()) {
+                    Some(&parent) if parent != current && groups[parent].hash == groups[start].hash => {
+                        current = parent;
+                    }
+                    _ => break,
+                }
+            }
+            current
+        })
+        .collect();
+
+    let mut merged: Vec<Option<DuplicateGroup>> = groups.into_iter().map(Some).collect();
+    for (i, &anchor) in anchors.iter().enumerate() {
+        if anchor == i {
+            continue;
+        }
+        let Some(group) = merged[i].take() else {
+            continue;
+        };
+        if let Some(target) = merged[anchor].as_mut() {
+            for dup in group.duplicates {
+                if !target.duplicates.contains(&dup) {
+                    target.duplicates.push(dup);
+                }
+            }
+        }
+    }
+
+    merged.into_iter().flatten().collect()
+}
+
+/// Merge duplicate groups whose originals name the same file.
+///
+/// On a case-insensitive volume "Report.pdf" and "report.pdf" are one file,
+/// but conflict copies of each derive distinct group keys. Groups are keyed
+/// by their original path, folded to lowercase when `is_case_sensitive`
+/// reports the containing directory as case-insensitive. The first group
+/// seen keeps its spelling of the original; later groups contribute their
+/// duplicates.
+pub fn merge_case_variant_groups<F>(
+    groups: Vec<DuplicateGroup>,
+    mut is_case_sensitive: F,
+) -> Vec<DuplicateGroup>
+where
+    F: FnMut(&Path) -> bool,
+{
+    let mut merged: Vec<DuplicateGroup> = Vec::with_capacity(groups.len());
+    let mut index_by_key: HashMap<PathBuf, usize> = HashMap::new();
+
+    for group in groups {
+        let case_sensitive = group
+            .original
+            .parent()
+            .map(&mut is_case_sensitive)
+            .unwrap_or(true);
+        let key = group_key(&group.original, case_sensitive);
+
+        match index_by_key.get(&key) {
+            Some(&i) => {
+                let target = &mut merged[i];
+                for dup in group.duplicates {
+                    let dup_key = group_key(&dup, case_sensitive);
+                    if !target
+                        .duplicates
+                        .iter()
+                        .any(|d| group_key(d, case_sensitive) == dup_key)
+                    {
+                        target.duplicates.push(dup);
+                    }
+                }
+            }
+            None => {
+                index_by_key.insert(key, merged.len());
+                merged.push(group);
+            }
+        }
+    }
+
+    merged
+}
+
+// ============================================================================
+// INTERNAL
 // ============================================================================
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::{self, File};
-    use std::io::Write;
-    use tempfile::TempDir;
+/// Size of a candidate's conflict file; placeholders count as empty since
+/// they are never read.
+fn candidate_size(candidate: &ConflictCandidate) -> u64 {
+    match candidate.kind {
+        FileKind::CloudPlaceholder => 0,
+        FileKind::Bundle => tree_size(&candidate.path),
+        FileKind::Regular => fs::metadata(&candidate.path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+/// Bytes verifying a candidate may read: its conflict file plus original.
+fn pair_size(candidate: &ConflictCandidate) -> u64 {
+    if candidate.kind == FileKind::CloudPlaceholder {
+        return 0;
+    }
+    let original = match candidate.kind {
+        FileKind::Bundle => tree_size(&candidate.presumed_original),
+        _ => fs::metadata(&candidate.presumed_original)
+            .map(|m| m.len())
+            .unwrap_or(0),
+    };
+    candidate_size(candidate) + original
+}
+
+/// Hash a file that nobody is writing to.
+///
+/// A file locked by another process, or whose size or mtime moves while it
+/// is read, would hash to a mix of old and new content and show up as
+/// bogus divergence. Such files fail with [`io::ErrorKind::ResourceBusy`]
+/// and land in [`ScanReport::skipped`] as "file in use".
+fn hash_at_rest<H>(path: &Path, before: &fs::Metadata, hash: H) -> io::Result<ContentHash>
+where
+    H: Fn(&Path) -> io::Result<ContentHash>,
+{
+    check_at_rest(path, before, || hash(path))
+}
+
+/// Run `read` on a file, failing if the file is locked or changes meanwhile.
+fn check_at_rest<T, R>(path: &Path, before: &fs::Metadata, read: R) -> io::Result<T>
+where
+    R: FnOnce() -> io::Result<T>,
+{
+    if is_locked(path) {
+        return Err(in_use_error(path, "locked by another process"));
+    }
+    let value = read()?;
+    let after = fs::metadata(path)?;
+    if after.len() != before.len() || after.modified().ok() != before.modified().ok() {
+        return Err(in_use_error(path, "changed while being read"));
+    }
+    Ok(value)
+}
+
+fn in_use_error(path: &Path, why: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ResourceBusy,
+        format!("file in use: {} {}", path.display(), why),
+    )
+}
+
+/// Fail with a skip reason when the conflict or its original was modified
+/// within `window`.
+fn check_settled(candidate: &ConflictCandidate, window: Option<Duration>) -> io::Result<()> {
+    let Some(window) = window else {
+        return Ok(());
+    };
+    for path in [&candidate.path, &candidate.presumed_original] {
+        if let Ok(meta) = fs::metadata(path)
+            && let Some(age) = modified_within(&meta, window)
+        {
+            return Err(too_recent_error(path, age));
+        }
+    }
+    Ok(())
+}
+
+/// How long ago a file was modified, when that is within `window`.
+///
+/// Modification times in the future count as just now.
+pub(crate) fn modified_within(meta: &fs::Metadata, window: Duration) -> Option<Duration> {
+    let modified = meta.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    (age < window).then_some(age)
+}
+
+pub(crate) fn too_recent_error(path: &Path, age: Duration) -> io::Error {
+    io::Error::other(format!(
+        "modified {}s ago, too recently to touch: {}",
+        age.as_secs(),
+        path.display()
+    ))
+}
+
+/// Modification times closer than this count as equal in fast mode
+/// (FAT and HFS+ store times with up to two seconds of slack).
+const FAST_MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// True if both files were last modified within [`FAST_MTIME_TOLERANCE`].
+fn mtimes_close(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    let (Ok(a), Ok(b)) = (a.modified(), b.modified()) else {
+        return false;
+    };
+    let gap = a.duration_since(b).or_else(|_| b.duration_since(a));
+    gap.is_ok_and(|gap| gap <= FAST_MTIME_TOLERANCE)
+}
+
+/// True if both metadata describe the same file (hard links share an inode).
+pub(crate) fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    file_id(a).is_some_and(|id| file_id(b) == Some(id))
+}
+
+/// Canonical comparison key for a path on a volume with the given case rules.
+fn group_key(path: &Path, case_sensitive: bool) -> PathBuf {
+    if case_sensitive {
+        path.to_path_buf()
+    } else {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    }
+}
+
+/// Volume case-sensitivity lookup, memoized per directory.
+fn cached_case_sensitivity() -> impl FnMut(&Path) -> bool {
+    let mut cache: HashMap<PathBuf, bool> = HashMap::new();
+    move |dir: &Path| {
+        *cache
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| is_case_sensitive_volume(dir))
+    }
+}
+
+/// Build a directory walker for one root, honoring depth and symlink settings.
+fn build_walker(root: &Path, config: &ScanConfig) -> WalkDir {
+    let normalized = normalize_path(root);
+    let mut walker = WalkDir::new(&normalized.path);
+
+    if let Some(max_depth) = config.max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+
+    walker.follow_links(config.follow_symlinks)
+}
+
+/// One step of a directory walk.
+pub(crate) enum Walked {
+    /// An entry to consider.
+    Entry(DirEntry),
+    /// A path not walked because of a symlink (loop, broken link, or a
+    /// directory already reached another way), with the reason.
+    SymlinkSkip(PathBuf, String),
+    /// Any other walk error.
+    Error(walkdir::Error),
+}
+
+/// Walks every configured root in order.
+///
+/// When following symlinks, directories are tracked by (device, inode) so
+/// one reachable through several links is walked only once, and links back
+/// to an ancestor can't send the walk in circles.
+pub(crate) struct Walk<'a> {
+    config: &'a ScanConfig,
+    roots: std::slice::Iter<'a, PathBuf>,
+    current: Option<walkdir::IntoIter>,
+    visited: HashSet<(u64, u64)>,
+    /// Files (and bundles) yielded so far, for `max_files`.
+    files: usize,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Walked;
+
+    fn next(&mut self) -> Option<Walked> {
+        let started = Instant::now();
+        let walked = self.next_walked();
+        self.config.budget.count_walk(started.elapsed());
+        walked
+    }
+}
+
+impl Walk<'_> {
+    fn next_walked(&mut self) -> Option<Walked> {
+        loop {
+            if self.config.cancel.is_cancelled() || out_of_time(self.config) {
+                return None;
+            }
+
+            let Some(iter) = &mut self.current else {
+                let root = self.roots.next()?;
+                self.current = Some(build_walker(root, self.config).into_iter());
+                continue;
+            };
+
+            let entry = match iter.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => return Some(classify_walk_error(e)),
+                None => {
+                    self.current = None;
+                    continue;
+                }
+            };
+
+            // With links followed, file_type() and metadata() describe the target
+            if self.config.follow_symlinks
+                && entry.file_type().is_dir()
+                && let Ok(meta) = entry.metadata()
+                && let Some(id) = file_id(&meta)
+                && !self.visited.insert((id.dev, id.ino))
+            {
+                iter.skip_current_dir();
+                return Some(Walked::SymlinkSkip(
+                    entry.into_path(),
+                    "directory already scanned through another path".to_string(),
+                ));
+            }
+
+            // Noisy trees (build output, package caches) aren't worth walking
+            if entry.depth() > 0
+                && entry.file_type().is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| is_ignored_dir(name, &self.config.ignore))
+            {
+                iter.skip_current_dir();
+                continue;
+            }
+
+            // Bundles are single documents: never look at files inside them
+            if is_bundle_entry(&entry) {
+                iter.skip_current_dir();
+            }
+            if !entry.file_type().is_dir() || is_bundle_entry(&entry) {
+                if files_spent(self.config, self.files) {
+                    return None;
+                }
+                self.files += 1;
+                self.config.budget.count_file();
+            }
+
+            return Some(Walked::Entry(entry));
+        }
+    }
+}
+
+/// Walk every configured root in order.
+///
+/// Starting a walk starts a new scan: the budget clock restarts.
+pub(crate) fn walk_roots(config: &ScanConfig) -> Walk<'_> {
+    config.budget.begin();
+    Walk {
+        config,
+        roots: config.roots.iter(),
+        current: None,
+        visited: HashSet::new(),
+        files: 0,
+    }
+}
+
+/// True when `files` already walked leave no room for another, recording
+/// why in `config.budget`.
+fn files_spent(config: &ScanConfig, files: usize) -> bool {
+    match config.max_files {
+        Some(max) if files >= max => {
+            config.budget.exhaust(format!("stopped after {} files (file budget)", max));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// True once `config`'s time budget is spent, recording why the first time.
+pub(crate) fn out_of_time(config: &ScanConfig) -> bool {
+    if let Some(max) = config.max_duration
+        && config.budget.elapsed() >= max
+    {
+        config.budget.exhaust(format!("stopped after {}s (time budget)", max.as_secs()));
+    }
+    config.budget.exhausted().is_some()
+}
+
+/// Whether a directory name matches an ignore pattern: an exact name, or
+/// `*.ext` for any name with that extension. Case-insensitive, like the
+/// default macOS volume.
+pub fn is_ignored_dir(name: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| match pattern.strip_prefix("*.") {
+        Some(ext) => Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case(ext)),
+        None => name.eq_ignore_ascii_case(pattern),
+    })
+}
+
+/// A bundle directory below the scan root.
+fn is_bundle_entry(entry: &DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && entry.file_name().to_str().is_some_and(is_bundle_name)
+}
+
+/// Regular files and bundles: the units that can be duplicates.
+fn is_file_like(entry: &DirEntry) -> bool {
+    entry.path().is_file() || is_bundle_entry(entry)
+}
+
+/// Separate symlink trouble from ordinary walk errors.
+fn classify_walk_error(err: walkdir::Error) -> Walked {
+    if let (Some(path), Some(ancestor)) = (err.path(), err.loop_ancestor()) {
+        return Walked::SymlinkSkip(
+            path.to_path_buf(),
+            format!("symlink loop back to {}", ancestor.display()),
+        );
+    }
+
+    if let Some(path) = err.path()
+        && fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+        && fs::metadata(path).is_err()
+    {
+        return Walked::SymlinkSkip(path.to_path_buf(), "broken symlink".to_string());
+    }
+
+    Walked::Error(err)
+}
+
+/// Classify a single file path as a conflict candidate, if it is one.
+///
+/// Returns None for hidden files (when excluded), non-UTF-8 names,
+/// and names that match no conflict pattern. A legacy ".name.icloud" stub
+/// is classified by its logical name and reported as a placeholder, as is
+/// a dataless file.
+fn candidate_for_path(path: &Path, config: &ScanConfig) -> Option<ConflictCandidate> {
+    let filename = path.file_name().and_then(|s| s.to_str())?;
+
+    if let Some(target) = placeholder_target(filename) {
+        if !config.include_hidden && target.starts_with('.') {
+            return None;
+        }
+        let pattern = detect_pattern(target)?;
+        let logical = path.with_file_name(target);
+        return Some(ConflictCandidate {
+            presumed_original: derive_original(&logical, &pattern),
+            path: logical,
+            pattern,
+            kind: FileKind::CloudPlaceholder,
+            sync_state: ItemState::Evicted,
+        });
+    }
+
+    if !config.include_hidden && filename.starts_with('.') {
+        return None;
+    }
+
+    let pattern = detect_pattern(filename)?;
+    let presumed_original = derive_original(path, &pattern);
+    let meta = fs::metadata(path).ok();
+    let kind = match &meta {
+        Some(m) if m.is_dir() => FileKind::Bundle,
+        Some(m) if is_dataless(m) => FileKind::CloudPlaceholder,
+        _ => FileKind::Regular,
+    };
+
+    Some(ConflictCandidate {
+        path: path.to_path_buf(),
+        pattern,
+        presumed_original,
+        kind,
+        sync_state: sync_state(path),
+    })
+}
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use crate::hash::{hash_file, hash_path_with};
+    use crate::hash_cache::{load_hash_cache, FileKey, HashCache};
+    use crate::types::DEFAULT_IGNORES;
+    use tempfile::TempDir;
+
+    /// Helper: create a temp directory with test files
+    fn setup_test_dir() -> TempDir {
+        let dir = TempDir::new().unwrap();
+
+        // Original file
+        let mut original = File::create(dir.path().join("document.txt")).unwrap();
+        writeln!(original, "original content").unwrap();
+
+        // Conflict copy (same content)
+        let mut copy = File::create(dir.path().join("document Copy.txt")).unwrap();
+        writeln!(copy, "original content").unwrap();
+
+        // Another conflict copy
+        let mut copy2 = File::create(dir.path().join("document Copy 2.txt")).unwrap();
+        writeln!(copy2, "original content").unwrap();
+
+        dir
+    }
+
+    /// Helper: create orphaned conflict (no original)
+    fn setup_orphaned_conflict() -> TempDir {
+        let dir = TempDir::new().unwrap();
+
+        // Conflict file without original
+        let mut orphan = File::create(dir.path().join("missing Copy.txt")).unwrap();
+        writeln!(orphan, "orphaned content").unwrap();
+
+        dir
+    }
+
+    /// Helper: create diverged conflict (different content)
+    fn setup_diverged_conflict() -> TempDir {
+        let dir = TempDir::new().unwrap();
+
+        // Original file
+        let mut original = File::create(dir.path().join("file.txt")).unwrap();
+        writeln!(original, "version A").unwrap();
+
+        // Conflict with different content
+        let mut conflict = File::create(dir.path().join("file Copy.txt")).unwrap();
+        writeln!(conflict, "version B - modified").unwrap();
+
+        dir
+    }
+
+    // --- dedupe_roots tests ---
+
+    #[test]
+    fn test_dedupe_roots_drops_exact_duplicates() {
+        let roots = vec![PathBuf::from("/a"), PathBuf::from("/b"), PathBuf::from("/a")];
+        assert_eq!(
+            dedupe_roots(&roots),
+            vec![PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_roots_drops_nested_roots() {
+        let roots = vec![
+            PathBuf::from("/icloud/Documents"),
+            PathBuf::from("/icloud"),
+            PathBuf::from("/icloud/Desktop"),
+            PathBuf::from("/other"),
+        ];
+        assert_eq!(
+            dedupe_roots(&roots),
+            vec![PathBuf::from("/icloud"), PathBuf::from("/other")]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_roots_keeps_sibling_prefixes() {
+        // "/docs-old" shares a string prefix with "/docs" but is not nested
+        let roots = vec![PathBuf::from("/docs"), PathBuf::from("/docs-old")];
+        assert_eq!(dedupe_roots(&roots), roots);
+    }
+
+    fn resolved(given: &str, real: &str) -> ResolvedRoot {
+        ResolvedRoot {
+            given: PathBuf::from(given),
+            real: PathBuf::from(real),
+        }
+    }
+
+    #[test]
+    fn test_dedupe_resolved_roots_compares_real_paths() {
+        let roots = vec![
+            resolved("/Users/a/Docs", "/Users/a/Library/Mobile Documents/Docs"),
+            resolved("/Users/a/Library/Mobile Documents", "/Users/a/Library/Mobile Documents"),
+            resolved("/Users/a/Music", "/Users/a/Music"),
+            resolved("/Users/a/tunes", "/Users/a/Music"),
+        ];
+        assert_eq!(
+            dedupe_resolved_roots(&roots),
+            vec![
+                PathBuf::from("/Users/a/Library/Mobile Documents"),
+                PathBuf::from("/Users/a/Music"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_overlapping_roots_report_each_file_once() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("a.txt"), "12345").unwrap();
+        fs::write(sub.join("a 2.txt"), "12345").unwrap();
+        std::os::unix::fs::symlink(&sub, dir.path().join("alias")).unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf(), sub.clone(), dir.path().join("alias")],
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.bytes_recoverable, 5);
+
+        assert_eq!(discover(&config, |_| {}).unwrap().candidates.len(), 1);
+        assert_eq!(candidates_iter(&config).filter(|c| c.is_ok()).count(), 1);
+    }
+
+    #[test]
+    fn test_root_warnings_flags_symlinks_into_and_out_of_icloud() {
+        let icloud = Path::new("/Users/a/Library/Mobile Documents");
+        let roots = vec![
+            resolved("/Users/a/Documents", "/Users/a/Library/Mobile Documents/com~apple~CloudDocs/Documents"),
+            resolved("/Users/a/Library/Mobile Documents/link", "/Volumes/ext/stuff"),
+            resolved("/Users/a/Music", "/Users/a/Music"),
+        ];
+
+        let warnings = root_warnings(&roots, Some(icloud));
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("into iCloud"));
+        assert!(warnings[1].contains("out of iCloud"));
+    }
+
+    #[test]
+    fn test_root_warnings_flags_overlap_after_resolving() {
+        let roots = vec![
+            resolved("/Users/a/Documents", "/icloud/Documents"),
+            resolved("/icloud", "/icloud"),
+        ];
+
+        let warnings = root_warnings(&roots, None);
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[1].contains("overlap"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_roots_follows_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let resolved = resolve_roots(&[link.clone(), real.clone()]);
+
+        assert!(resolved[0].is_redirected());
+        assert_eq!(resolved[0].real, real.canonicalize().unwrap());
+        assert_eq!(resolved[1].real, real.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_scan_multiple_roots_merges_into_one_report() {
+        let dir1 = setup_test_dir();
+        let dir2 = setup_diverged_conflict();
+
+        let config = ScanConfig {
+            roots: vec![dir1.path().to_path_buf(), dir2.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let report = scan(&config).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.content_diverged.len(), 1);
+
+        // Sizes differ, so nothing was hashed; dates come from the files
+        let pair = &report.content_diverged[0];
+        assert_eq!(pair.conflict.size, Some(21));
+        assert_eq!(pair.original.size, Some(10));
+        assert!(pair.conflict.hash.is_none());
+        assert!(pair.conflict.modified.is_some() && pair.original.modified.is_some());
+    }
+
+    // --- merge_case_variant_groups tests ---
+
+    fn group(original: &str, dups: &[&str]) -> DuplicateGroup {
+        DuplicateGroup {
+            original: PathBuf::from(original),
+            hash: crate::types::ContentHash([0u8; 32]),
+            duplicates: dups.iter().map(PathBuf::from).collect(),
+            size_bytes: 0,
+        }
+    }
+
+    // --- collapse_chains tests ---
+
+    #[test]
+    fn test_collapse_chains_anchors_true_original() {
+        let groups = vec![
+            group("/d/doc 2 Copy.txt", &["/d/doc 2 Copy 2.txt"]),
+            group("/d/doc.txt", &["/d/doc 2.txt"]),
+            group("/d/doc 2.txt", &["/d/doc 2 Copy.txt"]),
+        ];
+
+        let collapsed = collapse_chains(groups);
+
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(collapsed[0].original, PathBuf::from("/d/doc.txt"));
+        assert_eq!(
+            collapsed[0].duplicates,
+            vec![
+                PathBuf::from("/d/doc 2.txt"),
+                PathBuf::from("/d/doc 2 Copy 2.txt"),
+                PathBuf::from("/d/doc 2 Copy.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_chains_keeps_different_content_apart() {
+        let mut inner = group("/d/doc 2.txt", &["/d/doc 2 Copy.txt"]);
+        inner.hash = crate::types::ContentHash([1u8; 32]);
+        let groups = vec![group("/d/doc.txt", &["/d/doc 2.txt"]), inner];
+
+        assert_eq!(collapse_chains(groups).len(), 2);
+    }
+
+    #[test]
+    fn test_scan_collapses_conflict_of_conflict() {
+        let dir = TempDir::new().unwrap();
+        for name in ["doc.txt", "doc 2.txt", "doc 2 Copy.txt"] {
+            fs::write(dir.path().join(name), "same").unwrap();
+        }
+
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.confirmed_duplicates[0].original, dir.path().join("doc.txt"));
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_case_variants_on_insensitive_volume() {
+        let groups = vec![
+            group("/docs/Report.pdf", &["/docs/Report Copy.pdf"]),
+            group("/docs/report.pdf", &["/docs/report 2.pdf"]),
+        ];
+
+        let merged = merge_case_variant_groups(groups, |_| false);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].original, PathBuf::from("/docs/Report.pdf"));
+        assert_eq!(merged[0].duplicates.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_keeps_case_variants_on_sensitive_volume() {
+        let groups = vec![
+            group("/docs/Report.pdf", &["/docs/Report Copy.pdf"]),
+            group("/docs/report.pdf", &["/docs/report 2.pdf"]),
+        ];
+
+        let merged = merge_case_variant_groups(groups, |_| true);
+
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_does_not_repeat_duplicates() {
+        let groups = vec![
+            group("/docs/Report.pdf", &["/docs/Report Copy.pdf"]),
+            group("/docs/report.pdf", &["/docs/report copy.pdf"]),
+        ];
+
+        let merged = merge_case_variant_groups(groups, |_| false);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].duplicates.len(), 1);
+    }
+
+    // --- scan_dir tests ---
+
+    #[test]
+    fn test_scan_finds_confirmed_duplicates() {
+        let dir = setup_test_dir();
+        let report = scan_dir(dir.path()).unwrap();
+
+        // Should find duplicates: "document Copy.txt" and "document Copy 2.txt"
+        assert_eq!(report.confirmed_duplicates.len(), 1); // One group
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2); // Two copies
+    }
+
+    #[test]
+    fn test_scan_finds_orphaned_conflicts() {
+        let dir = setup_orphaned_conflict();
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert_eq!(report.orphaned_conflicts.len(), 1);
+        assert!(report.confirmed_duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_scan_finds_diverged_conflicts() {
+        let dir = setup_diverged_conflict();
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert_eq!(report.content_diverged.len(), 1);
+        assert!(report.confirmed_duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_scan_reports_placeholders_as_not_local() {
+        let dir = TempDir::new().unwrap();
+        // Evicted conflict: only its stub is on disk
+        fs::write(dir.path().join(".photo 2.heic.icloud"), "plist").unwrap();
+        fs::write(dir.path().join("photo.heic"), "pixels").unwrap();
+        // Local conflict whose original was evicted
+        fs::write(dir.path().join("notes 2.txt"), "text").unwrap();
+        fs::write(dir.path().join(".notes.txt.icloud"), "plist").unwrap();
+
+        let report = scan_dir(dir.path()).unwrap();
+
+        let mut not_local = report.not_local.clone();
+        not_local.sort();
+        assert_eq!(
+            not_local,
+            vec![dir.path().join("notes 2.txt"), dir.path().join("photo 2.heic")]
+        );
+        assert!(report.orphaned_conflicts.is_empty());
+        assert!(report.content_diverged.is_empty());
+
+        // The streaming path agrees
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+        let report = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(report.not_local.len(), 2);
+    }
+
+    #[test]
+    fn test_scan_empty_dir() {
+        let dir = TempDir::new().unwrap();
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert!(report.confirmed_duplicates.is_empty());
+        assert!(report.orphaned_conflicts.is_empty());
+        assert!(report.content_diverged.is_empty());
+        assert_eq!(report.bytes_recoverable, 0);
+    }
+
+    #[test]
+    fn test_scan_no_conflicts() {
+        let dir = TempDir::new().unwrap();
+
+        // Normal files, no conflict patterns
+        File::create(dir.path().join("file1.txt")).unwrap();
+        File::create(dir.path().join("file2.txt")).unwrap();
+
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert!(report.confirmed_duplicates.is_empty());
+        assert!(report.orphaned_conflicts.is_empty());
+        assert!(report.content_diverged.is_empty());
+    }
+
+    #[test]
+    fn test_scan_calculates_bytes_recoverable() {
+        let dir = TempDir::new().unwrap();
+
+        // Original
+        let mut original = File::create(dir.path().join("data.txt")).unwrap();
+        write!(original, "12345").unwrap(); // 5 bytes
+
+        // Copy with same content
+        let mut copy = File::create(dir.path().join("data Copy.txt")).unwrap();
+        write!(copy, "12345").unwrap(); // 5 bytes
+
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert_eq!(report.bytes_recoverable, 5); // Can recover 5 bytes
+    }
+
+    // --- scan with config tests ---
+
+    #[test]
+    fn test_scan_with_config() {
+        let dir = setup_test_dir();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let report = scan(&config).unwrap();
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+    }
+
+    #[test]
+    fn test_scan_records_stats() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let stats = scan(&config).unwrap().stats;
+
+        assert_eq!(stats.files_scanned, 3);
+        // The original is read once for both copies
+        assert_eq!(stats.bytes_hashed, 3 * "original content\n".len() as u64);
+        assert_eq!(stats.files_hashed, 3);
+        assert!(stats.hash_ms <= stats.duration_ms * rayon::current_num_threads() as u64 + 1);
+        assert_eq!(stats.roots, config.roots);
+        assert_eq!(stats.version, env!("CARGO_PKG_VERSION"));
+        assert!(stats.started_at > 0);
+    }
+
+    #[test]
+    fn test_scan_with_sha256_records_algorithm() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..Default::default()
+        };
+
+        let report = scan(&config).unwrap();
+
+        assert_eq!(report.hash_algorithm, HashAlgorithm::Sha256);
+        let group = &report.confirmed_duplicates[0];
+        assert_eq!(group.duplicates.len(), 2);
+        assert_eq!(group.hash, hash_path_with(&group.original, HashAlgorithm::Sha256).unwrap());
+        assert_ne!(group.hash, hash_file(&group.original).unwrap());
+    }
+
+    // --- streaming tests ---
+
+    #[test]
+    fn test_streaming_batches_merge_groups_across_batches() {
+        let dir = setup_test_dir();
+        for name in ["notes.txt", "notes Copy.txt", "notes Copy 2.txt"] {
+            fs::write(dir.path().join(name), "notes").unwrap();
+        }
+        fs::write(dir.path().join("lost Copy.txt"), "lost").unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let whole = scan_streaming(&config, usize::MAX, |_| {}).unwrap();
+        let batched = scan_streaming(&config, 1, |_| {}).unwrap();
+
+        let summary = |r: &ScanReport| {
+            let mut groups: Vec<_> = r
+                .confirmed_duplicates
+                .iter()
+                .map(|g| {
+                    let mut dups = g.duplicates.clone();
+                    dups.sort();
+                    (g.original.clone(), dups)
+                })
+                .collect();
+            groups.sort();
+            (groups, r.orphaned_conflicts.clone(), r.bytes_recoverable)
+        };
+        assert_eq!(summary(&batched), summary(&whole));
+        assert_eq!(batched.confirmed_duplicates.len(), 2);
+        assert!(batched.confirmed_duplicates.iter().all(|g| g.duplicates.len() == 2));
+    }
+
+    #[test]
+    fn test_streaming_hands_over_each_batch() {
+        let dir = setup_test_dir();
+        for name in ["notes.txt", "notes Copy.txt", "notes Copy 2.txt"] {
+            fs::write(dir.path().join(name), "notes").unwrap();
+        }
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let mut batches = Vec::new();
+        let report = scan_streaming_with(&config, 1, |_| {}, |batch| batches.push(batch.clone())).unwrap();
+
+        let streamed: usize = batches
+            .iter()
+            .flat_map(|b| &b.confirmed_duplicates)
+            .map(|g| g.duplicates.len())
+            .sum();
+        let reported: usize = report.confirmed_duplicates.iter().map(|g| g.duplicates.len()).sum();
+        assert_eq!(streamed, reported);
+        assert_eq!(batches.iter().map(|b| b.bytes_recoverable).sum::<u64>(), report.bytes_recoverable);
+        assert!(batches.iter().all(|b| b.confirmed_duplicates.len() <= 1));
+    }
+
+    #[test]
+    fn test_streaming_reports_growing_totals() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let most_verified = AtomicUsize::new(0);
+
+        scan_streaming(&config, 1, |p| {
+            assert!(p.candidates_verified <= p.candidates_found);
+            most_verified.fetch_max(p.candidates_verified, Ordering::Relaxed);
+        })
+        .unwrap();
+
+        assert_eq!(most_verified.into_inner(), 2);
+    }
+
+    // --- find_candidates tests (pattern-only, no hashing) ---
+
+    #[test]
+    fn test_find_candidates_returns_candidates() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let candidates = find_candidates(&config).unwrap();
+
+        // Should find 2 candidates: "document Copy.txt" and "document Copy 2.txt"
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_find_candidates_no_hashing_occurs() {
+        // Even with diverged content, find_candidates should return them
+        let dir = setup_diverged_conflict();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let candidates = find_candidates(&config).unwrap();
+
+        // Should find the candidate (hashing hasn't happened yet)
+        assert_eq!(candidates.len(), 1);
+        assert!(candidates[0].path.to_string_lossy().contains("Copy"));
+    }
+
+    #[test]
+    fn test_find_candidates_includes_orphaned() {
+        let dir = setup_orphaned_conflict();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let candidates = find_candidates(&config).unwrap();
+
+        // Should find orphaned candidate (original existence not checked yet)
+        assert_eq!(candidates.len(), 1);
+    }
+
+    #[test]
+    fn test_find_candidates_empty_for_no_conflicts() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join("normal.txt")).unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let candidates = find_candidates(&config).unwrap();
+        assert!(candidates.is_empty());
+    }
+
+    // --- candidates_iter tests (streaming) ---
+
+    #[test]
+    fn test_candidates_iter_matches_find_candidates() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        let mut streamed: Vec<PathBuf> = candidates_iter(&config)
+            .map(|c| c.unwrap().path)
+            .collect();
+        let mut buffered: Vec<PathBuf> = find_candidates(&config)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        streamed.sort();
+        buffered.sort();
+
+        assert_eq!(streamed, buffered);
+    }
+
+    #[test]
+    fn test_candidates_iter_is_lazy() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+
+        // Taking one item must not require walking the whole tree first
+        let first: Vec<_> = candidates_iter(&config).take(1).collect();
+        assert_eq!(first.len(), 1);
+    }
+
+    #[test]
+    fn test_candidates_iter_yields_error_for_missing_root() {
+        let config = ScanConfig {
+            roots: vec![PathBuf::from("/nonexistent/icloud-dedupe-root")],
+            ..Default::default()
+        };
+
+        let items: Vec<_> = candidates_iter(&config).collect();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[test]
+    fn test_candidates_iter_skips_hidden_when_excluded() {
+        let dir = TempDir::new().unwrap();
+        File::create(dir.path().join(".hidden Copy.txt")).unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            include_hidden: false,
+            ..Default::default()
+        };
+
+        assert_eq!(candidates_iter(&config).count(), 0);
+    }
+
+    // --- verify_candidates tests (parallel, indexed) ---
+
+    #[test]
+    fn test_verify_candidates_without_index_matches_verify_candidate() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+
+        let results = verify_candidates(&candidates, &config, |_| {});
+        let report = assemble_report(results);
+
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2);
+    }
+
+    #[test]
+    fn test_verify_group_matches_verify_candidate() {
+        let dir = setup_test_dir();
+        fs::write(dir.path().join("other Copy.txt"), "other").unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+
+        let results = verify_group(&dir.path().join("document.txt"), &candidates);
+
+        assert_eq!(results.len(), candidates.len());
+        for (candidate, (path, result)) in candidates.iter().zip(results) {
+            assert_eq!(path, candidate.path);
+            assert_eq!(
+                format!("{:?}", result.unwrap()),
+                format!("{:?}", verify_candidate(candidate).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_shared_hashes_read_each_original_once() {
+        let reads = AtomicUsize::new(0);
+        let shared = SharedHashes::of([Path::new("/docs/a.txt")]);
+        let read = |_: &Path| {
+            reads.fetch_add(1, Ordering::Relaxed);
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+        };
+
+        for _ in 0..3 {
+            let err = shared.hash_with(Path::new("/docs/a.txt"), read).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        }
+        assert_eq!(reads.load(Ordering::Relaxed), 1);
+
+        let _ = shared.hash_with(Path::new("/docs/b.txt"), read);
+        let _ = shared.hash_with(Path::new("/docs/b.txt"), read);
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_cancelled_scan_stops_without_verifying() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+        config.cancel.cancel();
+
+        assert!(find_candidates(&config).unwrap().is_empty());
+        let results = verify_candidates(&candidates, &config, |_| {});
+        assert!(results
+            .iter()
+            .all(|(_, r)| r.as_ref().is_err_and(|e| e.kind() == io::ErrorKind::Interrupted)));
+    }
+
+    #[test]
+    fn test_verify_candidates_reports_byte_progress() {
+        let dir = setup_test_dir();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+        let updates = std::sync::Mutex::new(Vec::new());
+
+        verify_candidates(&candidates, &config, |p| updates.lock().unwrap().push(*p));
+
+        // Workers race, so the final totals can arrive in any update
+        let updates = updates.into_inner().unwrap();
+        let last = updates.iter().max_by_key(|p| p.bytes_verified).unwrap();
+        assert_eq!(updates.len(), candidates.len());
+        assert!(last.bytes_to_verify > 0);
+        assert_eq!(last.bytes_verified, last.bytes_to_verify);
+        assert_eq!(last.eta(), Some(std::time::Duration::ZERO));
+        assert!(updates.iter().any(|p| p.bytes_hashed > 0));
+        assert!(updates.iter().any(|p| p.candidates_verified == candidates.len()));
+    }
+
+    #[test]
+    fn test_progress_eta_scales_with_remaining_bytes() {
+        let progress = ScanProgress {
+            bytes_verified: 100,
+            bytes_to_verify: 400,
+            elapsed: std::time::Duration::from_secs(10),
+            ..Default::default()
+        };
+        assert_eq!(progress.eta(), Some(std::time::Duration::from_secs(30)));
+        assert_eq!(ScanProgress::default().eta(), None);
+    }
+
+    #[test]
+    fn test_verify_candidates_reuses_and_invalidates_index() {
+        let dir = setup_diverged_conflict();
+        let state = TempDir::new().unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            index_path: Some(state.path().join("index.json")),
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+
+        let first = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(first.content_diverged.len(), 1);
+        assert!(config.index_path.as_ref().unwrap().exists());
+
+        // Unchanged files: verdict comes from the index
+        let second = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(second.content_diverged.len(), 1);
+
+        // Make the conflict identical to the original (size changes too)
+        fs::write(dir.path().join("file Copy.txt"), "version A\n").unwrap();
+        let third = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(third.confirmed_duplicates.len(), 1);
+        assert!(third.content_diverged.is_empty());
+    }
+
+    #[test]
+    fn test_verify_candidates_with_hash_cache_persists_hashes() {
+        let dir = setup_test_dir();
+        let state = TempDir::new().unwrap();
+        let cache_path = state.path().join("hashes.json");
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            hash_cache_path: Some(cache_path.clone()),
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+
+        let report = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+
+        // Original + two conflicts = three distinct files cached
+        assert_eq!(load_hash_cache(&cache_path).len(), 3);
+    }
+
+    /// A store that records which paths were put, on top of a cache.
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        cache: HashCache,
+        puts: std::sync::Mutex<Vec<PathBuf>>,
+    }
+
+    impl HashStore for RecordingStore {
+        fn get(&self, path: &Path, key: &FileKey) -> Option<ContentHash> {
+            self.cache.get(path, key)
+        }
+
+        fn put(&self, path: &Path, key: FileKey, hash: ContentHash) {
+            self.puts.lock().unwrap().push(path.to_path_buf());
+            self.cache.put(path, key, hash);
+        }
+    }
+
+    #[test]
+    fn test_verification_uses_embedder_hash_store() {
+        let dir = setup_test_dir();
+        let state = TempDir::new().unwrap();
+        let store = Arc::new(RecordingStore::default());
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            hash_store: Some(store.clone()),
+            hash_cache_path: Some(state.path().join("hashes.json")),
+            ..Default::default()
+        };
+
+        let first = scan(&config).unwrap();
+        assert_eq!(store.puts.lock().unwrap().len(), 3);
+        // The embedder's store replaces the file
+        assert!(!state.path().join("hashes.json").exists());
+
+        // Everything is a hit the second time round
+        let second = scan(&config).unwrap();
+        assert_eq!(store.puts.lock().unwrap().len(), 3);
+        assert_eq!(second.confirmed_duplicates[0].hash, first.confirmed_duplicates[0].hash);
+    }
+
+    // --- verify_candidate tests (hash-based, singular) ---
+
+    #[test]
+    fn test_verify_candidate_confirmed_duplicate() {
+        let dir = TempDir::new().unwrap();
+
+        // Original and copy with same content
+        let mut orig = File::create(dir.path().join("doc.txt")).unwrap();
+        writeln!(orig, "same content").unwrap();
+        let mut copy = File::create(dir.path().join("doc Copy.txt")).unwrap();
+        writeln!(copy, "same content").unwrap();
+
+        let candidate = ConflictCandidate {
+            path: dir.path().join("doc Copy.txt"),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: dir.path().join("doc.txt"),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
+
+        let result = verify_candidate(&candidate).unwrap();
+        assert!(matches!(result, VerificationResult::ConfirmedDuplicate { .. }));
+    }
+
+    /// Helper: a ".pages"-style bundle with one file.
+    fn make_bundle(path: &Path, content: &str) {
+        fs::create_dir_all(path.join("Data")).unwrap();
+        fs::write(path.join("Index.xml"), content).unwrap();
+    }
+
+    #[test]
+    fn test_bundles_are_verified_as_whole_trees() {
+        let dir = TempDir::new().unwrap();
+        make_bundle(&dir.path().join("Doc.pages"), "<doc/>");
+        make_bundle(&dir.path().join("Doc Copy.pages"), "<doc/>");
+        make_bundle(&dir.path().join("Doc 2.pages"), "<edited/>");
+        // Conflict-named files inside a bundle are part of the document
+        fs::write(dir.path().join("Doc.pages/Data/img 2.png"), "x").unwrap();
+        fs::write(dir.path().join("Doc Copy.pages/Data/img 2.png"), "x").unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let candidates = find_candidates(&config).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.iter().all(|c| c.kind == FileKind::Bundle));
+
+        let report = assemble_report(verify_candidates(&candidates, &config, |_| {}));
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(
+            report.confirmed_duplicates[0].duplicates,
+            vec![dir.path().join("Doc Copy.pages")]
+        );
+        assert_eq!(report.content_diverged.len(), 1);
+        assert!(report.orphaned_conflicts.is_empty());
+        assert_eq!(report.bytes_recoverable, "<doc/>".len() as u64 + 1);
+
+        let scanned = scan(&config).unwrap();
+        assert_eq!(scanned.confirmed_duplicates.len(), 1);
+        assert_eq!(scanned.content_diverged.len(), 1);
+    }
+
+    #[test]
+    fn test_combine_physical_prefers_private_sizes() {
+        assert_eq!(combine_physical([(100, Some(0)), (50, Some(50))]), Some(50));
+        assert_eq!(combine_physical([(100, Some(10)), (50, None)]), Some(60));
+        assert_eq!(combine_physical([(100, None), (50, None)]), None);
+        assert_eq!(combine_physical([]), None);
+    }
+
+    #[test]
+    fn test_verify_candidate_hardlink_recovers_nothing() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("doc.txt"), "same content").unwrap();
+        fs::hard_link(dir.path().join("doc.txt"), dir.path().join("doc Copy.txt")).unwrap();
+
+        let candidate = ConflictCandidate {
+            path: dir.path().join("doc Copy.txt"),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: dir.path().join("doc.txt"),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
+
+        let result = verify_candidate(&candidate).unwrap();
+        assert!(matches!(result, VerificationResult::Hardlinked { .. }));
+
+        let report = assemble_report(vec![(candidate.path.clone(), Ok(result))]);
+        assert!(report.confirmed_duplicates.is_empty());
+        assert_eq!(report.bytes_recoverable, 0);
+        assert_eq!(
+            report.hardlinked,
+            vec![(dir.path().join("doc Copy.txt"), dir.path().join("doc.txt"))]
+        );
+    }
+
+    /// Orphan in a/b, identical renamed file in a/other, diverged file in a.
+    fn setup_moved_original() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a");
+        fs::create_dir_all(a.join("b")).unwrap();
+        fs::create_dir_all(a.join("other")).unwrap();
+        fs::write(a.join("b/doc Copy.txt"), "moved content").unwrap();
+        fs::write(a.join("other/doc-final.txt"), "moved content").unwrap();
+        fs::write(a.join("decoy.txt"), "other content").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_find_original_by_content_searches_ancestors() {
+        let dir = setup_moved_original();
+        let conflict = dir.path().join("a/b/doc Copy.txt");
+
+        let (found, hash) = find_original_by_content(&conflict, dir.path(), HashAlgorithm::Blake3).unwrap().unwrap();
+        assert_eq!(found, dir.path().join("a/other/doc-final.txt"));
+        assert_eq!(hash, hash_file(&conflict).unwrap());
+
+        // Never looks above the root
+        let root = dir.path().join("a/b");
+        assert!(find_original_by_content(&conflict, &root, HashAlgorithm::Blake3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_scan_adopts_orphans_only_when_asked() {
+        let dir = setup_moved_original();
+        let orphan = dir.path().join("a/b/doc Copy.txt");
+
+        let report = scan_dir(dir.path()).unwrap();
+        assert_eq!(report.orphaned_conflicts, vec![orphan.clone()]);
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            search_orphans: true,
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
+        assert!(report.orphaned_conflicts.is_empty());
+        assert_eq!(report.confirmed_duplicates[0].original, dir.path().join("a/other/doc-final.txt"));
+        assert_eq!(report.confirmed_duplicates[0].duplicates, vec![orphan]);
+        assert_eq!(report.bytes_recoverable, "moved content".len() as u64);
+    }
+
+    #[test]
+    fn test_keeper_index_policies() {
+        use std::time::Duration;
+        let at = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let stats = [(at(10), 5), (at(30), 5), (at(20), 9)];
+
+        assert_eq!(keeper_index(&stats, KeepPolicy::OriginalName), 0);
+        assert_eq!(keeper_index(&stats, KeepPolicy::NewestMtime), 1);
+        assert_eq!(keeper_index(&stats, KeepPolicy::Largest), 2);
+        // Ties keep the current keeper
+        assert_eq!(keeper_index(&[(at(10), 5), (at(10), 5)], KeepPolicy::NewestMtime), 0);
+    }
+
+    #[test]
+    fn test_scan_keep_newest_keeps_conflict_copy() {
+        use std::time::Duration;
+        let dir = setup_test_dir();
+        let newest = dir.path().join("document Copy 2.txt");
+        File::options()
+            .write(true)
+            .open(&newest)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(3600))
+            .unwrap();
+
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            keep_policy: KeepPolicy::NewestMtime,
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
+
+        let group = &report.confirmed_duplicates[0];
+        assert_eq!(group.original, newest);
+        assert!(group.duplicates.contains(&dir.path().join("document.txt")));
+        assert_eq!(report.keep_policy, KeepPolicy::NewestMtime);
+        assert_eq!(report.bytes_recoverable, 2 * "original content\n".len() as u64);
+    }
+
+    #[test]
+    fn test_scan_separates_hardlinks_from_duplicates() {
+        let dir = setup_test_dir();
+        fs::hard_link(dir.path().join("document.txt"), dir.path().join("document 2.txt")).unwrap();
+
+        let report = scan_dir(dir.path()).unwrap();
+
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2);
+        assert_eq!(report.hardlinked.len(), 1);
+        assert_eq!(report.bytes_recoverable, 2 * "original content\n".len() as u64);
+    }
+
+    #[test]
+    fn test_verify_candidate_orphaned() {
+        let dir = TempDir::new().unwrap();
+
+        // Only the copy exists, no original
+        let mut copy = File::create(dir.path().join("missing Copy.txt")).unwrap();
+        writeln!(copy, "orphaned").unwrap();
+
+        let candidate = ConflictCandidate {
+            path: dir.path().join("missing Copy.txt"),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: dir.path().join("missing.txt"), // doesn't exist
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
+
+        let result = verify_candidate(&candidate).unwrap();
+        assert!(matches!(result, VerificationResult::OrphanedConflict { .. }));
+    }
+
+    #[test]
+    fn test_verify_candidate_diverged() {
+        let dir = TempDir::new().unwrap();
+
+        // Original and copy with DIFFERENT content
+        let mut orig = File::create(dir.path().join("file.txt")).unwrap();
+        writeln!(orig, "version A").unwrap();
+        let mut copy = File::create(dir.path().join("file Copy.txt")).unwrap();
+        writeln!(copy, "version B").unwrap();
+
+        let candidate = ConflictCandidate {
+            path: dir.path().join("file Copy.txt"),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: dir.path().join("file.txt"),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
+
+        let result = verify_candidate(&candidate).unwrap();
+        assert!(matches!(
+            result,
+            VerificationResult::ContentDiverged {
+                divergence: Divergence::Content { .. },
+                ..
+            }
+        ));
+    }
 
-    /// Helper: create a temp directory with test files
-    fn setup_test_dir() -> TempDir {
+    #[test]
+    fn test_verify_candidate_size_mismatch_skips_hashing() {
         let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("file.txt"), "short").unwrap();
+        fs::write(dir.path().join("file Copy.txt"), "much longer content").unwrap();
 
-        // Original file
-        let mut original = File::create(dir.path().join("document.txt")).unwrap();
-        writeln!(original, "original content").unwrap();
+        let candidate = ConflictCandidate {
+            path: dir.path().join("file Copy.txt"),
+            pattern: ConflictPattern::Copy { index: None },
+            presumed_original: dir.path().join("file.txt"),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
 
-        // Conflict copy (same content)
-        let mut copy = File::create(dir.path().join("document Copy.txt")).unwrap();
-        writeln!(copy, "original content").unwrap();
+        let result = verify_with(&candidate, VerificationLevel::Strict, HashAlgorithm::Blake3, |_| panic!("should not hash")).unwrap();
+        assert!(matches!(
+            result,
+            VerificationResult::ContentDiverged {
+                divergence: Divergence::Size {
+                    conflict_size: 19,
+                    original_size: 5,
+                },
+                ..
+            }
+        ));
+    }
 
-        // Another conflict copy
-        let mut copy2 = File::create(dir.path().join("document Copy 2.txt")).unwrap();
-        writeln!(copy2, "original content").unwrap();
+    #[test]
+    fn test_verify_large_files_compares_ends_before_hashing() {
+        let dir = TempDir::new().unwrap();
+        let span = PARTIAL_HASH_SPAN as usize;
+        let mut content = vec![0u8; 3 * span];
+        fs::write(dir.path().join("clip.mov"), &content).unwrap();
+        content[0] = 1;
+        fs::write(dir.path().join("clip 2.mov"), &content).unwrap();
+        content[0] = 0;
+        content[span + 1] = 1;
+        fs::write(dir.path().join("clip 3.mov"), &content).unwrap();
+
+        let candidate = |name: &str| ConflictCandidate {
+            path: dir.path().join(name),
+            pattern: ConflictPattern::Numbered { index: 2 },
+            presumed_original: dir.path().join("clip.mov"),
+            kind: FileKind::Regular,
+            sync_state: ItemState::Local,
+        };
 
-        dir
+        // Differs at the start: settled without a full hash
+        let result = verify_with(&candidate("clip 2.mov"), VerificationLevel::Strict, HashAlgorithm::Blake3, |_| {
+            panic!("should not hash")
+        })
+        .unwrap();
+        assert!(matches!(
+            result,
+            VerificationResult::ContentDiverged { divergence: Divergence::Ends { .. }, .. }
+        ));
+
+        // Differs only in the middle: needs the full hash
+        let result = verify_candidate(&candidate("clip 3.mov")).unwrap();
+        assert!(matches!(
+            result,
+            VerificationResult::ContentDiverged { divergence: Divergence::Content { .. }, .. }
+        ));
     }
 
-    /// Helper: create orphaned conflict (no original)
-    fn setup_orphaned_conflict() -> TempDir {
+    // --- scan with config tests ---
+
+    #[test]
+    #[cfg(unix)]
+    fn test_follow_symlinks_survives_loops() {
         let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("a.txt"), "x").unwrap();
+        fs::write(sub.join("a Copy.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path(), sub.join("back")).unwrap();
 
-        // Conflict file without original
-        let mut orphan = File::create(dir.path().join("missing Copy.txt")).unwrap();
-        writeln!(orphan, "orphaned content").unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let discovery = discover(&config, |_| {}).unwrap();
 
-        dir
+        assert_eq!(discovery.candidates.len(), 1);
+        assert_eq!(discovery.symlink_skips.len(), 1);
+        assert_eq!(discovery.symlink_skips[0].0, sub.join("back"));
     }
 
-    /// Helper: create diverged conflict (different content)
-    fn setup_diverged_conflict() -> TempDir {
+    #[test]
+    #[cfg(unix)]
+    fn test_follow_symlinks_walks_each_directory_once() {
         let dir = TempDir::new().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        fs::write(real.join("a.txt"), "x").unwrap();
+        fs::write(real.join("a Copy.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("alias")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("gone"), dir.path().join("broken")).unwrap();
 
-        // Original file
-        let mut original = File::create(dir.path().join("file.txt")).unwrap();
-        writeln!(original, "version A").unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
 
-        // Conflict with different content
-        let mut conflict = File::create(dir.path().join("file Copy.txt")).unwrap();
-        writeln!(conflict, "version B - modified").unwrap();
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 1);
 
-        dir
+        let mut reasons: Vec<&str> = report.symlink_skips.iter().map(|(_, r)| r.as_str()).collect();
+        reasons.sort();
+        assert_eq!(
+            reasons,
+            vec!["broken symlink", "directory already scanned through another path"]
+        );
     }
 
-    // --- scan_dir tests ---
-
     #[test]
-    fn test_scan_finds_confirmed_duplicates() {
-        let dir = setup_test_dir();
-        let report = scan_dir(dir.path()).unwrap();
+    fn test_top_level_dir_uses_deepest_root() {
+        let roots = vec![PathBuf::from("/icloud"), PathBuf::from("/icloud/Photos")];
 
-        // Should find duplicates: "document Copy.txt" and "document Copy 2.txt"
-        assert_eq!(report.confirmed_duplicates.len(), 1); // One group
-        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2); // Two copies
+        assert_eq!(
+            top_level_dir(Path::new("/icloud/Docs/work/a 2.txt"), &roots),
+            PathBuf::from("/icloud/Docs")
+        );
+        assert_eq!(
+            top_level_dir(Path::new("/icloud/Photos/2024/b 2.jpg"), &roots),
+            PathBuf::from("/icloud/Photos/2024")
+        );
+        assert_eq!(top_level_dir(Path::new("/icloud/c 2.txt"), &roots), PathBuf::from("/icloud"));
+        assert_eq!(top_level_dir(Path::new("/elsewhere/d 2.txt"), &roots), PathBuf::from("/elsewhere"));
     }
 
     #[test]
-    fn test_scan_finds_orphaned_conflicts() {
-        let dir = setup_orphaned_conflict();
-        let report = scan_dir(dir.path()).unwrap();
+    fn test_scan_rolls_up_duplicates_by_folder() {
+        let dir = TempDir::new().unwrap();
+        let docs = dir.path().join("Docs");
+        let notes = dir.path().join("Notes");
+        fs::create_dir_all(docs.join("deep")).unwrap();
+        fs::create_dir(&notes).unwrap();
+        fs::write(docs.join("deep/a.txt"), "aaaaaaaa").unwrap();
+        fs::write(docs.join("deep/a 2.txt"), "aaaaaaaa").unwrap();
+        fs::write(docs.join("b.txt"), "bbbb").unwrap();
+        fs::write(docs.join("b 2.txt"), "bbbb").unwrap();
+        fs::write(notes.join("c.txt"), "c").unwrap();
+        fs::write(notes.join("c 2.txt"), "c").unwrap();
 
-        assert_eq!(report.orphaned_conflicts.len(), 1);
-        assert!(report.confirmed_duplicates.is_empty());
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
+
+        assert_eq!(report.by_directory.len(), 2);
+        assert_eq!(report.by_directory[0].path, docs);
+        assert_eq!(report.by_directory[0].duplicates, 2);
+        assert_eq!(report.by_directory[0].bytes_recoverable, 12);
+        assert_eq!(report.by_directory[1].path, notes);
+        assert_eq!(report.by_directory[1].bytes_recoverable, 1);
+
+        assert_eq!(report.by_parent.len(), 3);
+        assert_eq!(report.by_parent[0].path, docs.join("deep"));
+        assert_eq!(report.by_parent[0].bytes_recoverable, 8);
+        assert_eq!(report.by_parent[1].path, docs);
+        assert_eq!(report.by_parent[2].path, notes);
     }
 
     #[test]
-    fn test_scan_finds_diverged_conflicts() {
-        let dir = setup_diverged_conflict();
-        let report = scan_dir(dir.path()).unwrap();
-
-        assert_eq!(report.content_diverged.len(), 1);
-        assert!(report.confirmed_duplicates.is_empty());
+    fn rollup_by_extension_folds_case_and_sorts_by_size() {
+        let group = |name: &str, copies: usize, size_bytes: u64| DuplicateGroup {
+            original: PathBuf::from(format!("/d/{name}")),
+            duplicates: (0..copies).map(|i| PathBuf::from(format!("/d/{i} {name}"))).collect(),
+            size_bytes,
+            hash: ContentHash([0; 32]),
+        };
+        let groups = [group("a.mov", 1, 500), group("b.MOV", 2, 100), group("c.psd", 1, 600), group("README", 1, 5)];
+
+        let rollup = rollup_by_extension(&groups);
+        let summary: Vec<(&str, usize, u64)> = rollup
+            .iter()
+            .map(|r| (r.extension.as_str(), r.duplicates, r.bytes_recoverable))
+            .collect();
+        assert_eq!(summary, [(".mov", 3, 700), (".psd", 1, 600), ("", 1, 5)]);
     }
 
     #[test]
-    fn test_scan_empty_dir() {
-        let dir = TempDir::new().unwrap();
-        let report = scan_dir(dir.path()).unwrap();
+    fn rollup_by_parent_keeps_the_largest() {
+        let group = |dir: &str| DuplicateGroup {
+            original: PathBuf::from(format!("/nonexistent/{dir}/a")),
+            duplicates: vec![PathBuf::from(format!("/nonexistent/{dir}/a 2"))],
+            size_bytes: 0,
+            hash: ContentHash([0; 32]),
+        };
+        let groups = [group("x"), group("y"), group("x")];
 
-        assert!(report.confirmed_duplicates.is_empty());
-        assert!(report.orphaned_conflicts.is_empty());
-        assert!(report.content_diverged.is_empty());
-        assert_eq!(report.bytes_recoverable, 0);
+        let rollup = rollup_by_parent(&groups, 1);
+        assert_eq!(rollup.len(), 1);
+        assert_eq!(rollup[0].path, PathBuf::from("/nonexistent/x"));
+        assert_eq!(rollup[0].duplicates, 2);
     }
 
     #[test]
-    fn test_scan_no_conflicts() {
+    fn test_file_budget_stops_walk_and_marks_partial() {
         let dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("f{}.txt", i)), "x").unwrap();
+            fs::write(dir.path().join(format!("f{} 2.txt", i)), "x").unwrap();
+        }
 
-        // Normal files, no conflict patterns
-        File::create(dir.path().join("file1.txt")).unwrap();
-        File::create(dir.path().join("file2.txt")).unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            max_files: Some(4),
+            ..Default::default()
+        };
+        let discovery = discover(&config, |_| {}).unwrap();
+        let mut report = assemble_report(verify_candidates(&discovery.candidates, &config, |_| {}));
+        finish_report(&mut report, &config);
 
-        let report = scan_dir(dir.path()).unwrap();
+        assert!(discovery.candidates.len() <= 4);
+        assert!(report.partial.unwrap().contains("4 files"));
 
-        assert!(report.confirmed_duplicates.is_empty());
-        assert!(report.orphaned_conflicts.is_empty());
-        assert!(report.content_diverged.is_empty());
+        // A budget the tree fits in is never reported as spent
+        let roomy = ScanConfig {
+            roots: config.roots.clone(),
+            max_files: Some(10),
+            ..Default::default()
+        };
+        assert!(scan(&roomy).unwrap().partial.is_none());
+
+        // A fresh walk restarts the budget
+        let full = ScanConfig {
+            roots: config.roots.clone(),
+            ..Default::default()
+        };
+        assert!(scan(&full).unwrap().partial.is_none());
     }
 
     #[test]
-    fn test_scan_calculates_bytes_recoverable() {
+    fn test_spent_time_budget_leaves_candidates_unverified() {
         let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "x").unwrap();
+        fs::write(dir.path().join("a 2.txt"), "x").unwrap();
 
-        // Original
-        let mut original = File::create(dir.path().join("data.txt")).unwrap();
-        write!(original, "12345").unwrap(); // 5 bytes
-
-        // Copy with same content
-        let mut copy = File::create(dir.path().join("data Copy.txt")).unwrap();
-        write!(copy, "12345").unwrap(); // 5 bytes
-
-        let report = scan_dir(dir.path()).unwrap();
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            max_duration: Some(std::time::Duration::ZERO),
+            ..Default::default()
+        };
+        let report = scan(&config).unwrap();
 
-        assert_eq!(report.bytes_recoverable, 5); // Can recover 5 bytes
+        assert!(report.confirmed_duplicates.is_empty());
+        assert!(report.partial.unwrap().contains("time budget"));
     }
 
-    // --- scan with config tests ---
+    #[test]
+    fn test_is_ignored_dir_matches_names_and_extensions() {
+        let patterns: Vec<String> = DEFAULT_IGNORES.iter().map(|s| s.to_string()).collect();
+
+        assert!(is_ignored_dir("node_modules", &patterns));
+        assert!(is_ignored_dir(".GIT", &patterns));
+        assert!(is_ignored_dir("Photos Library.photoslibrary", &patterns));
+        assert!(!is_ignored_dir("photoslibrary", &patterns));
+        assert!(!is_ignored_dir("src", &patterns));
+    }
 
     #[test]
-    fn test_scan_with_config() {
-        let dir = setup_test_dir();
+    fn test_scan_skips_ignored_directories_but_not_roots() {
+        let dir = TempDir::new().unwrap();
+        let modules = dir.path().join("node_modules");
+        fs::create_dir(&modules).unwrap();
+        fs::write(modules.join("index.js"), "x").unwrap();
+        fs::write(modules.join("index 2.js"), "x").unwrap();
 
         let config = ScanConfig {
             roots: vec![dir.path().to_path_buf()],
             ..Default::default()
         };
+        assert!(scan(&config).unwrap().confirmed_duplicates.is_empty());
 
-        let report = scan(&config).unwrap();
+        // Scanning the ignored directory directly still works
+        let config = ScanConfig {
+            roots: vec![modules.clone()],
+            ..Default::default()
+        };
+        assert_eq!(scan(&config).unwrap().confirmed_duplicates.len(), 1);
 
-        assert_eq!(report.confirmed_duplicates.len(), 1);
+        // So does an emptied ignore list
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ignore: vec![],
+            ..Default::default()
+        };
+        assert_eq!(scan(&config).unwrap().confirmed_duplicates.len(), 1);
     }
 
-    // --- find_candidates tests (pattern-only, no hashing) ---
-
     #[test]
-    fn test_find_candidates_returns_candidates() {
-        let dir = setup_test_dir();
+    #[cfg(unix)]
+    fn test_locked_files_are_skipped_as_in_use() {
+        use std::os::unix::io::AsRawFd;
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("doc.txt"), "old").unwrap();
+        fs::write(dir.path().join("doc 2.txt"), "new").unwrap();
+        let holder = File::open(dir.path().join("doc 2.txt")).unwrap();
+        assert_eq!(unsafe { libc::flock(holder.as_raw_fd(), libc::LOCK_EX) }, 0);
+
         let config = ScanConfig {
             roots: vec![dir.path().to_path_buf()],
             ..Default::default()
         };
-
         let candidates = find_candidates(&config).unwrap();
+        let err = verify_candidate(&candidates[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
 
-        // Should find 2 candidates: "document Copy.txt" and "document Copy 2.txt"
-        assert_eq!(candidates.len(), 2);
+        let report = scan(&config).unwrap();
+        assert!(report.content_diverged.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].1.starts_with("file in use"));
     }
 
     #[test]
-    fn test_find_candidates_no_hashing_occurs() {
-        // Even with diverged content, find_candidates should return them
-        let dir = setup_diverged_conflict();
+    fn test_recently_modified_pairs_are_skipped() {
+        let dir = setup_test_dir();
         let config = ScanConfig {
             roots: vec![dir.path().to_path_buf()],
+            ignore_newer_than: Some(Duration::from_secs(3600)),
             ..Default::default()
         };
 
-        let candidates = find_candidates(&config).unwrap();
+        let report = scan(&config).unwrap();
 
-        // Should find the candidate (hashing hasn't happened yet)
-        assert_eq!(candidates.len(), 1);
-        assert!(candidates[0].path.to_string_lossy().contains("Copy"));
+        assert!(report.confirmed_duplicates.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+        assert!(report.skipped[0].1.contains("too recently to touch"));
+
+        let settled = ScanConfig {
+            ignore_newer_than: Some(Duration::ZERO),
+            ..config
+        };
+        assert_eq!(scan(&settled).unwrap().confirmed_duplicates.len(), 1);
     }
 
     #[test]
-    fn test_find_candidates_includes_orphaned() {
-        let dir = setup_orphaned_conflict();
+    fn test_materialize_verifies_what_arrives_in_time() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".photo 2.heic.icloud"), "plist").unwrap();
+        fs::write(dir.path().join("photo.heic"), "pixels").unwrap();
+        fs::write(dir.path().join("notes 2.txt"), "text").unwrap();
+        fs::write(dir.path().join(".notes.txt.icloud"), "plist").unwrap();
         let config = ScanConfig {
             roots: vec![dir.path().to_path_buf()],
             ..Default::default()
         };
-
         let candidates = find_candidates(&config).unwrap();
 
-        // Should find orphaned candidate (original existence not checked yet)
-        assert_eq!(candidates.len(), 1);
+        // Only the photo downloads; the notes original never arrives
+        let requested = std::sync::Mutex::new(Vec::new());
+        let download = |path: &Path| {
+            requested.lock().unwrap().push(path.to_path_buf());
+            if path.ends_with("photo 2.heic") {
+                fs::write(path, "pixels")?;
+                fs::remove_file(crate::platform::placeholder_stub_path(path).unwrap())?;
+            }
+            Ok(())
+        };
+        let mut waiting = Vec::new();
+        let refreshed = materialize_candidates(
+            &candidates,
+            Duration::from_millis(300),
+            &CancelToken::new(),
+            download,
+            |n| waiting.push(n),
+        );
+
+        assert_eq!(requested.lock().unwrap().len(), 2);
+        assert_eq!(waiting.first(), Some(&1));
+        let report = assemble_report(verify_candidates(&refreshed, &config, |_| {}));
+        assert_eq!(report.confirmed_duplicates.len(), 1);
+        assert_eq!(report.not_local, vec![dir.path().join("notes 2.txt")]);
     }
 
     #[test]
-    fn test_find_candidates_empty_for_no_conflicts() {
+    fn test_materialize_without_download_support_changes_nothing() {
         let dir = TempDir::new().unwrap();
-        File::create(dir.path().join("normal.txt")).unwrap();
-
+        fs::write(dir.path().join(".photo 2.heic.icloud"), "plist").unwrap();
         let config = ScanConfig {
             roots: vec![dir.path().to_path_buf()],
             ..Default::default()
         };
-
         let candidates = find_candidates(&config).unwrap();
-        assert!(candidates.is_empty());
-    }
+        let unsupported = |_: &Path| Err(io::Error::from(io::ErrorKind::Unsupported));
 
-    // --- verify_candidate tests (hash-based, singular) ---
+        let refreshed = materialize_candidates(&candidates, Duration::from_secs(60), &CancelToken::new(), unsupported, |_| {
+            panic!("nothing to wait for")
+        });
+        assert_eq!(refreshed[0].kind, FileKind::CloudPlaceholder);
+    }
 
     #[test]
-    fn test_verify_candidate_confirmed_duplicate() {
-        let dir = TempDir::new().unwrap();
+    fn test_wait_for_sync_reports_what_is_still_syncing() {
+        let groups = vec![group("/d/a.txt", &["/d/a 2.txt"]), group("/d/b.txt", &["/d/b 2.txt"])];
+        let state_of = |path: &Path| match path.to_str() {
+            Some("/d/a 2.txt") => ItemState::Uploading,
+            Some("/d/b.txt") => ItemState::Evicted,
+            _ => ItemState::Local,
+        };
 
-        // Original and copy with same content
-        let mut orig = File::create(dir.path().join("doc.txt")).unwrap();
-        writeln!(orig, "same content").unwrap();
-        let mut copy = File::create(dir.path().join("doc Copy.txt")).unwrap();
-        writeln!(copy, "same content").unwrap();
+        let mut waiting = Vec::new();
+        let syncing = wait_for_sync(&groups, Duration::ZERO, &CancelToken::new(), state_of, |n| waiting.push(n));
+        assert_eq!(syncing, vec![(PathBuf::from("/d/a 2.txt"), ItemState::Uploading)]);
+        assert_eq!(waiting, vec![1]);
+
+        let settled = settled_groups(&groups, &syncing);
+        assert_eq!(settled.len(), 1);
+        assert_eq!(settled[0].original, PathBuf::from("/d/b.txt"));
+
+        let idle = wait_for_sync(&groups, Duration::from_secs(60), &CancelToken::new(), |_| ItemState::Local, |_| {});
+        assert!(idle.is_empty());
+    }
 
+    #[test]
+    fn test_eviction_during_verification_is_reported_not_local() {
         let candidate = ConflictCandidate {
-            path: dir.path().join("doc Copy.txt"),
-            pattern: ConflictPattern::Copy { index: None },
-            presumed_original: dir.path().join("doc.txt"),
+            path: PathBuf::from("/icloud/clip 2.mov"),
+            pattern: ConflictPattern::Numbered { index: 2 },
+            presumed_original: PathBuf::from("/icloud/clip.mov"),
             kind: FileKind::Regular,
+            sync_state: ItemState::Local,
         };
+        let evicted = io::Error::other(crate::hash::NotLocalError {
+            path: candidate.presumed_original.clone(),
+        });
 
-        let result = verify_candidate(&candidate).unwrap();
-        assert!(matches!(result, VerificationResult::ConfirmedDuplicate { .. }));
+        let result = evicted_while_verifying(&candidate, evicted).unwrap();
+        assert!(matches!(result, VerificationResult::NotLocal { path, .. } if path == candidate.path));
+        let other = evicted_while_verifying(&candidate, io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(other.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    /// Set an extended attribute; false where the filesystem refuses.
+    fn set_xattr(path: &Path, name: &str, value: &[u8]) -> bool {
+        crate::platform::set_extended_attribute(path, name, value).is_ok()
     }
 
     #[test]
-    fn test_verify_candidate_orphaned() {
-        let dir = TempDir::new().unwrap();
+    fn test_compare_metadata_keeps_copies_whose_xattrs_differ() {
+        let dir = setup_test_dir();
+        let tagged = dir.path().join("document Copy.txt");
+        if !set_xattr(&tagged, "user.icloud-dedupe.note", b"keep me") {
+            return; // no xattr support here
+        }
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
 
-        // Only the copy exists, no original
-        let mut copy = File::create(dir.path().join("missing Copy.txt")).unwrap();
-        writeln!(copy, "orphaned").unwrap();
+        // Content alone: both copies are duplicates
+        let report = scan(&config).unwrap();
+        assert_eq!(report.confirmed_duplicates[0].duplicates.len(), 2);
+        assert!(report.metadata_differs.is_empty());
 
-        let candidate = ConflictCandidate {
-            path: dir.path().join("missing Copy.txt"),
-            pattern: ConflictPattern::Copy { index: None },
-            presumed_original: dir.path().join("missing.txt"), // doesn't exist
-            kind: FileKind::Regular,
+        let config = ScanConfig {
+            compare_metadata: vec![MetadataField::Tags, MetadataField::Xattrs],
+            ..config
         };
-
-        let result = verify_candidate(&candidate).unwrap();
-        assert!(matches!(result, VerificationResult::OrphanedConflict { .. }));
+        let report = scan(&config).unwrap();
+        assert_eq!(report.confirmed_duplicates[0].duplicates, vec![dir.path().join("document Copy 2.txt")]);
+        assert_eq!(
+            report.metadata_differs,
+            vec![MetadataMismatch {
+                conflict: tagged,
+                original: dir.path().join("document.txt"),
+                fields: vec![MetadataField::Xattrs],
+            }]
+        );
+        assert_eq!(report.bytes_recoverable, "original content\n".len() as u64);
     }
 
     #[test]
-    fn test_verify_candidate_diverged() {
+    fn test_fast_level_reports_probable_duplicates_without_hashing() {
         let dir = TempDir::new().unwrap();
-
-        // Original and copy with DIFFERENT content
-        let mut orig = File::create(dir.path().join("file.txt")).unwrap();
-        writeln!(orig, "version A").unwrap();
-        let mut copy = File::create(dir.path().join("file Copy.txt")).unwrap();
-        writeln!(copy, "version B").unwrap();
+        let original = dir.path().join("notes.txt");
+        let conflict = dir.path().join("notes 2.txt");
+        fs::write(&original, "same").unwrap();
+        fs::write(&conflict, "diff").unwrap();
+        let stamp = fs::metadata(&original).unwrap().modified().unwrap();
+        File::options().write(true).open(&conflict).unwrap().set_modified(stamp).unwrap();
 
         let candidate = ConflictCandidate {
-            path: dir.path().join("file Copy.txt"),
-            pattern: ConflictPattern::Copy { index: None },
-            presumed_original: dir.path().join("file.txt"),
+            path: conflict.clone(),
+            pattern: ConflictPattern::Numbered { index: 2 },
+            presumed_original: original.clone(),
             kind: FileKind::Regular,
+            sync_state: ItemState::Local,
         };
+        let result = verify_with(&candidate, VerificationLevel::Fast, HashAlgorithm::Blake3, |_| panic!("hashed")).unwrap();
+        assert!(matches!(result, VerificationResult::ProbableDuplicate { .. }));
 
-        let result = verify_candidate(&candidate).unwrap();
-        assert!(matches!(result, VerificationResult::ContentDiverged { .. }));
-    }
+        // Strict mode hashes and sees the difference
+        let config = ScanConfig {
+            roots: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        };
+        assert_eq!(scan(&config).unwrap().content_diverged.len(), 1);
 
-    // --- scan with config tests ---
+        let fast = ScanConfig {
+            verification_level: VerificationLevel::Fast,
+            ..config
+        };
+        let report = scan(&fast).unwrap();
+        assert_eq!(report.probable_duplicates, vec![(conflict, original)]);
+        assert!(report.confirmed_duplicates.is_empty());
+        assert_eq!(report.bytes_recoverable, 0);
+    }
 
     #[test]
     fn test_scan_respects_max_depth() {
@@ -0,0 +1,584 @@
+//! Read-only verification against an APFS local snapshot.
+//!
+//! Hashing the live tree races with whatever is editing it (including iCloud
+//! itself). In snapshot mode we take — or reuse — an APFS local snapshot,
+//! mount it read-only, verify candidates against the frozen copy, and map
+//! the results back to live paths. Before anything is quarantined, live
+//! files are re-hashed and only moved if they still match.
+//!
+//! Snapshots are created with `tmutil localsnapshot` and mounted with
+//! `mount_apfs -s`. Both exist only on macOS; elsewhere every effect
+//! function returns `ErrorKind::Unsupported`.
+//!
+//! Structure:
+//! - Constants: snapshot naming
+//! - Types: SnapshotMount
+//! - Pure functions: tmutil output parsing, path and report remapping
+//! - Effect functions: create, list, mount, unmount, live re-match
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::hash::{files_match, hash_file};
+use crate::types::{
+    ConflictCandidate, DirectoryRollup, DuplicateGroup, MetadataMismatch, ScanReport, ScanStats,
+};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Prefix of snapshots created by `tmutil localsnapshot`.
+pub const TM_SNAPSHOT_PREFIX: &str = "com.apple.TimeMachine.";
+
+/// Suffix of snapshots created by `tmutil localsnapshot`.
+pub const TM_SNAPSHOT_SUFFIX: &str = ".local";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// A snapshot mounted read-only for the duration of a scan.
+///
+/// Unmounts (and removes the mount point) when dropped.
+#[derive(Debug)]
+pub struct SnapshotMount {
+    /// Snapshot name, e.g. "com.apple.TimeMachine.2024-05-01-101500.local".
+    pub name: String,
+    /// Mount point of the live volume the snapshot was taken of.
+    pub volume: PathBuf,
+    /// Where the snapshot is mounted.
+    pub mount_point: PathBuf,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Extract the snapshot name from `tmutil localsnapshot` output.
+///
+/// tmutil prints "Created local snapshot with date: 2024-05-01-101500".
+pub fn parse_created_snapshot(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (_, date) = line.split_once("date:")?;
+        let date = date.trim();
+        (!date.is_empty()).then(|| format!("{}{}{}", TM_SNAPSHOT_PREFIX, date, TM_SNAPSHOT_SUFFIX))
+    })
+}
+
+/// Extract snapshot names from `tmutil listlocalsnapshots <volume>` output.
+pub fn parse_snapshot_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("com.apple."))
+        .map(String::from)
+        .collect()
+}
+
+/// Path of `path` relative to the volume mounted at `volume`.
+///
+/// Paths that don't start with the mount point are treated as relative to
+/// `/`: on the Data volume, `/Users/...` is a firmlink into
+/// `/System/Volumes/Data/Users/...`.
+pub fn relative_to_volume<'a>(path: &'a Path, volume: &Path) -> &'a Path {
+    path.strip_prefix(volume)
+        .or_else(|_| path.strip_prefix("/"))
+        .unwrap_or(path)
+}
+
+/// Move `path` from under `from` to under `to`. Paths outside `from` are
+/// returned unchanged.
+pub fn rebase(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(rel) => to.join(rel),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+impl SnapshotMount {
+    /// The snapshot copy of a live path.
+    pub fn to_snapshot(&self, live: &Path) -> PathBuf {
+        self.mount_point.join(relative_to_volume(live, &self.volume))
+    }
+
+    /// The live path for a path inside the snapshot mount.
+    pub fn to_live(&self, snapshot: &Path, live_root: &Path) -> PathBuf {
+        let snapshot_root = self.to_snapshot(live_root);
+        rebase(snapshot, &snapshot_root, live_root)
+    }
+
+    /// Point candidates at their snapshot copies.
+    pub fn snapshot_candidates(&self, candidates: &[ConflictCandidate]) -> Vec<ConflictCandidate> {
+        candidates
+            .iter()
+            .map(|c| ConflictCandidate {
+                path: self.to_snapshot(&c.path),
+                pattern: c.pattern.clone(),
+                presumed_original: self.to_snapshot(&c.presumed_original),
+                kind: c.kind.clone(),
+                sync_state: c.sync_state,
+            })
+            .collect()
+    }
+
+    /// Map every path in a report built from the snapshot back to live paths.
+    pub fn live_report(&self, report: ScanReport, live_roots: &[PathBuf]) -> ScanReport {
+        let live = |p: PathBuf| -> PathBuf {
+            live_roots
+                .iter()
+                .find(|root| p.starts_with(self.to_snapshot(root)))
+                .map(|root| self.to_live(&p, root))
+                .unwrap_or(p)
+        };
+
+        ScanReport {
+            confirmed_duplicates: report
+                .confirmed_duplicates
+                .into_iter()
+                .map(|g| DuplicateGroup {
+                    original: live(g.original),
+                    hash: g.hash,
+                    duplicates: g.duplicates.into_iter().map(live).collect(),
+                    size_bytes: g.size_bytes,
+                })
+                .collect(),
+            orphaned_conflicts: report.orphaned_conflicts.into_iter().map(live).collect(),
+            content_diverged: report
+                .content_diverged
+                .into_iter()
+                .map(|mut d| {
+                    d.conflict.path = live(d.conflict.path);
+                    d.original.path = live(d.original.path);
+                    d
+                })
+                .collect(),
+            not_local: report.not_local.into_iter().map(live).collect(),
+            hardlinked: report
+                .hardlinked
+                .into_iter()
+                .map(|(l, o)| (live(l), live(o)))
+                .collect(),
+            probable_duplicates: report
+                .probable_duplicates
+                .into_iter()
+                .map(|(c, o)| (live(c), live(o)))
+                .collect(),
+            metadata_differs: report
+                .metadata_differs
+                .into_iter()
+                .map(|m| MetadataMismatch {
+                    conflict: live(m.conflict),
+                    original: live(m.original),
+                    ..m
+                })
+                .collect(),
+            unsynced: report.unsynced.into_iter().map(|(p, s)| (live(p), s)).collect(),
+            bytes_recoverable: report.bytes_recoverable,
+            keep_policy: report.keep_policy,
+            by_directory: report
+                .by_directory
+                .into_iter()
+                .map(|d| DirectoryRollup {
+                    path: live(d.path),
+                    ..d
+                })
+                .collect(),
+            schema_version: report.schema_version,
+            by_extension: report.by_extension,
+            by_parent: report
+                .by_parent
+                .into_iter()
+                .map(|d| DirectoryRollup {
+                    path: live(d.path),
+                    ..d
+                })
+                .collect(),
+            bytes_recoverable_physical: report.bytes_recoverable_physical,
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|(p, e)| (live(p), e))
+                .collect(),
+            symlink_skips: report
+                .symlink_skips
+                .into_iter()
+                .map(|(p, e)| (live(p), e))
+                .collect(),
+            warnings: report.warnings,
+            partial: report.partial,
+            hash_algorithm: report.hash_algorithm,
+            stats: ScanStats {
+                roots: report.stats.roots.into_iter().map(live).collect(),
+                ..report.stats
+            },
+        }
+    }
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Keep only duplicates whose live files still hash to the snapshot hash.
+///
+/// The original is re-hashed; a group whose original changed is dropped
+/// entirely. Each duplicate is then compared byte for byte against the
+/// original, stopping at the first difference. Returns the surviving groups
+/// and the paths that no longer match.
+pub fn rematch_live(groups: &[DuplicateGroup]) -> (Vec<DuplicateGroup>, Vec<PathBuf>) {
+    let mut kept = Vec::new();
+    let mut changed = Vec::new();
+
+    for group in groups {
+        if hash_file(&group.original).ok().as_ref() != Some(&group.hash) {
+            changed.extend(group.duplicates.iter().cloned());
+            continue;
+        }
+
+        let (same, differ): (Vec<PathBuf>, Vec<PathBuf>) = group
+            .duplicates
+            .iter()
+            .cloned()
+            .partition(|dup| files_match(&group.original, dup).unwrap_or(false));
+
+        changed.extend(differ);
+        if !same.is_empty() {
+            kept.push(DuplicateGroup {
+                original: group.original.clone(),
+                hash: group.hash.clone(),
+                duplicates: same,
+                size_bytes: group.size_bytes,
+            });
+        }
+    }
+
+    (kept, changed)
+}
+
+/// Create a new APFS local snapshot and return its name.
+#[cfg(target_os = "macos")]
+pub fn create_snapshot() -> io::Result<String> {
+    let output = run_tool("tmutil", &["localsnapshot"])?;
+    parse_created_snapshot(&output).ok_or_else(|| {
+        io::Error::other(format!("Unexpected tmutil output: {}", output.trim()))
+    })
+}
+
+/// Create a new APFS local snapshot and return its name.
+#[cfg(not(target_os = "macos"))]
+pub fn create_snapshot() -> io::Result<String> {
+    Err(unsupported())
+}
+
+/// List local snapshots of the volume mounted at `volume`.
+#[cfg(target_os = "macos")]
+pub fn list_snapshots(volume: &Path) -> io::Result<Vec<String>> {
+    let volume = volume.to_string_lossy().into_owned();
+    Ok(parse_snapshot_list(&run_tool(
+        "tmutil",
+        &["listlocalsnapshots", volume.as_str()],
+    )?))
+}
+
+/// List local snapshots of the volume mounted at `volume`.
+#[cfg(not(target_os = "macos"))]
+pub fn list_snapshots(_volume: &Path) -> io::Result<Vec<String>> {
+    Err(unsupported())
+}
+
+/// Mount point of the volume containing `path`.
+#[cfg(target_os = "macos")]
+pub fn volume_mount_point(path: &Path) -> io::Result<PathBuf> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mount = unsafe { CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+    Ok(PathBuf::from(mount.to_string_lossy().into_owned()))
+}
+
+/// Mount point of the volume containing `path`.
+#[cfg(not(target_os = "macos"))]
+pub fn volume_mount_point(_path: &Path) -> io::Result<PathBuf> {
+    Err(unsupported())
+}
+
+/// Mount a snapshot of `volume` read-only under a fresh temp directory.
+#[cfg(target_os = "macos")]
+pub fn mount_snapshot(name: &str, volume: &Path) -> io::Result<SnapshotMount> {
+    let mount_point = std::env::temp_dir().join(format!(
+        "icloud-dedupe-snapshot-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&mount_point)?;
+
+    let volume_str = volume.to_string_lossy().into_owned();
+    let mount_str = mount_point.to_string_lossy().into_owned();
+    if let Err(e) = run_tool(
+        "mount_apfs",
+        &["-o", "rdonly,nobrowse", "-s", name, volume_str.as_str(), mount_str.as_str()],
+    ) {
+        let _ = std::fs::remove_dir(&mount_point);
+        return Err(e);
+    }
+
+    Ok(SnapshotMount {
+        name: name.to_string(),
+        volume: volume.to_path_buf(),
+        mount_point,
+    })
+}
+
+/// Mount a snapshot of `volume` read-only under a fresh temp directory.
+#[cfg(not(target_os = "macos"))]
+pub fn mount_snapshot(_name: &str, _volume: &Path) -> io::Result<SnapshotMount> {
+    Err(unsupported())
+}
+
+/// Find the snapshot to use for `roots`: `existing` if given (it must be
+/// listed for the volume), otherwise a newly created one. Then mount it.
+///
+/// All roots must live on the same volume.
+pub fn prepare_snapshot(roots: &[PathBuf], existing: Option<&str>) -> io::Result<SnapshotMount> {
+    let mut volume: Option<PathBuf> = None;
+    for root in roots {
+        let v = volume_mount_point(root)?;
+        match &volume {
+            Some(first) if *first != v => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Snapshot mode needs all roots on one volume ({} vs {})",
+                        first.display(),
+                        v.display()
+                    ),
+                ));
+            }
+            _ => volume = Some(v),
+        }
+    }
+    let volume = volume
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No scan roots"))?;
+
+    let name = match existing {
+        Some(name) => {
+            if !list_snapshots(&volume)?.iter().any(|s| s == name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No snapshot named {} on {}", name, volume.display()),
+                ));
+            }
+            name.to_string()
+        }
+        None => create_snapshot()?,
+    };
+
+    mount_snapshot(&name, &volume)
+}
+
+impl Drop for SnapshotMount {
+    fn drop(&mut self) {
+        #[cfg(target_os = "macos")]
+        {
+            let mount_str = self.mount_point.to_string_lossy().into_owned();
+            let _ = run_tool("umount", &[mount_str.as_str()]);
+            let _ = std::fs::remove_dir(&self.mount_point);
+        }
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Run a system tool, returning stdout or an error carrying stderr.
+#[cfg(target_os = "macos")]
+fn run_tool(program: &str, args: &[&str]) -> io::Result<String> {
+    let output = std::process::Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "APFS snapshots are only available on macOS",
+    )
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ContentHash, HashAlgorithm, KeepPolicy};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn mount() -> SnapshotMount {
+        SnapshotMount {
+            name: "com.apple.TimeMachine.2024-05-01-101500.local".to_string(),
+            volume: PathBuf::from("/System/Volumes/Data"),
+            mount_point: PathBuf::from("/tmp/snap"),
+        }
+    }
+
+    #[test]
+    fn test_parse_created_snapshot() {
+        let out = "NOTE: local snapshots are considered purgeable\nCreated local snapshot with date: 2024-05-01-101500\n";
+        assert_eq!(
+            parse_created_snapshot(out).as_deref(),
+            Some("com.apple.TimeMachine.2024-05-01-101500.local")
+        );
+        assert_eq!(parse_created_snapshot("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_snapshot_list() {
+        let out = "Snapshots for disk /:\ncom.apple.TimeMachine.2024-05-01-101500.local\ncom.apple.os.update-ABC\n";
+        assert_eq!(
+            parse_snapshot_list(out),
+            vec![
+                "com.apple.TimeMachine.2024-05-01-101500.local".to_string(),
+                "com.apple.os.update-ABC".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_to_volume_handles_firmlinks() {
+        let volume = Path::new("/System/Volumes/Data");
+        assert_eq!(
+            relative_to_volume(Path::new("/System/Volumes/Data/Users/a"), volume),
+            Path::new("Users/a")
+        );
+        assert_eq!(relative_to_volume(Path::new("/Users/a"), volume), Path::new("Users/a"));
+    }
+
+    #[test]
+    fn test_snapshot_and_live_paths_round_trip() {
+        let m = mount();
+        let root = PathBuf::from("/Users/a/Docs");
+        let live = root.join("x Copy.txt");
+
+        let snap = m.to_snapshot(&live);
+        assert_eq!(snap, PathBuf::from("/tmp/snap/Users/a/Docs/x Copy.txt"));
+        assert_eq!(m.to_live(&snap, &root), live);
+    }
+
+    #[test]
+    fn test_live_report_remaps_all_paths() {
+        let m = mount();
+        let root = PathBuf::from("/Users/a");
+        let snap = |p: &str| m.to_snapshot(&root.join(p));
+
+        let report = ScanReport {
+            confirmed_duplicates: vec![DuplicateGroup {
+                original: snap("x.txt"),
+                hash: ContentHash([1; 32]),
+                duplicates: vec![snap("x Copy.txt")],
+                size_bytes: 0,
+            }],
+            orphaned_conflicts: vec![snap("y 2.txt")],
+            content_diverged: vec![(snap("z 2.txt"), snap("z.txt")).into()],
+            not_local: vec![snap("v 2.txt")],
+            hardlinked: vec![(snap("u 2.txt"), snap("u.txt"))],
+            probable_duplicates: vec![],
+            metadata_differs: vec![],
+            bytes_recoverable: 10,
+            keep_policy: KeepPolicy::OriginalName,
+            by_directory: vec![],
+            bytes_recoverable_physical: None,
+            skipped: vec![(snap("w 2.txt"), "denied".to_string())],
+            symlink_skips: vec![],
+            warnings: vec![],
+            partial: None,
+            stats: ScanStats::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            by_parent: vec![],
+            schema_version: 0,
+            by_extension: vec![],
+            unsynced: Vec::new(),
+        };
+
+        let live = m.live_report(report, std::slice::from_ref(&root));
+
+        assert_eq!(live.confirmed_duplicates[0].original, root.join("x.txt"));
+        assert_eq!(live.confirmed_duplicates[0].duplicates[0], root.join("x Copy.txt"));
+        assert_eq!(live.orphaned_conflicts[0], root.join("y 2.txt"));
+        assert_eq!(live.content_diverged[0].conflict.path, root.join("z 2.txt"));
+        assert_eq!(live.content_diverged[0].original.path, root.join("z.txt"));
+        assert_eq!(live.not_local[0], root.join("v 2.txt"));
+        assert_eq!(live.hardlinked[0], (root.join("u 2.txt"), root.join("u.txt")));
+        assert_eq!(live.skipped[0].0, root.join("w 2.txt"));
+        assert_eq!(live.bytes_recoverable, 10);
+    }
+
+    #[test]
+    fn test_rematch_live_drops_changed_files() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("a.txt");
+        let same = dir.path().join("a Copy.txt");
+        let edited = dir.path().join("a Copy 2.txt");
+        fs::write(&original, "data").unwrap();
+        fs::write(&same, "data").unwrap();
+        fs::write(&edited, "data, edited since the snapshot").unwrap();
+
+        let groups = vec![DuplicateGroup {
+            original: original.clone(),
+            hash: hash_file(&original).unwrap(),
+            duplicates: vec![same.clone(), edited.clone()],
+            size_bytes: 0,
+        }];
+
+        let (kept, changed) = rematch_live(&groups);
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].duplicates, vec![same]);
+        assert_eq!(changed, vec![edited]);
+    }
+
+    #[test]
+    fn test_rematch_live_drops_group_when_original_changed() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("a.txt");
+        let dup = dir.path().join("a Copy.txt");
+        fs::write(&original, "new").unwrap();
+        fs::write(&dup, "old").unwrap();
+
+        let groups = vec![DuplicateGroup {
+            original,
+            hash: hash_file(&dup).unwrap(),
+            duplicates: vec![dup.clone()],
+            size_bytes: 0,
+        }];
+
+        let (kept, changed) = rematch_live(&groups);
+        assert!(kept.is_empty());
+        assert_eq!(changed, vec![dup]);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_effects_unsupported_off_macos() {
+        let err = prepare_snapshot(&[PathBuf::from("/")], None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
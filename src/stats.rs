@@ -0,0 +1,323 @@
+//! Quarantine history: what each session quarantined, and what became of it.
+//!
+//! Every quarantine, restore, and purge appends a line per session to a
+//! small log in the quarantine directory. Unlike the manifest, the log
+//! outlives the files it describes, so `stats` can show how much space
+//! purges have recovered over time.
+//!
+//! The log is informational: a line that doesn't parse is skipped, and a
+//! failure to append never fails the operation being logged.
+//!
+//! Structure:
+//! - Types: log entries, tallies, the summarized statistics
+//! - Pure functions: entries from receipts, summarize
+//! - Effect functions: load, append
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::format_date;
+use crate::types::QuarantineReceipt;
+
+/// History log filename within the quarantine directory.
+const HISTORY_FILENAME: &str = "history.jsonl";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// What happened to a batch of quarantined files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Quarantined,
+    Restored,
+    Purged,
+}
+
+/// One line of the log: files of one session handled together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When it happened, seconds since the Unix epoch.
+    pub at: u64,
+    pub outcome: Outcome,
+    /// Session the files were quarantined in (None for receipts from
+    /// before sessions were recorded).
+    pub session: Option<String>,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A count of files and their size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// One quarantine session and what became of its files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionHistory {
+    pub id: Option<String>,
+    /// When the session's first entry was logged, seconds since the Unix epoch.
+    pub started_at: u64,
+    pub quarantined: Tally,
+    pub restored: Tally,
+    pub purged: Tally,
+}
+
+/// Activity in one calendar month (UTC).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonthStats {
+    /// "YYYY-MM".
+    pub month: String,
+    pub quarantined: Tally,
+    pub restored: Tally,
+    pub purged: Tally,
+    /// Bytes purged up to the end of this month.
+    pub recovered_to_date: u64,
+}
+
+/// Everything the log says, totalled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QuarantineStats {
+    /// When the first entry was logged, if any.
+    pub since: Option<u64>,
+    pub quarantined: Tally,
+    pub restored: Tally,
+    /// Purged files: the space actually recovered.
+    pub purged: Tally,
+    /// Quarantined, neither restored nor purged yet.
+    pub pending: Tally,
+    /// Sessions, oldest first.
+    pub sessions: Vec<SessionHistory>,
+    /// Months with any activity, oldest first.
+    pub months: Vec<MonthStats>,
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+/// Location of the log within a quarantine directory.
+pub fn history_log_path(quarantine_dir: &Path) -> PathBuf {
+    quarantine_dir.join(HISTORY_FILENAME)
+}
+
+impl Tally {
+    fn add(&mut self, files: usize, bytes: u64) {
+        self.files += files;
+        self.bytes += bytes;
+    }
+}
+
+/// Log entries for receipts that just had `outcome`, one per session in
+/// the order sessions first appear.
+pub fn entries_for(receipts: &[QuarantineReceipt], outcome: Outcome, at: u64) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    for receipt in receipts {
+        match entries.iter_mut().find(|e| e.session == receipt.session) {
+            Some(entry) => {
+                entry.files += 1;
+                entry.bytes += receipt.size_bytes;
+            }
+            None => entries.push(HistoryEntry {
+                at,
+                outcome,
+                session: receipt.session.clone(),
+                files: 1,
+                bytes: receipt.size_bytes,
+            }),
+        }
+    }
+    entries
+}
+
+/// Total up log entries, per session and per month.
+pub fn summarize(entries: &[HistoryEntry]) -> QuarantineStats {
+    let mut stats = QuarantineStats {
+        since: entries.iter().map(|e| e.at).min(),
+        ..Default::default()
+    };
+    let mut months: BTreeMap<String, MonthStats> = BTreeMap::new();
+
+    for entry in entries {
+        let session = match stats.sessions.iter_mut().position(|s| s.id == entry.session) {
+            Some(i) => &mut stats.sessions[i],
+            None => {
+                stats.sessions.push(SessionHistory {
+                    id: entry.session.clone(),
+                    started_at: entry.at,
+                    quarantined: Tally::default(),
+                    restored: Tally::default(),
+                    purged: Tally::default(),
+                });
+                stats.sessions.last_mut().expect("just pushed")
+            }
+        };
+        session.started_at = session.started_at.min(entry.at);
+
+        let month_key = format_date(entry.at)[..7].to_string();
+        let month = months.entry(month_key.clone()).or_insert_with(|| MonthStats {
+            month: month_key,
+            quarantined: Tally::default(),
+            restored: Tally::default(),
+            purged: Tally::default(),
+            recovered_to_date: 0,
+        });
+
+        let (total, per_session, per_month) = match entry.outcome {
+            Outcome::Quarantined => (&mut stats.quarantined, &mut session.quarantined, &mut month.quarantined),
+            Outcome::Restored => (&mut stats.restored, &mut session.restored, &mut month.restored),
+            Outcome::Purged => (&mut stats.purged, &mut session.purged, &mut month.purged),
+        };
+        total.add(entry.files, entry.bytes);
+        per_session.add(entry.files, entry.bytes);
+        per_month.add(entry.files, entry.bytes);
+    }
+
+    stats.sessions.sort_by_key(|s| s.started_at);
+    let mut recovered = 0;
+    stats.months = months
+        .into_values()
+        .map(|mut month| {
+            recovered += month.purged.bytes;
+            month.recovered_to_date = recovered;
+            month
+        })
+        .collect();
+    stats.pending = Tally {
+        files: stats.quarantined.files.saturating_sub(stats.restored.files + stats.purged.files),
+        bytes: stats.quarantined.bytes.saturating_sub(stats.restored.bytes + stats.purged.bytes),
+    };
+    stats
+}
+
+// ============================================================================
+// EFFECT FUNCTIONS
+// ============================================================================
+
+/// Read the log. A missing log is empty; lines that don't parse are skipped.
+pub fn load_history_log(path: &Path) -> io::Result<Vec<HistoryEntry>> {
+    let contents = match fs::read_to_string(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        result => result?,
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Append entries to the log, creating it if needed.
+pub fn append_history(path: &Path, entries: &[HistoryEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Failed to serialize history entry: {}", e))
+        })?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentHash;
+    use tempfile::TempDir;
+
+    const JUNE: u64 = 1_780_358_400; // 2026-06-02
+    const JULY: u64 = 1_783_036_800; // 2026-07-03
+
+    fn entry(at: u64, outcome: Outcome, session: &str, files: usize, bytes: u64) -> HistoryEntry {
+        HistoryEntry {
+            at,
+            outcome,
+            session: Some(session.to_string()),
+            files,
+            bytes,
+        }
+    }
+
+    fn receipt(session: Option<&str>, size_bytes: u64) -> QuarantineReceipt {
+        QuarantineReceipt {
+            id: "r".to_string(),
+            original_path: PathBuf::from("/a.txt"),
+            quarantine_path: PathBuf::from("/q/a.txt"),
+            hash: ContentHash([0; 32]),
+            quarantined_at: "2026-06-02T00:00:00Z".to_string(),
+            size_bytes,
+            had_xattrs: false,
+            settings: None,
+            session: session.map(str::to_string),
+            metadata: None,
+            provenance: None,
+            file_id: None,
+        }
+    }
+
+    #[test]
+    fn entries_for_groups_by_session() {
+        let receipts = [receipt(Some("a"), 10), receipt(None, 5), receipt(Some("a"), 20)];
+        let entries = entries_for(&receipts, Outcome::Purged, JUNE);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], entry(JUNE, Outcome::Purged, "a", 2, 30));
+        assert_eq!(entries[1].session, None);
+        assert_eq!(entries[1].bytes, 5);
+    }
+
+    #[test]
+    fn summarize_totals_sessions_and_months() {
+        let entries = [
+            entry(JUNE, Outcome::Quarantined, "a", 3, 300),
+            entry(JUNE, Outcome::Restored, "a", 1, 100),
+            entry(JULY, Outcome::Quarantined, "b", 2, 50),
+            entry(JULY, Outcome::Purged, "a", 2, 200),
+        ];
+        let stats = summarize(&entries);
+
+        assert_eq!(stats.since, Some(JUNE));
+        assert_eq!(stats.purged, Tally { files: 2, bytes: 200 });
+        assert_eq!(stats.pending, Tally { files: 2, bytes: 50 });
+
+        assert_eq!(stats.sessions.len(), 2);
+        assert_eq!(stats.sessions[0].id.as_deref(), Some("a"));
+        assert_eq!(stats.sessions[0].restored.files, 1);
+        assert_eq!(stats.sessions[0].purged.files, 2);
+
+        let months: Vec<&str> = stats.months.iter().map(|m| m.month.as_str()).collect();
+        assert_eq!(months, ["2026-06", "2026-07"]);
+        assert_eq!(stats.months[0].recovered_to_date, 0);
+        assert_eq!(stats.months[1].recovered_to_date, 200);
+    }
+
+    #[test]
+    fn log_round_trips_and_skips_bad_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = history_log_path(dir.path());
+        assert!(load_history_log(&path).unwrap().is_empty());
+
+        append_history(&path, &[entry(JUNE, Outcome::Quarantined, "a", 1, 10)]).unwrap();
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{truncated\n").unwrap();
+        append_history(&path, &[entry(JULY, Outcome::Purged, "a", 1, 10)]).unwrap();
+
+        let entries = load_history_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].outcome, Outcome::Purged);
+    }
+}
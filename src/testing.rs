@@ -0,0 +1,214 @@
+//! Property-test harness for conflict pattern detection.
+//!
+//! Generates (original name, conflict transformation) pairs and checks that
+//! turning an original into an iCloud conflict name and back is lossless:
+//! `derive_original(detect_pattern(conflictify(x))) == x`.
+//!
+//! Enabled with the `testing` feature so downstream pattern plugins can run
+//! the same generators and checks against their own detect/derive functions.
+//! The `fuzz/` directory drives the same check from libFuzzer.
+//!
+//! Structure:
+//! - Types: ConflictTransform
+//! - Pure functions: conflictify, plausibility filter, round-trip checks
+//! - Strategies: proptest generators
+
+use std::path::{Path, PathBuf};
+
+use proptest::prelude::*;
+
+use crate::pattern::{derive_original, detect_pattern};
+use crate::types::ConflictPattern;
+
+/// Largest conflict index the generators produce.
+const MAX_GENERATED_INDEX: u32 = 999;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// One of the ways iCloud renames a conflicting file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictTransform {
+    /// "foo.txt" → "foo Copy.txt"
+    Copy,
+    /// "foo.txt" → "foo Copy 2.txt"
+    CopyN(u32),
+    /// "foo.txt" → "foo 2.txt"
+    Numbered(u32),
+}
+
+// ============================================================================
+// PURE FUNCTIONS
+// ============================================================================
+
+impl ConflictTransform {
+    /// Turn an original filename into its conflict name.
+    ///
+    /// The suffix goes before the last extension, as iCloud does.
+    pub fn apply(&self, original: &str) -> String {
+        let (stem, ext) = match original.rfind('.') {
+            Some(pos) if pos > 0 => (&original[..pos], &original[pos..]),
+            _ => (original, ""),
+        };
+
+        match self {
+            ConflictTransform::Copy => format!("{} Copy{}", stem, ext),
+            ConflictTransform::CopyN(n) => format!("{} Copy {}{}", stem, n, ext),
+            ConflictTransform::Numbered(n) => format!("{} {}{}", stem, n, ext),
+        }
+    }
+
+    /// The pattern `detect_pattern` should report for the transformed name.
+    pub fn expected_pattern(&self) -> ConflictPattern {
+        match *self {
+            ConflictTransform::Copy => ConflictPattern::Copy { index: None },
+            ConflictTransform::CopyN(n) => ConflictPattern::Copy { index: Some(n) },
+            ConflictTransform::Numbered(n) => ConflictPattern::Numbered { index: n },
+        }
+    }
+}
+
+/// Could `name` be the original of an iCloud conflict?
+///
+/// Rejects names that can't be filenames, hidden files, names that already
+/// look like conflicts, and names with stray spaces or a trailing dot, which
+/// make the conflict suffix ambiguous.
+pub fn is_plausible_original(name: &str) -> bool {
+    if name.is_empty() || name.contains('/') || name.contains('\0') || name.starts_with('.') {
+        return false;
+    }
+    if name.starts_with(' ') || name.ends_with(' ') || name.ends_with('.') {
+        return false;
+    }
+    if name.contains(" .") || name.contains("  ") {
+        return false;
+    }
+    detect_pattern(name).is_none()
+}
+
+/// Check the round trip with the built-in detector.
+pub fn check_roundtrip(original: &str, transform: ConflictTransform) -> Result<(), String> {
+    check_roundtrip_with(original, transform, detect_pattern, derive_original)
+}
+
+/// Check the round trip with custom detect/derive functions.
+///
+/// Returns a description of the first mismatch.
+pub fn check_roundtrip_with<D, R>(
+    original: &str,
+    transform: ConflictTransform,
+    detect: D,
+    derive: R,
+) -> Result<(), String>
+where
+    D: Fn(&str) -> Option<ConflictPattern>,
+    R: Fn(&Path, &ConflictPattern) -> PathBuf,
+{
+    let conflict = transform.apply(original);
+
+    let pattern = detect(&conflict)
+        .ok_or_else(|| format!("{:?}: no pattern detected in {:?}", transform, conflict))?;
+
+    let expected = transform.expected_pattern();
+    if pattern != expected {
+        return Err(format!(
+            "{:?}: detected {:?} in {:?}, expected {:?}",
+            transform, pattern, conflict, expected
+        ));
+    }
+
+    let derived = derive(Path::new(&conflict), &pattern);
+    if derived != Path::new(original) {
+        return Err(format!(
+            "{:?}: {:?} derived {:?}, expected {:?}",
+            transform, conflict, derived, original
+        ));
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// STRATEGIES
+// ============================================================================
+
+/// Plausible original filenames: a few words plus an optional extension.
+pub fn arb_original_name() -> impl Strategy<Value = String> {
+    // "Copy" as a plain word is the classic way to confuse the detector
+    let word = "([Cc]opy|[A-Za-z][A-Za-z0-9_()'-]{0,11})";
+    let stem = prop::collection::vec(word, 1..4).prop_map(|words| words.join(" "));
+    let ext = prop::option::of("[A-Za-z0-9]{1,5}");
+
+    (stem, ext)
+        .prop_map(|(stem, ext)| match ext {
+            Some(ext) => format!("{}.{}", stem, ext),
+            None => stem,
+        })
+        .prop_filter("must be a plausible original", |name| is_plausible_original(name))
+}
+
+/// Every supported conflict transformation.
+pub fn arb_transform() -> impl Strategy<Value = ConflictTransform> {
+    prop_oneof![
+        Just(ConflictTransform::Copy),
+        (2..=MAX_GENERATED_INDEX).prop_map(ConflictTransform::CopyN),
+        (2..=MAX_GENERATED_INDEX).prop_map(ConflictTransform::Numbered),
+    ]
+}
+
+/// (original, transformation) pairs.
+pub fn arb_conflict_pair() -> impl Strategy<Value = (String, ConflictTransform)> {
+    (arb_original_name(), arb_transform())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_inserts_before_last_extension() {
+        assert_eq!(ConflictTransform::Copy.apply("a.tar.gz"), "a.tar Copy.gz");
+        assert_eq!(ConflictTransform::CopyN(3).apply("notes"), "notes Copy 3");
+        assert_eq!(ConflictTransform::Numbered(2).apply("my file.txt"), "my file 2.txt");
+    }
+
+    #[test]
+    fn test_plausible_original_rejects_conflicts_and_junk() {
+        assert!(is_plausible_original("report.pdf"));
+        assert!(!is_plausible_original("report 2.pdf"));
+        assert!(!is_plausible_original("report Copy.pdf"));
+        assert!(!is_plausible_original(".hidden"));
+        assert!(!is_plausible_original("a/b"));
+        assert!(!is_plausible_original(""));
+    }
+
+    #[test]
+    fn test_check_roundtrip_reports_mismatch() {
+        let result = check_roundtrip_with(
+            "foo.txt",
+            ConflictTransform::Numbered(2),
+            detect_pattern,
+            |path, _| path.with_file_name("wrong.txt"),
+        );
+        assert!(result.unwrap_err().contains("wrong.txt"));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_conflictify_then_derive_is_identity((original, transform) in arb_conflict_pair()) {
+            if let Err(e) = check_roundtrip(&original, transform) {
+                prop_assert!(false, "{}", e);
+            }
+        }
+
+        #[test]
+        fn prop_original_names_are_not_conflicts(original in arb_original_name()) {
+            prop_assert!(detect_pattern(&original).is_none());
+        }
+    }
+}
@@ -1,11 +1,13 @@
 //! TUI module for interactive terminal interface.
 //!
 //! Organized along FP/Unix boundaries:
+//! - `names`: Pure path → unambiguous display name mapping
 //! - `state`: Pure data types (Screen, Action, Transition)
 //! - `update`: Pure state transitions (Screen, Action) → Transition
-//! - `theme`: Color semantics and style constants
+//! - `theme`: Color semantics, style constants, monochrome fallback
 //! - Future: `view` (pure rendering), `run` (effects)
 
+pub mod names;
 pub mod run;
 pub mod state;
 pub mod theme;
@@ -0,0 +1,110 @@
+//! Short, unambiguous display names for lists of paths.
+//!
+//! Lists show filenames, but "IMG_0001.jpg" from five folders reads as the
+//! same file five times. Like editors naming buffers, each path gets the
+//! shortest trailing run of components that no other path in the list
+//! shares: "Vacation/IMG_0001.jpg" vs "Work/IMG_0001.jpg".
+//!
+//! Pure functions — no I/O.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Minimal distinguishing suffix for each path, in input order.
+///
+/// Unique filenames stay bare. Paths sharing a filename grow leading
+/// components until they differ. Identical paths get identical names.
+pub fn display_names<P: AsRef<Path>>(paths: &[P]) -> Vec<String> {
+    let paths: Vec<&Path> = paths.iter().map(AsRef::as_ref).collect();
+    let lens: Vec<usize> = paths.iter().map(|p| p.components().count()).collect();
+    let mut depths = vec![1usize; paths.len()];
+
+    loop {
+        let mut by_suffix: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+        for (i, path) in paths.iter().enumerate() {
+            by_suffix.entry(suffix(path, depths[i])).or_default().push(i);
+        }
+
+        let mut grew = false;
+        for members in by_suffix.values() {
+            let first = paths[members[0]];
+            if members.iter().all(|&i| paths[i] == first) {
+                continue;
+            }
+            for &i in members {
+                if depths[i] < lens[i] {
+                    depths[i] += 1;
+                    grew = true;
+                }
+            }
+        }
+
+        if !grew {
+            break;
+        }
+    }
+
+    paths
+        .iter()
+        .zip(depths)
+        .map(|(path, depth)| suffix(path, depth).display().to_string())
+        .collect()
+}
+
+/// The last `depth` components of `path`.
+fn suffix(path: &Path, depth: usize) -> PathBuf {
+    let components: Vec<_> = path.components().collect();
+    let start = components.len().saturating_sub(depth);
+    components[start..].iter().collect()
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_filenames_stay_bare() {
+        let names = display_names(&["/a/report.pdf", "/b/notes.txt"]);
+        assert_eq!(names, vec!["report.pdf", "notes.txt"]);
+    }
+
+    #[test]
+    fn shared_filenames_gain_parent_dirs() {
+        let names = display_names(&[
+            "/photos/Vacation/IMG_0001.jpg",
+            "/photos/Work/IMG_0001.jpg",
+            "/photos/Work/IMG_0002.jpg",
+        ]);
+        assert_eq!(
+            names,
+            vec!["Vacation/IMG_0001.jpg", "Work/IMG_0001.jpg", "IMG_0002.jpg"]
+        );
+    }
+
+    #[test]
+    fn grows_only_as_far_as_needed() {
+        let names = display_names(&["/x/same/a.txt", "/y/same/a.txt", "/z/other/a.txt"]);
+        assert_eq!(names, vec!["x/same/a.txt", "y/same/a.txt", "other/a.txt"]);
+    }
+
+    #[test]
+    fn nested_path_that_runs_out_of_components() {
+        let names = display_names(&["/a/b.txt", "/x/a/b.txt"]);
+        assert_eq!(names, vec!["/a/b.txt", "x/a/b.txt"]);
+    }
+
+    #[test]
+    fn identical_paths_get_identical_names() {
+        let names = display_names(&["/a/b.txt", "/a/b.txt"]);
+        assert_eq!(names, vec!["b.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(display_names::<&str>(&[]).is_empty());
+    }
+}
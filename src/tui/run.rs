@@ -7,27 +7,68 @@
 //! Architecture: two producer threads feed a single mpsc channel.
 //! - Key reader thread: forwards crossterm key events
 //! - Scanner thread: sends progress updates and the final report
+//!
 //! The event loop consumes from the channel, dispatching to pure handlers.
 
-use std::io;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::thread;
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
 };
 use crossterm::ExecutableCommand;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
-use crate::scanner::{assemble_report, find_candidates_with_progress, verify_candidate};
+use crate::history::{load_history, record_scan};
+use crate::scanner::{assemble_report, discover, finish_report, verify_candidates};
 use crate::types::ScanConfig;
 
 use super::state::{Action, App, AppEvent, Screen, Transition};
-use super::update::{handle_background_event, update};
+use super::theme::strip_colors;
+use super::update::{handle_background_event, toggle_changes, update};
 use super::view::render;
 
+// ============================================================================
+// TERMINAL CAPABILITIES
+// ============================================================================
+
+/// What the attached terminal can handle, decided before entering TUI mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// Switch to the alternate screen (keeps scrollback intact on exit).
+    pub alternate_screen: bool,
+    /// Render colors. When false, only modifiers (bold, reverse) are drawn.
+    pub color: bool,
+}
+
+/// Decide terminal capabilities from environment values (pure).
+///
+/// `term` is the value of `TERM`; `no_color` is whether `NO_COLOR` is set
+/// to a non-empty value. Unset or "dumb" terminals get neither feature;
+/// hardware-style "vt*" terminals get no alternate screen and no color.
+pub fn detect_capabilities(term: Option<&str>, no_color: bool) -> TerminalCapabilities {
+    let term = term.unwrap_or("");
+    let dumb = term.is_empty() || term == "dumb";
+    let legacy = term.starts_with("vt");
+
+    TerminalCapabilities {
+        alternate_screen: !dumb && !legacy,
+        color: !dumb && !legacy && !no_color,
+    }
+}
+
+/// Read terminal capabilities from the process environment.
+fn capabilities_from_env() -> TerminalCapabilities {
+    let term = std::env::var("TERM").ok();
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    detect_capabilities(term.as_deref(), no_color)
+}
+
 // ============================================================================
 // KEY MAPPING
 // ============================================================================
@@ -58,13 +99,14 @@ pub fn map_key(key: KeyEvent) -> Option<Action> {
         KeyCode::Char('q') => Some(Action::Quit),
         KeyCode::Char('s') => Some(Action::Skip),
         KeyCode::Char('o') => Some(Action::OpenFolder),
+        KeyCode::Char('d') => Some(Action::ToggleChanges),
 
         // Confirm
         KeyCode::Char('Y') | KeyCode::Char('y') => Some(Action::ConfirmYes),
         KeyCode::Char('N') => Some(Action::ConfirmNo),
 
         // Number keys for overview navigation
-        KeyCode::Char(c @ '1'..='4') => Some(Action::NumberKey(c as u8 - b'0')),
+        KeyCode::Char(c @ '1'..='5') => Some(Action::NumberKey(c as u8 - b'0')),
 
         _ => None,
     }
@@ -75,27 +117,76 @@ pub fn map_key(key: KeyEvent) -> Option<Action> {
 // ============================================================================
 
 /// Set up the terminal for TUI mode.
-fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
-    enable_raw_mode()?;
-    io::stdout().execute(EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(io::stdout());
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
+///
+/// Fails with `ErrorKind::Unsupported` when stdout is not a terminal or raw
+/// mode cannot be enabled, so the caller can offer a non-TUI fallback. Any
+/// later failure restores the terminal before returning.
+fn setup_terminal(
+    caps: TerminalCapabilities,
+) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    if !io::stdout().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "stdout is not a terminal",
+        ));
+    }
+
+    enable_raw_mode().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("cannot enable raw mode: {}", e),
+        )
+    })?;
+
+    let prepare = || {
+        if caps.alternate_screen {
+            io::stdout().execute(EnterAlternateScreen)?;
+        } else {
+            io::stdout().execute(Clear(ClearType::All))?;
+        }
+        Terminal::new(CrosstermBackend::new(io::stdout()))
+    };
+    prepare().inspect_err(|_| {
+        let _ = restore_terminal(caps);
+    })
 }
 
 /// Restore the terminal to normal mode.
-fn restore_terminal() -> io::Result<()> {
+fn restore_terminal(caps: TerminalCapabilities) -> io::Result<()> {
     disable_raw_mode()?;
-    io::stdout().execute(LeaveAlternateScreen)?;
+    if caps.alternate_screen {
+        io::stdout().execute(LeaveAlternateScreen)?;
+    }
     Ok(())
 }
 
+/// Restores the terminal when dropped, so every way out of the event loop,
+/// errors included, leaves it usable.
+struct TerminalGuard {
+    caps: TerminalCapabilities,
+}
+
+impl TerminalGuard {
+    /// Restore the terminal now, reporting failure.
+    fn restore(self) -> io::Result<()> {
+        let caps = self.caps;
+        std::mem::forget(self);
+        restore_terminal(caps)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore_terminal(self.caps);
+    }
+}
+
 /// Install a panic hook that restores the terminal before printing the panic.
-fn install_panic_hook() {
+fn install_panic_hook(caps: TerminalCapabilities) {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
         // Best-effort terminal restoration
-        let _ = restore_terminal();
+        let _ = restore_terminal(caps);
         original_hook(panic_info);
     }));
 }
@@ -122,36 +213,38 @@ fn spawn_key_reader(tx: mpsc::Sender<AppEvent>) {
 }
 
 /// Spawn a thread that runs the scanner and sends progress/completion events.
-fn spawn_scanner(config: ScanConfig, tx: mpsc::Sender<AppEvent>) {
+///
+/// With a history path, the finished scan is recorded there (best-effort).
+fn spawn_scanner(config: ScanConfig, history: Option<PathBuf>, tx: mpsc::Sender<AppEvent>) {
     thread::spawn(move || {
         // Phase 1: Discovery
         let tx_progress = tx.clone();
-        let candidates = match find_candidates_with_progress(&config, move |scanned, found| {
+        let discovery = match discover(&config, move |progress| {
             // Best-effort: if the receiver is gone, we'll notice on next send
-            let _ = tx_progress.send(AppEvent::ScanProgress {
-                files_scanned: scanned,
-                candidates_found: found,
-            });
+            let _ = tx_progress.send(AppEvent::ScanProgress(*progress));
         }) {
-            Ok(c) => c,
+            Ok(d) => d,
             Err(e) => {
                 let _ = tx.send(AppEvent::ScanError(e.to_string()));
                 return;
             }
         };
 
-        // Phase 2: Verification (parallel with rayon)
-        use rayon::prelude::*;
-
-        let results: Vec<_> = candidates
-            .par_iter()
-            .map(|c| (c.path.clone(), verify_candidate(c)))
-            .collect();
+        // Phase 2: Verification (parallel, reusing indexed verdicts)
+        let results = verify_candidates(&discovery.candidates, &config, |progress| {
+            let _ = tx.send(AppEvent::ScanProgress(*progress));
+        });
 
         // Phase 3: Assemble report
-        let report = assemble_report(results);
+        let mut report = assemble_report(results);
+        finish_report(&mut report, &config);
+        report.symlink_skips = discovery.symlink_skips;
+
+        if let Some(path) = &history {
+            let _ = record_scan(path, &report, &config.roots);
+        }
 
-        let _ = tx.send(AppEvent::ScanComplete(report));
+        let _ = tx.send(AppEvent::ScanComplete(Box::new(report)));
     });
 }
 
@@ -163,20 +256,40 @@ fn spawn_scanner(config: ScanConfig, tx: mpsc::Sender<AppEvent>) {
 ///
 /// This is the main entry point for the TUI. It sets up the terminal,
 /// spawns a scanner thread, and runs the event loop until the user quits.
+///
+/// Terminals without color or alternate-screen support get a degraded
+/// monochrome rendering. If the terminal cannot enter raw mode at all,
+/// returns `ErrorKind::Unsupported` before any scanning starts.
 pub fn run(config: ScanConfig) -> io::Result<()> {
-    install_panic_hook();
-    let mut terminal = setup_terminal()?;
+    run_with_history(config, None)
+}
+
+/// Like [`run`], comparing against and recording into the scan history at
+/// `history`, so the Overview can show what changed since the last scan.
+pub fn run_with_history(config: ScanConfig, history: Option<PathBuf>) -> io::Result<()> {
+    let caps = capabilities_from_env();
+    install_panic_hook(caps);
+    let mut terminal = setup_terminal(caps)?;
+    let guard = TerminalGuard { caps };
     let mut app = App::scanning();
+    app.previous = history
+        .as_deref()
+        .and_then(|path| load_history(path).previous(&config.roots).cloned());
 
     let (tx, rx) = mpsc::channel::<AppEvent>();
 
     // Spawn producer threads
     spawn_key_reader(tx.clone());
-    spawn_scanner(config, tx);
+    spawn_scanner(config, history, tx);
 
     loop {
         // Render
-        terminal.draw(|frame| render(&app, frame))?;
+        terminal.draw(|frame| {
+            render(&app, frame);
+            if !caps.color {
+                strip_colors(frame.buffer_mut());
+            }
+        })?;
 
         // Check quit flag
         if app.should_quit {
@@ -198,6 +311,8 @@ pub fn run(config: ScanConfig) -> io::Result<()> {
                             app.should_quit = true;
                         }
                         // All other actions ignored during scanning
+                    } else if action == Action::ToggleChanges {
+                        toggle_changes(&mut app);
                     } else {
                         let screen = std::mem::take(&mut app.screen);
                         let report_ref = app.report.as_ref().expect("checked above");
@@ -223,8 +338,7 @@ pub fn run(config: ScanConfig) -> io::Result<()> {
         }
     }
 
-    restore_terminal()?;
-    Ok(())
+    guard.restore()
 }
 
 // ============================================================================
@@ -300,7 +414,7 @@ mod tests {
 
     #[test]
     fn number_keys_map_to_number_actions() {
-        for n in 1..=4u8 {
+        for n in 1..=5u8 {
             let key = KeyEvent::new(KeyCode::Char((b'0' + n) as char), KeyModifiers::NONE);
             assert_eq!(map_key(key), Some(Action::NumberKey(n)));
         }
@@ -312,12 +426,48 @@ mod tests {
         assert_eq!(map_key(key), None);
     }
 
+    #[test]
+    fn d_toggles_changes() {
+        let key = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE);
+        assert_eq!(map_key(key), Some(Action::ToggleChanges));
+    }
+
     #[test]
     fn enter_maps_to_enter_action() {
         let key = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
         assert_eq!(map_key(key), Some(Action::Enter));
     }
 
+    #[test]
+    fn capabilities_full_for_modern_terminal() {
+        let caps = detect_capabilities(Some("xterm-256color"), false);
+        assert!(caps.alternate_screen);
+        assert!(caps.color);
+    }
+
+    #[test]
+    fn capabilities_degrade_for_dumb_or_missing_term() {
+        for term in [None, Some(""), Some("dumb")] {
+            let caps = detect_capabilities(term, false);
+            assert!(!caps.alternate_screen);
+            assert!(!caps.color);
+        }
+    }
+
+    #[test]
+    fn capabilities_no_color_keeps_alternate_screen() {
+        let caps = detect_capabilities(Some("xterm"), true);
+        assert!(caps.alternate_screen);
+        assert!(!caps.color);
+    }
+
+    #[test]
+    fn capabilities_legacy_vt_terminal_is_basic() {
+        let caps = detect_capabilities(Some("vt100"), false);
+        assert!(!caps.alternate_screen);
+        assert!(!caps.color);
+    }
+
     #[test]
     fn esc_maps_to_back() {
         let key = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
@@ -13,7 +13,8 @@ use std::path::PathBuf;
 
 use crossterm::event::KeyEvent;
 
-use crate::types::ScanReport;
+use crate::history::ScanSummary;
+use crate::types::{ScanProgress, ScanReport};
 
 // ============================================================================
 // APP EVENTS
@@ -31,10 +32,10 @@ use crate::types::ScanReport;
 pub enum AppEvent {
     /// A terminal key event from the crossterm reader thread.
     Key(KeyEvent),
-    /// Scanner progress: files scanned so far, candidates found so far.
-    ScanProgress { files_scanned: usize, candidates_found: usize },
+    /// Scanner progress: discovery counts, then verification bytes and ETA.
+    ScanProgress(ScanProgress),
     /// Scanner finished successfully with a complete report.
-    ScanComplete(ScanReport),
+    ScanComplete(Box<ScanReport>),
     /// Scanner failed with an error message.
     ScanError(String),
 }
@@ -55,6 +56,12 @@ pub struct App {
     /// Scan results, shared across screens. None while scanning.
     pub report: Option<ScanReport>,
 
+    /// Summary of the previous scan of the same roots, if one was recorded.
+    pub previous: Option<ScanSummary>,
+
+    /// Whether the Overview shows what changed since `previous`.
+    pub show_changes: bool,
+
     /// Set to true when the app should exit on the next tick.
     pub should_quit: bool,
 }
@@ -70,9 +77,9 @@ pub struct App {
 /// counters. Shared data (the scan report) lives in [`App::report`].
 #[derive(Debug, PartialEq)]
 pub enum Screen {
-    /// Scan in progress. Counter updated via callback.
+    /// Scan in progress. Counters updated via callback.
     Scanning {
-        candidates_found: usize,
+        progress: ScanProgress,
     },
 
     /// Summary dashboard after scan completes.
@@ -108,6 +115,11 @@ pub enum Screen {
         cursor: usize,
     },
 
+    /// Conflicts left unchecked because they aren't downloaded.
+    NotLocalList {
+        cursor: usize,
+    },
+
     /// Confirmation gate before quarantine.
     Confirm {
         /// Duplicate group indices being quarantined.
@@ -170,6 +182,8 @@ pub enum Action {
     Skip,
     /// Open containing folder in system file manager.
     OpenFolder,
+    /// Show or hide changes since the previous scan (Overview).
+    ToggleChanges,
     /// Confirm action (Y on confirmation screen).
     ConfirmYes,
     /// Decline action (N on confirmation screen).
@@ -224,8 +238,10 @@ impl App {
     /// Create an App in the Scanning state (before scan completes).
     pub fn scanning() -> Self {
         App {
-            screen: Screen::Scanning { candidates_found: 0 },
+            screen: Screen::Scanning { progress: ScanProgress::default() },
             report: None,
+            previous: None,
+            show_changes: false,
             should_quit: false,
         }
     }
@@ -235,6 +251,8 @@ impl App {
         App {
             screen: Screen::Overview,
             report: Some(report),
+            previous: None,
+            show_changes: false,
             should_quit: false,
         }
     }
@@ -281,7 +299,7 @@ mod tests {
     #[test]
     fn app_scanning_starts_with_zero_candidates() {
         let app = App::scanning();
-        assert_eq!(app.screen, Screen::Scanning { candidates_found: 0 });
+        assert_eq!(app.screen, Screen::Scanning { progress: ScanProgress::default() });
         assert!(app.report.is_none());
         assert!(!app.should_quit);
     }
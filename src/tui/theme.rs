@@ -11,6 +11,7 @@
 //! - Dim: de-emphasized (hashes, timestamps)
 //! - Bold: important (counts, filenames)
 
+use ratatui::buffer::Buffer;
 use ratatui::style::{Color, Modifier, Style};
 
 // ============================================================================
@@ -60,6 +61,21 @@ pub const STYLE_PROGRESS: Style = Style::new().fg(Color::Cyan);
 /// Footer / help line.
 pub const STYLE_HELP: Style = Style::new().fg(Color::DarkGray);
 
+// ============================================================================
+// MONOCHROME FALLBACK
+// ============================================================================
+
+/// Drop all colors from a rendered buffer, keeping modifiers.
+///
+/// Used on terminals without color support. Cursor rows stay visible
+/// because STYLE_CURSOR relies on REVERSED rather than a color.
+pub fn strip_colors(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        cell.set_fg(Color::Reset);
+        cell.set_bg(Color::Reset);
+    }
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -86,4 +102,21 @@ mod tests {
     fn cursor_style_is_reversed() {
         assert!(STYLE_CURSOR.add_modifier.contains(Modifier::REVERSED));
     }
+
+    #[test]
+    fn strip_colors_resets_colors_but_keeps_modifiers() {
+        use ratatui::layout::Rect;
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        buf.set_string(0, 0, "ab", STYLE_DANGER.add_modifier(Modifier::BOLD));
+        buf.set_string(2, 0, "cd", STYLE_SELECTED);
+
+        strip_colors(&mut buf);
+
+        for cell in buf.content.iter() {
+            assert_eq!(cell.fg, Color::Reset);
+            assert_eq!(cell.bg, Color::Reset);
+        }
+        assert!(buf.content[0].modifier.contains(Modifier::BOLD));
+    }
 }
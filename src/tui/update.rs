@@ -7,6 +7,8 @@
 //! Two entry points:
 //! - `update()`: user actions (key presses) — returns Transition
 //! - `handle_background_event()`: background events (scan progress, etc.) — mutates App directly
+//!
+//! `toggle_changes()` flips App-level display state the same way.
 
 use crate::types::ScanReport;
 
@@ -45,6 +47,12 @@ pub fn update(screen: Screen, action: &Action, report: &ScanReport) -> Transitio
             action,
             |c| Screen::SkippedList { cursor: c },
         ),
+        Screen::NotLocalList { cursor } => update_simple_list(
+            cursor,
+            report.not_local.len(),
+            action,
+            |c| Screen::NotLocalList { cursor: c },
+        ),
         Screen::Confirm { group_indices } => update_confirm(group_indices, action),
         // Progress and Done are driven by the effects layer, not user actions
         // (except Quit and navigation)
@@ -96,6 +104,13 @@ fn update_overview(action: &Action, report: &ScanReport) -> Transition {
                 Transition::Screen(Screen::SkippedList { cursor: 0 })
             }
         }
+        Action::NumberKey(5) => {
+            if report.not_local.is_empty() {
+                Transition::Screen(Screen::Overview)
+            } else {
+                Transition::Screen(Screen::NotLocalList { cursor: 0 })
+            }
+        }
         Action::Quit => Transition::Quit,
         _ => Transition::Screen(Screen::Overview),
     }
@@ -202,7 +217,7 @@ fn update_duplicate_detail(
     }
 }
 
-/// Simple list (orphans, diverged, skipped): cursor movement + back.
+/// Simple list (orphans, diverged, skipped, not downloaded): cursor movement + back.
 ///
 /// The `make_screen` closure reconstructs the correct Screen variant
 /// with an updated cursor, preserving the list type identity.
@@ -253,6 +268,20 @@ fn noop(screen: Screen, action: &Action) -> Transition {
     }
 }
 
+// ============================================================================
+// APP-LEVEL TOGGLES
+// ============================================================================
+
+/// Show or hide the changes-since-last-scan overlay.
+///
+/// Only meaningful on the Overview, and only when a previous scan of the
+/// same roots is known; elsewhere it does nothing.
+pub fn toggle_changes(app: &mut App) {
+    if app.screen == Screen::Overview && app.previous.is_some() {
+        app.show_changes = !app.show_changes;
+    }
+}
+
 // ============================================================================
 // BACKGROUND EVENT HANDLER
 // ============================================================================
@@ -265,15 +294,15 @@ fn noop(screen: Screen, action: &Action) -> Transition {
 /// The effects boundary calls this when it receives a non-Key AppEvent.
 pub fn handle_background_event(app: &mut App, event: AppEvent) {
     match event {
-        AppEvent::ScanProgress { candidates_found, .. } => {
+        AppEvent::ScanProgress(progress) => {
             // Only update if we're still on the Scanning screen.
             // Late-arriving progress events after completion are ignored.
             if matches!(app.screen, Screen::Scanning { .. }) {
-                app.screen = Screen::Scanning { candidates_found };
+                app.screen = Screen::Scanning { progress };
             }
         }
         AppEvent::ScanComplete(report) => {
-            app.report = Some(report);
+            app.report = Some(*report);
             app.screen = Screen::Overview;
         }
         AppEvent::ScanError(msg) => {
@@ -295,9 +324,23 @@ pub fn handle_background_event(app: &mut App, event: AppEvent) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{DuplicateGroup, ContentHash, ScanReport};
+    use crate::types::{DuplicateGroup, ContentHash, ScanProgress, ScanReport};
     use std::path::PathBuf;
 
+    fn progress(files_scanned: usize, candidates_found: usize) -> ScanProgress {
+        ScanProgress {
+            files_scanned,
+            candidates_found,
+            ..Default::default()
+        }
+    }
+
+    fn scanning(candidates_found: usize) -> Screen {
+        Screen::Scanning {
+            progress: progress(0, candidates_found),
+        }
+    }
+
     fn empty_report() -> ScanReport {
         ScanReport::default()
     }
@@ -309,6 +352,7 @@ mod tests {
                 original: PathBuf::from(format!("original_{}.txt", i)),
                 hash: ContentHash([0u8; 32]),
                 duplicates: vec![PathBuf::from(format!("copy_{}.txt", i))],
+                size_bytes: 0,
             });
         }
         report
@@ -317,11 +361,9 @@ mod tests {
     fn report_with_all_categories() -> ScanReport {
         let mut report = report_with_duplicates(3);
         report.orphaned_conflicts = vec![PathBuf::from("orphan.txt")];
-        report.content_diverged = vec![(
-            PathBuf::from("conflict.txt"),
-            PathBuf::from("original.txt"),
-        )];
+        report.content_diverged = vec![(PathBuf::from("conflict.txt"), PathBuf::from("original.txt")).into()];
         report.skipped = vec![(PathBuf::from("bad.txt"), "permission denied".into())];
+        report.not_local = vec![PathBuf::from("cloud 2.txt")];
         report
     }
 
@@ -329,15 +371,15 @@ mod tests {
 
     #[test]
     fn scanning_quit() {
-        let screen = Screen::Scanning { candidates_found: 42 };
+        let screen = scanning(42);
         assert_eq!(update(screen, &Action::Quit, &empty_report()), Transition::Quit);
     }
 
     #[test]
     fn scanning_ignores_other_actions() {
-        let screen = Screen::Scanning { candidates_found: 42 };
+        let screen = scanning(42);
         let result = update(screen, &Action::MoveUp, &empty_report());
-        assert_eq!(result, Transition::Screen(Screen::Scanning { candidates_found: 42 }));
+        assert_eq!(result, Transition::Screen(scanning(42)));
     }
 
     // -- Overview --
@@ -384,6 +426,16 @@ mod tests {
         assert_eq!(result, Transition::Screen(Screen::SkippedList { cursor: 0 }));
     }
 
+    #[test]
+    fn overview_number5_opens_not_local_list() {
+        let report = report_with_all_categories();
+        let result = update(Screen::Overview, &Action::NumberKey(5), &report);
+        assert_eq!(result, Transition::Screen(Screen::NotLocalList { cursor: 0 }));
+
+        let result = update(Screen::Overview, &Action::NumberKey(5), &empty_report());
+        assert_eq!(result, Transition::Screen(Screen::Overview));
+    }
+
     // -- DuplicateList --
 
     #[test]
@@ -651,33 +703,24 @@ mod tests {
     #[test]
     fn scan_progress_updates_scanning_screen() {
         let mut app = App::scanning();
-        handle_background_event(&mut app, AppEvent::ScanProgress {
-            files_scanned: 500,
-            candidates_found: 12,
-        });
-        assert_eq!(app.screen, Screen::Scanning { candidates_found: 12 });
+        handle_background_event(&mut app, AppEvent::ScanProgress(progress(500, 12)));
+        assert_eq!(app.screen, Screen::Scanning { progress: progress(500, 12) });
         assert!(app.report.is_none());
     }
 
     #[test]
     fn scan_progress_accumulates() {
         let mut app = App::scanning();
-        handle_background_event(&mut app, AppEvent::ScanProgress {
-            files_scanned: 100,
-            candidates_found: 3,
-        });
-        handle_background_event(&mut app, AppEvent::ScanProgress {
-            files_scanned: 500,
-            candidates_found: 12,
-        });
-        assert_eq!(app.screen, Screen::Scanning { candidates_found: 12 });
+        handle_background_event(&mut app, AppEvent::ScanProgress(progress(100, 3)));
+        handle_background_event(&mut app, AppEvent::ScanProgress(progress(500, 12)));
+        assert_eq!(app.screen, Screen::Scanning { progress: progress(500, 12) });
     }
 
     #[test]
     fn scan_complete_stores_report_and_transitions_to_overview() {
         let mut app = App::scanning();
         let report = report_with_duplicates(3);
-        handle_background_event(&mut app, AppEvent::ScanComplete(report));
+        handle_background_event(&mut app, AppEvent::ScanComplete(Box::new(report)));
         assert_eq!(app.screen, Screen::Overview);
         assert!(app.report.is_some());
         assert_eq!(app.report.as_ref().unwrap().confirmed_duplicates.len(), 3);
@@ -686,7 +729,7 @@ mod tests {
     #[test]
     fn scan_complete_with_empty_report_still_shows_overview() {
         let mut app = App::scanning();
-        handle_background_event(&mut app, AppEvent::ScanComplete(ScanReport::default()));
+        handle_background_event(&mut app, AppEvent::ScanComplete(Box::default()));
         assert_eq!(app.screen, Screen::Overview);
         assert!(app.report.is_some());
     }
@@ -704,10 +747,35 @@ mod tests {
         // the screen should not change.
         let mut app = App::with_report(empty_report());
         assert_eq!(app.screen, Screen::Overview);
-        handle_background_event(&mut app, AppEvent::ScanProgress {
-            files_scanned: 999,
-            candidates_found: 50,
-        });
+        handle_background_event(&mut app, AppEvent::ScanProgress(progress(999, 50)));
         assert_eq!(app.screen, Screen::Overview);
     }
+
+    // -- Changes overlay --
+
+    fn previous_scan() -> crate::history::ScanSummary {
+        crate::history::summarize(&empty_report(), &[], 0)
+    }
+
+    #[test]
+    fn toggle_changes_flips_on_overview_with_history() {
+        let mut app = App::with_report(empty_report());
+        app.previous = Some(previous_scan());
+        toggle_changes(&mut app);
+        assert!(app.show_changes);
+        toggle_changes(&mut app);
+        assert!(!app.show_changes);
+    }
+
+    #[test]
+    fn toggle_changes_noop_without_history_or_off_overview() {
+        let mut app = App::with_report(empty_report());
+        toggle_changes(&mut app);
+        assert!(!app.show_changes);
+
+        app.previous = Some(previous_scan());
+        app.screen = Screen::duplicate_list();
+        toggle_changes(&mut app);
+        assert!(!app.show_changes);
+    }
 }
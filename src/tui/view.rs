@@ -14,8 +14,13 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, Wrap};
 use ratatui::Frame;
 
-use crate::types::{DuplicateGroup, ScanReport};
+use crate::history::{diff, ScanDelta};
+use crate::messages::{count, Locale, Noun};
+use crate::platform::sync_state;
+use crate::report::{diverged_summary, format_delta, format_eta, format_recoverable};
+use crate::types::{ItemState, ScanProgress, ScanReport};
 
+use super::names::display_names;
 use super::state::{App, Screen};
 use super::theme;
 
@@ -44,12 +49,16 @@ pub fn render(app: &App, frame: &mut Frame) {
     let content_area = chunks[1];
 
     match &app.screen {
-        Screen::Scanning { candidates_found } => {
-            render_scanning(*candidates_found, frame, content_area);
+        Screen::Scanning { progress } => {
+            render_scanning(progress, frame, content_area);
         }
         Screen::Overview => {
             if let Some(report) = &app.report {
-                render_overview(report, frame, content_area);
+                let previous = app.previous.as_ref();
+                let changes = previous
+                    .filter(|_| app.show_changes)
+                    .map(|p| diff(p, report));
+                render_overview(report, previous.is_some(), changes, frame, content_area);
             }
         }
         Screen::DuplicateList { cursor, selected } => {
@@ -77,7 +86,14 @@ pub fn render(app: &App, frame: &mut Frame) {
                 let items: Vec<String> = report
                     .content_diverged
                     .iter()
-                    .map(|(c, o)| format!("{} ≠ {}", c.display(), o.display()))
+                    .map(|d| {
+                        format!(
+                            "{} ≠ {}  ({})",
+                            d.conflict.path.display(),
+                            d.original.path.display(),
+                            diverged_summary(d)
+                        )
+                    })
                     .collect();
                 render_simple_list(&items, *cursor, frame, content_area);
             }
@@ -92,6 +108,16 @@ pub fn render(app: &App, frame: &mut Frame) {
                 render_simple_list(&items, *cursor, frame, content_area);
             }
         }
+        Screen::NotLocalList { cursor } => {
+            if let Some(report) = &app.report {
+                render_simple_list(
+                    &report.not_local.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                    *cursor,
+                    frame,
+                    content_area,
+                );
+            }
+        }
         Screen::Confirm { group_indices } => {
             if let Some(report) = &app.report {
                 render_confirm(report, group_indices, frame, content_area);
@@ -120,6 +146,7 @@ fn render_title(screen: &Screen) -> Paragraph<'static> {
         Screen::OrphanList { .. } => "Orphaned Conflicts",
         Screen::DivergedList { .. } => "Diverged Files",
         Screen::SkippedList { .. } => "Skipped Files",
+        Screen::NotLocalList { .. } => "Not Downloaded",
         Screen::Confirm { .. } => "Confirm Quarantine",
         Screen::Progress { .. } => "Quarantining...",
         Screen::Done { .. } => "Complete",
@@ -134,14 +161,15 @@ fn render_title(screen: &Screen) -> Paragraph<'static> {
 fn render_help(screen: &Screen) -> Paragraph<'static> {
     let help_text = match screen {
         Screen::Scanning { .. } => "^C quit",
-        Screen::Overview => "[1-4] navigate  [q] quit",
+        Screen::Overview => "[1-5] navigate  [q] quit",
         Screen::DuplicateList { .. } => {
             "[j/k] move  [Space] toggle  [a] all  [n] none  [Enter] details  [Q] quarantine  [Esc] back"
         }
         Screen::DuplicateDetail { .. } => "[Q] quarantine  [s] skip  [o] open folder  [Esc] back",
-        Screen::OrphanList { .. } | Screen::DivergedList { .. } | Screen::SkippedList { .. } => {
-            "[j/k] move  [Esc] back"
-        }
+        Screen::OrphanList { .. }
+        | Screen::DivergedList { .. }
+        | Screen::SkippedList { .. }
+        | Screen::NotLocalList { .. } => "[j/k] move  [Esc] back",
         Screen::Confirm { .. } => "[Y] yes, quarantine  [N] no, go back",
         Screen::Progress { .. } => "",
         Screen::Done { .. } => "[Enter] overview  [q] quit",
@@ -154,18 +182,38 @@ fn render_help(screen: &Screen) -> Paragraph<'static> {
 // SCREEN: SCANNING
 // ============================================================================
 
-fn render_scanning(candidates_found: usize, frame: &mut Frame, area: Rect) {
-    let text = vec![
+fn render_scanning(progress: &ScanProgress, frame: &mut Frame, area: Rect) {
+    let mut text = vec![
         Line::from(""),
         Line::from(Span::styled(
             "  Discovering conflict patterns...",
             theme::STYLE_INTERACTIVE,
         )),
         Line::from(""),
-        Line::from(format!("    Found: {} candidates", candidates_found)),
+        Line::from(format!("    Found: {} candidates", progress.candidates_found)),
         Line::from(""),
     ];
 
+    // Verification is dominated by a few big files: show bytes, not counts
+    if progress.bytes_to_verify > 0 {
+        let eta = progress
+            .eta()
+            .map(|eta| format!(", about {} left", format_eta(eta)))
+            .unwrap_or_default();
+        text.push(Line::from(Span::styled(
+            "  Verifying content...",
+            theme::STYLE_INTERACTIVE,
+        )));
+        text.push(Line::from(""));
+        text.push(Line::from(format!(
+            "    Checked: {} of {}{}",
+            format_size(progress.bytes_verified, BINARY),
+            format_size(progress.bytes_to_verify, BINARY),
+            eta
+        )));
+        text.push(Line::from(""));
+    }
+
     let paragraph = Paragraph::new(text).wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
@@ -174,7 +222,16 @@ fn render_scanning(candidates_found: usize, frame: &mut Frame, area: Rect) {
 // SCREEN: OVERVIEW
 // ============================================================================
 
-fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
+/// Folders listed on the overview, largest first.
+const OVERVIEW_FOLDERS: usize = 3;
+
+fn render_overview(
+    report: &ScanReport,
+    has_history: bool,
+    changes: Option<ScanDelta>,
+    frame: &mut Frame,
+    area: Rect,
+) {
     let dup_count = report.confirmed_duplicates.len();
     let dup_files: usize = report
         .confirmed_duplicates
@@ -184,7 +241,8 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
     let orphan_count = report.orphaned_conflicts.len();
     let diverged_count = report.content_diverged.len();
     let skipped_count = report.skipped.len();
-    let recoverable = format_size(report.bytes_recoverable, BINARY);
+    let not_local_count = report.not_local.len();
+    let recoverable = format_recoverable(report);
 
     let mut lines = vec![
         Line::from(""),
@@ -201,7 +259,11 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
         lines.push(Line::from(vec![
             Span::styled("  ✓  ", theme::STYLE_SAFE),
             Span::styled(
-                format!("{} confirmed duplicates ({} files)", dup_count, dup_files),
+                format!(
+                    "{} ({})",
+                    count(Locale::English, dup_count, Noun::ConfirmedDuplicate),
+                    count(Locale::English, dup_files, Noun::File)
+                ),
                 theme::STYLE_SAFE,
             ),
             Span::styled(format!("     {} recoverable", recoverable), theme::STYLE_DIM),
@@ -218,7 +280,7 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
         lines.push(Line::from(vec![
             Span::styled("  ⚠  ", theme::STYLE_WARNING),
             Span::styled(
-                format!("{} orphaned conflicts", orphan_count),
+                count(Locale::English, orphan_count, Noun::OrphanedConflict),
                 theme::STYLE_WARNING,
             ),
             Span::styled("       needs review", theme::STYLE_DIM),
@@ -230,13 +292,37 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
         lines.push(Line::from(vec![
             Span::styled("  ≠  ", theme::STYLE_DANGER),
             Span::styled(
-                format!("{} diverged files", diverged_count),
+                count(Locale::English, diverged_count, Noun::DivergedFile),
                 theme::STYLE_DANGER,
             ),
             Span::styled("         different content", theme::STYLE_DIM),
         ]));
     }
 
+    // Probable duplicates line (fast scans)
+    if !report.probable_duplicates.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  ≈  ", theme::STYLE_DIM),
+            Span::styled(
+                count(Locale::English, report.probable_duplicates.len(), Noun::ProbableDuplicate),
+                theme::STYLE_DIM,
+            ),
+            Span::styled("      not hashed", theme::STYLE_DIM),
+        ]));
+    }
+
+    // Same content, different metadata
+    if !report.metadata_differs.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  ≡  ", theme::STYLE_DIM),
+            Span::styled(
+                count(Locale::English, report.metadata_differs.len(), Noun::MetadataMismatch),
+                theme::STYLE_DIM,
+            ),
+            Span::styled("      kept", theme::STYLE_DIM),
+        ]));
+    }
+
     // Skipped line
     if skipped_count > 0 {
         lines.push(Line::from(vec![
@@ -246,6 +332,52 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
         ]));
     }
 
+    // Not-downloaded line
+    if not_local_count > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("  ☁  ", theme::STYLE_DIM),
+            Span::styled(format!("{} not downloaded", not_local_count), theme::STYLE_DIM),
+            Span::styled("         not checked", theme::STYLE_DIM),
+        ]));
+    }
+
+    // A budget cut the scan short
+    if let Some(reason) = &report.partial {
+        lines.push(Line::from(vec![
+            Span::styled("  ⏸  ", theme::STYLE_WARNING),
+            Span::styled(format!("Partial scan: {}", reason), theme::STYLE_WARNING),
+        ]));
+    }
+
+    // Folders holding the most recoverable space
+    if !report.by_directory.is_empty() {
+        lines.push(Line::from(""));
+        for dir in report.by_directory.iter().take(OVERVIEW_FOLDERS) {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  ▸  {:>10}  ", format_size(dir.bytes_recoverable, BINARY)),
+                    theme::STYLE_DIM,
+                ),
+                Span::raw(dir.path.display().to_string()),
+                Span::styled(
+                    format!(" ({})", count(Locale::English, dir.duplicates, Noun::File)),
+                    theme::STYLE_DIM,
+                ),
+            ]));
+        }
+    }
+
+    // Changes since the previous scan of the same roots
+    if let Some(delta) = changes {
+        lines.push(Line::from(""));
+        for change in format_delta(&delta) {
+            lines.push(Line::from(Span::styled(
+                format!("  Δ  {}", change),
+                theme::STYLE_INTERACTIVE,
+            )));
+        }
+    }
+
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "  ─────────────────────────────────────────────────────",
@@ -280,6 +412,21 @@ fn render_overview(report: &ScanReport, frame: &mut Frame, area: Rect) {
         lines.push(Line::from(nav_items2));
     }
 
+    if not_local_count > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("  [5] ", theme::STYLE_INTERACTIVE),
+            Span::raw("View not downloaded"),
+        ]));
+    }
+
+    if has_history {
+        let label = if changes.is_some() { "Hide changes" } else { "Changes since last scan" };
+        lines.push(Line::from(vec![
+            Span::styled("  [d] ", theme::STYLE_INTERACTIVE),
+            Span::raw(label),
+        ]));
+    }
+
     let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
@@ -305,8 +452,9 @@ fn render_duplicate_list(
     .split(area);
 
     // Build list items
+    let names = display_names(&groups.iter().map(|g| &g.original).collect::<Vec<_>>());
     let mut lines: Vec<Line> = Vec::new();
-    for (i, group) in groups.iter().enumerate() {
+    for (i, (group, name)) in groups.iter().zip(names).enumerate() {
         let is_selected = selected.contains(&i);
         let is_cursor = i == cursor;
 
@@ -316,7 +464,6 @@ fn render_duplicate_list(
             Span::styled("[ ] ", theme::STYLE_UNCHECKED)
         };
 
-        let name = group_display_name(group);
         let copies = group.duplicates.len();
         let size: u64 = group
             .duplicates
@@ -325,9 +472,8 @@ fn render_duplicate_list(
             .sum();
 
         let info = format!(
-            "  {} cop{}, {}",
-            copies,
-            if copies == 1 { "y" } else { "ies" },
+            "  {}, {}",
+            count(Locale::English, copies, Noun::Copy),
             format_size(size, BINARY)
         );
 
@@ -368,9 +514,8 @@ fn render_duplicate_list(
 
     let tally = if selected_count > 0 {
         format!(
-            "  Selected: {} group{} ({})",
-            selected_count,
-            if selected_count == 1 { "" } else { "s" },
+            "  Selected: {} ({})",
+            count(Locale::English, selected_count, Noun::Group),
             format_size(selected_size, BINARY)
         )
     } else {
@@ -381,19 +526,16 @@ fn render_duplicate_list(
     frame.render_widget(tally_widget, chunks[1]);
 }
 
-/// Extract a display name from a duplicate group (filename of original).
-fn group_display_name(group: &DuplicateGroup) -> String {
-    group
-        .original
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| group.original.display().to_string())
-}
-
 // ============================================================================
 // SCREEN: DUPLICATE DETAIL
 // ============================================================================
 
+/// Sync state of a file iCloud is still working on, checked live so the
+/// detail view shows whether it's safe to act on now.
+fn unsettled(path: &Path) -> Option<ItemState> {
+    Some(sync_state(path)).filter(|&s| s != ItemState::Local)
+}
+
 fn render_duplicate_detail(
     report: &ScanReport,
     group_index: usize,
@@ -434,6 +576,12 @@ fn render_duplicate_detail(
             )));
         }
     }
+    if let Some(state) = unsettled(&group.original) {
+        lines.push(Line::from(Span::styled(
+            format!("    iCloud: {}", state.name()),
+            theme::STYLE_WARNING,
+        )));
+    }
 
     lines.push(Line::from(""));
 
@@ -448,11 +596,15 @@ fn render_duplicate_detail(
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| dup.display().to_string());
-        lines.push(Line::from(vec![
+        let mut spans = vec![
             Span::raw("    "),
             Span::styled("• ", theme::STYLE_DANGER),
             Span::raw(dup_name),
-        ]));
+        ];
+        if let Some(state) = unsettled(dup) {
+            spans.push(Span::styled(format!("  (iCloud: {})", state.name()), theme::STYLE_WARNING));
+        }
+        lines.push(Line::from(spans));
     }
 
     lines.push(Line::from(""));
@@ -497,7 +649,7 @@ fn format_system_time(time: std::time::SystemTime) -> String {
 }
 
 // ============================================================================
-// SCREEN: SIMPLE LIST (orphans, diverged, skipped)
+// SCREEN: SIMPLE LIST (orphans, diverged, skipped, not downloaded)
 // ============================================================================
 
 fn render_simple_list(items: &[String], cursor: usize, frame: &mut Frame, area: Rect) {
@@ -555,18 +707,18 @@ fn render_confirm(
         Line::from(Span::styled("  You're about to quarantine:", theme::STYLE_WARNING)),
         Line::from(""),
         Line::from(format!(
-            "    {} files ({}) from {} duplicate group{}",
-            files.len(),
+            "    {} ({}) from {}",
+            count(Locale::English, files.len(), Noun::File),
             format_size(total_size, BINARY),
-            group_indices.len(),
-            if group_indices.len() == 1 { "" } else { "s" }
+            count(Locale::English, group_indices.len(), Noun::DuplicateGroup)
         )),
         Line::from(""),
     ];
 
     // File list
     let max_show = 10;
-    for (i, path) in files.iter().enumerate() {
+    let names = display_names(&files);
+    for (i, name) in names.iter().enumerate() {
         if i >= max_show {
             lines.push(Line::from(Span::styled(
                 format!("    ...{} more", files.len() - max_show),
@@ -574,10 +726,6 @@ fn render_confirm(
             )));
             break;
         }
-        let name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.display().to_string());
         lines.push(Line::from(format!("    {}", name)));
     }
 
@@ -603,19 +751,11 @@ fn render_progress(
     frame: &mut Frame,
     area: Rect,
 ) {
-    let pct = if total > 0 {
-        (done * 100) / total
-    } else {
-        0
-    };
+    let pct = (done * 100).checked_div(total).unwrap_or(0);
 
     // Build a text-based progress bar
     let bar_width = 40;
-    let filled = if total > 0 {
-        (done * bar_width) / total
-    } else {
-        0
-    };
+    let filled = (done * bar_width).checked_div(total).unwrap_or(0);
     let empty = bar_width - filled;
     let bar = format!(
         "[{}{}] {}%",
@@ -675,9 +815,8 @@ fn render_done(
         Line::from(vec![
             Span::styled(
                 format!(
-                    "  ✓ {} file{} quarantined ({})",
-                    quarantined,
-                    if quarantined == 1 { "" } else { "s" },
+                    "  ✓ {} quarantined ({})",
+                    count(Locale::English, quarantined, Noun::File),
                     format_size(bytes_recovered, BINARY)
                 ),
                 theme::STYLE_SAFE,
@@ -688,7 +827,7 @@ fn render_done(
     if failed > 0 {
         lines.push(Line::from(vec![
             Span::styled(
-                format!("  ⚠  {} file{} skipped", failed, if failed == 1 { "" } else { "s" }),
+                format!("  ⚠  {} skipped", count(Locale::English, failed, Noun::File)),
                 theme::STYLE_WARNING,
             ),
         ]));
@@ -734,7 +873,7 @@ fn render_done(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ContentHash, DuplicateGroup, ScanReport};
+    use crate::types::{ContentHash, DuplicateGroup, ScanProgress, ScanReport};
     use ratatui::backend::TestBackend;
     use ratatui::Terminal;
     use std::path::PathBuf;
@@ -753,14 +892,13 @@ mod tests {
                 PathBuf::from("/docs/report Copy.pdf"),
                 PathBuf::from("/docs/report Copy 2.pdf"),
             ],
+            size_bytes: 0,
         });
         report.bytes_recoverable = 45_000_000;
         report.orphaned_conflicts = vec![PathBuf::from("orphan.txt")];
-        report.content_diverged = vec![(
-            PathBuf::from("conflict.txt"),
-            PathBuf::from("original.txt"),
-        )];
+        report.content_diverged = vec![(PathBuf::from("conflict.txt"), PathBuf::from("original.txt")).into()];
         report.skipped = vec![(PathBuf::from("bad.txt"), "permission denied".into())];
+        report.not_local = vec![PathBuf::from("cloud 2.txt")];
         report
     }
 
@@ -796,7 +934,7 @@ mod tests {
         let mut terminal = make_terminal();
         let mut app = App::scanning();
         app.screen = Screen::Scanning {
-            candidates_found: 42,
+            progress: ScanProgress { candidates_found: 42, ..Default::default() },
         };
         terminal.draw(|frame| render(&app, frame)).unwrap();
 
@@ -810,6 +948,53 @@ mod tests {
         assert!(content.contains("42"), "Buffer should contain candidate count 42");
     }
 
+    #[test]
+    fn overview_shows_changes_when_toggled() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        let mut previous_report = report_with_data();
+        previous_report.confirmed_duplicates.clear();
+
+        let mut app = App::with_report(report_with_data());
+        app.previous = Some(crate::history::summarize(&previous_report, &[], 0));
+
+        let text = |t: &Terminal<TestBackend>| -> String {
+            t.backend().buffer().content().iter().map(|c| c.symbol().to_string()).collect()
+        };
+
+        terminal.draw(|frame| render(&app, frame)).unwrap();
+        assert!(text(&terminal).contains("Changes since last scan"));
+        assert!(!text(&terminal).contains("+1 new duplicate group"));
+
+        app.show_changes = true;
+        terminal.draw(|frame| render(&app, frame)).unwrap();
+        assert!(text(&terminal).contains("+1 new duplicate group"));
+    }
+
+    #[test]
+    fn scanning_screen_shows_verification_eta() {
+        let mut terminal = make_terminal();
+        let mut app = App::scanning();
+        app.screen = Screen::Scanning {
+            progress: ScanProgress {
+                candidates_found: 3,
+                bytes_verified: 1024,
+                bytes_to_verify: 4096,
+                elapsed: std::time::Duration::from_secs(10),
+                ..Default::default()
+            },
+        };
+        terminal.draw(|frame| render(&app, frame)).unwrap();
+
+        let buffer = terminal.backend().buffer().clone();
+        let content: String = buffer
+            .content()
+            .iter()
+            .map(|cell| cell.symbol().to_string())
+            .collect();
+        assert!(content.contains("1 KiB of 4 KiB"));
+        assert!(content.contains("30s left"));
+    }
+
     #[test]
     fn duplicate_list_renders_without_panic() {
         let mut terminal = make_terminal();
@@ -842,6 +1027,30 @@ mod tests {
         assert!(content.contains("report.pdf"), "Should show filename");
     }
 
+    #[test]
+    fn duplicate_list_disambiguates_shared_filenames() {
+        let mut terminal = make_terminal();
+        let mut report = report_with_data();
+        report.confirmed_duplicates.push(DuplicateGroup {
+            original: PathBuf::from("/archive/report.pdf"),
+            hash: ContentHash([1u8; 32]),
+            duplicates: vec![PathBuf::from("/archive/report 2.pdf")],
+            size_bytes: 0,
+        });
+        let mut app = App::with_report(report);
+        app.screen = Screen::DuplicateList { cursor: 0, selected: Default::default() };
+        terminal.draw(|frame| render(&app, frame)).unwrap();
+
+        let buffer = terminal.backend().buffer().clone();
+        let content: String = buffer
+            .content()
+            .iter()
+            .map(|cell| cell.symbol().to_string())
+            .collect();
+        assert!(content.contains("docs/report.pdf"));
+        assert!(content.contains("archive/report.pdf"));
+    }
+
     #[test]
     fn duplicate_detail_renders_without_panic() {
         let mut terminal = make_terminal();
@@ -963,13 +1172,14 @@ mod tests {
         let mut terminal = make_terminal();
         let report = report_with_data();
         let screens = vec![
-            Screen::Scanning { candidates_found: 10 },
+            Screen::Scanning { progress: ScanProgress { candidates_found: 10, ..Default::default() } },
             Screen::Overview,
             Screen::DuplicateList { cursor: 0, selected: Default::default() },
             Screen::DuplicateDetail { group_index: 0 },
             Screen::OrphanList { cursor: 0 },
             Screen::DivergedList { cursor: 0 },
             Screen::SkippedList { cursor: 0 },
+            Screen::NotLocalList { cursor: 0 },
             Screen::Confirm { group_indices: vec![0] },
             Screen::Progress { done: 5, total: 10, current: None, errors: vec![] },
             Screen::Done { quarantined: 5, failed: 0, bytes_recovered: 0, errors: vec![] },
@@ -978,6 +1188,8 @@ mod tests {
             let app = App {
                 screen,
                 report: Some(report.clone()),
+                previous: None,
+                show_changes: false,
                 should_quit: false,
             };
             terminal
@@ -991,12 +1203,13 @@ mod tests {
         // Verify render_title doesn't panic for any variant
         let screens = vec![
             Screen::Overview,
-            Screen::Scanning { candidates_found: 0 },
+            Screen::Scanning { progress: ScanProgress { candidates_found: 0, ..Default::default() } },
             Screen::DuplicateList { cursor: 0, selected: Default::default() },
             Screen::DuplicateDetail { group_index: 0 },
             Screen::OrphanList { cursor: 0 },
             Screen::DivergedList { cursor: 0 },
             Screen::SkippedList { cursor: 0 },
+            Screen::NotLocalList { cursor: 0 },
             Screen::Confirm { group_indices: vec![] },
             Screen::Progress { done: 0, total: 0, current: None, errors: vec![] },
             Screen::Done { quarantined: 0, failed: 0, bytes_recovered: 0, errors: vec![] },
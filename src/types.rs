@@ -3,7 +3,14 @@
 //! Pass 4: Complete types with fields and attributes.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::cancel::{CancelToken, ScanBudget};
+use crate::hash::DEFAULT_PARALLEL_HASH_MIN_SIZE;
+use crate::hash_cache::HashStore;
+use std::time::Duration;
 
 // ============================================================================
 // PRIMITIVES
@@ -11,7 +18,8 @@ use std::path::PathBuf;
 
 /// Content identity — proof that two files are byte-identical.
 ///
-/// Wraps a 32-byte hash (SHA-256 or BLAKE3).
+/// Wraps a 32-byte digest of a [`HashAlgorithm`]. XXH3-128's 16-byte
+/// digest fills the first half; the rest is zero.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ContentHash(pub [u8; 32]);
 
@@ -59,6 +67,41 @@ fn hex_to_bytes(hex: &str) -> Result<[u8; 32], String> {
 // ENUMS
 // ============================================================================
 
+/// Algorithm behind a [`ContentHash`].
+///
+/// BLAKE3 is fast and cryptographic. SHA-256 is slower but what most audit
+/// tooling expects. XXH3-128 is fastest, but not cryptographic: fine for
+/// spotting copies, not for proving a file wasn't tampered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    #[serde(rename = "blake3")]
+    Blake3,
+    #[serde(rename = "sha256")]
+    Sha256,
+    #[serde(rename = "xxh3-128")]
+    Xxh3_128,
+}
+
+impl HashAlgorithm {
+    /// Every algorithm, default first.
+    pub const ALL: [HashAlgorithm; 3] = [Self::Blake3, Self::Sha256, Self::Xxh3_128];
+
+    /// Name as written in receipts, reports, and config files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+            Self::Xxh3_128 => "xxh3-128",
+        }
+    }
+
+    /// The algorithm called `name` (as returned by [`HashAlgorithm::name`]).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
 /// The conflict naming patterns iCloud uses.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConflictPattern {
@@ -80,6 +123,41 @@ pub enum FileKind {
     CloudPlaceholder,
 }
 
+/// Where an iCloud file stands in syncing, as of when it was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemState {
+    /// Content on this Mac and in the cloud (or not an iCloud file).
+    Local,
+    /// Content only in the cloud.
+    Evicted,
+    /// Local changes not yet uploaded.
+    Uploading,
+    /// Content on its way to this Mac.
+    Downloading,
+    /// iCloud holds conflicting versions not yet resolved.
+    Conflicted,
+}
+
+impl ItemState {
+    /// Name as shown in reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Evicted => "evicted",
+            Self::Uploading => "uploading",
+            Self::Downloading => "downloading",
+            Self::Conflicted => "conflicted",
+        }
+    }
+
+    /// Whether iCloud is still working on the item: moving it now can
+    /// create a new conflict.
+    pub fn is_syncing(self) -> bool {
+        matches!(self, Self::Uploading | Self::Downloading | Self::Conflicted)
+    }
+}
+
 /// Terminal states after verifying a conflict candidate.
 #[derive(Debug)]
 pub enum VerificationResult {
@@ -98,6 +176,48 @@ pub enum VerificationResult {
     ContentDiverged {
         conflict_path: PathBuf,
         original_path: PathBuf,
+        divergence: Divergence,
+    },
+    /// Conflict or original isn't downloaded: not checked, to avoid
+    /// forcing iCloud to fetch it.
+    NotLocal {
+        path: PathBuf,
+        presumed_original: PathBuf,
+    },
+    /// Conflict is a hard link to its original (same device and inode):
+    /// removing it would recover nothing.
+    Hardlinked {
+        path: PathBuf,
+        original: PathBuf,
+    },
+    /// Same size and modification time, not hashed ([`VerificationLevel::Fast`]):
+    /// likely a duplicate, but never safe to remove on that evidence.
+    ProbableDuplicate {
+        path: PathBuf,
+        original: PathBuf,
+    },
+    /// Same content, but metadata the user asked to compare
+    /// ([`ScanConfig::compare_metadata`]) differs: not removed.
+    MetadataDiffers {
+        path: PathBuf,
+        original: PathBuf,
+        fields: Vec<MetadataField>,
+    },
+}
+
+/// How a conflict file was shown to differ from its original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// Sizes differ, so neither file needed hashing.
+    Size { conflict_size: u64, original_size: u64 },
+    /// Same size, different content.
+    Content {
+        conflict_hash: ContentHash,
+        original_hash: ContentHash,
+    },
+    /// Same size, but the first or last 64 KiB differ, so the full files
+    /// were never hashed. The hashes cover only those ends.
+    Ends {
         conflict_hash: ContentHash,
         original_hash: ContentHash,
     },
@@ -108,7 +228,7 @@ pub enum VerificationResult {
 // ============================================================================
 
 /// A file that matches a conflict naming pattern.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConflictCandidate {
     /// Path to the conflict file.
     pub path: PathBuf,
@@ -118,10 +238,12 @@ pub struct ConflictCandidate {
     pub presumed_original: PathBuf,
     /// File type classification.
     pub kind: FileKind,
+    /// Sync state of the conflict file when it was found.
+    pub sync_state: ItemState,
 }
 
 /// A group of confirmed duplicates sharing the same content.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DuplicateGroup {
     /// The file to keep (clean name).
     pub original: PathBuf,
@@ -129,6 +251,10 @@ pub struct DuplicateGroup {
     pub hash: ContentHash,
     /// Files to remove (conflict-named).
     pub duplicates: Vec<PathBuf>,
+    /// Size of each file in the group, in bytes (0 in reports saved before
+    /// sizes were recorded).
+    #[serde(default)]
+    pub size_bytes: u64,
 }
 
 /// Record of a quarantined file (for restore).
@@ -148,21 +274,445 @@ pub struct QuarantineReceipt {
     pub size_bytes: u64,
     /// Whether the original had extended attributes.
     pub had_xattrs: bool,
+    /// Settings the quarantine ran under (absent in older manifests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<RunSettings>,
+    /// Quarantine run this file was moved in (absent in older manifests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Metadata put back on restore (absent in older manifests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PreservedMetadata>,
+    /// Why the file was quarantined (absent in older manifests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// Device and inode the file had before it was quarantined (absent in
+    /// older manifests).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<FileId>,
 }
 
+/// Where a file lives on disk: its device and inode numbers. A file keeps
+/// them when renamed on its volume; a replacement gets new ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileId {
+    pub dev: u64,
+    pub ino: u64,
+}
+
+/// Why a file was quarantined, for reviewing receipts long after the run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Provenance {
+    /// Conflict suffix the file name matched ("Copy", "Copy 2", "2"). None
+    /// for duplicates whose names match no pattern, like adopted orphans.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The file its duplicate group kept.
+    pub kept: PathBuf,
+    /// [`ScanStats::id`] of the scan that found it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan: Option<String>,
+    /// Device and inode of the kept file at the time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kept_id: Option<FileId>,
+}
+
+/// Metadata captured when a file is quarantined.
+///
+/// A rename keeps all of it, but a copy to another volume may not; restore
+/// puts back whatever went missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreservedMetadata {
+    /// Extended attributes by name (Finder tags included), hex-encoded.
+    /// Very large ones, like resource forks, aren't kept.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub xattrs: BTreeMap<String, String>,
+    /// Creation time, nanoseconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_nanos: Option<u64>,
+}
+
+/// The settings a quarantine ran under.
+///
+/// Kept on each receipt so a later audit can tell why a file qualified as
+/// a duplicate at the time, even after defaults or config files change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunSettings {
+    /// icloud-dedupe version.
+    pub tool_version: String,
+    /// Content hash used to confirm duplicates.
+    pub hash_algorithm: String,
+    /// Directories that were scanned.
+    pub roots: Vec<PathBuf>,
+    /// Maximum directory depth (None = unlimited).
+    pub max_depth: Option<usize>,
+    /// Whether symbolic links were followed.
+    pub follow_symlinks: bool,
+    /// Whether hidden files were included.
+    pub include_hidden: bool,
+    /// Whether verdicts could be reused from the scan index.
+    pub used_index: bool,
+    /// Whether hashes could be reused from the hash cache.
+    pub used_hash_cache: bool,
+    /// Whether the quarantine mirrored original paths.
+    pub preserve_structure: bool,
+}
+
+/// One side of a diverged pair: what is known about the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergedFile {
+    pub path: PathBuf,
+    /// Size in bytes, when known.
+    pub size: Option<u64>,
+    /// Last modification, seconds since the Unix epoch, when known.
+    pub modified: Option<u64>,
+    /// Content hash, when the file was hashed (files of different sizes
+    /// never are).
+    pub hash: Option<ContentHash>,
+}
+
+impl DivergedFile {
+    fn at(path: PathBuf) -> Self {
+        DivergedFile {
+            path,
+            ..Default::default()
+        }
+    }
+}
+
+/// A conflict file that differs from its presumed original, with enough
+/// about each side to judge which one to keep.
+///
+/// Reports from before schema version 2 held just the two paths, as a
+/// `[conflict, original]` array; those still deserialize.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "DivergedRecord")]
+pub struct DivergedPair {
+    pub conflict: DivergedFile,
+    pub original: DivergedFile,
+    /// The hashes cover only the first and last 64 KiB, where the files
+    /// already differed, so neither was read in full.
+    pub ends_only: bool,
+}
+
+impl DivergedPair {
+    /// The pair as verification left it: hashes or sizes, whichever it
+    /// compared. Modification times are filled in later.
+    pub fn new(conflict: PathBuf, original: PathBuf, divergence: &Divergence) -> Self {
+        let mut pair = DivergedPair {
+            conflict: DivergedFile::at(conflict),
+            original: DivergedFile::at(original),
+            ends_only: matches!(divergence, Divergence::Ends { .. }),
+        };
+        match divergence {
+            Divergence::Size {
+                conflict_size,
+                original_size,
+            } => {
+                pair.conflict.size = Some(*conflict_size);
+                pair.original.size = Some(*original_size);
+            }
+            Divergence::Content {
+                conflict_hash,
+                original_hash,
+            }
+            | Divergence::Ends {
+                conflict_hash,
+                original_hash,
+            } => {
+                pair.conflict.hash = Some(conflict_hash.clone());
+                pair.original.hash = Some(original_hash.clone());
+            }
+        }
+        pair
+    }
+}
+
+/// A diverged pair as saved: a full record, or the bare path pair older
+/// reports held.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DivergedRecord {
+    Paths(PathBuf, PathBuf),
+    Full {
+        conflict: DivergedFile,
+        original: DivergedFile,
+        #[serde(default)]
+        ends_only: bool,
+    },
+}
+
+impl From<(PathBuf, PathBuf)> for DivergedPair {
+    /// Just the paths (conflict, original), nothing known about either.
+    fn from((conflict, original): (PathBuf, PathBuf)) -> Self {
+        DivergedPair {
+            conflict: DivergedFile::at(conflict),
+            original: DivergedFile::at(original),
+            ends_only: false,
+        }
+    }
+}
+
+impl From<DivergedRecord> for DivergedPair {
+    fn from(record: DivergedRecord) -> Self {
+        match record {
+            DivergedRecord::Paths(conflict, original) => (conflict, original).into(),
+            DivergedRecord::Full {
+                conflict,
+                original,
+                ends_only,
+            } => DivergedPair {
+                conflict,
+                original,
+                ends_only,
+            },
+        }
+    }
+}
+
+/// A conflict file with the same content as its original but different
+/// metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataMismatch {
+    /// The conflict file.
+    pub conflict: PathBuf,
+    /// Its original.
+    pub original: PathBuf,
+    /// The compared metadata that differs.
+    pub fields: Vec<MetadataField>,
+}
+
+/// Duplicates and recoverable space under one folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryRollup {
+    /// The folder: directly below a scan root (or the root itself, for
+    /// duplicates sitting at its top level) in `by_directory`, the
+    /// duplicates' own directory in `by_parent`.
+    pub path: PathBuf,
+    /// Duplicate files under this folder.
+    pub duplicates: usize,
+    /// Bytes recoverable by removing them.
+    pub bytes_recoverable: u64,
+}
+
+/// Duplicates and recoverable space of one kind of file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionRollup {
+    /// Lowercase extension with its dot (".mov"), or empty for files
+    /// without one.
+    pub extension: String,
+    /// Duplicate files with this extension.
+    pub duplicates: usize,
+    /// Bytes recoverable by removing them.
+    pub bytes_recoverable: u64,
+}
+
+/// Version of the JSON layout of [`ScanReport`], bumped when a change
+/// would mislead an older reader rather than just add fields it ignores.
+///
+/// - 1: the first versioned layout
+/// - 2: `content_diverged` entries became objects with each side's size,
+///   modification time, and hash, instead of `[conflict, original]` pairs
+pub const REPORT_SCHEMA_VERSION: u32 = 2;
+
 /// Complete scan results partitioned by outcome.
-#[derive(Debug, Clone, Default, Serialize)]
+///
+/// Deserializes from saved JSON reports; fields missing from reports
+/// written by older versions take their defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScanReport {
+    /// Schema version of the JSON the report was read from (0 for reports
+    /// saved before versions were recorded). Always written as
+    /// [`REPORT_SCHEMA_VERSION`].
+    #[serde(serialize_with = "serialize_schema_version")]
+    pub schema_version: u32,
     /// Groups of confirmed duplicates.
     pub confirmed_duplicates: Vec<DuplicateGroup>,
     /// Conflict files whose originals are missing.
     pub orphaned_conflicts: Vec<PathBuf>,
     /// Conflict files that differ from their presumed originals.
-    pub content_diverged: Vec<(PathBuf, PathBuf)>,
+    pub content_diverged: Vec<DivergedPair>,
+    /// Conflict files not checked because they or their originals are
+    /// iCloud placeholders (content not downloaded).
+    pub not_local: Vec<PathBuf>,
+    /// Files that are hard links to the file they duplicate (link, original).
+    /// Not quarantined and not counted in `bytes_recoverable`.
+    pub hardlinked: Vec<(PathBuf, PathBuf)>,
+    /// Conflict files matching their originals in size and modification
+    /// time, left unhashed by a fast scan (conflict, original). Not
+    /// quarantined and not counted in `bytes_recoverable`.
+    pub probable_duplicates: Vec<(PathBuf, PathBuf)>,
+    /// Conflict files equal to their originals in content but not in the
+    /// metadata compared. Not quarantined and not counted in
+    /// `bytes_recoverable`.
+    pub metadata_differs: Vec<MetadataMismatch>,
+    /// Files of duplicate groups that iCloud hadn't finished syncing when
+    /// the scan ended (path, state): acting on them may act on content
+    /// about to change.
+    pub unsynced: Vec<(PathBuf, ItemState)>,
     /// Total bytes recoverable by removing duplicates.
     pub bytes_recoverable: u64,
+    /// Policy that picked each group's `original`.
+    pub keep_policy: KeepPolicy,
+    /// Duplicates and recoverable bytes per top-level folder, largest first.
+    pub by_directory: Vec<DirectoryRollup>,
+    /// Duplicates and recoverable bytes per parent directory, largest
+    /// first, limited to the top `PARENT_ROLLUP_LIMIT`.
+    pub by_parent: Vec<DirectoryRollup>,
+    /// Duplicates and recoverable bytes per file extension, largest first.
+    pub by_extension: Vec<ExtensionRollup>,
+    /// Bytes removing the duplicates would actually free, when the volume
+    /// reports block sharing (APFS clones share blocks with their source).
+    /// None when unknown.
+    pub bytes_recoverable_physical: Option<u64>,
     /// Files skipped due to read errors (path, error message).
     pub skipped: Vec<(PathBuf, String)>,
+    /// Paths not walked because of symlinks: loops, broken links, or
+    /// directories already reached another way (path, reason).
+    pub symlink_skips: Vec<(PathBuf, String)>,
+    /// Problems with the scan itself, such as roots that resolve through
+    /// symlinks or overlap (shown in the report header).
+    pub warnings: Vec<String>,
+    /// Why the scan stopped before covering everything (a file or time
+    /// budget ran out). None for a complete scan.
+    pub partial: Option<String>,
+    /// Algorithm behind every hash in the report.
+    pub hash_algorithm: HashAlgorithm,
+    /// What was scanned, when, and how long it took.
+    pub stats: ScanStats,
+}
+
+fn serialize_schema_version<S: serde::Serializer>(_: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(REPORT_SCHEMA_VERSION)
+}
+
+/// Context for a scan report: what was scanned, by which version, and how
+/// fast.
+///
+/// Left at its defaults (zero files) in reports that weren't produced by a
+/// scan, such as saved reports from older versions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanStats {
+    /// Identifies the scan; receipts of the files it quarantined point
+    /// back to it.
+    pub id: String,
+    /// Version of icloud-dedupe that ran the scan.
+    pub version: String,
+    /// When the scan started, seconds since the Unix epoch.
+    pub started_at: u64,
+    /// Wall-clock duration of the scan, in milliseconds.
+    pub duration_ms: u64,
+    /// Roots that were scanned.
+    pub roots: Vec<PathBuf>,
+    /// Files walked.
+    pub files_scanned: usize,
+    /// Files read and hashed (cache and index hits read nothing).
+    pub files_hashed: usize,
+    /// Bytes read and hashed.
+    pub bytes_hashed: u64,
+    /// Time spent reading and hashing, summed over all threads, in
+    /// milliseconds.
+    pub hash_ms: u64,
+    /// Time spent walking directories, in milliseconds.
+    pub walk_ms: u64,
+    /// Wall-clock time spent verifying candidates, in milliseconds.
+    pub verify_ms: u64,
+    /// How the roots were walked. None in reports from versions that
+    /// didn't record it.
+    pub walk: Option<WalkSettings>,
+}
+
+/// The walk settings a scan ran with: what it could have found.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalkSettings {
+    /// Maximum directory depth (None = unlimited).
+    pub max_depth: Option<usize>,
+    /// Whether symbolic links were followed.
+    pub follow_symlinks: bool,
+    /// Whether hidden files were included.
+    pub include_hidden: bool,
+    /// Directory names that weren't walked into.
+    pub ignore: Vec<String>,
+}
+
+impl ScanStats {
+    /// Wall-clock duration of the scan.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+
+    /// Files walked per second. None for an instant scan.
+    pub fn files_per_sec(&self) -> Option<f64> {
+        (self.duration_ms > 0).then(|| self.files_scanned as f64 * 1000.0 / self.duration_ms as f64)
+    }
+
+    /// Bytes hashed per second. None for an instant scan.
+    pub fn hashed_bytes_per_sec(&self) -> Option<f64> {
+        (self.duration_ms > 0).then(|| self.bytes_hashed as f64 * 1000.0 / self.duration_ms as f64)
+    }
+
+    /// Bytes hashed per second of verification: the throughput the disk
+    /// and cores sustained together. None if verification took no time.
+    pub fn verify_bytes_per_sec(&self) -> Option<f64> {
+        (self.verify_ms > 0).then(|| self.bytes_hashed as f64 * 1000.0 / self.verify_ms as f64)
+    }
+
+    /// Bytes each thread read and hashed per second while hashing. Close
+    /// to the hash's single-core speed (BLAKE3: around 1 GB/s) means the
+    /// scan was CPU-bound; far below it, threads were waiting on the disk.
+    /// None if nothing was hashed.
+    pub fn thread_bytes_per_sec(&self) -> Option<f64> {
+        (self.hash_ms > 0).then(|| self.bytes_hashed as f64 * 1000.0 / self.hash_ms as f64)
+    }
+}
+
+/// Progress of a scan, passed to progress callbacks.
+///
+/// Discovery fills in the file and candidate counts; verification fills in
+/// the byte counts. Byte totals cover each candidate and its original, so
+/// the ETA reflects that a few huge files dominate verification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Files looked at during discovery.
+    pub files_scanned: usize,
+    /// Conflict candidates found so far.
+    pub candidates_found: usize,
+    /// Total size of the candidates found so far.
+    pub bytes_discovered: u64,
+    /// Candidates verified so far.
+    pub candidates_verified: usize,
+    /// Bytes (conflict + original) of the candidates verified so far.
+    pub bytes_verified: u64,
+    /// Bytes (conflict + original) of all candidates to verify.
+    pub bytes_to_verify: u64,
+    /// Bytes actually read and hashed (cache and index hits read nothing).
+    pub bytes_hashed: u64,
+    /// Evicted files requested from iCloud and not downloaded yet
+    /// ([`ScanConfig::materialize`]).
+    pub downloading: usize,
+    /// Time since the current phase started.
+    pub elapsed: Duration,
+}
+
+impl ScanProgress {
+    /// Estimated time left in verification, from the byte rate so far.
+    ///
+    /// None until some bytes have been verified.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.bytes_verified == 0 {
+            return None;
+        }
+        let remaining = self.bytes_to_verify.saturating_sub(self.bytes_verified);
+        Some(
+            self.elapsed
+                .mul_f64(remaining as f64 / self.bytes_verified as f64),
+        )
+    }
 }
 
 /// The manifest file tracking quarantined items.
@@ -174,6 +724,189 @@ pub struct Manifest {
     pub quarantined: Vec<QuarantineReceipt>,
 }
 
+/// How one receipt's file looks in quarantine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReceiptCheck {
+    /// Present and hashing as recorded.
+    Ok,
+    /// Nothing at the quarantine path.
+    Missing,
+    /// Present but hashing differently.
+    Corrupted { actual: ContentHash },
+    /// Present but couldn't be hashed.
+    Unreadable { error: String },
+}
+
+/// What became of the file a duplicate's group kept, since the duplicate
+/// was quarantined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum KeptFileCheck {
+    /// The same file is still at its path.
+    Unchanged,
+    /// Nothing is at its path any more: it was moved or deleted.
+    Gone,
+    /// A different file now sits at its path.
+    Replaced,
+}
+
+/// A receipt whose kept file has moved, been replaced, or been deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeptFileChange {
+    pub id: String,
+    /// Where the kept file was.
+    pub kept: PathBuf,
+    #[serde(flatten)]
+    pub check: KeptFileCheck,
+}
+
+/// One receipt checked against its file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptVerification {
+    pub id: String,
+    pub original_path: PathBuf,
+    pub quarantine_path: PathBuf,
+    #[serde(flatten)]
+    pub check: ReceiptCheck,
+}
+
+/// Result of checking every receipt in a manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestVerification {
+    /// Receipts checked.
+    pub checked: usize,
+    /// Receipts whose file is missing, corrupted, or unreadable.
+    pub problems: Vec<ReceiptVerification>,
+    /// Receipts whose group's kept file changed since. Worth a look before
+    /// purging, but not a problem with the quarantine itself.
+    pub kept_changed: Vec<KeptFileChange>,
+}
+
+impl ManifestVerification {
+    /// True when every file is present and intact.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// What a restore does when something already sits at the original path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestoreConflict {
+    /// Leave both alone and report the conflict.
+    #[default]
+    Fail,
+    /// Restore next to it as "name (restored).ext".
+    Rename,
+    /// Replace it, but only if its content matches the receipt's hash.
+    OverwriteIfIdentical,
+}
+
+/// Which receipts a selective purge deletes. Every criterion given must
+/// match; an empty selection matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PurgeSelection {
+    /// Receipt IDs (empty = any).
+    pub ids: Vec<String>,
+    /// Only receipts quarantined at least this long ago.
+    pub older_than: Option<Duration>,
+    /// Glob on the original path; without a `/` it matches the file name.
+    pub pattern: Option<String>,
+}
+
+impl PurgeSelection {
+    /// True when no criterion is set.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.older_than.is_none() && self.pattern.is_none()
+    }
+}
+
+/// A quarantine manifest packaged for archiving or moving to another
+/// machine (`quarantine export`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestExport {
+    /// Export format version.
+    pub version: u32,
+    /// Home directory of the exporting user, for re-mapping paths on import.
+    pub home: Option<PathBuf>,
+    /// Quarantine directory the receipts' quarantine paths point into.
+    pub quarantine_dir: PathBuf,
+    /// Whether the files were exported alongside the manifest.
+    pub files_included: bool,
+    /// The exported receipts.
+    pub manifest: Manifest,
+}
+
+/// Quarantine contents with the totals `status` shows, for `status
+/// --format json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantineStatus {
+    pub quarantine_dir: PathBuf,
+    /// Files in quarantine.
+    pub files: usize,
+    /// Their total size in bytes.
+    pub total_bytes: u64,
+    /// Retention period in seconds (None = files are kept until purged).
+    pub retention_secs: Option<u64>,
+    /// Quarantine runs, in the order they were recorded.
+    pub sessions: Vec<SessionSummary>,
+    /// Every receipt, with its age.
+    pub receipts: Vec<ReceiptStatus>,
+}
+
+/// A receipt with how long it has been in quarantine.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptStatus {
+    #[serde(flatten)]
+    pub receipt: QuarantineReceipt,
+    /// Seconds since it was quarantined (None if the timestamp doesn't parse).
+    pub age_secs: Option<u64>,
+    /// Seconds until retention purges it (None without a retention period).
+    pub expires_in_secs: Option<u64>,
+}
+
+/// What restoring several receipts did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    /// Restored receipts: ID and where the file went.
+    pub restored: Vec<(String, PathBuf)>,
+    /// Receipts that couldn't be restored: ID and why.
+    pub failed: Vec<(String, String)>,
+    /// Receipts not attempted because the restore was cancelled.
+    pub not_attempted: usize,
+}
+
+/// What reconciling a leftover quarantine journal did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JournalRecovery {
+    /// Moves that had finished; their receipts were added to the manifest.
+    pub finished: usize,
+    /// Moves that never happened or were undone; the files stayed put.
+    pub rolled_back: usize,
+    /// Files found neither at their original path nor in quarantine.
+    pub missing: Vec<PathBuf>,
+}
+
+impl JournalRecovery {
+    /// True when there was nothing to reconcile.
+    pub fn is_empty(&self) -> bool {
+        self.finished == 0 && self.rolled_back == 0 && self.missing.is_empty()
+    }
+}
+
+/// One quarantine run, as listed by `status --sessions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSummary {
+    /// Session ID (None for receipts from before sessions were recorded).
+    pub id: Option<String>,
+    /// When the run's first file was quarantined (ISO 8601 string).
+    pub started_at: String,
+    /// Files quarantined in the run.
+    pub files: usize,
+    /// Bytes quarantined in the run.
+    pub bytes: u64,
+}
+
 // ============================================================================
 // CONFIGURATION
 // ============================================================================
@@ -186,10 +919,135 @@ pub enum OutputFormat {
     Human,
     /// Machine-readable JSON.
     Json,
+    /// One CSV row per duplicate, orphan, or diverged file.
+    Csv,
+    /// Markdown tables per category, under a summary.
+    Markdown,
+    /// A self-contained HTML page.
+    Html,
+    /// One JSON record per line: a finding each, then a summary.
+    Jsonl,
+    /// Just the human summary block, with no per-file listings.
+    Summary,
+}
+
+/// A kind of finding a report can be limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportCategory {
+    /// Confirmed duplicates, with their folder rollups and recoverable
+    /// space, plus hard links, probable duplicates, and metadata mismatches.
+    Duplicates,
+    /// Conflicts whose originals are missing.
+    Orphans,
+    /// Conflicts that differ from their originals.
+    Diverged,
+    /// Files skipped for read errors or symlinks, and ones not downloaded.
+    Skipped,
+}
+
+/// Which file of a duplicate group is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeepPolicy {
+    /// Keep the file with the clean (non-conflict) name.
+    #[default]
+    OriginalName,
+    /// Keep the most recently modified file, even if it's a conflict copy.
+    NewestMtime,
+    /// Keep the largest file (bundles can differ in size by metadata).
+    Largest,
+}
+
+/// How much evidence verification needs before calling two files equal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerificationLevel {
+    /// Hash every same-size pair.
+    #[default]
+    Strict,
+    /// Skip hashing when size and modification time already match, and
+    /// report such pairs as probable duplicates.
+    Fast,
+}
+
+/// File metadata that can be compared on top of content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataField {
+    /// Extended attributes, apart from bookkeeping ones the system sets on
+    /// its own (quarantine flags, last-used dates).
+    Xattrs,
+    /// Finder tags.
+    Tags,
+    /// Creation date, to the second.
+    Created,
+}
+
+impl MetadataField {
+    /// Name as shown in reports.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Xattrs => "xattrs",
+            Self::Tags => "tags",
+            Self::Created => "created",
+        }
+    }
+}
+
+/// How files are read for hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadTuning {
+    /// Read buffer in bytes (None = adaptive per file).
+    pub buffer_size: Option<usize>,
+    /// Files at least this big are hashed on several cores (None = always
+    /// one core per file).
+    pub parallel_min_size: Option<u64>,
+    /// Ask the OS to read ahead aggressively (`F_RDAHEAD` on macOS,
+    /// `POSIX_FADV_SEQUENTIAL` on Linux), so slow storage keeps the hasher
+    /// fed.
+    pub readahead: bool,
+}
+
+impl Default for ReadTuning {
+    fn default() -> Self {
+        Self {
+            buffer_size: None,
+            parallel_min_size: None,
+            readahead: true,
+        }
+    }
+}
+
+/// How to retry reads that fail transiently, as iCloud-backed reads do
+/// while the daemon is still materializing a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first failure (0 = never retry).
+    pub retries: u32,
+    /// Wait before the first retry; doubled for each one after.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
 }
 
+/// Directories skipped by default: build output, package caches, and
+/// libraries whose numbered files ("foo 2.js") aren't iCloud conflicts.
+pub const DEFAULT_IGNORES: [&str; 5] = [
+    ".git",
+    "node_modules",
+    ".build",
+    "DerivedData",
+    "*.photoslibrary",
+];
+
 /// Configuration for scanning operations.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ScanConfig {
     /// Root directories to scan.
     pub roots: Vec<PathBuf>,
@@ -199,8 +1057,54 @@ pub struct ScanConfig {
     pub follow_symlinks: bool,
     /// Include hidden files (dotfiles).
     pub include_hidden: bool,
+    /// Directory names never walked into, compared case-insensitively;
+    /// `*.ext` matches by extension. Roots themselves are always walked.
+    pub ignore: Vec<String>,
     /// Case-insensitive pattern matching (for "copy" vs "Copy").
     pub case_insensitive: bool,
+    /// Scan index for incremental verification (None = always re-verify).
+    pub index_path: Option<PathBuf>,
+    /// Persistent hash cache (None = hash every file read).
+    pub hash_cache_path: Option<PathBuf>,
+    /// Hash store to use instead of the file at `hash_cache_path`, for
+    /// embedders with a cache of their own.
+    pub hash_store: Option<Arc<dyn HashStore>>,
+    /// Algorithm for content hashes.
+    pub hash_algorithm: HashAlgorithm,
+    /// Hash read buffer in bytes (None = adaptive per file).
+    pub hash_buffer_size: Option<usize>,
+    /// Files at least this big are hashed on several cores (None = always
+    /// one core per file).
+    pub parallel_hash_min_size: Option<u64>,
+    /// Hint the OS to read ahead while hashing.
+    pub readahead: bool,
+    /// Retries for reads that fail transiently during verification.
+    pub retry: RetryPolicy,
+    /// Whether same-size pairs are always hashed.
+    pub verification_level: VerificationLevel,
+    /// Metadata that must also match for a conflict to count as a
+    /// duplicate (empty = content only).
+    pub compare_metadata: Vec<MetadataField>,
+    /// Which file of each duplicate group to keep.
+    pub keep_policy: KeepPolicy,
+    /// Look near orphaned conflicts for identical files to adopt as their
+    /// originals.
+    pub search_orphans: bool,
+    /// Stop after walking this many files, keeping what was found.
+    pub max_files: Option<usize>,
+    /// Stop after scanning this long, keeping what was found.
+    pub max_duration: Option<Duration>,
+    /// Skip files modified this recently: a sync or an open document may
+    /// still be writing them.
+    pub ignore_newer_than: Option<Duration>,
+    /// Download evicted candidates and originals before verifying them,
+    /// waiting at most this long per batch (None = leave them unread and
+    /// report them as not downloaded).
+    pub materialize: Option<Duration>,
+    /// Clock and verdict for `max_files` and `max_duration`.
+    pub budget: ScanBudget,
+    /// Stops the walk and any remaining verification when cancelled.
+    pub cancel: CancelToken,
 }
 
 impl Default for ScanConfig {
@@ -210,11 +1114,52 @@ impl Default for ScanConfig {
             max_depth: None,
             follow_symlinks: false,
             include_hidden: true,
+            ignore: DEFAULT_IGNORES.iter().map(|s| s.to_string()).collect(),
             case_insensitive: true,
+            index_path: None,
+            hash_cache_path: None,
+            hash_store: None,
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash_buffer_size: None,
+            parallel_hash_min_size: Some(DEFAULT_PARALLEL_HASH_MIN_SIZE),
+            readahead: true,
+            retry: RetryPolicy::default(),
+            verification_level: VerificationLevel::Strict,
+            compare_metadata: Vec::new(),
+            keep_policy: KeepPolicy::OriginalName,
+            search_orphans: false,
+            max_files: None,
+            max_duration: None,
+            ignore_newer_than: None,
+            materialize: None,
+            budget: ScanBudget::default(),
+            cancel: CancelToken::new(),
         }
     }
 }
 
+impl ScanConfig {
+    /// How this scan reads files for hashing.
+    pub fn read_tuning(&self) -> ReadTuning {
+        ReadTuning {
+            buffer_size: self.hash_buffer_size,
+            parallel_min_size: self.parallel_hash_min_size,
+            readahead: self.readahead,
+        }
+    }
+}
+
+/// Where quarantined duplicates are moved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Destination {
+    /// The app's own quarantine directory.
+    #[default]
+    Quarantine,
+    /// The macOS Trash, so Finder's "Put Back" works. The manifest still
+    /// lives in the quarantine directory.
+    Trash,
+}
+
 /// Configuration for quarantine operations.
 #[derive(Debug)]
 pub struct QuarantineConfig {
@@ -225,6 +1170,21 @@ pub struct QuarantineConfig {
     pub dry_run: bool,
     /// Preserve directory structure in quarantine.
     pub preserve_structure: bool,
+    /// Algorithm of the hashes being quarantined, recorded in receipts so
+    /// restores verify with the same one.
+    pub hash_algorithm: HashAlgorithm,
+    /// Where duplicates go.
+    pub destination: Destination,
+    /// Clone each duplicate into the quarantine (APFS `clonefile`) and
+    /// then remove the original, instead of renaming it. Falls back to a
+    /// rename or copy where clones aren't supported.
+    pub clone_files: bool,
+    /// Path globs never quarantined, whatever the scan found (see
+    /// [`crate::quarantine::is_protected`]).
+    pub protected: Vec<String>,
+    /// Before moving anything, wait up to this long for iCloud to finish
+    /// syncing the files involved. None only warns.
+    pub sync_wait: Option<Duration>,
 }
 
 impl Default for QuarantineConfig {
@@ -233,6 +1193,11 @@ impl Default for QuarantineConfig {
             quarantine_dir: PathBuf::new(), // Will be set at runtime
             dry_run: false,
             preserve_structure: true,
+            hash_algorithm: HashAlgorithm::Blake3,
+            destination: Destination::Quarantine,
+            clone_files: false,
+            protected: Vec::new(),
+            sync_wait: None,
         }
     }
 }